@@ -0,0 +1,102 @@
+//! Serializes and parses a replay with 100k inputs, to measure the input data encoding.
+//!
+//! Before benchmarking, prints how many allocations and bytes one serialization
+//! and one parse allocate. Run with `cargo bench --bench input_codec`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use techmino_replay_toolkit::*;
+
+const INPUTS: u64 = 100_000;
+
+/// Counts the allocations made through the global allocator.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f` once, printing the allocations it made.
+fn report_allocations<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+
+    let result = f();
+
+    println!(
+        "{name}: {} allocations, {} bytes",
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    );
+
+    result
+}
+
+/// Builds a replay with pseudo-random inputs, the same ones every run.
+fn large_replay(mode: InputParseMode) -> GameReplayData {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut data = GameReplayData::default();
+    data.metadata.version = match mode {
+        InputParseMode::Absolute => "V0.17.22".into(),
+        InputParseMode::Relative => "V0.17.21".into(),
+    };
+
+    let mut frame = 0;
+    for _ in 0..INPUTS / 2 {
+        let key = InputEventKey::try_from((next() % 20 + 1) as u8).unwrap();
+        frame += next() % 30;
+        data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Press, key });
+        frame += next() % 10;
+        data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Release, key });
+    }
+
+    data
+}
+
+fn bench_input_codec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("input_codec");
+
+    for (name, mode) in [("absolute", InputParseMode::Absolute), ("relative", InputParseMode::Relative)] {
+        let data = large_replay(mode);
+
+        let raw = report_allocations(&format!("serialize_to_raw/{name}"), || data.serialize_to_raw(None).unwrap());
+        report_allocations(&format!("try_from_raw/{name}"), || GameReplayData::try_from_raw(&raw, None).unwrap());
+
+        group.bench_function(format!("serialize_to_raw/{name}"), |b| b.iter(|| data.serialize_to_raw(None)));
+        group.bench_function(format!("try_from_raw/{name}"), |b| b.iter(|| GameReplayData::try_from_raw(&raw, None)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_input_codec);
+criterion_main!(benches);
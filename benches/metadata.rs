@@ -0,0 +1,38 @@
+//! Serializes and parses a replay whose metadata has a large `private` field, like the ones
+//! saved from the `custom_clear` and `custom_puzzle` modes with big fields.
+//!
+//! Run with `cargo bench --bench metadata`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use serde_json::json;
+use techmino_replay_toolkit::*;
+
+/// Builds a replay with a `private` field of about 1 MB.
+fn replay_with_large_private() -> GameReplayData {
+    let cells = ["Z", "S", "J", "L", "T", "O", "I", ""];
+    let field: Vec<Vec<&str>> = (0..10_000)
+        .map(|row| (0..10).map(|column| cells[(row * 7 + column * 3) % cells.len()]).collect())
+        .collect();
+
+    let mut data = GameReplayData::default();
+    data.metadata.version = "V0.17.22".into();
+    data.metadata.mode = "custom_puzzle".into();
+    data.metadata.private = Some(json!({ "field": [field], "opponent": "X", "life": 0 }));
+
+    data
+}
+
+fn bench_metadata(c: &mut Criterion) {
+    let data = replay_with_large_private();
+    let raw = data.serialize_to_raw(None).unwrap();
+
+    let mut group = c.benchmark_group("metadata");
+
+    group.bench_function("serialize_to_raw", |b| b.iter(|| data.serialize_to_raw(None)));
+    group.bench_function("try_from_raw", |b| b.iter(|| GameReplayData::try_from_raw(&raw, None)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_metadata);
+criterion_main!(benches);
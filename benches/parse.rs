@@ -0,0 +1,95 @@
+//! Parses and serializes a small sprint replay and a large synthetic marathon replay,
+//! parses only their metadata, and runs the VLQ codec on its own.
+//!
+//! The large replay is generated from a fixed seed, so the numbers are comparable across runs
+//! and machines. Run with `cargo bench --bench parse`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use techmino_replay_toolkit::*;
+
+/// The `someinputs` test replay, a short 10-line sprint.
+const SPRINT: &str = include_str!("../src/tests/cases/someinputs.b64.rep");
+
+const MARATHON_INPUTS: u64 = 200_000;
+const VLQ_VALUES: u64 = 1_000_000;
+
+/// A xorshift generator, so that the synthetic data is the same every run.
+fn rng(mut state: u64) -> impl FnMut() -> u64 {
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
+
+/// Builds a marathon replay with pseudo-random inputs, the same ones every run.
+fn marathon_replay() -> GameReplayData {
+    let mut next = rng(0x2545_f491_4f6c_dd1d);
+
+    let mut data = GameReplayData::default();
+    data.metadata.version = "V0.17.22".into();
+    data.metadata.mode = "marathon_n".into();
+    data.metadata.player = "Bench".into();
+    data.metadata.setting = PlayerSettings::assumed_defaults();
+
+    let mut frame = 0;
+    for _ in 0..MARATHON_INPUTS / 2 {
+        let key = InputEventKey::try_from((next() % 20 + 1) as u8).unwrap();
+        frame += next() % 30;
+        data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Press, key });
+        frame += next() % 10;
+        data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Release, key });
+    }
+
+    data
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let sprint = GameReplayData::try_from_base64(SPRINT, None).unwrap();
+    let marathon = marathon_replay();
+
+    let mut group = c.benchmark_group("parse");
+
+    for (name, data) in [("sprint", &sprint), ("marathon", &marathon)] {
+        let base64 = data.serialize_to_base64(None).unwrap();
+        let compressed = data.serialize_to_compressed(None).unwrap();
+
+        group.throughput(Throughput::Elements(data.inputs.len() as u64));
+        group.bench_with_input(BenchmarkId::new("try_from_base64", name), &base64, |b, base64| {
+            b.iter(|| GameReplayData::try_from_base64(base64, None))
+        });
+        group.bench_with_input(BenchmarkId::new("try_from_compressed", name), &compressed, |b, compressed| {
+            b.iter(|| GameReplayData::try_from_compressed(compressed, None))
+        });
+        group.bench_with_input(BenchmarkId::new("serialize_to_base64", name), data, |b, data| {
+            b.iter(|| data.serialize_to_base64(None))
+        });
+
+        // The inputs are left encoded, so this only decompresses and parses the metadata
+        group.bench_with_input(BenchmarkId::new("metadata_only", name), &compressed, |b, compressed| {
+            b.iter(|| LazyReplayData::try_from_compressed(compressed, None))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_vlq(c: &mut Criterion) {
+    let mut next = rng(0x9e37_79b9_7f4a_7c15);
+
+    // Mostly small values, like the frame deltas and key codes of real replays
+    let values: Vec<u64> = (0..VLQ_VALUES).map(|_| next() >> (next() % 64)).collect();
+    let bytes = vlq::encode(&values);
+
+    let mut group = c.benchmark_group("vlq");
+    group.throughput(Throughput::Elements(VLQ_VALUES));
+
+    group.bench_function("encode", |b| b.iter(|| vlq::encode(&values)));
+    group.bench_function("decode", |b| b.iter(|| vlq::decode(&bytes)));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_vlq);
+criterion_main!(benches);
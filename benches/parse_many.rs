@@ -0,0 +1,70 @@
+//! Parses a synthetic set of 1000 replays, one after another and, with the `rayon` feature,
+//! on thread pools of increasing size.
+//!
+//! Run with `cargo bench --features rayon --bench parse_many`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use techmino_replay_toolkit::*;
+
+const REPLAYS: usize = 1000;
+const INPUTS_PER_REPLAY: u64 = 2000;
+
+/// Builds compressed replays with pseudo-random inputs, the same ones every run.
+fn synthetic_replays() -> Vec<Vec<u8>> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..REPLAYS)
+        .map(|_| {
+            let mut data = GameReplayData::default();
+            data.metadata.version = "V0.17.22".into();
+            data.metadata.mode = "sprint_10l".into();
+
+            let mut frame = 0;
+            for _ in 0..INPUTS_PER_REPLAY / 2 {
+                let key = InputEventKey::try_from((next() % 20 + 1) as u8).unwrap();
+                frame += next() % 30;
+                data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Press, key });
+                frame += next() % 10;
+                data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Release, key });
+            }
+
+            data.serialize_to_compressed(None).unwrap()
+        })
+        .collect()
+}
+
+fn bench_parse_many(c: &mut Criterion) {
+    let replays = synthetic_replays();
+    let options = ParseOptions::new();
+
+    let mut group = c.benchmark_group("parse_many");
+    group.sample_size(10);
+
+    group.bench_function("sequential", |b| {
+        b.iter_batched(|| replays.clone(), |replays| parse_many(replays, &options), BatchSize::LargeInput)
+    });
+
+    #[cfg(feature = "rayon")]
+    for threads in [1, 2, 4, 8] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+
+        group.bench_function(format!("rayon/{threads}"), |b| {
+            b.iter_batched(
+                || replays.clone(),
+                |replays| pool.install(|| par_parse_many(replays, &options)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_many);
+criterion_main!(benches);
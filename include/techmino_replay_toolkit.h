@@ -0,0 +1,145 @@
+#ifndef TECHMINO_REPLAY_TOOLKIT_H
+#define TECHMINO_REPLAY_TOOLKIT_H
+
+/* Generated with cbindgen from src/capi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a C API call.
+typedef enum TrtStatus {
+  // The call succeeded.
+  TRT_STATUS_OK = 0,
+  // A pointer argument was null.
+  TRT_STATUS_NULL_POINTER = 1,
+  // A string argument isn't valid UTF-8.
+  TRT_STATUS_INVALID_UTF8 = 2,
+  // An index argument is out of bounds.
+  TRT_STATUS_INDEX_OUT_OF_BOUNDS = 3,
+  // See [`ReplayParseError::Base64DecodeError`].
+  TRT_STATUS_BASE64_DECODE_ERROR = 10,
+  // See [`ReplayParseError::ZlibDecompressError`].
+  TRT_STATUS_ZLIB_DECOMPRESS_ERROR = 11,
+  // See [`ReplayParseError::MetadataSeparatorNotFound`].
+  TRT_STATUS_METADATA_SEPARATOR_NOT_FOUND = 12,
+  // See [`ReplayParseError::MetadataNotUtf8`].
+  TRT_STATUS_METADATA_NOT_UTF8 = 13,
+  // See [`ReplayParseError::MetadataDeserializeError`].
+  TRT_STATUS_METADATA_DESERIALIZE_ERROR = 14,
+  // See [`ReplayParseError::UnknownInputParseMode`] and [`ReplaySerializeError::UnknownInputParseMode`].
+  TRT_STATUS_UNKNOWN_INPUT_PARSE_MODE = 15,
+  // See [`ReplayParseError::MalformedInputData`].
+  TRT_STATUS_MALFORMED_INPUT_DATA = 16,
+  // See [`ReplayParseError::MalformedVlq`].
+  TRT_STATUS_MALFORMED_VLQ = 17,
+  // See [`ReplayParseError::StrictViolation`].
+  TRT_STATUS_STRICT_VIOLATION = 18,
+  // See [`ReplayParseError::EmptyInput`].
+  TRT_STATUS_EMPTY_INPUT = 19,
+  // See [`ReplaySerializeError::UnsortedInput`].
+  TRT_STATUS_UNSORTED_INPUT = 20,
+  // See [`ReplaySerializeError::MetadataSerializeError`].
+  TRT_STATUS_METADATA_SERIALIZE_ERROR = 21,
+  // See [`ReplayParseError::NotAReplay`].
+  TRT_STATUS_NOT_A_REPLAY = 22,
+  // See [`ReplaySerializeError::UndecodableInputs`].
+  TRT_STATUS_UNDECODABLE_INPUTS = 23,
+  // See [`ReplaySerializeError::InvalidKeyCode`].
+  TRT_STATUS_INVALID_KEY_CODE = 24,
+} TrtStatus;
+
+// A parsed replay.
+typedef struct TrtReplay TrtReplay;
+
+// An input of a replay, as returned by [`trt_replay_input_at`].
+typedef struct TrtInput {
+  // The frame the input happened on.
+  uint64_t frame;
+  // 0 for a press, 1 for a release.
+  uint8_t kind;
+  // The key code, see [`InputEventKey`].
+  uint8_t key;
+} TrtInput;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses a NUL-terminated base64 replay string.
+//
+// On success, `*out` is set to a new replay, which must be freed with [`trt_free_replay`].
+//
+// # Safety
+//
+// `string` must be null or point to a NUL-terminated string, and `out` must be null or valid for writes.
+TrtStatus trt_parse_base64(const char *string, TrtReplay **out);
+
+// Parses a compressed replay, e.g. the contents of a `.rep` file, of `len` bytes.
+//
+// On success, `*out` is set to a new replay, which must be freed with [`trt_free_replay`].
+//
+// # Safety
+//
+// `data` must be null or point to `len` readable bytes, and `out` must be null or valid for writes.
+TrtStatus trt_parse_compressed(const uint8_t *data, uintptr_t len, TrtReplay **out);
+
+// Serializes a replay into a base64 string.
+//
+// On success, `*out` is set to a new NUL-terminated string, which must be freed with [`trt_free_string`].
+//
+// # Safety
+//
+// `replay` must be null or a replay from this API that hasn't been freed,
+// and `out` must be null or valid for writes.
+TrtStatus trt_serialize_base64(const TrtReplay *replay, char **out);
+
+// Returns the number of inputs of a replay, or 0 if `replay` is null.
+//
+// # Safety
+//
+// `replay` must be null or a replay from this API that hasn't been freed.
+uintptr_t trt_replay_input_count(const TrtReplay *replay);
+
+// Copies the input at `index` of a replay into `*out`.
+//
+// # Safety
+//
+// `replay` must be null or a replay from this API that hasn't been freed,
+// and `out` must be null or valid for writes.
+TrtStatus trt_replay_input_at(const TrtReplay *replay, uintptr_t index, TrtInput *out);
+
+// Returns the metadata of a replay as a new NUL-terminated JSON string, which must be freed
+// with [`trt_free_string`], or null if it fails.
+//
+// # Safety
+//
+// `replay` must be null or a replay from this API that hasn't been freed.
+char *trt_replay_metadata_json(const TrtReplay *replay);
+
+// Frees a replay. Does nothing if `replay` is null.
+//
+// # Safety
+//
+// `replay` must be null or a replay from this API that hasn't been freed.
+void trt_free_replay(TrtReplay *replay);
+
+// Frees a string returned by this API. Does nothing if `string` is null.
+//
+// # Safety
+//
+// `string` must be null or a string from this API that hasn't been freed.
+void trt_free_string(char *string);
+
+// Returns a message describing the last failed call on this thread, or null if no call has failed.
+//
+// The message is owned by the library, and stays valid until the next failed call on this thread.
+const char *trt_last_error_message(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TECHMINO_REPLAY_TOOLKIT_H */
@@ -0,0 +1,54 @@
+# Run with `maturin develop && pytest`, or through `cargo test --features python`,
+# which runs every `test_*` function here against the Rust build of the module.
+
+from pathlib import Path
+
+import techmino_replay_toolkit as trt
+
+FIXTURE = Path(__file__).resolve().parents[2] / "src" / "tests" / "cases" / "someinputs.b64.rep"
+
+
+def test_parse_base64():
+    replay = trt.parse_base64(FIXTURE.read_text().strip())
+
+    assert replay.metadata["player"] == "NOT_A_ROBOT"
+    assert replay.inputs[0] == (62, "Press", "HardDrop")
+    assert all(kind in ("Press", "Release") for _, kind, _ in replay.inputs)
+
+
+def test_parse_file():
+    replay = trt.parse_file(str(FIXTURE))
+    compressed = trt.parse_bytes(replay.to_bytes())
+
+    assert compressed.inputs == replay.inputs
+    assert compressed.metadata == replay.metadata
+
+
+def test_inputs_dict():
+    replay = trt.parse_file(FIXTURE)
+    columns = replay.inputs_dict()
+
+    assert list(columns) == ["frame", "kind", "key"]
+    assert list(zip(columns["frame"], columns["kind"], columns["key"])) == replay.inputs
+
+
+def test_round_trip(tmp_path):
+    replay = trt.parse_file(FIXTURE)
+
+    again = trt.parse_base64(replay.to_base64())
+    assert again.inputs == replay.inputs
+    assert again.metadata == replay.metadata
+
+    path = tmp_path / "someinputs.rep"
+    replay.save(path)
+    assert trt.parse_file(path).inputs == replay.inputs
+
+
+def test_errors():
+    for parse, data in [(trt.parse_base64, "not a replay"), (trt.parse_bytes, b"not a replay")]:
+        try:
+            parse(data)
+        except trt.ReplayError as error:
+            assert isinstance(error, ValueError)
+        else:
+            raise AssertionError("expected a ReplayError")
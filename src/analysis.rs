@@ -0,0 +1,1241 @@
+//! Heuristic analyses of the inputs of a replay, for things the game doesn't record.
+//!
+//! - [`tas_likelihood`] looks for signs of the inputs being made by a program.
+//! - [`placement_stats`] estimates how many pieces were placed, and how.
+//! - [`input_density`] counts the inputs over time, e.g. for drawing a graph of them.
+//! - [`chords`] finds keys pressed together, e.g. to see whether IRS and IHS are used at once.
+//! - [`drop_intervals`] measures the time between hard drops, i.e. the tempo of the player.
+//! - [`idle_periods`] finds the stretches without inputs, e.g. pauses or where replays were spliced.
+//! - [`aggregate`] combines statistics over many replays, e.g. for a yearly recap of a player,
+//!   and [`StatsAccumulator`] does so in parts that can be merged, e.g. on several threads.
+//!
+//! Unlike [`stats`][crate::stats], the results are estimates: they're derived from the inputs
+//! alone, without simulating the game.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::{peak_presses_in_window, ReplayStats};
+use crate::types::*;
+use crate::{KeyCategory, KeySet, FRAMES_PER_SECOND, GAME_START_FRAME};
+
+/// How strongly one property of the inputs suggests that they were made by a program,
+/// e.g. a TAS (tool-assisted speedrun) tool or a bot. See [`TasAnalysis`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasSignal {
+    /// The measured value. What it measures is described on the fields of [`TasAnalysis`].
+    pub value: f64,
+    /// How unlikely the value is for a human, from 0 (typical) to 1 (not humanly possible),
+    /// as judged by the [thresholds][TasThresholds] of the signal.
+    ///
+    /// The score rises linearly between the two thresholds.
+    pub score: f64,
+}
+
+impl TasSignal {
+    /// Scores the value, 0 at or below `human` and 1 at or above `inhuman`.
+    fn new(value: f64, thresholds: TasThresholds) -> TasSignal {
+        let TasThresholds { human, inhuman } = thresholds;
+        let score = ((value - human) / (inhuman - human)).clamp(0.0, 1.0);
+        TasSignal { value, score }
+    }
+}
+
+/// The values between which a [`TasSignal`] goes from looking human to looking made by a program.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasThresholds {
+    /// The value at or below which the signal scores 0.
+    pub human: f64,
+    /// The value at or above which the signal scores 1.
+    pub inhuman: f64,
+}
+
+impl TasThresholds {
+    const fn new(human: f64, inhuman: f64) -> TasThresholds {
+        TasThresholds { human, inhuman }
+    }
+}
+
+/// Options for [`tas_likelihood_with`]: the thresholds of each signal of [`TasAnalysis`].
+///
+/// The defaults are heuristics picked by hand, not measured from human and tool-assisted replays,
+/// so they should be tuned to the replays they're used on, e.g. the top scores of a leaderboard.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasOptions {
+    /// The thresholds of [`TasAnalysis::sustained_kps`], in key presses per second.
+    /// Defaults to 12 and 25.
+    pub sustained_kps: TasThresholds,
+    /// The thresholds of [`TasAnalysis::periodic_intervals`]. Defaults to 0.4 and 0.9.
+    pub periodic_intervals: TasThresholds,
+    /// The thresholds of [`TasAnalysis::opposing_alternation`]. Defaults to 0.3 and 0.9.
+    pub opposing_alternation: TasThresholds,
+    /// The thresholds of [`TasAnalysis::every_frame_stretch`], in frames. Defaults to 8 and 30.
+    pub every_frame_stretch: TasThresholds,
+    /// The thresholds of [`TasAnalysis::one_frame_holds`]. Defaults to 0.15 and 0.6.
+    pub one_frame_holds: TasThresholds,
+}
+
+impl Default for TasOptions {
+    fn default() -> Self {
+        TasOptions {
+            sustained_kps: TasThresholds::new(12.0, 25.0),
+            periodic_intervals: TasThresholds::new(0.4, 0.9),
+            opposing_alternation: TasThresholds::new(0.3, 0.9),
+            every_frame_stretch: TasThresholds::new(8.0, 30.0),
+            one_frame_holds: TasThresholds::new(0.15, 0.6),
+        }
+    }
+}
+
+/// Objective signals of whether the inputs of a replay were made by a program instead of a human,
+/// computed by [`tas_likelihood`].
+///
+/// The metadata's [`tas_used`][GameReplayMetadata::tas_used] flag is reported by the game,
+/// and is easily removed; these signals only look at the inputs. None of them is proof on its own,
+/// and a short replay may not have enough inputs for them to mean anything, so the result is a
+/// [`score`][TasAnalysis::score] rather than a verdict.
+///
+/// Only the inputs after the countdown are analyzed.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasAnalysis {
+    /// The highest average number of key presses per second over any 10 seconds.
+    pub sustained_kps: TasSignal,
+    /// The fraction of intervals between consecutive key presses that are exactly as long as the
+    /// interval before them, as when keys are pressed at a fixed rate.
+    pub periodic_intervals: TasSignal,
+    /// The fraction of switches between opposing keys (e.g. [`MoveLeft`][InputEventKey::MoveLeft]
+    /// and [`MoveRight`][InputEventKey::MoveRight]) where one key is released on the exact frame
+    /// the other one is pressed.
+    pub opposing_alternation: TasSignal,
+    /// The longest stretch of consecutive frames that each have an input, in frames.
+    pub every_frame_stretch: TasSignal,
+    /// The fraction of key presses that are held for exactly 1 frame, i.e. about 17 milliseconds.
+    pub one_frame_holds: TasSignal,
+    /// The mean of the scores of the signals, from 0 (looks human) to 1 (looks made by a program).
+    pub score: f64,
+}
+
+/// The number of frames [`TasAnalysis::sustained_kps`] is averaged over.
+const SUSTAINED_WINDOW: u64 = 10 * FRAMES_PER_SECOND;
+
+/// Computes objective signals of whether the inputs of a replay were made by a program,
+/// scored with the default [`TasOptions`].
+///
+/// See [`TasAnalysis`] for the signals. The inputs don't need to be sorted.
+pub fn tas_likelihood(replay: &GameReplayData) -> TasAnalysis {
+    tas_likelihood_with(replay, TasOptions::default())
+}
+
+/// Like [`tas_likelihood`], but scores the signals with the thresholds of the options.
+pub fn tas_likelihood_with(replay: &GameReplayData, options: TasOptions) -> TasAnalysis {
+    let mut inputs: Vec<GameInputEvent> =
+        replay.inputs.iter().copied().filter(|input| input.frame >= GAME_START_FRAME).collect();
+    inputs.sort_by_key(|input| input.frame);
+
+    let presses: Vec<u64> =
+        inputs.iter().filter(|input| input.kind == InputEventKind::Press).map(|input| input.frame).collect();
+
+    let sustained_kps = peak_presses_in_window(&presses, SUSTAINED_WINDOW) as f64
+        * FRAMES_PER_SECOND as f64
+        / SUSTAINED_WINDOW as f64;
+
+    let intervals: Vec<u64> = presses.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let periodic = fraction(
+        intervals.windows(2).filter(|pair| pair[0] == pair[1]).count(),
+        intervals.len().saturating_sub(1),
+    );
+
+    let signals = [
+        TasSignal::new(sustained_kps, options.sustained_kps),
+        TasSignal::new(periodic, options.periodic_intervals),
+        TasSignal::new(opposing_alternation(&inputs), options.opposing_alternation),
+        TasSignal::new(every_frame_stretch(&inputs) as f64, options.every_frame_stretch),
+        TasSignal::new(one_frame_holds(&inputs), options.one_frame_holds),
+    ];
+
+    let score = signals.iter().map(|signal| signal.score).sum::<f64>() / signals.len() as f64;
+    let [sustained_kps, periodic_intervals, opposing_alternation, every_frame_stretch, one_frame_holds] =
+        signals;
+
+    TasAnalysis {
+        sustained_kps,
+        periodic_intervals,
+        opposing_alternation,
+        every_frame_stretch,
+        one_frame_holds,
+        score,
+    }
+}
+
+/// Estimates of how the pieces of a replay were placed, computed by [`placement_stats`].
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacementStats {
+    /// The estimated number of pieces placed.
+    pub estimated_pieces: u64,
+    /// The estimated number of pieces placed per second after the countdown,
+    /// up until the last input of the replay.
+    pub pps: f64,
+    /// The number of presses of [`Hold`][InputEventKey::Hold].
+    pub holds: u64,
+    /// The average number of rotation key presses per piece.
+    pub rotations_per_piece: f64,
+    /// The fraction of the pieces that were placed with [`HardDrop`][InputEventKey::HardDrop].
+    pub hard_drop_ratio: f64,
+}
+
+/// Estimates how the pieces of a replay were placed from its inputs, without simulating the game.
+///
+/// A piece is counted for every press of [`HardDrop`][InputEventKey::HardDrop], and for every
+/// press of [`SonicDrop`][InputEventKey::SonicDrop] that is followed by a lock-inducing press of
+/// [`SoftDrop`][InputEventKey::SoftDrop] or one of the `Down` keys before the next hard or sonic drop.
+/// Only the inputs after the countdown are counted. The inputs don't need to be sorted.
+///
+/// This assumes that every piece is locked by the player, so the estimate is too low when pieces
+/// lock by themselves: in modes with high gravity (e.g. 20G) or short lock delays, and for
+/// sonic drops that are left to lock without another input.
+/// The zangi-move drop keys ([`LeftZangi`][InputEventKey::LeftZangi], [`LeftDrop`][InputEventKey::LeftDrop]
+/// and their right counterparts) aren't counted, as whether they lock depends on the board.
+pub fn placement_stats(replay: &GameReplayData) -> PlacementStats {
+    use InputEventKey::*;
+
+    let mut presses: Vec<GameInputEvent> = replay
+        .inputs
+        .iter()
+        .copied()
+        .filter(|input| input.kind == InputEventKind::Press && input.frame >= GAME_START_FRAME)
+        .collect();
+    presses.sort_by_key(|input| input.frame);
+
+    let mut stats = PlacementStats::default();
+    let (mut hard_drops, mut rotations) = (0, 0);
+    // Whether the last sonic drop hasn't been counted as a piece yet
+    let mut sonic_dropped = false;
+
+    for press in &presses {
+        match press.key {
+            HardDrop => {
+                hard_drops += 1;
+                sonic_dropped = false;
+            }
+            SonicDrop => sonic_dropped = true,
+            SoftDrop | Down1 | Down4 | Down10 if sonic_dropped => {
+                stats.estimated_pieces += 1;
+                sonic_dropped = false;
+            }
+            RotateLeft | RotateRight | Rotate180 => rotations += 1,
+            Hold => stats.holds += 1,
+            _ => {}
+        }
+    }
+
+    stats.estimated_pieces += hard_drops;
+
+    let gameplay_secs = replay.gameplay_duration_secs();
+    if gameplay_secs > 0.0 {
+        stats.pps = stats.estimated_pieces as f64 / gameplay_secs;
+    }
+
+    if stats.estimated_pieces > 0 {
+        stats.rotations_per_piece = rotations as f64 / stats.estimated_pieces as f64;
+        stats.hard_drop_ratio = hard_drops as f64 / stats.estimated_pieces as f64;
+    }
+
+    stats
+}
+
+/// Options for [`input_density_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DensityOptions {
+    /// Whether the buckets start at the end of the countdown ([`GAME_START_FRAME`]) instead of
+    /// at frame 0. The inputs during the countdown are left out then.
+    pub align_to_gameplay: bool,
+}
+
+/// The number of key presses of each kind of key, in a [`DensityBucket`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyCategoryCounts {
+    /// Presses of the keys in [`KeyCategory::Movement`].
+    pub movement: u64,
+    /// Presses of the keys in [`KeyCategory::Rotation`].
+    pub rotation: u64,
+    /// Presses of the keys in [`KeyCategory::Drop`], and the zangi-move keys in [`KeyCategory::Misc`].
+    pub drop: u64,
+    /// Presses of [`Hold`][InputEventKey::Hold], the [function keys][KeyCategory::Function], and the
+    /// keys added by forks of the game in [`KeyCategory::Other`].
+    pub other: u64,
+}
+
+/// The inputs in a span of frames, computed by [`input_density`].
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DensityBucket {
+    /// The first frame of the bucket.
+    pub start_frame: u64,
+    /// The frame after the last frame of the bucket.
+    pub end_frame: u64,
+    /// The number of input events in the bucket, both presses and releases.
+    pub events: u64,
+    /// The number of key presses in the bucket, by the kind of key.
+    pub presses: KeyCategoryCounts,
+    /// The number of key presses per second in the bucket.
+    pub kps: f64,
+}
+
+/// Splits the replay into buckets of `bucket_frames` frames from frame 0, and counts the inputs
+/// in each one, e.g. for drawing a graph of how busy the player was.
+///
+/// See [`input_density_with`] for more information.
+///
+/// # Panics
+///
+/// Panics if `bucket_frames` is 0.
+pub fn input_density(replay: &GameReplayData, bucket_frames: u64) -> Vec<DensityBucket> {
+    input_density_with(replay, bucket_frames, DensityOptions::default())
+}
+
+/// Splits the replay into buckets of `bucket_frames` frames, and counts the inputs in each one.
+///
+/// The buckets are consecutive, and the last one ends after the last input of the replay,
+/// so it may be shorter than `bucket_frames`; its [`kps`][DensityBucket::kps] is computed
+/// from its true width. Buckets without inputs are included. The inputs don't need to be sorted.
+///
+/// # Panics
+///
+/// Panics if `bucket_frames` is 0.
+pub fn input_density_with(
+    replay: &GameReplayData,
+    bucket_frames: u64,
+    options: DensityOptions,
+) -> Vec<DensityBucket> {
+    assert!(bucket_frames > 0, "the buckets must be at least one frame long");
+
+    let start = if options.align_to_gameplay { GAME_START_FRAME } else { 0 };
+    let last_frame = replay.inputs.iter().map(|input| input.frame).filter(|&frame| frame >= start).max();
+    let Some(last_frame) = last_frame else { return Vec::new() };
+
+    let count = (last_frame - start) / bucket_frames + 1;
+    let mut buckets: Vec<DensityBucket> = (0..count)
+        .map(|index| {
+            let start_frame = start + index * bucket_frames;
+            let end_frame = (start_frame + bucket_frames).min(last_frame + 1);
+            DensityBucket { start_frame, end_frame, ..Default::default() }
+        })
+        .collect();
+
+    for input in replay.inputs.iter().filter(|input| input.frame >= start) {
+        let bucket = &mut buckets[((input.frame - start) / bucket_frames) as usize];
+        bucket.events += 1;
+
+        if input.kind == InputEventKind::Press {
+            let presses = &mut bucket.presses;
+            *match input.key.category() {
+                KeyCategory::Movement => &mut presses.movement,
+                KeyCategory::Rotation => &mut presses.rotation,
+                KeyCategory::Drop | KeyCategory::Misc => &mut presses.drop,
+                KeyCategory::Hold | KeyCategory::Function | KeyCategory::Other => &mut presses.other,
+            } += 1;
+        }
+    }
+
+    for bucket in &mut buckets {
+        let KeyCategoryCounts { movement, rotation, drop, other } = bucket.presses;
+        let width = (bucket.end_frame - bucket.start_frame) as f64;
+        bucket.kps = (movement + rotation + drop + other) as f64 * FRAMES_PER_SECOND as f64 / width;
+    }
+
+    buckets
+}
+
+/// Options for [`chords_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChordOptions {
+    /// How many frames after the first press of a chord a press still counts as part of it.
+    ///
+    /// With the default of 0, only presses on the exact same frame make a chord.
+    pub window: u64,
+}
+
+/// Two or more keys pressed together, found by [`chords`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chord {
+    /// The frame of the first press of the chord.
+    pub frame: u64,
+    /// The number of frames between the first and the last press of the chord.
+    pub span: u64,
+    /// The keys pressed.
+    pub keys: KeySet,
+}
+
+impl Chord {
+    /// Whether any key of the chord is in the category, e.g. to find chords of a rotation
+    /// and [`Hold`][InputEventKey::Hold].
+    pub fn has_category(&self, category: KeyCategory) -> bool {
+        self.keys.iter().any(|key| key.category() == category)
+    }
+
+    /// The keys of the chord that are in the category.
+    pub fn keys_in(&self, category: KeyCategory) -> KeySet {
+        self.keys.iter().filter(|key| key.category() == category).collect()
+    }
+}
+
+/// Finds the frames where two or more different keys are pressed at once.
+///
+/// See [`chords_with`] for more information.
+pub fn chords(replay: &GameReplayData) -> Vec<Chord> {
+    chords_with(replay, ChordOptions::default())
+}
+
+/// Finds the groups of two or more different keys pressed within
+/// [`window`][ChordOptions::window] frames of each other, in order of their first press.
+///
+/// The presses are grouped from the earliest one: a chord takes every press up to `window` frames
+/// after its first one, and the next press after that starts the next group. Pressing the same key
+/// twice in a group counts it once, so a group of one key isn't a chord.
+///
+/// The inputs during the countdown are included, as keys held when it ends are used for IRS and IHS.
+/// The inputs don't need to be sorted.
+pub fn chords_with(replay: &GameReplayData, options: ChordOptions) -> Vec<Chord> {
+    let mut presses: Vec<GameInputEvent> =
+        replay.inputs.iter().copied().filter(|input| input.kind == InputEventKind::Press).collect();
+    presses.sort_by_key(|input| input.frame);
+
+    let mut chords = Vec::new();
+    let mut presses = presses.iter().peekable();
+
+    while let Some(first) = presses.next() {
+        let mut chord = Chord { frame: first.frame, span: 0, keys: KeySet::from_iter([first.key]) };
+
+        while let Some(press) = presses.next_if(|press| press.frame - first.frame <= options.window) {
+            chord.span = press.frame - first.frame;
+            chord.keys.insert(press.key);
+        }
+
+        if chord.keys.len() >= 2 {
+            chords.push(chord);
+        }
+    }
+
+    chords
+}
+
+/// Options for [`drop_intervals_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropIntervalOptions {
+    /// The longest interval, in frames, between the drops of a [burst][DropBurst].
+    ///
+    /// Defaults to 10 frames, i.e. 6 drops per second.
+    pub burst_threshold: u64,
+    /// The fewest drops a [burst][DropBurst] has. Defaults to 5.
+    pub min_burst_drops: usize,
+}
+
+impl Default for DropIntervalOptions {
+    fn default() -> Self {
+        DropIntervalOptions { burst_threshold: 10, min_burst_drops: 5 }
+    }
+}
+
+/// A run of hard drops in quick succession, found by [`drop_intervals`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropBurst {
+    /// The frame of the first drop.
+    pub start_frame: u64,
+    /// The frame of the last drop.
+    pub end_frame: u64,
+    /// The number of drops.
+    pub drops: usize,
+}
+
+/// The intervals between the hard drops of a replay, computed by [`drop_intervals`].
+///
+/// Without at least two drops there are no intervals, and the statistics are 0 or [`None`].
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropIntervals {
+    /// The number of frames between each hard drop and the next one, in order.
+    pub intervals: Vec<u64>,
+    /// The mean interval, in frames.
+    pub mean: f64,
+    /// The median interval, in frames, or the mean of the middle two for an even number of intervals.
+    pub median: f64,
+    /// The standard deviation of the intervals, in frames.
+    pub std_dev: f64,
+    /// The shortest interval.
+    pub min: Option<u64>,
+    /// The longest interval.
+    pub max: Option<u64>,
+    /// The runs of drops in quick succession, in order.
+    pub bursts: Vec<DropBurst>,
+}
+
+impl DropIntervals {
+    /// Computes the statistics of intervals.
+    fn new(intervals: Vec<u64>, bursts: Vec<DropBurst>) -> DropIntervals {
+        if intervals.is_empty() {
+            return DropIntervals { bursts, ..Default::default() };
+        }
+
+        let count = intervals.len() as f64;
+        let mean = intervals.iter().sum::<u64>() as f64 / count;
+        let deviations = intervals.iter().map(|&interval| interval as f64 - mean);
+        let variance = deviations.map(|deviation| deviation * deviation).sum::<f64>() / count;
+
+        let mut sorted = intervals.clone();
+        sorted.sort_unstable();
+        let middle = sorted.len() / 2;
+        let median = match sorted.len() % 2 {
+            0 => (sorted[middle - 1] + sorted[middle]) as f64 / 2.0,
+            _ => sorted[middle] as f64,
+        };
+
+        DropIntervals {
+            mean,
+            median,
+            std_dev: sqrt(variance),
+            min: sorted.first().copied(),
+            max: sorted.last().copied(),
+            intervals,
+            bursts,
+        }
+    }
+
+    /// Combines the intervals of several replays, e.g. for the statistics of all of a player's replays.
+    ///
+    /// The intervals and bursts are concatenated, and the statistics are computed over all of them.
+    pub fn combine(all: impl IntoIterator<Item = DropIntervals>) -> DropIntervals {
+        let (mut intervals, mut bursts) = (Vec::new(), Vec::new());
+        for drop_intervals in all {
+            intervals.extend(drop_intervals.intervals);
+            bursts.extend(drop_intervals.bursts);
+        }
+
+        DropIntervals::new(intervals, bursts)
+    }
+}
+
+/// Measures the intervals between the hard drops of a replay, with the default
+/// [`DropIntervalOptions`].
+///
+/// See [`drop_intervals_with`] for more information.
+pub fn drop_intervals(replay: &GameReplayData) -> DropIntervals {
+    drop_intervals_with(replay, DropIntervalOptions::default())
+}
+
+/// Measures the intervals between the presses of [`HardDrop`][InputEventKey::HardDrop],
+/// and finds the bursts of drops in quick succession.
+///
+/// Only the drops after the countdown are counted, as the ones during it don't drop anything.
+/// The inputs don't need to be sorted.
+pub fn drop_intervals_with(replay: &GameReplayData, options: DropIntervalOptions) -> DropIntervals {
+    let mut drops: Vec<u64> = replay
+        .inputs
+        .iter()
+        .filter(|input| input.kind == InputEventKind::Press && input.key == InputEventKey::HardDrop)
+        .map(|input| input.frame)
+        .filter(|&frame| frame >= GAME_START_FRAME)
+        .collect();
+    drops.sort_unstable();
+
+    let intervals: Vec<u64> = drops.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+    let mut bursts = Vec::new();
+    let mut start = 0;
+    // One past the last interval, so the burst running at the end is ended too
+    for index in 0..=intervals.len() {
+        if intervals.get(index).is_some_and(|&interval| interval <= options.burst_threshold) {
+            continue;
+        }
+
+        // The drops from `start` to `index` are each within the threshold of the one before
+        let drops_in_run = index - start + 1;
+        if drops_in_run >= options.min_burst_drops.max(2) {
+            let (start_frame, end_frame) = (drops[start], drops[index]);
+            bursts.push(DropBurst { start_frame, end_frame, drops: drops_in_run });
+        }
+        start = index + 1;
+    }
+
+    DropIntervals::new(intervals, bursts)
+}
+
+/// The square root, which `core` doesn't have.
+#[cfg(feature = "std")]
+fn sqrt(value: f64) -> f64 {
+    value.sqrt()
+}
+
+/// The square root, which `core` doesn't have, by Newton's method.
+#[cfg(not(feature = "std"))]
+fn sqrt(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 0.0;
+    }
+
+    let mut root = value.max(1.0);
+    loop {
+        let next = (root + value / root) / 2.0;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+/// Finds the stretches of more than `min_gap` frames without any input, in order.
+///
+/// Each stretch runs from the frame of the input before it, or frame 0 at the start of the replay,
+/// to the frame of the input after it. The replay format has no end marker, so the stretch after
+/// the last input is only included if the length of the replay in frames is given as `total_frames`,
+/// e.g. from a game simulation or the duration of a recording.
+/// The inputs don't need to be sorted.
+pub fn idle_periods(replay: &GameReplayData, min_gap: u64, total_frames: Option<u64>) -> Vec<Range<u64>> {
+    let mut frames: Vec<u64> = replay.inputs.iter().map(|input| input.frame).collect();
+    frames.sort_unstable();
+    frames.dedup();
+
+    let start = core::iter::once(0).chain(frames.iter().copied());
+    let end = frames.iter().copied().chain(total_frames);
+
+    start
+        .zip(end)
+        .filter(|&(start, end)| end.saturating_sub(start) > min_gap)
+        .map(|(start, end)| start..end)
+        .collect()
+}
+
+/// The distribution of the average keys per second of replays, in [`AggregateStats`].
+///
+/// Only replays with inputs after the countdown are counted.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KpsDistribution {
+    /// The number of replays counted.
+    pub replays: u64,
+    /// The mean of the average keys per second of the replays, each rounded to a millionth.
+    pub mean: f64,
+    /// The lowest average keys per second.
+    pub min: f64,
+    /// The highest average keys per second.
+    pub max: f64,
+    /// The number of replays in each 1 KPS wide bucket, by the whole KPS it starts at,
+    /// e.g. 3 for 3 to below 4 KPS. Buckets without replays are left out.
+    pub histogram: BTreeMap<u64, u64>,
+}
+
+/// Statistics combined over many replays, computed by [`aggregate`] or a [`StatsAccumulator`].
+///
+/// Only counts are kept, so the replays don't need to be held at once. They can be of any version
+/// and mode; settings a replay doesn't have, e.g. in versions from before they were added, are left
+/// out of the histograms of that setting.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateStats {
+    /// The number of replays.
+    pub replays: u64,
+    /// The number of replays with [`tas_used`][GameReplayMetadata::tas_used] set.
+    pub tas_flagged: u64,
+    /// The frames after the countdown, until the last input, over all replays.
+    pub gameplay_frames: u64,
+    /// The number of key presses for each key, countdown included.
+    ///
+    /// Keys that were never pressed are left out.
+    pub presses_per_key: BTreeMap<InputEventKey, u64>,
+    /// The distribution of the average keys per second of the replays.
+    pub average_kps: KpsDistribution,
+    /// The highest number of key presses within one second in any of the replays.
+    pub peak_kps: f64,
+    /// The number of replays using each DAS value, in frames.
+    pub das: BTreeMap<u64, u64>,
+    /// The number of replays using each ARR value, in frames.
+    pub arr: BTreeMap<u64, u64>,
+    /// The number of replays using each soft drop ARR value, in frames.
+    pub sdarr: BTreeMap<u64, u64>,
+    /// The number of replays of each mode, e.g. `sprint_40l`.
+    pub modes: BTreeMap<String, u64>,
+    /// The number of replays made with each version of the game, as written in the metadata.
+    pub versions: BTreeMap<String, u64>,
+}
+
+impl AggregateStats {
+    /// The most common DAS value, the lowest one on a tie, or `None` if no replay has one.
+    pub fn most_common_das(&self) -> Option<u64> {
+        most_common(&self.das).copied()
+    }
+
+    /// The most common ARR value, the lowest one on a tie, or `None` if no replay has one.
+    pub fn most_common_arr(&self) -> Option<u64> {
+        most_common(&self.arr).copied()
+    }
+
+    /// The most played mode, the first one by name on a tie, or `None` without replays.
+    pub fn most_common_mode(&self) -> Option<&str> {
+        most_common(&self.modes).map(String::as_str)
+    }
+}
+
+/// Builds [`AggregateStats`] one replay at a time, e.g. with one accumulator per thread of a
+/// parallel pipeline, [merged][StatsAccumulator::merge] at the end.
+///
+/// Every statistic is a count, a sum of integers, or a maximum or minimum, so the result doesn't
+/// depend on the order of the replays, or on how they're split up between accumulators.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct StatsAccumulator {
+    /// The statistics so far, without the mean KPS.
+    stats: AggregateStats,
+    /// The sum of the average keys per second of the replays, in millionths, as a sum of floats
+    /// would depend on the order.
+    kps_micros: u128,
+}
+
+impl StatsAccumulator {
+    /// An accumulator without replays.
+    pub fn new() -> StatsAccumulator {
+        StatsAccumulator::default()
+    }
+
+    /// Adds the statistics of a replay. The inputs don't need to be sorted.
+    pub fn add(&mut self, replay: &GameReplayData) {
+        let replay_stats = ReplayStats::from_replay(replay);
+        let metadata = &replay.metadata;
+        let stats = &mut self.stats;
+
+        stats.replays += 1;
+        stats.tas_flagged += u64::from(metadata.tas_used == Some(true));
+        let gameplay_frames = replay.duration_frames().saturating_sub(GAME_START_FRAME);
+        stats.gameplay_frames += gameplay_frames;
+
+        for (key, presses) in replay_stats.presses_per_key {
+            *stats.presses_per_key.entry(key).or_default() += presses;
+        }
+        if gameplay_frames > 0 {
+            let kps = replay_stats.average_kps;
+            // Rounded by hand, as `f64::round` needs `std`; the KPS is never negative
+            self.kps_micros += (kps * 1_000_000.0 + 0.5) as u128;
+
+            let histogram = BTreeMap::from([(kps as u64, 1)]);
+            let distribution = KpsDistribution { replays: 1, mean: 0.0, min: kps, max: kps, histogram };
+            merge_kps(&mut stats.average_kps, distribution);
+        }
+        stats.peak_kps = stats.peak_kps.max(replay_stats.peak_kps);
+
+        for (histogram, value) in [
+            (&mut stats.das, metadata.setting.das),
+            (&mut stats.arr, metadata.setting.arr),
+            (&mut stats.sdarr, metadata.setting.sdarr),
+        ] {
+            if let Some(value) = value {
+                *histogram.entry(value).or_default() += 1;
+            }
+        }
+
+        *stats.modes.entry(metadata.mode.clone()).or_default() += 1;
+        *stats.versions.entry(metadata.version.clone()).or_default() += 1;
+    }
+
+    /// Adds the replays of another accumulator, as if they had been added to this one.
+    pub fn merge(&mut self, other: StatsAccumulator) {
+        let (stats, other_stats) = (&mut self.stats, other.stats);
+
+        stats.replays += other_stats.replays;
+        stats.tas_flagged += other_stats.tas_flagged;
+        stats.gameplay_frames += other_stats.gameplay_frames;
+        merge_counts(&mut stats.presses_per_key, other_stats.presses_per_key);
+        merge_kps(&mut stats.average_kps, other_stats.average_kps);
+        self.kps_micros += other.kps_micros;
+        stats.peak_kps = stats.peak_kps.max(other_stats.peak_kps);
+        merge_counts(&mut stats.das, other_stats.das);
+        merge_counts(&mut stats.arr, other_stats.arr);
+        merge_counts(&mut stats.sdarr, other_stats.sdarr);
+        merge_counts(&mut stats.modes, other_stats.modes);
+        merge_counts(&mut stats.versions, other_stats.versions);
+    }
+
+    /// The statistics of all the replays added.
+    pub fn finish(self) -> AggregateStats {
+        let mut stats = self.stats;
+
+        let kps = &mut stats.average_kps;
+        if kps.replays > 0 {
+            kps.mean = self.kps_micros as f64 / 1_000_000.0 / kps.replays as f64;
+        }
+
+        stats
+    }
+}
+
+/// Combines the statistics of many replays, e.g. all of a player's replays of a year.
+///
+/// The replays are read one at a time, so they can come from an iterator that parses them as it
+/// goes. See [`AggregateStats`] for the statistics, and [`StatsAccumulator`] to combine them in
+/// parallel.
+pub fn aggregate<'a>(replays: impl IntoIterator<Item = &'a GameReplayData>) -> AggregateStats {
+    let mut accumulator = StatsAccumulator::new();
+    for replay in replays {
+        accumulator.add(replay);
+    }
+    accumulator.finish()
+}
+
+/// Adds counts to the counts of the same keys.
+fn merge_counts<K: Ord>(counts: &mut BTreeMap<K, u64>, other: BTreeMap<K, u64>) {
+    for (key, count) in other {
+        *counts.entry(key).or_default() += count;
+    }
+}
+
+/// Combines two distributions, except for their means.
+fn merge_kps(kps: &mut KpsDistribution, other: KpsDistribution) {
+    if other.replays == 0 {
+        return;
+    }
+
+    if kps.replays == 0 {
+        (kps.min, kps.max) = (other.min, other.max);
+    } else {
+        (kps.min, kps.max) = (kps.min.min(other.min), kps.max.max(other.max));
+    }
+    kps.replays += other.replays;
+    merge_counts(&mut kps.histogram, other.histogram);
+}
+
+/// The key with the highest count, the first one on a tie.
+fn most_common<K>(counts: &BTreeMap<K, u64>) -> Option<&K> {
+    // `max_by_key` returns the last of equal elements
+    counts.iter().rev().max_by_key(|&(_, &count)| count).map(|(key, _)| key)
+}
+
+/// The key that does the opposite of the key, if any.
+fn opposite(key: InputEventKey) -> Option<InputEventKey> {
+    use InputEventKey::*;
+
+    match key {
+        MoveLeft => Some(MoveRight),
+        MoveRight => Some(MoveLeft),
+        RotateLeft => Some(RotateRight),
+        RotateRight => Some(RotateLeft),
+        InstantLeft => Some(InstantRight),
+        InstantRight => Some(InstantLeft),
+        LeftDrop => Some(RightDrop),
+        RightDrop => Some(LeftDrop),
+        LeftZangi => Some(RightZangi),
+        RightZangi => Some(LeftZangi),
+        _ => None,
+    }
+}
+
+/// See [`TasAnalysis::opposing_alternation`]. Expects the inputs to be sorted.
+fn opposing_alternation(inputs: &[GameInputEvent]) -> f64 {
+    // The frame each key was last released on
+    let mut released = [None; 32];
+    let (mut switches, mut instant) = (0, 0);
+
+    for input in inputs {
+        match input.kind {
+            InputEventKind::Release => released[u8::from(input.key) as usize] = Some(input.frame),
+            InputEventKind::Press => {
+                let Some(opposite) = opposite(input.key) else { continue };
+                let Some(frame) = released[u8::from(opposite) as usize] else { continue };
+
+                // Only a switch if the opposite key was the last of the two to be used
+                if released[u8::from(input.key) as usize].is_some_and(|own| own > frame) {
+                    continue;
+                }
+
+                switches += 1;
+                instant += usize::from(frame == input.frame);
+            }
+        }
+    }
+
+    fraction(instant, switches)
+}
+
+/// See [`TasAnalysis::every_frame_stretch`]. Expects the inputs to be sorted.
+fn every_frame_stretch(inputs: &[GameInputEvent]) -> u64 {
+    let mut frames: Vec<u64> = inputs.iter().map(|input| input.frame).collect();
+    frames.dedup();
+
+    let mut longest = u64::from(!frames.is_empty());
+    let mut current = longest;
+    for pair in frames.windows(2) {
+        current = if pair[1] == pair[0] + 1 { current + 1 } else { 1 };
+        longest = longest.max(current);
+    }
+
+    longest
+}
+
+/// See [`TasAnalysis::one_frame_holds`]. Expects the inputs to be sorted.
+fn one_frame_holds(inputs: &[GameInputEvent]) -> f64 {
+    // The frame each key was pressed on, if it's held
+    let mut pressed = [None; 32];
+    let (mut holds, mut one_frame) = (0, 0);
+
+    for input in inputs {
+        let pressed = &mut pressed[u8::from(input.key) as usize];
+
+        match input.kind {
+            InputEventKind::Press => *pressed = pressed.or(Some(input.frame)),
+            InputEventKind::Release => {
+                if let Some(start) = pressed.take() {
+                    holds += 1;
+                    one_frame += usize::from(input.frame - start == 1);
+                }
+            }
+        }
+    }
+
+    fraction(one_frame, holds)
+}
+
+/// `part / total`, or 0 if the total is 0.
+fn fraction(part: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        total => part as f64 / total as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+    use InputEventKind::*;
+
+    #[test]
+    fn test_frame_perfect_inputs() {
+        // Alternating between left and right every frame, for a minute
+        let mut replay = GameReplayData::default();
+        for frame in GAME_START_FRAME..GAME_START_FRAME + 3600 {
+            let (key, other) = if frame % 2 == 0 { (MoveLeft, MoveRight) } else { (MoveRight, MoveLeft) };
+            if frame > GAME_START_FRAME {
+                replay.inputs.push(GameInputEvent { frame, kind: Release, key: other });
+            }
+            replay.inputs.push(GameInputEvent { frame, kind: Press, key });
+        }
+
+        let analysis = tas_likelihood(&replay);
+        assert_eq!(analysis.sustained_kps.value, 60.0);
+        assert_eq!(analysis.periodic_intervals.value, 1.0);
+        assert_eq!(analysis.opposing_alternation.value, 1.0);
+        assert_eq!(analysis.every_frame_stretch.value, 3600.0);
+        assert_eq!(analysis.one_frame_holds.value, 1.0);
+        assert_eq!(analysis.score, 1.0);
+
+        // Looser thresholds score the same values lower
+        let loose = TasThresholds { human: 30.0, inhuman: 90.0 };
+        let options = TasOptions { sustained_kps: loose, ..Default::default() };
+        let loosened = tas_likelihood_with(&replay, options);
+        assert_eq!(loosened.sustained_kps, TasSignal { value: 60.0, score: 0.5 });
+        assert_eq!(loosened.score, 0.9);
+
+        // The inputs during the countdown aren't analyzed
+        for input in &mut replay.inputs {
+            input.frame -= GAME_START_FRAME;
+        }
+        assert_eq!(tas_likelihood(&replay).every_frame_stretch.value, 3420.0);
+    }
+
+    #[test]
+    fn test_placement_stats() {
+        // A 40 line sprint takes 100 pieces; here one every 20 frames, so at 3 PPS
+        let mut replay = GameReplayData::default();
+        let mut tap = |frame, key| {
+            replay.inputs.push(GameInputEvent { frame, kind: Press, key });
+            replay.inputs.push(GameInputEvent { frame: frame + 3, kind: Release, key });
+        };
+
+        for piece in 0..100 {
+            let frame = GAME_START_FRAME + piece * 20;
+            tap(frame, if piece % 2 == 0 { MoveLeft } else { MoveRight });
+            if piece % 4 == 0 {
+                tap(frame + 4, RotateRight);
+            }
+            if piece % 10 == 0 {
+                tap(frame + 8, Hold);
+            }
+
+            match piece % 25 {
+                // Sonic dropped, then locked with a soft drop
+                0 => {
+                    tap(frame + 12, SonicDrop);
+                    tap(frame + 16, SoftDrop);
+                }
+                // Sonic dropped to slide it into place, then hard dropped
+                1 => {
+                    tap(frame + 12, SonicDrop);
+                    tap(frame + 16, HardDrop);
+                }
+                _ => tap(frame + 16, HardDrop),
+            }
+        }
+
+        let stats = placement_stats(&replay);
+        assert_eq!(stats.estimated_pieces, 100);
+        assert_eq!(stats.holds, 10);
+        assert_eq!(stats.rotations_per_piece, 0.25);
+        assert_eq!(stats.hard_drop_ratio, 0.96);
+        // The last input is the release of the last hard drop
+        assert!((stats.pps - 100.0 / (1999.0 / 60.0)).abs() < 1e-9, "{}", stats.pps);
+
+        // The inputs during the countdown aren't counted
+        let early = GameInputEvent::press(100, HardDrop);
+        replay.inputs.insert(0, early);
+        assert_eq!(placement_stats(&replay).estimated_pieces, 100);
+    }
+
+    #[test]
+    fn test_input_density() {
+        let replay = GameReplayData {
+            inputs: [
+                (100, Press, Hold),
+                (110, Release, Hold),
+                (180, Press, MoveLeft),
+                (185, Release, MoveLeft),
+                (190, Press, RotateLeft),
+                (200, Press, HardDrop),
+                (201, Release, HardDrop),
+                (239, Press, MoveRight),
+                (240, Press, SonicDrop),
+                (300, Press, Function1),
+                (330, Release, RotateLeft),
+            ]
+            .into_iter()
+            .map(|(frame, kind, key)| GameInputEvent { frame, kind, key })
+            .collect(),
+            ..Default::default()
+        };
+
+        let buckets = input_density(&replay, 120);
+        let spans: Vec<_> = buckets.iter().map(|b| (b.start_frame, b.end_frame, b.events)).collect();
+        assert_eq!(spans, [(0, 120, 2), (120, 240, 6), (240, 331, 3)]);
+
+        let counts = |movement, rotation, drop, other| KeyCategoryCounts { movement, rotation, drop, other };
+        assert_eq!(buckets[1].presses, counts(2, 1, 1, 0));
+        assert_eq!(buckets[1].kps, 2.0);
+        // The last bucket is 91 frames wide
+        assert_eq!(buckets[2].presses, counts(0, 0, 1, 1));
+        assert_eq!(buckets[2].kps, 2.0 * 60.0 / 91.0);
+
+        let aligned = input_density_with(&replay, 60, DensityOptions { align_to_gameplay: true });
+        let spans: Vec<_> = aligned.iter().map(|b| (b.start_frame, b.end_frame, b.events)).collect();
+        assert_eq!(spans, [(180, 240, 6), (240, 300, 1), (300, 331, 2)]);
+        assert_eq!(aligned[0].kps, 4.0);
+
+        let json = serde_json::to_value(&aligned[2]).unwrap();
+        assert_eq!(json["startFrame"], 300);
+        assert_eq!(json["presses"]["other"], 1);
+
+        assert!(input_density(&GameReplayData::default(), 60).is_empty());
+    }
+
+    #[test]
+    fn test_chords() {
+        let replay = GameReplayData {
+            inputs: [
+                (170, Press, RotateLeft),
+                (170, Press, Hold),
+                (175, Release, Hold),
+                (190, Release, RotateLeft),
+                (300, Press, MoveLeft),
+                (302, Press, HardDrop),
+                (303, Release, MoveLeft),
+                (304, Release, HardDrop),
+                (400, Press, MoveRight),
+                (400, Press, MoveRight),
+            ]
+            .into_iter()
+            .map(|(frame, kind, key)| GameInputEvent { frame, kind, key })
+            .collect(),
+            ..Default::default()
+        };
+
+        let irs_ihs = Chord { frame: 170, span: 0, keys: KeySet::from_iter([RotateLeft, Hold]) };
+        assert_eq!(chords(&replay), [irs_ihs]);
+        assert_eq!(chords_with(&replay, ChordOptions { window: 1 }), [irs_ihs]);
+
+        let near = Chord { frame: 300, span: 2, keys: KeySet::from_iter([MoveLeft, HardDrop]) };
+        let found = chords_with(&replay, ChordOptions { window: 2 });
+        assert_eq!(found, [irs_ihs, near]);
+
+        let with_hold: Vec<_> = found.iter().filter(|chord| chord.has_category(KeyCategory::Hold)).collect();
+        assert_eq!(with_hold, [&irs_ihs]);
+        assert_eq!(near.keys_in(KeyCategory::Drop), KeySet::from_iter([HardDrop]));
+
+        // The presses at 400 are too late for the first chord, and are of the same key
+        let json = serde_json::to_value(chords_with(&replay, ChordOptions { window: 200 })).unwrap();
+        assert_eq!(json[0]["keys"], serde_json::json!(["MoveLeft", "RotateLeft", "HardDrop", "Hold"]));
+        assert_eq!((json[0]["span"].as_u64(), json.as_array().unwrap().len()), (Some(132), 1));
+    }
+
+    #[test]
+    fn test_drop_intervals() {
+        let frames = [100, 200, 230, 236, 242, 248, 254, 260, 300, 320];
+        let mut replay = GameReplayData::default();
+        for frame in frames.into_iter().rev() {
+            replay.inputs.push(GameInputEvent { frame, kind: Press, key: HardDrop });
+            replay.inputs.push(GameInputEvent { frame: frame + 2, kind: Release, key: HardDrop });
+        }
+        replay.inputs.push(GameInputEvent::press(250, SonicDrop));
+
+        // The drop at 100 is during the countdown
+        let intervals = drop_intervals(&replay);
+        assert_eq!(intervals.intervals, [30, 6, 6, 6, 6, 6, 40, 20]);
+        assert_eq!((intervals.mean, intervals.median), (15.0, 6.0));
+        assert_eq!((intervals.min, intervals.max), (Some(6), Some(40)));
+        // The squared deviations are 225, 5 times 81, 625 and 25, so the variance is 1280 / 8
+        assert!((intervals.std_dev - 160f64.sqrt()).abs() < 1e-9, "{}", intervals.std_dev);
+        assert_eq!(intervals.bursts, [DropBurst { start_frame: 230, end_frame: 260, drops: 6 }]);
+
+        let options = DropIntervalOptions { burst_threshold: 20, min_burst_drops: 2 };
+        let bursts = drop_intervals_with(&replay, options).bursts;
+        let spans: Vec<_> = bursts.iter().map(|b| (b.start_frame, b.end_frame, b.drops)).collect();
+        assert_eq!(spans, [(230, 260, 6), (300, 320, 2)]);
+
+        let combined = DropIntervals::combine([intervals.clone(), intervals]);
+        assert_eq!((combined.intervals.len(), combined.bursts.len(), combined.mean), (16, 2, 15.0));
+
+        let json = serde_json::to_value(drop_intervals(&replay)).unwrap();
+        assert_eq!(json["bursts"][0]["startFrame"], 230);
+    }
+
+    #[test]
+    fn test_no_drop_intervals() {
+        let empty = drop_intervals(&GameReplayData::default());
+        assert_eq!(empty, DropIntervals::default());
+        assert_eq!((empty.mean, empty.median, empty.std_dev, empty.min), (0.0, 0.0, 0.0, None));
+
+        // A single drop has no intervals
+        let mut replay = GameReplayData::default();
+        replay.inputs.push(GameInputEvent::press(200, HardDrop));
+        assert_eq!(drop_intervals(&replay), DropIntervals::default());
+        assert_eq!(DropIntervals::combine([]), DropIntervals::default());
+    }
+
+    #[test]
+    fn test_idle_periods() {
+        let mut replay = GameReplayData::default();
+        for frame in (180..1000).step_by(20).chain((1585..2000).step_by(15)) {
+            replay.inputs.push(GameInputEvent { frame, kind: Press, key: MoveLeft });
+            replay.inputs.push(GameInputEvent { frame: frame + 5, kind: Release, key: MoveLeft });
+        }
+        replay.inputs.reverse();
+
+        let spans = |replay: &GameReplayData, min_gap, total_frames| -> Vec<(u64, u64)> {
+            let periods = idle_periods(replay, min_gap, total_frames);
+            periods.into_iter().map(|period| (period.start, period.end)).collect()
+        };
+
+        // The last input before the pause is the release at 985, and the first one after it is at 1585
+        assert_eq!(spans(&replay, 300, None), [(985, 1585)]);
+        assert_eq!(spans(&replay, 599, None), [(985, 1585)]);
+        assert_eq!(spans(&replay, 600, None), []);
+        assert_eq!(spans(&replay, 100, None), [(0, 180), (985, 1585)]);
+
+        // The last input is at 1995
+        assert_eq!(spans(&replay, 300, Some(2295)), [(985, 1585)]);
+        assert_eq!(spans(&replay, 300, Some(2400)), [(985, 1585), (1995, 2400)]);
+
+        assert_eq!(spans(&GameReplayData::default(), 300, None), []);
+        assert_eq!(spans(&GameReplayData::default(), 300, Some(600)), [(0, 600)]);
+    }
+
+    #[test]
+    fn test_no_inputs() {
+        let analysis = tas_likelihood(&GameReplayData::default());
+        assert_eq!(analysis, TasAnalysis::default());
+        assert_eq!(placement_stats(&GameReplayData::default()), PlacementStats::default());
+    }
+
+    /// A random integer in `[1, max]`.
+    fn roll(rng: &mut impl FnMut() -> u64, max: u64) -> u64 {
+        rng() % max + 1
+    }
+
+    /// A replay made of random inputs and settings.
+    fn random_replay(rng: &mut impl FnMut() -> u64) -> GameReplayData {
+        let mut frame = roll(rng, 400);
+        let inputs = (0..roll(rng, 200))
+            .map(|_| {
+                frame += roll(rng, 30) - 1;
+                let kind = if roll(rng, 2) == 1 { Press } else { Release };
+                let key = [MoveLeft, MoveRight, HardDrop, Hold, RotateLeft][roll(rng, 5) as usize - 1];
+                GameInputEvent { frame, kind, key }
+            })
+            .collect();
+
+        let mut replay = GameReplayData { inputs, ..Default::default() };
+        let metadata = &mut replay.metadata;
+        metadata.mode = ["sprint_40l", "marathon_n", "dig_10l"][roll(rng, 3) as usize - 1].into();
+        metadata.version = ["V0.17.22", "V0.16.4", "V0.15.3"][roll(rng, 3) as usize - 1].into();
+        metadata.tas_used = (roll(rng, 4) == 1).then_some(true);
+        metadata.setting.das = (roll(rng, 5) > 1).then(|| roll(rng, 10));
+        metadata.setting.arr = (roll(rng, 5) > 1).then(|| roll(rng, 3) - 1);
+        replay
+    }
+
+    #[test]
+    fn test_accumulator_partitions() {
+        let mut rng = crate::tests::helpers::xorshift(1113);
+        let replays: Vec<GameReplayData> = (0..60).map(|_| random_replay(&mut rng)).collect();
+
+        let sequential = aggregate(&replays);
+        assert_eq!(sequential.replays, 60);
+        assert!(sequential.average_kps.replays > 50, "{:?}", sequential.average_kps);
+
+        for _ in 0..50 {
+            // Deal the replays out to a random number of accumulators, in a random order
+            let mut parts = vec![StatsAccumulator::new(); roll(&mut rng, 8) as usize];
+            let mut order: Vec<usize> = (0..replays.len()).collect();
+            for i in (1..order.len()).rev() {
+                order.swap(i, roll(&mut rng, i as u64 + 1) as usize - 1);
+            }
+            for index in order {
+                let part = roll(&mut rng, parts.len() as u64) as usize - 1;
+                parts[part].add(&replays[index]);
+            }
+
+            // Merge them in a random order too
+            let mut merged = parts.swap_remove(roll(&mut rng, parts.len() as u64) as usize - 1);
+            while !parts.is_empty() {
+                merged.merge(parts.swap_remove(roll(&mut rng, parts.len() as u64) as usize - 1));
+            }
+
+            assert_eq!(merged.finish(), sequential);
+        }
+
+        let mut empty = StatsAccumulator::new();
+        empty.merge(StatsAccumulator::new());
+        assert_eq!(empty.finish(), AggregateStats::default());
+    }
+
+    #[test]
+    fn test_accumulator_kps_histogram() {
+        // 1000 presses on the first frame after the countdown, i.e. 60000 KPS
+        use crate::tests::helpers::replay;
+
+        let fast = replay(vec![GameInputEvent::press(GAME_START_FRAME + 1, MoveLeft); 1000]);
+        let slow = replay(vec![GameInputEvent::press(GAME_START_FRAME + 40, MoveLeft)]);
+
+        let stats = aggregate([&fast, &slow, &slow]);
+        assert_eq!(stats.average_kps.histogram, BTreeMap::from([(1, 2), (60000, 1)]));
+        assert_eq!((stats.average_kps.min, stats.average_kps.max), (1.5, 60000.0));
+        assert_eq!(stats.average_kps.mean, 20001.0);
+    }
+}
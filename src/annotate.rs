@@ -0,0 +1,259 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{KEY_MASK, KIND_BIT};
+use crate::types::*;
+use crate::vlq;
+
+/// A value of the input data, with where it is and what it means.
+///
+/// See [`annotate_input_bytes`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputAnnotation {
+    /// The index of the value. Even values are times, odd values are key codes.
+    pub index: usize,
+    /// The offset of the first byte of the value in the input data.
+    pub offset: usize,
+    /// The bytes of the value.
+    pub bytes: Vec<u8>,
+    /// The decoded value, or `None` if its bytes aren't a valid VLQ.
+    pub value: Option<u64>,
+    /// What the value means.
+    pub meaning: InputValueMeaning,
+}
+
+/// What a value of the input data means. See [`InputAnnotation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputValueMeaning {
+    /// The time of an event, and the frame it works out to.
+    Time {
+        /// The frame of the event.
+        frame: u64,
+    },
+    /// The key code of an event.
+    KeyCode {
+        /// The key that was pressed or released.
+        key: InputEventKey,
+        /// Whether the key was pressed or released.
+        kind: InputEventKind,
+    },
+    /// A time at the end of the data without a key code, which is ignored when parsing.
+    TrailingTime,
+    /// A value that can't be parsed, which ends the annotations.
+    Malformed(MalformedInputValue),
+}
+
+/// Why a value of the input data can't be parsed. See [`InputValueMeaning::Malformed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MalformedInputValue {
+    /// The data ends in the middle of the value.
+    TruncatedVlq,
+    /// The value doesn't fit in a [`u64`].
+    VlqOverflow,
+    /// The frame of the time doesn't fit in a [`u64`].
+    FrameOverflow,
+    /// The key bits of the key code aren't a known key.
+    UnknownKey {
+        /// The key bits, i.e. the lowest 5 bits of the key code.
+        key_bits: u8,
+    },
+    /// The key code has bits set above the kind bit.
+    ExtraBits,
+}
+
+/// Decodes the input data of a replay value by value, describing each value,
+/// e.g. to find out why a replay fails to parse.
+///
+/// The annotations stop after the first [malformed][InputValueMeaning::Malformed] value.
+/// The input data is the part of a raw replay after the metadata; see [`SplitReplay`][crate::SplitReplay].
+///
+/// ```
+/// use techmino_replay_toolkit::{annotate_input_bytes, InputParseMode};
+///
+/// let annotations = annotate_input_bytes(&[0x86, 0x34, 0x06, 0x01, 0x3f], InputParseMode::Absolute);
+///
+/// assert_eq!(annotations[0].to_string(), "0x86 0x34 -> 820 | frame=820");
+/// assert_eq!(annotations[1].to_string(), "0x06 -> 6 | key=HardDrop kind=Press");
+/// assert_eq!(annotations[3].to_string(), "0x3f -> 63 | malformed: unknown key 31");
+/// assert_eq!(annotations[3].offset, 4);
+/// ```
+pub fn annotate_input_bytes(bytes: &[u8], mode: InputParseMode) -> Vec<InputAnnotation> {
+    let mut annotations = Vec::new();
+    let mut values = vlq::decode_iter(bytes);
+
+    let mut prev_frame: u64 = 0;
+    let mut frame = 0;
+
+    loop {
+        let offset = values.position();
+        let Some(value) = values.next() else { break };
+        let index = annotations.len();
+
+        let (value, meaning) = match value {
+            Err(vlq::VlqError::Truncated { .. }) => (None, Err(MalformedInputValue::TruncatedVlq)),
+            Err(vlq::VlqError::Overflow { .. }) => (None, Err(MalformedInputValue::VlqOverflow)),
+            Ok(time) if index % 2 == 0 => {
+                let time_frame = match mode {
+                    InputParseMode::Relative => prev_frame.checked_add(time),
+                    InputParseMode::Absolute => Some(time),
+                };
+
+                let meaning = match time_frame {
+                    None => Err(MalformedInputValue::FrameOverflow),
+                    Some(_) if values.position() == bytes.len() => Ok(InputValueMeaning::TrailingTime),
+                    Some(time_frame) => {
+                        frame = time_frame;
+                        Ok(InputValueMeaning::Time { frame })
+                    }
+                };
+
+                (Some(time), meaning)
+            }
+            Ok(code) => {
+                let key_bits = (code & KEY_MASK) as u8;
+
+                let meaning = if code & !(KIND_BIT | KEY_MASK) != 0 {
+                    Err(MalformedInputValue::ExtraBits)
+                } else {
+                    match InputEventKey::try_from(key_bits) {
+                        Ok(key) => {
+                            prev_frame = frame;
+                            let kind = InputEventKind::from(code & KIND_BIT != 0);
+                            Ok(InputValueMeaning::KeyCode { key, kind })
+                        }
+                        Err(()) => Err(MalformedInputValue::UnknownKey { key_bits }),
+                    }
+                };
+
+                (Some(code), meaning)
+            }
+        };
+
+        // Errors leave the position at the end, so the bytes of a malformed VLQ are found by hand
+        let end = match value {
+            Some(_) => values.position(),
+            None => {
+                let len = bytes[offset..].iter().position(|&byte| byte < 0x80);
+                len.map_or(bytes.len(), |len| offset + len + 1)
+            }
+        };
+
+        let malformed = meaning.is_err();
+        annotations.push(InputAnnotation {
+            index,
+            offset,
+            bytes: bytes[offset..end].to_vec(),
+            value,
+            meaning: meaning.unwrap_or_else(InputValueMeaning::Malformed),
+        });
+
+        if malformed {
+            break;
+        }
+    }
+
+    annotations
+}
+
+impl fmt::Display for InputAnnotation {
+    /// Formats the annotation like `0x86 0x34 -> 820 | frame=820`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.bytes.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{byte:#04x}")?;
+        }
+
+        match self.value {
+            Some(value) => write!(f, " -> {value} | ")?,
+            None => write!(f, " -> ? | ")?,
+        }
+
+        match self.meaning {
+            InputValueMeaning::Time { frame } => write!(f, "frame={frame}"),
+            InputValueMeaning::KeyCode { key, kind } => write!(f, "key={} kind={kind:?}", key.name()),
+            InputValueMeaning::TrailingTime => write!(f, "time without a key code, ignored"),
+            InputValueMeaning::Malformed(malformed) => write!(f, "malformed: {malformed}"),
+        }
+    }
+}
+
+impl fmt::Display for MalformedInputValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MalformedInputValue::TruncatedVlq => write!(f, "the data ends in the middle of the value"),
+            MalformedInputValue::VlqOverflow => write!(f, "the value is too large"),
+            MalformedInputValue::FrameOverflow => write!(f, "the frame is too large"),
+            MalformedInputValue::UnknownKey { key_bits } => write!(f, "unknown key {key_bits}"),
+            MalformedInputValue::ExtraBits => write!(f, "bits above the kind bit are set"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_annotate_round_trip() {
+        // HardDrop pressed on frame 62 and released on frame 300
+        let bytes = vlq::encode(&[62, 6, 238, 38]);
+
+        let annotations = annotate_input_bytes(&bytes, InputParseMode::Relative);
+        let meanings: Vec<_> = annotations.iter().map(|a| a.meaning).collect();
+        assert_eq!(
+            meanings,
+            [
+                InputValueMeaning::Time { frame: 62 },
+                InputValueMeaning::KeyCode { key: InputEventKey::HardDrop, kind: InputEventKind::Press },
+                InputValueMeaning::Time { frame: 300 },
+                InputValueMeaning::KeyCode { key: InputEventKey::HardDrop, kind: InputEventKind::Release },
+            ]
+        );
+        assert_eq!(annotations[2].to_string(), "0x81 0x6e -> 238 | frame=300");
+
+        let offsets: Vec<_> = annotations.iter().map(|a| a.offset).collect();
+        assert_eq!(offsets, [0, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_annotate_corrupted() {
+        // Two good events, then a key code with an extra bit, and data that isn't reached
+        let bytes = [0x10, 0x01, 0x81, 0x00, 0x21, 0x05, 0xc1, 0x03, 0x07];
+
+        let annotations = annotate_input_bytes(&bytes, InputParseMode::Absolute);
+        assert_eq!(annotations.len(), 6);
+
+        let last = &annotations[5];
+        assert_eq!((last.index, last.offset, &last.bytes[..]), (5, 6, &[0xc1, 0x03][..]));
+        assert_eq!(last.meaning, InputValueMeaning::Malformed(MalformedInputValue::ExtraBits));
+        assert_eq!(last.to_string(), "0xc1 0x03 -> 8323 | malformed: bits above the kind bit are set");
+
+        let truncated = annotate_input_bytes(&[0x01, 0x02, 0x10, 0x85, 0x80], InputParseMode::Absolute);
+        assert_eq!(truncated.len(), 4);
+        assert_eq!(truncated[3].offset, 3);
+        assert_eq!(truncated[3].bytes, [0x85, 0x80]);
+        let expected = "0x85 0x80 -> ? | malformed: the data ends in the middle of the value";
+        assert_eq!(truncated[3].to_string(), expected);
+
+        let trailing = annotate_input_bytes(&[0x01, 0x02, 0x10], InputParseMode::Relative);
+        assert_eq!(trailing[2].meaning, InputValueMeaning::TrailingTime);
+
+        let mut bytes = vlq::encode(&[1, 2, u64::MAX, 2]);
+        let overflow = annotate_input_bytes(&bytes, InputParseMode::Relative);
+        assert_eq!(overflow.len(), 3);
+        assert_eq!(overflow[2].meaning, InputValueMeaning::Malformed(MalformedInputValue::FrameOverflow));
+
+        bytes[2] = 0xff;
+        let overflow = annotate_input_bytes(&bytes, InputParseMode::Relative);
+        assert_eq!(overflow[2].meaning, InputValueMeaning::Malformed(MalformedInputValue::VlqOverflow));
+        assert_eq!(overflow[2].bytes.len(), 10);
+    }
+}
@@ -0,0 +1,125 @@
+//! Comments on frames of a replay, kept in its metadata so that they travel with it.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::mem;
+
+use serde_json::{Map, Value};
+
+use crate::types::*;
+
+/// The nonstandard metadata key the [annotations][GameReplayData::annotations] of a replay are
+/// stored under.
+pub const ANNOTATIONS_KEY: &str = "trtAnnotations";
+
+impl GameReplayData {
+    /// The comments attached to frames of the replay, e.g. `misdrop here` by the author of a TAS.
+    ///
+    /// They're stored in the [`nonstandard`][GameReplayMetadata::nonstandard] metadata under
+    /// [`ANNOTATIONS_KEY`], as an object from frames to comments, e.g. `{"180":"PC setup starts"}`.
+    /// The game ignores them, so the replay still plays, and they're kept by every format of this
+    /// crate: the text format writes them as `#@` comments between the inputs.
+    ///
+    /// Entries that aren't a frame and a string are skipped.
+    pub fn annotations(&self) -> BTreeMap<u64, String> {
+        let Some(Value::Object(map)) = self.metadata.nonstandard.get(ANNOTATIONS_KEY) else {
+            return BTreeMap::new();
+        };
+
+        map.iter()
+            .filter_map(|(frame, text)| Some((parse_frame(frame)?, text.as_str()?.to_string())))
+            .collect()
+    }
+
+    /// Attaches a comment to a frame, replacing the one it had, which is returned.
+    ///
+    /// The comments are kept in the order of their frames. See
+    /// [`annotations`][GameReplayData::annotations] for how they're stored.
+    pub fn set_annotation(&mut self, frame: u64, text: impl Into<String>) -> Option<String> {
+        let entry = self.metadata.nonstandard.entry(ANNOTATIONS_KEY).or_insert_with(|| Map::new().into());
+        if !entry.is_object() {
+            *entry = Map::new().into();
+        }
+        let map = entry.as_object_mut().expect("the annotations are an object");
+
+        let previous = map.insert(frame.to_string(), Value::String(text.into()));
+
+        // With the `preserve-order` feature new frames are added at the end, else sorted as strings
+        let mut entries: Vec<(String, Value)> = mem::take(map).into_iter().collect();
+        entries.sort_by_key(|(frame, _)| frame.parse::<u64>().ok());
+        map.extend(entries);
+
+        match previous {
+            Some(Value::String(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Removes the comment on a frame, returning it.
+    ///
+    /// Once the last comment is removed, so is the [`ANNOTATIONS_KEY`] in the metadata.
+    pub fn remove_annotation(&mut self, frame: u64) -> Option<String> {
+        let nonstandard = &mut self.metadata.nonstandard;
+        let map = nonstandard.get_mut(ANNOTATIONS_KEY)?.as_object_mut()?;
+
+        // `Map::remove` would move the last key in its place with the `preserve-order` feature
+        let key = frame.to_string();
+        let removed = map.get_mut(&key).map(Value::take);
+        map.retain(|other, _| *other != key);
+        if map.is_empty() {
+            nonstandard.retain(|key, _| key != ANNOTATIONS_KEY);
+        }
+
+        match removed {
+            Some(Value::String(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the key of an annotation, skipping keys that aren't written like [`set_annotation`] does,
+/// e.g. `+5` or `007`.
+///
+/// [`set_annotation`]: GameReplayData::set_annotation
+pub(crate) fn parse_frame(key: &str) -> Option<u64> {
+    key.parse().ok().filter(|frame: &u64| frame.to_string() == key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations() {
+        let mut data = GameReplayData::default();
+        assert!(data.annotations().is_empty());
+
+        assert_eq!(data.set_annotation(300, "PC setup starts"), None);
+        assert_eq!(data.set_annotation(20, "misdrop here"), None);
+        assert_eq!(data.set_annotation(300, "PC"), Some("PC setup starts".to_string()));
+        assert_eq!(
+            data.annotations().into_iter().collect::<Vec<_>>(),
+            [(20, "misdrop here".to_string()), (300, "PC".to_string())]
+        );
+        assert_eq!(
+            serde_json::to_string(&data.metadata.nonstandard[ANNOTATIONS_KEY]).unwrap(),
+            r#"{"20":"misdrop here","300":"PC"}"#
+        );
+
+        // Entries written by something else are skipped, but kept
+        let map = data.metadata.nonstandard[ANNOTATIONS_KEY].as_object_mut().unwrap();
+        map.insert("later".to_string(), Value::Bool(true));
+        map.insert("007".to_string(), "bond".into());
+        assert_eq!(data.annotations().len(), 2);
+
+        assert_eq!(data.remove_annotation(20), Some("misdrop here".to_string()));
+        assert_eq!(data.remove_annotation(20), None);
+        assert_eq!(data.remove_annotation(300), Some("PC".to_string()));
+        assert!(data.metadata.nonstandard.contains_key(ANNOTATIONS_KEY));
+        data.metadata.nonstandard[ANNOTATIONS_KEY].as_object_mut().unwrap().clear();
+        data.set_annotation(1, "");
+        assert_eq!(data.remove_annotation(1), Some(String::new()));
+        assert!(data.metadata.nonstandard.is_empty());
+    }
+}
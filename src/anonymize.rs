@@ -0,0 +1,80 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::options::AnonymizeOptions;
+use crate::types::*;
+
+impl GameReplayData {
+    /// Removes the personal data from the metadata, e.g. before sharing the replay publicly.
+    ///
+    /// This replaces the player's name with a placeholder, removes the time of day from the date
+    /// (or the whole date, see [`AnonymizeOptions::blank_date`]), and clears the nonstandard fields
+    /// of the metadata and the settings, which mods sometimes fill with device identifiers.
+    ///
+    /// The fields the game needs are kept with placeholder values, so the game still accepts the replay.
+    /// Dates in a format without a time of day are blanked.
+    pub fn anonymize(&mut self, options: AnonymizeOptions) {
+        let metadata = &mut self.metadata;
+
+        metadata.player = options.player;
+        metadata.nonstandard.clear();
+        metadata.setting.nonstandard.clear();
+
+        metadata.date = match metadata.date.split_once(' ') {
+            Some((day, time)) if !options.blank_date => {
+                // Keeps the format of the time, e.g. with or without the seconds
+                let time: String = time.chars().map(|c| if c.is_ascii_digit() { '0' } else { c }).collect();
+                format!("{day} {time}")
+            }
+            _ => String::new(),
+        };
+
+        if options.clear_seed {
+            metadata.seed = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use serde_json::Value;
+
+    fn replay() -> GameReplayData {
+        let mut replay = GameReplayData::default();
+        replay.metadata.player = "NOT_A_ROBOT".to_string();
+        replay.metadata.version = "V0.17.17".to_string();
+        replay.metadata.date = "2024/10/09 14:44:11".to_string();
+        replay.metadata.seed = 358231284;
+        replay.metadata.nonstandard.insert("deviceId".to_string(), Value::from("1234"));
+        replay.metadata.setting.nonstandard.insert("modSetting".to_string(), Value::from(true));
+        replay
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let mut data = replay();
+        data.anonymize(AnonymizeOptions::new());
+
+        let metadata = &data.metadata;
+        assert_eq!(metadata.player, "Anonymous");
+        assert_eq!(metadata.date, "2024/10/09 00:00:00");
+        assert_eq!(metadata.seed, 358231284);
+        assert!(metadata.nonstandard.is_empty() && metadata.setting.nonstandard.is_empty());
+
+        let mut data = replay();
+        data.metadata.date = "2021/03/14 15:09".to_string();
+        data.anonymize(AnonymizeOptions::new().player("Someone").clear_seed(true));
+        assert_eq!(data.metadata.player, "Someone");
+        assert_eq!(data.metadata.date, "2021/03/14 00:00");
+        assert_eq!(data.metadata.seed, 0);
+
+        let mut data = replay();
+        data.anonymize(AnonymizeOptions::new().blank_date(true));
+        assert_eq!(data.metadata.date, "");
+
+        let serialized = data.serialize_to_base64(None).unwrap();
+        assert_eq!(GameReplayData::try_from_base64(&serialized, None).unwrap(), data);
+    }
+}
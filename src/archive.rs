@@ -0,0 +1,205 @@
+//! Reading replays from the zip archives the game exports its data in.
+
+use std::fmt;
+use std::io::{Read, Seek};
+
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::types::*;
+use crate::LazyReplayData;
+
+/// An error from reading a [`ReplayArchive`].
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive couldn't be read, e.g. because it isn't a zip archive or because of an I/O error.
+    ///
+    /// See [`zip`'s Error type][ZipError] for more information.
+    Zip(ZipError),
+    /// The archive has no replay with the name.
+    ///
+    /// Contains the name.
+    NotFound(String),
+    /// The replay was read, but it couldn't be parsed.
+    Parse(ReplayParseError),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Zip(error) => write!(f, "Couldn't read the zip archive: {error}"),
+            ArchiveError::NotFound(name) => write!(f, "The archive has no replay named '{name}'"),
+            // The Display form of replay errors has where in the replay it went wrong
+            ArchiveError::Parse(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchiveError::Zip(error) => Some(error),
+            ArchiveError::NotFound(_) => None,
+            ArchiveError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl From<ZipError> for ArchiveError {
+    fn from(value: ZipError) -> Self {
+        Self::Zip(value)
+    }
+}
+
+/// A replay in a [`ReplayArchive`], as listed by [`ReplayArchive::entries`].
+#[derive(Debug)]
+pub struct ArchiveEntry {
+    /// The path of the replay in the archive, e.g. `replay/2024_01_01_12_00_00.rep`.
+    pub name: String,
+    /// The size of the replay file, as the game saves it.
+    pub size: u64,
+    /// The metadata of the replay, or why it couldn't be parsed. The inputs aren't decoded.
+    pub metadata: Result<GameReplayMetadata, ReplayParseError>,
+}
+
+/// A zip archive of the game's data, e.g. from its data export, to read the replays from.
+///
+/// The replays are the `.rep` files in the archive, which the game puts in its `replay` folder.
+/// The index of them it writes alongside isn't needed, so it isn't read. The replays are
+/// only inflated when they're read, one at a time.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use techmino_replay_toolkit::ReplayArchive;
+///
+/// let mut archive = ReplayArchive::open(File::open("export.zip")?)?;
+/// for name in archive.names().to_vec() {
+///     let replay = archive.get(&name)?;
+///     println!("{name}: {} inputs", replay.inputs.len());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ReplayArchive<R> {
+    zip: ZipArchive<R>,
+    /// The names of the replays, sorted.
+    names: Vec<String>,
+}
+
+impl<R: Read + Seek> ReplayArchive<R> {
+    /// Opens a zip archive, reading the list of the files in it.
+    pub fn open(reader: R) -> Result<ReplayArchive<R>, ArchiveError> {
+        let zip = ZipArchive::new(reader)?;
+
+        let mut names: Vec<String> = zip
+            .file_names()
+            .filter(|name| match name.rsplit_once('.') {
+                Some((_, extension)) => !name.ends_with('/') && extension.eq_ignore_ascii_case("rep"),
+                None => false,
+            })
+            .map(String::from)
+            .collect();
+        names.sort();
+
+        Ok(ReplayArchive { zip, names })
+    }
+
+    /// The names of the replays in the archive, i.e. their paths in it, sorted.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Lists the replays in the archive with their sizes and metadata, sorted by name.
+    ///
+    /// Every replay is read for its metadata, but the inputs aren't decoded. Fails if the archive
+    /// can't be read, but not if a replay can't be parsed, which is reported in its entry.
+    pub fn entries(&mut self) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+        let names = self.names.clone();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let data = self.read(&name)?;
+                let metadata = LazyReplayData::try_from_compressed(&data, None).map(|replay| replay.metadata);
+
+                Ok(ArchiveEntry { name, size: data.len() as u64, metadata })
+            })
+            .collect()
+    }
+
+    /// Reads a replay from the archive without parsing it, i.e. the contents of its `.rep` file.
+    pub fn read(&mut self, name: &str) -> Result<Vec<u8>, ArchiveError> {
+        if self.names.binary_search_by(|other| other.as_str().cmp(name)).is_err() {
+            return Err(ArchiveError::NotFound(name.into()));
+        }
+
+        let mut file = self.zip.by_name(name)?;
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data).map_err(ZipError::Io)?;
+
+        Ok(data)
+    }
+
+    /// Reads and parses a replay from the archive.
+    pub fn get(&mut self, name: &str) -> Result<GameReplayData, ArchiveError> {
+        let data = self.read(name)?;
+
+        GameReplayData::try_from_compressed(&data, None).map_err(ArchiveError::Parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use crate::fixtures;
+
+    /// A zip archive like the game's data export, with two of the fixtures as replays.
+    fn export_zip() -> (Vec<u8>, Vec<GameReplayData>) {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut replays = Vec::new();
+        let options = SimpleFileOptions::default();
+
+        for fixture in fixtures::all().into_iter().filter(|fixture| fixture.name != "huge") {
+            let replay = fixture.expected.unwrap();
+            zip.start_file(format!("replay/{}.rep", fixture.name), options).unwrap();
+            zip.write_all(&replay.serialize_to_compressed(None).unwrap()).unwrap();
+            replays.push(replay);
+        }
+
+        zip.add_directory("replay/", options).unwrap();
+        zip.start_file("replay/broken.REP", options).unwrap();
+        zip.write_all(b"not a replay").unwrap();
+        zip.start_file("conf/settings", options).unwrap();
+        zip.write_all(b"not a replay either").unwrap();
+
+        (zip.finish().unwrap().into_inner(), replays)
+    }
+
+    #[test]
+    fn test_read_archive() {
+        let (zip, replays) = export_zip();
+        let mut archive = ReplayArchive::open(Cursor::new(zip)).unwrap();
+        assert_eq!(archive.names(), ["replay/broken.REP", "replay/earlyinput.rep", "replay/someinputs.rep"]);
+
+        assert_eq!(archive.get("replay/someinputs.rep").unwrap(), replays[1]);
+        assert!(matches!(archive.get("replay/broken.REP"), Err(ArchiveError::Parse(_))));
+        for name in ["conf/settings", "replay/missing.rep"] {
+            assert!(matches!(archive.get(name), Err(ArchiveError::NotFound(missing)) if missing == name));
+        }
+
+        let entries = archive.entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].metadata.is_err());
+        assert_eq!(entries[1].metadata.as_ref().unwrap(), &replays[0].metadata);
+        assert_eq!(entries[1].size, replays[0].serialize_to_compressed(None).unwrap().len() as u64);
+
+        let error = ReplayArchive::open(Cursor::new(b"not a zip".to_vec())).unwrap_err();
+        assert!(matches!(error, ArchiveError::Zip(_)));
+        assert!(error.to_string().starts_with("Couldn't read the zip archive: "), "{error}");
+    }
+}
@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+use crate::types::*;
+use crate::ParseOptions;
+
+/// Parses many compressed replays, e.g. the contents of `.rep` files, one after another.
+///
+/// The results are in the same order as the inputs.
+/// To parse the replays on multiple threads, see [`par_parse_many`] (requires the `rayon` feature).
+pub fn parse_many<I>(inputs: I, options: &ParseOptions) -> Vec<Result<GameReplayData, ReplayParseError>>
+where
+    I: IntoIterator<Item = Vec<u8>>,
+{
+    inputs
+        .into_iter()
+        .map(|data| GameReplayData::try_from_compressed_with(&data, options))
+        .collect()
+}
+
+/// Parses many compressed replays, e.g. the contents of `.rep` files, in parallel using
+/// [`rayon`](https://docs.rs/rayon)'s global thread pool.
+///
+/// The results are in the same order as the inputs.
+#[cfg(feature = "rayon")]
+pub fn par_parse_many<I>(inputs: I, options: &ParseOptions) -> Vec<Result<GameReplayData, ReplayParseError>>
+where
+    I: rayon::iter::IntoParallelIterator<Item = Vec<u8>>,
+    I::Iter: rayon::iter::IndexedParallelIterator,
+{
+    use rayon::iter::ParallelIterator;
+
+    inputs
+        .into_par_iter()
+        .map(|data| GameReplayData::try_from_compressed_with(&data, options))
+        .collect()
+}
+
+/// Parses every `.rep` file in a directory, e.g. the game's `replays` directory.
+///
+/// Other files and subdirectories are skipped. The results are sorted by path.
+/// With the `rayon` feature, the replays are parsed in parallel.
+///
+/// Fails if the directory or one of the replay files can't be read.
+#[cfg(feature = "std")]
+pub fn parse_replay_dir(
+    path: impl AsRef<std::path::Path>,
+    options: &ParseOptions,
+) -> std::io::Result<Vec<(std::path::PathBuf, Result<GameReplayData, ReplayParseError>)>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_file() && path.extension().is_some_and(|ext| ext == "rep") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let contents = paths.iter().map(std::fs::read).collect::<std::io::Result<Vec<_>>>()?;
+
+    #[cfg(feature = "rayon")]
+    let results = par_parse_many(contents, options);
+    #[cfg(not(feature = "rayon"))]
+    let results = parse_many(contents, options);
+
+    Ok(paths.into_iter().zip(results).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_many_order() {
+        let mut replays = Vec::new();
+        for frame in 0..20 {
+            let mut data = GameReplayData::default();
+            data.metadata.version = "V0.17.22".into();
+            data.inputs.push(GameInputEvent { frame, kind: InputEventKind::Press, key: InputEventKey::Hold });
+
+            replays.push(data.serialize_to_compressed(None).unwrap());
+        }
+        replays.insert(7, b"not a replay".to_vec());
+
+        let results = parse_many(replays.clone(), &ParseOptions::new());
+        assert_eq!(results.len(), 21);
+        assert!(matches!(results[7], Err(ReplayParseError::ZlibDecompressError(_))));
+
+        let frames: Vec<u64> = results.iter().flatten().map(|data| data.inputs[0].frame).collect();
+        assert_eq!(frames, (0..20).collect::<Vec<_>>());
+
+        #[cfg(feature = "rayon")]
+        {
+            let parallel = par_parse_many(replays, &ParseOptions::new());
+            let parallel: Vec<_> = parallel.into_iter().map(Result::ok).collect();
+            assert_eq!(parallel, results.into_iter().map(Result::ok).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! The `trt diff` subcommand.
+
+use std::fmt::Write;
+
+use techmino_replay_toolkit::{GameInputEvent, GameReplayData, InputDivergence, MetadataDiff, ReplayDiff};
+
+/// How many events to show before and after the first divergence.
+const CONTEXT: usize = 3;
+
+/// Which parts of the replays to compare.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffScope {
+    All,
+    Metadata,
+    Inputs,
+}
+
+/// The differences between replays `a` and `b`, or `None` if they're the same within the scope.
+pub fn report(a: &GameReplayData, b: &GameReplayData, scope: DiffScope) -> Option<String> {
+    let diff = a.diff(b);
+    let metadata_diff = a.metadata.diff(&b.metadata);
+
+    let metadata = scope != DiffScope::Inputs && !metadata_diff.is_empty();
+    let inputs = scope != DiffScope::Metadata && diff.first_divergence.is_some();
+    if !metadata && !inputs {
+        return None;
+    }
+
+    let mut report = String::new();
+
+    if scope != DiffScope::Inputs {
+        metadata_report(&mut report, &metadata_diff);
+    }
+    if scope == DiffScope::All {
+        report.push('\n');
+    }
+    if scope != DiffScope::Metadata {
+        inputs_report(&mut report, a, b, &diff);
+    }
+
+    Some(report)
+}
+
+/// Lists each metadata field that differs, like `setting.das: 8 -> 10`.
+fn metadata_report(report: &mut String, differences: &[MetadataDiff]) {
+    match differences.len() {
+        0 => return report.push_str("Metadata: identical\n"),
+        1 => report.push_str("Metadata: 1 difference\n"),
+        count => writeln!(report, "Metadata: {count} differences").unwrap(),
+    }
+
+    for difference in differences {
+        writeln!(report, "  {difference}").unwrap();
+    }
+}
+
+/// Describes the first divergence of the inputs, with the events around it side by side.
+fn inputs_report(report: &mut String, a: &GameReplayData, b: &GameReplayData, diff: &ReplayDiff) {
+    let Some(InputDivergence { index, frame }) = diff.first_divergence else {
+        return report.push_str("Inputs: identical\n");
+    };
+
+    writeln!(
+        report,
+        "Inputs: first divergence at index {index} (frame {frame}), {} added, {} removed",
+        diff.added_events, diff.removed_events
+    )
+    .unwrap();
+    if diff.ordering_only {
+        report.push_str("  Only the order of events on the same frame differs\n");
+    }
+
+    let event = |inputs: &[GameInputEvent], i: usize| {
+        inputs.get(i).map_or("-".to_string(), |event| {
+            format!("{} {:?} {}", event.frame, event.kind, event.key.name())
+        })
+    };
+
+    writeln!(report, "\n    {:>7}  {:<28} b", "index", "a").unwrap();
+
+    let end = (index + CONTEXT + 1).min(a.inputs.len().max(b.inputs.len()));
+    for i in index.saturating_sub(CONTEXT)..end {
+        let marker = if i == index { '>' } else { ' ' };
+        let (ours, theirs) = (event(&a.inputs, i), event(&b.inputs, i));
+        writeln!(report, "  {marker} {i:>7}  {ours:<28} {theirs}").unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+//! The `trt dump` subcommand.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde_json::Value;
+use techmino_replay_toolkit::{annotate_input_bytes, decode_base64_replay, decompress_replay};
+use techmino_replay_toolkit::{InputParseMode, InputValueMeaning, RawMetadata, SplitReplay};
+
+use crate::{debug, detect_format, Format};
+
+/// Undoes the base64 and compression of a replay, as far as possible.
+///
+/// If the data is cut off or corrupted, the part before the damage is returned,
+/// with a warning on stderr.
+pub fn raw_bytes(path: &Path, data: &[u8], format: Format) -> Result<Vec<u8>, String> {
+    let format = match format {
+        Format::Replay => detect_format(path, data)?,
+        format => format,
+    };
+
+    let compressed = match format {
+        Format::Raw => return Ok(data.to_vec()),
+        Format::Base64 => {
+            let text = std::str::from_utf8(data).map_err(debug)?;
+            let path = path.display();
+            decode_base64_replay(text).map_err(|error| format!("'{path}' isn't valid base64: {error}"))?
+        }
+        _ => data.to_vec(),
+    };
+
+    match decompress_replay(&compressed) {
+        Ok(raw) => Ok(raw),
+        Err(error) => {
+            let decompressed = error.output.len();
+            eprintln!("warning: Failed to decompress after {decompressed} bytes: {:?}", error.kind);
+            Ok(error.output)
+        }
+    }
+}
+
+/// A hex dump, 16 bytes per line, with the offset on the left and the ASCII on the right.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        write!(dump, "{:08x} ", line * 16).unwrap();
+        for i in 0..16 {
+            // An extra space between the two halves
+            let gap = if i == 8 { " " } else { "" };
+            match chunk.get(i) {
+                Some(byte) => write!(dump, "{gap} {byte:02x}").unwrap(),
+                None => write!(dump, "{gap}   ").unwrap(),
+            }
+        }
+
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+            .collect();
+        writeln!(dump, "  |{ascii}|").unwrap();
+    }
+
+    dump
+}
+
+/// The metadata JSON, pretty-printed.
+pub fn metadata(raw: &[u8]) -> Result<String, String> {
+    let split = SplitReplay::from_raw(raw).map_err(|error| error.to_string())?;
+    let metadata: Value = serde_json::from_slice(split.metadata_bytes)
+        .map_err(|error| format!("The metadata isn't valid JSON: {error}"))?;
+
+    Ok(serde_json::to_string_pretty(&metadata).map_err(debug)? + "\n")
+}
+
+/// An annotated decode of the input data, one line per value,
+/// with the first malformed value marked with `!`.
+pub fn inputs(raw: &[u8], mode: Option<InputParseMode>) -> Result<String, String> {
+    let split = SplitReplay::from_raw(raw).map_err(|error| error.to_string())?;
+
+    let version = RawMetadata(split.metadata_bytes.to_vec()).version().map(str::to_string);
+    let mode = mode.or_else(|| version.as_deref().and_then(InputParseMode::try_infer_from_version));
+    let Some(mode) = mode else {
+        let version = version.as_deref().unwrap_or("missing");
+        let message = format!("Can't tell how the inputs are timed from the version '{version}'");
+        return Err(message + ", choose it with --mode");
+    };
+
+    let mut dump = format!("{mode:?} timing, {} bytes\n", split.input_bytes.len());
+    let annotations = annotate_input_bytes(split.input_bytes, mode);
+
+    for annotation in &annotations {
+        let marker = match annotation.meaning {
+            InputValueMeaning::Malformed(_) => '!',
+            _ => ' ',
+        };
+
+        writeln!(dump, "{marker} {:>8}  {annotation}", annotation.offset).unwrap();
+    }
+
+    let last = annotations.last();
+    if let Some(last) = last.filter(|last| matches!(last.meaning, InputValueMeaning::Malformed(_))) {
+        let (index, offset) = (last.index, last.offset);
+        let unread = split.input_bytes.len() - offset - last.bytes.len();
+        writeln!(dump, "\nMalformed value {index} at byte {offset}, {unread} bytes left after it").unwrap();
+    }
+
+    Ok(dump)
+}
@@ -0,0 +1,84 @@
+//! The `trt edit` subcommand.
+
+use clap::Args;
+use serde_json::Value;
+use techmino_replay_toolkit::{GameReplayData, InputEventKey, KeyParseError};
+
+use crate::debug;
+
+/// The changes to make to a replay, in the order they're applied.
+#[derive(Args)]
+pub struct Edits {
+    /// Removes the inputs after a frame, releasing the keys still held on it.
+    #[arg(long, value_name = "FRAME")]
+    trim_after: Option<u64>,
+    /// Shifts every input by a number of frames, e.g. `+60` or `-60`.
+    #[arg(long, value_name = "FRAMES", allow_hyphen_values = true)]
+    offset: Option<i64>,
+    /// Replaces a key with another, e.g. `MoveLeft=MoveRight`. Can be repeated;
+    /// the replacements are made at the same time, so two keys can be swapped.
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_remap)]
+    remap: Vec<(InputEventKey, InputEventKey)>,
+    /// Sets a metadata field, e.g. `seed=42` or `note=hello`. Can be repeated.
+    ///
+    /// The value is read as JSON if it is valid JSON, or as a string otherwise.
+    /// Fields that aren't part of the standard metadata are kept as nonstandard metadata.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_assignment)]
+    set_meta: Vec<(String, Value)>,
+    /// Sets the player's name.
+    #[arg(long, value_name = "NAME")]
+    set_player: Option<String>,
+}
+
+impl Edits {
+    /// Applies the edits in the order of the fields, i.e. in the order of `trt edit --help`.
+    pub fn apply(&self, replay: &mut GameReplayData) -> Result<(), String> {
+        if let Some(frame) = self.trim_after {
+            replay.truncate_at_frame(frame);
+        }
+
+        if let Some(delta) = self.offset {
+            replay.offset_frames(delta).map_err(|error| format!("Can't offset the inputs: {error:?}"))?;
+        }
+
+        if !self.remap.is_empty() {
+            replay.remap_keys(|key| {
+                self.remap.iter().find(|(from, _)| *from == key).map_or(key, |&(_, to)| to)
+            });
+        }
+
+        if !self.set_meta.is_empty() {
+            let mut metadata = serde_json::to_value(&replay.metadata).map_err(debug)?;
+            for (key, value) in &self.set_meta {
+                metadata[key] = value.clone();
+            }
+
+            replay.metadata = serde_json::from_value(metadata)
+                .map_err(|error| format!("Can't set the metadata: {error}"))?;
+        }
+
+        if let Some(player) = &self.set_player {
+            replay.metadata.player = player.clone();
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the key with a name or label, ignoring case, e.g. `HardDrop` or `hard drop`.
+fn parse_key(name: &str) -> Result<InputEventKey, String> {
+    name.parse().map_err(|error: KeyParseError| error.to_string())
+}
+
+fn parse_remap(remap: &str) -> Result<(InputEventKey, InputEventKey), String> {
+    let (from, to) = remap.split_once('=').ok_or("Expected FROM=TO")?;
+
+    Ok((parse_key(from.trim())?, parse_key(to.trim())?))
+}
+
+fn parse_assignment(assignment: &str) -> Result<(String, Value), String> {
+    let (key, value) = assignment.split_once('=').ok_or("Expected KEY=VALUE")?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+    Ok((key.to_string(), value))
+}
@@ -0,0 +1,44 @@
+//! The `trt --interactive` mode, which reads one pasted replay after another.
+
+use std::io::{self, BufRead};
+
+use techmino_replay_toolkit::GameReplayData;
+
+const PROMPT: &str = "Paste a replay string below, then press Enter (Ctrl-D to quit):";
+
+/// Reads replays from stdin until it's closed, printing a summary of each one.
+///
+/// Replays can span several lines, e.g. when they were wrapped when copying them. The lines are
+/// collected until they make a valid replay, or until an empty line, which reports the error.
+pub fn run() -> Result<(), String> {
+    let mut lines = io::stdin().lock().lines();
+    let mut pasted = String::new();
+
+    eprintln!("{PROMPT}");
+
+    loop {
+        let line = match lines.next() {
+            Some(line) => line.map_err(|error| format!("Failed to read stdin: {error}"))?,
+            // Ctrl-D or the end of piped input
+            None if pasted.trim().is_empty() => return Ok(()),
+            None => String::new(),
+        };
+
+        if line.trim().is_empty() && pasted.trim().is_empty() {
+            continue;
+        }
+
+        pasted.push_str(&line);
+        pasted.push('\n');
+
+        match GameReplayData::try_from_base64(&pasted, None) {
+            Ok(replay) => println!("{}", replay.summary()),
+            // Wait for the rest of a wrapped replay
+            Err(_) if !line.trim().is_empty() => continue,
+            Err(error) => eprintln!("error: That isn't a valid replay: {error}"),
+        }
+
+        pasted.clear();
+        eprintln!("{PROMPT}");
+    }
+}
@@ -0,0 +1,136 @@
+//! The `trt list` subcommand, and the `@N` references to the replays it lists.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use techmino_replay_toolkit::{locate_replay_dir, replay_dir_candidates, GameReplayMetadata, LazyReplayData};
+
+use crate::{debug, detect_format, read_input, Format};
+
+/// A replay in the replay directory.
+pub struct Listing {
+    pub path: PathBuf,
+    pub metadata: GameReplayMetadata,
+}
+
+/// The file name of a replay that couldn't be read, and why.
+pub type Unreadable = (String, String);
+
+/// The replay directory given with `--replay-dir`, or else the game's.
+pub fn replay_dir(overridden: Option<&Path>) -> Result<PathBuf, String> {
+    if let Some(dir) = overridden {
+        return Ok(dir.to_path_buf());
+    }
+
+    locate_replay_dir().ok_or_else(|| {
+        let candidates: Vec<String> =
+            replay_dir_candidates().iter().map(|path| format!("\n  {}", path.display())).collect();
+
+        let candidates = candidates.concat();
+        format!("Can't find the game's replay directory, choose it with --replay-dir. Looked in:{candidates}")
+    })
+}
+
+/// Reads the metadata of every `.rep` file in the directory, newest first.
+///
+/// Also returns the replays that couldn't be read.
+pub fn list(dir: &Path) -> Result<(Vec<Listing>, Vec<Unreadable>), String> {
+    let entries = fs::read_dir(dir).map_err(|error| format!("Failed to read '{}': {error}", dir.display()))?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("rep")))
+        .collect();
+    paths.sort();
+
+    let mut listings = Vec::new();
+    let mut failures = Vec::new();
+
+    for path in paths {
+        match read_metadata(&path) {
+            Ok(metadata) => listings.push(Listing { path, metadata }),
+            Err(reason) => failures.push((file_name(&path), reason)),
+        }
+    }
+
+    // The game writes dates with the largest unit first, so they sort as strings.
+    // Stable, so replays with the same date stay in path order
+    listings.sort_by(|a, b| b.metadata.date.cmp(&a.metadata.date));
+
+    Ok((listings, failures))
+}
+
+/// Parses only the metadata of a replay, leaving the inputs undecoded.
+fn read_metadata(path: &Path) -> Result<GameReplayMetadata, String> {
+    let data = read_input(path)?;
+
+    let replay = match detect_format(path, &data)? {
+        Format::Base64 => {
+            let text = std::str::from_utf8(&data).map_err(debug)?;
+            LazyReplayData::try_from_base64(text, None)
+        }
+        Format::Raw => LazyReplayData::try_from_raw(&data, None),
+        _ => LazyReplayData::try_from_compressed(&data, None),
+    };
+
+    replay.map(|replay| replay.metadata).map_err(|error| format!("Not a valid replay: {error}"))
+}
+
+/// Resolves a reference like `@3` to the path of the third replay listed by `trt list`.
+///
+/// Returns `None` if the path isn't a reference.
+pub fn resolve(reference: &Path, replay_dir: Option<&Path>) -> Option<Result<PathBuf, String>> {
+    let index: usize = reference.to_str()?.strip_prefix('@')?.parse().ok()?;
+
+    let resolved = self::replay_dir(replay_dir).and_then(|dir| {
+        let (listings, _) = list(&dir)?;
+
+        match index.checked_sub(1).and_then(|index| listings.into_iter().nth(index)) {
+            Some(listing) => Ok(listing.path),
+            None => Err(format!("There's no replay @{index} in '{}', see `trt list`", dir.display())),
+        }
+    });
+
+    Some(resolved)
+}
+
+/// Formats the listings as a table numbered from 1, ending with a newline.
+pub fn to_table(listings: &[Listing]) -> String {
+    let rows: Vec<[String; 6]> = listings
+        .iter()
+        .enumerate()
+        .map(|(i, Listing { path, metadata })| {
+            [
+                (i + 1).to_string(),
+                metadata.date.clone(),
+                metadata.mode.clone(),
+                metadata.player.clone(),
+                metadata.version.clone(),
+                file_name(path),
+            ]
+        })
+        .collect();
+
+    let header = ["#", "Date", "Mode", "Player", "Version", "File"].map(String::from);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| rows.iter().chain([&header]).map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    let mut table = String::new();
+    for row in [&header].into_iter().chain(&rows) {
+        // The index is right-aligned, the last column isn't padded
+        write!(table, "{:>1$}", row[0], widths[0]).unwrap();
+        for (cell, width) in row[1..5].iter().zip(&widths[1..]) {
+            write!(table, "  {cell:<width$}").unwrap();
+        }
+        writeln!(table, "  {}", row[5]).unwrap();
+    }
+
+    table
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
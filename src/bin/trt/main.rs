@@ -0,0 +1,947 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use diff::DiffScope;
+use edit::Edits;
+use stats::StatsTotals;
+use techmino_replay_toolkit::analysis::StatsAccumulator;
+use techmino_replay_toolkit::verify::{inputs_equal_with, VerifyOptions};
+use techmino_replay_toolkit::{detect_replay_encoding, AnonymizeOptions, GameReplayData, ReplayEncoding};
+use techmino_replay_toolkit::{decode_base64_replay, export_schema, InputParseMode, ReplayArchive, SizeReport};
+
+mod diff;
+mod dump;
+mod edit;
+mod interactive;
+mod list;
+mod stats;
+
+const BANNER: &str = concat!(
+    "\
+    ╭~~~~~~~~~~~~~╮  \n\
+    ┊ ▀▀█▀▀    █  ┊  Techmino Replay Toolkit\n\
+    ┊   █  █▀█ █▀ ┊  v",
+    env!("CARGO_PKG_VERSION"),
+    "\n\
+    ┊   █  █   █▄ ┊  https://github.com/techmino-hub/techmino-replay-toolkit-rs\n\
+    ╰~~~~~~~~~~~~~╯  \n\
+    This program and library is licensed under the GNU General Public License version 3.\n\
+    For more information, see <https://www.gnu.org/licenses/>.",
+);
+
+/// Tools for Techmino replays.
+///
+/// Replays can be compressed like the game saves them, base64 like the game exports them,
+/// or raw and uncompressed, which is detected from the data. `-` reads from stdin instead of a file,
+/// and `@N` reads the Nth replay listed by `trt list`.
+///
+/// Without a command, reads a replay from stdin until it's closed and writes it as JSON,
+/// like `trt decode -`.
+#[derive(Parser)]
+#[command(name = "trt", version, before_long_help = BANNER)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Reads one pasted replay after another, printing a summary of each, until stdin is closed.
+    #[arg(short, long)]
+    interactive: bool,
+    /// The directory `trt list` and `@N` read the replays from, instead of the game's.
+    #[arg(long, global = true)]
+    replay_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decodes a replay into a readable format.
+    Decode {
+        /// The replay, or `-` for stdin.
+        input: PathBuf,
+        /// How the replay is encoded, instead of detecting it.
+        #[arg(long, value_enum)]
+        from: Option<InputEncoding>,
+        /// The format to write.
+        #[arg(short, long, value_enum, default_value_t = DecodeFormat::Json)]
+        format: DecodeFormat,
+        /// Where to write the decoded replay, instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Encodes a replay written as JSON or RON into the game's format.
+    Encode {
+        /// The JSON or RON file, told apart by its extension, or `-` for JSON from stdin.
+        input: PathBuf,
+        /// The format to write.
+        #[arg(short, long, value_enum, default_value_t = EncodeFormat::B64)]
+        to: EncodeFormat,
+        /// Where to write the encoded replay, instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Prints the insides of a replay, e.g. to find out why it fails to parse.
+    ///
+    /// The data is decompressed as far as possible, even if it's cut off or corrupted.
+    Dump {
+        /// The replay, or `-` for stdin.
+        input: PathBuf,
+        /// How the replay is encoded, instead of detecting it.
+        #[arg(long, value_enum)]
+        from: Option<InputEncoding>,
+        /// What to print.
+        #[arg(short, long, value_enum, default_value_t = DumpStage::Inputs)]
+        stage: DumpStage,
+        /// How the inputs are timed, instead of inferring it from the game version.
+        #[arg(long, value_enum)]
+        mode: Option<TimingMode>,
+    },
+    /// Lists the replays in the game's replay directory, newest first.
+    ///
+    /// The numbers can be used instead of paths in the other commands, e.g. `trt info @1`.
+    List,
+    /// Prints a summary of a replay.
+    Info {
+        /// The replay, or `-` for stdin.
+        input: PathBuf,
+        /// How the replay is encoded, instead of detecting it.
+        #[arg(long, value_enum)]
+        from: Option<InputEncoding>,
+        /// Also lists this many of the first inputs.
+        #[arg(long, default_value_t = 0)]
+        inputs: usize,
+        /// Also prints the sizes of the metadata and input data, and how well the replay compresses.
+        ///
+        /// Replays that are compressed are measured as they are, others as they would be saved.
+        #[arg(long)]
+        sizes: bool,
+    },
+    /// Prints statistics about the inputs of replays.
+    ///
+    /// Durations and keys per second only count the time after the countdown,
+    /// and hold durations are in frames.
+    Stats {
+        /// The replays, directories of `.rep` files, or `-` for stdin.
+        ///
+        /// Replays in directories that can't be read are skipped with a warning.
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Prints the statistics as JSON instead of tables.
+        #[arg(long)]
+        json: bool,
+        /// Combines the statistics of all the replays, instead of one table per replay.
+        ///
+        /// Also counts the modes, versions and DAS and ARR settings of the replays,
+        /// and how their average keys per second are distributed.
+        #[arg(long)]
+        aggregate: bool,
+    },
+    /// Compares two replays, exiting with an error if they differ.
+    ///
+    /// Prints the metadata fields that differ and the events around the first input that differs.
+    Diff {
+        /// The first replay, or `-` for stdin.
+        a: PathBuf,
+        /// The second replay, or `-` for stdin.
+        b: PathBuf,
+        /// Only compares the inputs.
+        #[arg(long, conflicts_with = "metadata_only")]
+        inputs_only: bool,
+        /// Only compares the metadata.
+        #[arg(long)]
+        metadata_only: bool,
+    },
+    /// Checks that two replays press the same keys on the same frames, exiting with an error if not.
+    ///
+    /// Only the inputs are compared, e.g. a submitted TAS and its re-recording. Prints PASS, or FAIL
+    /// with the inputs of both replays on the first frame that differs.
+    Verify {
+        /// The first replay, or `-` for stdin.
+        a: PathBuf,
+        /// The second replay, or `-` for stdin.
+        b: PathBuf,
+        /// Ignores the inputs after this frame.
+        #[arg(long, value_name = "FRAME")]
+        until_frame: Option<u64>,
+        /// Ignores the inputs during the countdown.
+        #[arg(long)]
+        ignore_countdown: bool,
+        /// Ignores the order of inputs on the same frame.
+        #[arg(long)]
+        ignore_same_frame_order: bool,
+        /// Prints the result as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Edits the inputs and metadata of a replay.
+    ///
+    /// The edits are applied in the order they're listed here, whatever order they're given in,
+    /// so e.g. `--trim-after` refers to the frames before `--offset` shifts them.
+    Edit {
+        /// The replay to edit.
+        input: PathBuf,
+        #[command(flatten)]
+        edits: Edits,
+        /// Where to write the edited replay, in the format of its extension like `trt convert`.
+        #[arg(short, long, required_unless_present = "in_place")]
+        out: Option<PathBuf>,
+        /// Overwrites the input file, in the same format, instead of writing to `--out`.
+        #[arg(long, conflicts_with = "out")]
+        in_place: bool,
+    },
+    /// Removes the personal data from a replay before sharing it.
+    ///
+    /// Replaces the player's name, removes the time of day from the date, and removes
+    /// the nonstandard metadata and settings, which mods sometimes fill with device identifiers.
+    Anonymize {
+        /// The replay, or `-` for stdin.
+        input: PathBuf,
+        /// Where to write the replay, in the format of its extension like `trt convert`,
+        /// instead of writing it to stdout as base64.
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// The name that replaces the player's name.
+        #[arg(long, default_value = "Anonymous")]
+        player: String,
+        /// Removes the whole date.
+        #[arg(long)]
+        blank_date: bool,
+        /// Sets the seed to 0, which changes the pieces when the game plays the replay.
+        #[arg(long)]
+        clear_seed: bool,
+    },
+    /// Converts a replay between formats, inferred from the file extensions.
+    ///
+    /// The extensions are `.rep` (compressed, or detected when reading), `.b64` or `.b64.rep` (base64),
+    /// `.raw` (uncompressed), `.json`, `.ron` and `.txt` (the text format).
+    /// `.jsonl` writes the inputs as JSON Lines.
+    Convert {
+        /// The file to read.
+        input: PathBuf,
+        /// The file to write.
+        output: PathBuf,
+    },
+    /// Converts every `.rep` file in a directory, e.g. the game's `replays` directory,
+    /// or in a zip archive exported by the game.
+    ///
+    /// Replays that fail to convert are reported at the end, without stopping the others.
+    Batch {
+        /// The directory to read the replays from.
+        #[arg(required_unless_present = "from_zip", conflicts_with = "from_zip")]
+        dir: Option<PathBuf>,
+        /// Reads the replays from a zip archive exported by the game instead of a directory.
+        #[arg(long, value_name = "ZIP")]
+        from_zip: Option<PathBuf>,
+        /// The format to write.
+        #[arg(short, long, value_enum, default_value_t = BatchFormat::Json)]
+        to: BatchFormat,
+        /// The directory to write the converted replays to, created if it doesn't exist.
+        #[arg(short, long)]
+        out: PathBuf,
+        /// How many replays to convert at once. Defaults to the number of CPUs.
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+        /// Skips replays whose output file already exists.
+        #[arg(long)]
+        skip_existing: bool,
+    },
+    /// Prints the JSON Schema of the replay JSON that `trt decode` writes and `trt encode` reads.
+    Schema,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DecodeFormat {
+    /// The serde form of the replay, as JSON.
+    Json,
+    /// The inputs as JSON Lines, one object per input.
+    Jsonl,
+    /// The serde form of the replay, as RON.
+    Ron,
+    /// The plain-text format, with the metadata and one line per input.
+    Text,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EncodeFormat {
+    /// The base64 string the game imports and exports.
+    B64,
+    /// The compressed bytes the game saves as `.rep` files.
+    Rep,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InputEncoding {
+    /// The base64 string the game imports and exports.
+    B64,
+    /// The compressed bytes the game saves as `.rep` files.
+    Rep,
+    /// The uncompressed metadata JSON and input data.
+    Raw,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpStage {
+    /// The decompressed replay as a hex dump.
+    Decompressed,
+    /// The metadata JSON, pretty-printed.
+    Metadata,
+    /// Each value of the input data, with its bytes and meaning, up to the first malformed one.
+    Inputs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TimingMode {
+    /// Each input stores its frame, as in V0.17.22 and later.
+    Absolute,
+    /// Each input stores the frames since the previous input, as before V0.17.22.
+    Relative,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BatchFormat {
+    /// The serde form of the replay, as JSON, in `.json` files.
+    Json,
+    /// The inputs as JSON Lines, one object per input, in `.jsonl` files.
+    Jsonl,
+    /// The serde form of the replay, as RON, in `.ron` files.
+    Ron,
+    /// The plain-text format, in `.txt` files.
+    Text,
+    /// The base64 string the game imports and exports, in `.b64.rep` files.
+    B64,
+    /// The compressed bytes the game saves, in `.rep` files.
+    Rep,
+}
+
+/// Every format a replay can be read from or written to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// A replay in any of the encodings below, detected from the data.
+    Replay,
+    Base64,
+    Compressed,
+    Raw,
+    Json,
+    Jsonl,
+    Ron,
+    Text,
+}
+
+impl From<DecodeFormat> for Format {
+    fn from(format: DecodeFormat) -> Self {
+        match format {
+            DecodeFormat::Json => Format::Json,
+            DecodeFormat::Jsonl => Format::Jsonl,
+            DecodeFormat::Ron => Format::Ron,
+            DecodeFormat::Text => Format::Text,
+        }
+    }
+}
+
+impl From<EncodeFormat> for Format {
+    fn from(format: EncodeFormat) -> Self {
+        match format {
+            EncodeFormat::B64 => Format::Base64,
+            EncodeFormat::Rep => Format::Compressed,
+        }
+    }
+}
+
+impl From<InputEncoding> for Format {
+    fn from(encoding: InputEncoding) -> Self {
+        match encoding {
+            InputEncoding::B64 => Format::Base64,
+            InputEncoding::Rep => Format::Compressed,
+            InputEncoding::Raw => Format::Raw,
+        }
+    }
+}
+
+impl From<BatchFormat> for Format {
+    fn from(format: BatchFormat) -> Self {
+        match format {
+            BatchFormat::Json => Format::Json,
+            BatchFormat::Jsonl => Format::Jsonl,
+            BatchFormat::Ron => Format::Ron,
+            BatchFormat::Text => Format::Text,
+            BatchFormat::B64 => Format::Base64,
+            BatchFormat::Rep => Format::Compressed,
+        }
+    }
+}
+
+impl Format {
+    /// Infers the format of a file from its extension.
+    fn from_path(path: &Path) -> Result<Format, String> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_ascii_lowercase();
+        let (_, extension) = name.rsplit_once('.').unwrap_or_default();
+
+        match extension {
+            "rep" if name.ends_with(".b64.rep") => Ok(Format::Base64),
+            "rep" => Ok(Format::Replay),
+            "b64" => Ok(Format::Base64),
+            "raw" => Ok(Format::Raw),
+            "json" => Ok(Format::Json),
+            "jsonl" => Ok(Format::Jsonl),
+            "ron" => Ok(Format::Ron),
+            "txt" => Ok(Format::Text),
+            _ => Err(format!("Can't tell the format of '{}' from its extension", path.display())),
+        }
+    }
+
+    /// Infers the format to write a file in from its extension, which is compressed for `.rep` files.
+    fn for_output(path: &Path) -> Result<Format, String> {
+        match Format::from_path(path)? {
+            // The game saves compressed replays
+            Format::Replay => Ok(Format::Compressed),
+            format => Ok(format),
+        }
+    }
+
+    /// The extension of files in this format, the reverse of [`Format::from_path`].
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Replay | Format::Compressed => "rep",
+            Format::Base64 => "b64.rep",
+            Format::Raw => "raw",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Ron => "ron",
+            Format::Text => "txt",
+        }
+    }
+}
+
+impl Command {
+    /// The replays the command reads, which can be `@N` references to the replays of `trt list`.
+    fn inputs_mut(&mut self) -> Vec<&mut PathBuf> {
+        match self {
+            Command::Decode { input, .. }
+            | Command::Encode { input, .. }
+            | Command::Info { input, .. }
+            | Command::Dump { input, .. }
+            | Command::Edit { input, .. }
+            | Command::Anonymize { input, .. }
+            | Command::Convert { input, .. } => vec![input],
+            Command::Stats { inputs, .. } => inputs.iter_mut().collect(),
+            Command::Diff { a, b, .. } | Command::Verify { a, b, .. } => vec![a, b],
+            Command::List | Command::Batch { .. } | Command::Schema => Vec::new(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let Cli { command, interactive, replay_dir } = Cli::parse();
+
+    let result = match command {
+        Some(_) if interactive => {
+            let message = "--interactive can't be used with a command";
+            Cli::command().error(ErrorKind::ArgumentConflict, message).exit()
+        }
+        Some(command) => run(command, replay_dir.as_deref()),
+        None if interactive => interactive::run(),
+        None => decode_stdin(),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut command: Command, replay_dir: Option<&Path>) -> Result<(), String> {
+    for input in command.inputs_mut() {
+        if let Some(path) = list::resolve(input, replay_dir) {
+            *input = path?;
+        }
+    }
+
+    match command {
+        Command::Decode { input, from, format, output } => {
+            let replay = read_replay(&input, from.map_or(Format::Replay, Format::from))?;
+            write_output(output.as_deref(), &write_replay(&replay, format.into())?)
+        }
+        Command::Encode { input, to, output } => {
+            let format = match input.to_str() {
+                Some("-") => Format::Json,
+                _ => Format::from_path(&input)?,
+            };
+            if !matches!(format, Format::Json | Format::Ron) {
+                return Err(format!("'{}' isn't a JSON or RON file", input.display()));
+            }
+
+            let replay = read_replay(&input, format)?;
+            write_output(output.as_deref(), &write_replay(&replay, to.into())?)
+        }
+        Command::Dump { input, from, stage, mode } => {
+            let format = from.map_or(Format::Replay, Format::from);
+            let raw = dump::raw_bytes(&input, &read_input(&input)?, format)?;
+            let mode = mode.map(|mode| match mode {
+                TimingMode::Absolute => InputParseMode::Absolute,
+                TimingMode::Relative => InputParseMode::Relative,
+            });
+
+            let output = match stage {
+                DumpStage::Decompressed => dump::hex_dump(&raw),
+                DumpStage::Metadata => dump::metadata(&raw)?,
+                DumpStage::Inputs => dump::inputs(&raw, mode)?,
+            };
+
+            write_output(None, output.as_bytes())
+        }
+        Command::List => {
+            let dir = list::replay_dir(replay_dir)?;
+            let (listings, failures) = list::list(&dir)?;
+
+            match listings.is_empty() {
+                true => println!("No replays in '{}'", dir.display()),
+                false => print!("{}", list::to_table(&listings)),
+            }
+            for (name, reason) in failures {
+                eprintln!("warning: Skipped '{name}': {reason}");
+            }
+
+            Ok(())
+        }
+        Command::Info { input, from, inputs, sizes } => {
+            let format = from.map_or(Format::Replay, Format::from);
+            let data = read_input(&input)?;
+            let replay = parse_replay(&input, &data, format)?;
+            print!("{replay:.inputs$}");
+
+            if sizes {
+                print!("{}", size_report(&input, &data, format, &replay)?);
+            }
+            Ok(())
+        }
+        Command::Stats { inputs, json, aggregate } => {
+            let mut reports = Vec::new();
+            let mut totals = StatsTotals::default();
+            let mut combined = StatsAccumulator::new();
+
+            for input in &inputs {
+                let (paths, in_dir) = match input.is_dir() {
+                    true => (replay_files(input)?, true),
+                    false => (vec![input.clone()], false),
+                };
+
+                // One replay at a time, so directories of any size can be aggregated
+                for path in paths {
+                    let replay = match read_replay(&path, Format::Replay) {
+                        Ok(replay) => replay,
+                        Err(reason) if in_dir => {
+                            eprintln!("warning: Skipped '{}': {reason}", path.display());
+                            continue;
+                        }
+                        Err(reason) => return Err(reason),
+                    };
+
+                    if aggregate {
+                        totals.add(&replay);
+                        combined.add(&replay);
+                    } else {
+                        let mut stats = StatsTotals::default();
+                        stats.add(&replay);
+                        reports.push(stats.report(path.display().to_string()));
+                    }
+                }
+            }
+
+            if aggregate {
+                let mut report = totals.report("total".to_string());
+                report.aggregate = Some(combined.finish());
+                reports.push(report);
+            }
+
+            let output = match json {
+                true if aggregate => serde_json::to_string_pretty(&reports[0]).map_err(debug)? + "\n",
+                true => serde_json::to_string_pretty(&reports).map_err(debug)? + "\n",
+                false => reports.iter().map(|report| report.to_table()).collect::<Vec<_>>().join("\n"),
+            };
+
+            write_output(None, output.as_bytes())
+        }
+        Command::Diff { a, b, inputs_only, metadata_only } => {
+            let scope = match (inputs_only, metadata_only) {
+                (true, _) => DiffScope::Inputs,
+                (_, true) => DiffScope::Metadata,
+                _ => DiffScope::All,
+            };
+
+            match diff::report(&read_replay(&a, Format::Replay)?, &read_replay(&b, Format::Replay)?, scope) {
+                Some(report) => {
+                    print!("{report}");
+                    Err("The replays differ".to_string())
+                }
+                None => {
+                    println!("No differences");
+                    Ok(())
+                }
+            }
+        }
+        Command::Verify { a, b, until_frame, ignore_countdown, ignore_same_frame_order, json } => {
+            let options = VerifyOptions { ignore_countdown, until_frame, ignore_same_frame_order };
+            let (a, b) = (read_replay(&a, Format::Replay)?, read_replay(&b, Format::Replay)?);
+            let report = inputs_equal_with(&a, &b, options);
+
+            match json {
+                true => println!("{}", serde_json::to_string_pretty(&report).map_err(debug)?),
+                false => print!("{report}"),
+            }
+            match report.passed() {
+                true => Ok(()),
+                false => Err("The inputs differ".to_string()),
+            }
+        }
+        Command::Edit { input, edits, out, in_place } => {
+            let data = read_input(&input)?;
+            // Resolved here rather than when parsing, so the replay is written back in the same format
+            let format = match input.to_str() {
+                Some("-") => Format::Replay,
+                _ => Format::from_path(&input)?,
+            };
+            let format = match format {
+                Format::Replay => detect_format(&input, &data)?,
+                format => format,
+            };
+
+            let mut replay = parse_replay(&input, &data, format)?;
+            edits.apply(&mut replay)?;
+
+            let (output, format) = match out {
+                Some(out) if !in_place && same_file(&input, &out) => {
+                    let out = out.display();
+                    return Err(format!("Refusing to overwrite '{out}', pass --in-place to edit it in place"));
+                }
+                Some(out) => {
+                    let format = Format::for_output(&out)?;
+                    (out, format)
+                }
+                None if input.to_str() == Some("-") => return Err("Can't edit stdin in place".to_string()),
+                None => (input, format),
+            };
+
+            write_output(Some(&output), &write_replay(&replay, format)?)
+        }
+        Command::Anonymize { input, out, player, blank_date, clear_seed } => {
+            let mut replay = read_replay(&input, Format::Replay)?;
+            replay.anonymize(
+                AnonymizeOptions::new().player(player).blank_date(blank_date).clear_seed(clear_seed),
+            );
+
+            match out {
+                Some(out) if same_file(&input, &out) => {
+                    Err(format!("Refusing to overwrite '{}'", out.display()))
+                }
+                Some(out) => write_output(Some(&out), &write_replay(&replay, Format::for_output(&out)?)?),
+                None => write_output(None, &write_replay(&replay, Format::Base64)?),
+            }
+        }
+        Command::Convert { input, output } => {
+            let replay = read_replay(&input, Format::from_path(&input)?)?;
+            write_output(Some(&output), &write_replay(&replay, Format::for_output(&output)?)?)
+        }
+        Command::Batch { dir, from_zip, to, out, jobs, skip_existing } => {
+            let jobs = jobs.or_else(|| thread::available_parallelism().ok()).map_or(1, NonZeroUsize::get);
+            let source = match (dir, from_zip) {
+                (Some(dir), None) => BatchSource::Dir(dir),
+                (None, Some(zip)) => BatchSource::Zip(zip),
+                _ => unreachable!("clap requires exactly one of them"),
+            };
+            let outcomes = batch(&source, to.into(), &out, jobs, skip_existing)?;
+            print!("{}", batch_summary(&outcomes));
+
+            match outcomes.iter().any(|(_, outcome)| outcome.is_err()) {
+                true => Err("Some replays failed to convert".to_string()),
+                false => Ok(()),
+            }
+        }
+        Command::Schema => {
+            println!("{}", export_schema());
+            Ok(())
+        }
+    }
+}
+
+/// Writes the replay on stdin as JSON, the default when no command is given.
+fn decode_stdin() -> Result<(), String> {
+    let stdin = Path::new("-");
+    if io::stdin().is_terminal() {
+        eprintln!("Paste a replay, then press Enter and Ctrl-D (or see `trt --help`):");
+    }
+
+    let data = read_input(stdin)?;
+    if data.trim_ascii().is_empty() {
+        return Err("There's no replay on stdin, see `trt --help`".to_string());
+    }
+
+    let replay = parse_replay(stdin, &data, Format::Replay)?;
+    write_output(None, &write_replay(&replay, Format::Json)?)
+}
+
+/// What happened to a replay in a batch, with the reason if it failed.
+type BatchOutcome = Result<Converted, String>;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Converted {
+    Written,
+    Skipped,
+}
+
+/// Where the replays of a batch are read from.
+enum BatchSource {
+    /// The `.rep` files in a directory.
+    Dir(PathBuf),
+    /// The replays in a zip archive exported by the game.
+    Zip(PathBuf),
+}
+
+/// Converts every replay of `source` into `out`, returning the file names and outcomes sorted by name.
+fn batch(
+    source: &BatchSource,
+    format: Format,
+    out: &Path,
+    jobs: usize,
+    skip_existing: bool,
+) -> Result<Vec<(String, BatchOutcome)>, String> {
+    let (paths, archive) = match source {
+        BatchSource::Dir(dir) => (replay_files(dir)?, None),
+        BatchSource::Zip(zip) => {
+            let file =
+                fs::File::open(zip).map_err(|error| format!("Failed to read '{}': {error}", zip.display()))?;
+            let archive = ReplayArchive::open(file).map_err(|error| format!("'{}': {error}", zip.display()))?;
+
+            // The paths are the ones in the archive, which are read from it
+            (archive.names().iter().map(PathBuf::from).collect(), Some(Mutex::new(archive)))
+        }
+    };
+
+    fs::create_dir_all(out).map_err(|error| format!("Failed to create '{}': {error}", out.display()))?;
+
+    let convert = |path: &Path| -> BatchOutcome {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let output = out.join(format!("{stem}.{}", format.extension()));
+        if skip_existing && output.exists() {
+            return Ok(Converted::Skipped);
+        }
+
+        let replay = match &archive {
+            Some(archive) => {
+                let name = path.to_string_lossy();
+                let data = archive.lock().unwrap().read(&name).map_err(|error| error.to_string())?;
+                parse_replay(path, &data, Format::Replay)?
+            }
+            None => read_replay(path, Format::Replay)?,
+        };
+        write_output(Some(&output), &write_replay(&replay, format)?)?;
+
+        Ok(Converted::Written)
+    };
+
+    // Each worker takes the next replay until there are none left
+    let next = AtomicUsize::new(0);
+    let mut outcomes: Vec<(usize, BatchOutcome)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(paths.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outcomes = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else { break outcomes };
+                        outcomes.push((index, convert(path)));
+                    }
+                })
+            })
+            .collect();
+
+        workers.into_iter().flat_map(|worker| worker.join().expect("A batch worker panicked")).collect()
+    });
+    outcomes.sort_by_key(|(index, _)| *index);
+
+    let names = paths.iter().map(|path| path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+    Ok(names.zip(outcomes.into_iter().map(|(_, outcome)| outcome)).collect())
+}
+
+/// A table of how many replays in a batch were converted, skipped and failed, followed by the failures.
+fn batch_summary(outcomes: &[(String, BatchOutcome)]) -> String {
+    let count = |wanted: fn(&BatchOutcome) -> bool| {
+        outcomes.iter().filter(|(_, outcome)| wanted(outcome)).count()
+    };
+
+    let mut summary = format!(
+        "converted  {}\n\
+         skipped    {}\n\
+         failed     {}\n",
+        count(|outcome| outcome == &Ok(Converted::Written)),
+        count(|outcome| outcome == &Ok(Converted::Skipped)),
+        count(BatchOutcome::is_err),
+    );
+
+    for (name, outcome) in outcomes {
+        if let Err(reason) = outcome {
+            summary.push_str(&format!("\nfailed: {name}\n  {reason}\n"));
+        }
+    }
+
+    summary
+}
+
+/// Reads a file, or stdin if the path is `-`.
+fn read_input(path: &Path) -> Result<Vec<u8>, String> {
+    if path.to_str() == Some("-") {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data).map_err(|error| format!("Failed to read stdin: {error}"))?;
+
+        return Ok(data);
+    }
+
+    fs::read(path).map_err(|error| format!("Failed to read '{}': {error}", path.display()))
+}
+
+/// Writes to a file, or stdout if there's no path.
+fn write_output(path: Option<&Path>, data: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => {
+            fs::write(path, data).map_err(|error| format!("Failed to write '{}': {error}", path.display()))
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(data)
+                .and_then(|()| stdout.flush())
+                .map_err(|error| format!("Failed to write to stdout: {error}"))
+        }
+    }
+}
+
+/// The `.rep` files in a directory, sorted by path.
+fn replay_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|error| format!("Failed to read '{}': {error}", dir.display()))?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("rep")))
+        .collect();
+    paths.sort();
+
+    Ok(paths)
+}
+
+fn read_replay(path: &Path, format: Format) -> Result<GameReplayData, String> {
+    parse_replay(path, &read_input(path)?, format)
+}
+
+/// Detects how a replay is encoded, as one of [`Format::Base64`], [`Format::Compressed`] or [`Format::Raw`].
+fn detect_format(path: &Path, data: &[u8]) -> Result<Format, String> {
+    match detect_replay_encoding(data) {
+        ReplayEncoding::Base64 => Ok(Format::Base64),
+        ReplayEncoding::Compressed => Ok(Format::Compressed),
+        ReplayEncoding::Raw => Ok(Format::Raw),
+        ReplayEncoding::Unknown => {
+            let path = path.display();
+            Err(format!("Can't tell how '{path}' is encoded, choose the encoding with --from"))
+        }
+    }
+}
+
+fn parse_replay(path: &Path, data: &[u8], format: Format) -> Result<GameReplayData, String> {
+    let text = || std::str::from_utf8(data).map_err(debug);
+    let format = match format {
+        Format::Replay => detect_format(path, data)?,
+        format => format,
+    };
+
+    let replay = match format {
+        // The Display form of replay errors has where in the replay it went wrong
+        Format::Base64 => GameReplayData::try_from_base64(text()?, None).map_err(|error| error.to_string()),
+        // Detected replays have been resolved to their encoding above
+        Format::Replay | Format::Compressed => {
+            GameReplayData::try_from_compressed(data, None).map_err(|error| error.to_string())
+        }
+        Format::Raw => GameReplayData::try_from_raw(data, None).map_err(|error| error.to_string()),
+        Format::Json => serde_json::from_slice(data).map_err(debug),
+        Format::Ron => ron::from_str(text()?).map_err(debug),
+        // The Display form of text format errors has the line and column
+        Format::Text => GameReplayData::from_text(text()?).map_err(|error| error.to_string()),
+        Format::Jsonl => {
+            let path = path.display();
+            return Err(format!("Can't read a replay from '{path}', as JSON Lines only has inputs"));
+        }
+    };
+
+    replay.map_err(|error| format!("'{}' isn't a valid replay: {error}", path.display()))
+}
+
+/// The sizes of a replay, as it was compressed if it was read compressed.
+fn size_report(
+    path: &Path,
+    data: &[u8],
+    format: Format,
+    replay: &GameReplayData,
+) -> Result<SizeReport, String> {
+    let format = match format {
+        Format::Replay => detect_format(path, data)?,
+        format => format,
+    };
+
+    let compressed = match format {
+        Format::Base64 => decode_base64_replay(std::str::from_utf8(data).map_err(debug)?).map_err(debug)?,
+        Format::Replay | Format::Compressed => data.to_vec(),
+        _ => return replay.size_report().map_err(unserializable),
+    };
+
+    SizeReport::from_compressed(&compressed).map_err(|error| error.to_string())
+}
+
+/// Whether two paths lead to the same file, which is only possible if the first one exists.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn write_replay(replay: &GameReplayData, format: Format) -> Result<Vec<u8>, String> {
+    let text = match format {
+        Format::Replay | Format::Compressed => {
+            return replay.serialize_to_compressed(None).map_err(unserializable);
+        }
+        Format::Raw => return replay.serialize_to_raw(None).map_err(unserializable),
+        Format::Jsonl => {
+            let mut data = Vec::new();
+            replay.inputs_to_jsonl(&mut data).map_err(unserializable)?;
+            return Ok(data);
+        }
+        Format::Base64 => replay.serialize_to_base64(None).map_err(unserializable)?,
+        Format::Json => serde_json::to_string_pretty(replay).map_err(unserializable)?,
+        Format::Ron => ron::ser::to_string_pretty(replay, Default::default()).map_err(unserializable)?,
+        // Already ends with a newline
+        Format::Text => return replay.to_text().map(String::into_bytes).map_err(unserializable),
+    };
+
+    Ok(format!("{text}\n").into_bytes())
+}
+
+/// Formats an error for a message, for errors without a useful `Display` form.
+fn debug(error: impl Debug) -> String {
+    format!("{error:?}")
+}
+
+fn unserializable(error: impl Debug) -> String {
+    format!("Failed to serialize the replay: {error:?}")
+}
+
+/// Formats a duration like the game's timer, e.g. `1:23.45`.
+fn format_duration(secs: f64) -> String {
+    format!("{}:{:05.2}", (secs / 60.0) as u64, secs % 60.0)
+}
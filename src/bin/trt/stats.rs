@@ -0,0 +1,245 @@
+//! The `trt stats` subcommand.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+use techmino_replay_toolkit::analysis::{drop_intervals, AggregateStats, DropIntervals};
+use techmino_replay_toolkit::stats::ReplayStats;
+use techmino_replay_toolkit::{GameReplayData, InputEventKey, FRAMES_PER_SECOND, GAME_START_FRAME};
+
+use crate::format_duration;
+
+/// The statistics of one or more replays, in the form they're printed in.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsReport {
+    /// The file the replay was read from, or `total` for aggregated statistics.
+    pub name: String,
+    /// The number of replays the statistics are made from.
+    pub replays: usize,
+    /// The number of replays with `tasUsed` set in their metadata.
+    pub tas_flagged: usize,
+    /// The frames after the countdown, until the last input.
+    pub duration_frames: u64,
+    pub total_presses: u64,
+    pub hard_drops: u64,
+    pub average_kps: f64,
+    pub peak_kps: f64,
+    pub drop_tempo: DropTempo,
+    /// The keys that were pressed, the most pressed first.
+    pub keys: Vec<KeyReport>,
+    /// The modes, versions, settings and KPS distribution of aggregated replays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<AggregateStats>,
+}
+
+/// The statistics of one key.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyReport {
+    pub key: InputEventKey,
+    pub presses: u64,
+    /// Hold duration percentiles in frames, or `None` if the key was never released.
+    pub hold_p50: Option<u64>,
+    pub hold_p90: Option<u64>,
+    pub hold_max: Option<u64>,
+}
+
+/// The intervals between hard drops after the countdown, in frames.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropTempo {
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    /// The number of runs of 5 or more drops at most 10 frames apart.
+    pub bursts: usize,
+}
+
+/// Statistics that can be added up over several replays.
+#[derive(Default)]
+pub struct StatsTotals {
+    replays: usize,
+    tas_flagged: usize,
+    duration_frames: u64,
+    gameplay_presses: u64,
+    peak_kps: f64,
+    presses_per_key: BTreeMap<InputEventKey, u64>,
+    hold_durations: BTreeMap<InputEventKey, Vec<u64>>,
+    drop_intervals: Vec<DropIntervals>,
+}
+
+impl StatsTotals {
+    /// Adds the statistics of a replay.
+    pub fn add(&mut self, replay: &GameReplayData) {
+        let stats = ReplayStats::from_replay(replay);
+
+        self.replays += 1;
+        self.tas_flagged += usize::from(replay.metadata.tas_used == Some(true));
+        self.duration_frames += replay.duration_frames().saturating_sub(GAME_START_FRAME);
+        self.gameplay_presses += stats.total_presses - stats.countdown_presses;
+        self.peak_kps = self.peak_kps.max(stats.peak_kps);
+        self.drop_intervals.push(drop_intervals(replay));
+
+        for (key, presses) in stats.presses_per_key {
+            *self.presses_per_key.entry(key).or_default() += presses;
+        }
+
+        // The same pairing as the library's hold duration statistics, which don't keep the durations
+        for press in replay.pair_key_presses().presses {
+            if let Some(duration) = press.duration() {
+                self.hold_durations.entry(press.key).or_default().push(duration);
+            }
+        }
+    }
+
+    pub fn report(mut self, name: String) -> StatsReport {
+        let average_kps = match self.duration_frames {
+            0 => 0.0,
+            frames => self.gameplay_presses as f64 * FRAMES_PER_SECOND as f64 / frames as f64,
+        };
+
+        let mut keys: Vec<KeyReport> = self
+            .presses_per_key
+            .iter()
+            .map(|(&key, &presses)| {
+                let mut durations = self.hold_durations.remove(&key).unwrap_or_default();
+                durations.sort_unstable();
+
+                KeyReport {
+                    key,
+                    presses,
+                    hold_p50: percentile(&durations, 50),
+                    hold_p90: percentile(&durations, 90),
+                    hold_max: durations.last().copied(),
+                }
+            })
+            .collect();
+        // Stable, so keys with the same count stay in key order
+        keys.sort_by_key(|key| Reverse(key.presses));
+
+        let drops = DropIntervals::combine(self.drop_intervals);
+        let drop_tempo = DropTempo {
+            mean: drops.mean,
+            median: drops.median,
+            std_dev: drops.std_dev,
+            bursts: drops.bursts.len(),
+        };
+
+        StatsReport {
+            name,
+            replays: self.replays,
+            tas_flagged: self.tas_flagged,
+            duration_frames: self.duration_frames,
+            total_presses: self.presses_per_key.values().sum(),
+            hard_drops: self.presses_per_key.get(&InputEventKey::HardDrop).copied().unwrap_or(0),
+            average_kps,
+            peak_kps: self.peak_kps,
+            drop_tempo,
+            keys,
+            aggregate: None,
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[u64], percent: usize) -> Option<u64> {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+
+    sorted.get(rank - 1).copied()
+}
+
+impl StatsReport {
+    /// Formats the report as a table, ending with a newline.
+    pub fn to_table(&self) -> String {
+        let tas = match (self.replays, self.tas_flagged) {
+            (1, 0) => "no".to_string(),
+            (1, _) => "yes".to_string(),
+            (replays, flagged) => format!("{flagged} of {replays} replays"),
+        };
+
+        let mut table = format!(
+            "{}\n\
+             Duration:     {} ({} frames after the countdown)\n\
+             TAS:          {tas}\n\
+             Presses:      {} ({} hard drops)\n\
+             Keys/second:  {:.2} average, {} peak\n\
+             Drop tempo:   {:.2} frames average, {} median, {:.2} std dev, {} bursts\n",
+            self.name,
+            format_duration(self.duration_frames as f64 / FRAMES_PER_SECOND as f64),
+            self.duration_frames,
+            self.total_presses,
+            self.hard_drops,
+            self.average_kps,
+            self.peak_kps,
+            self.drop_tempo.mean,
+            self.drop_tempo.median,
+            self.drop_tempo.std_dev,
+            self.drop_tempo.bursts,
+        );
+
+        if let Some(aggregate) = &self.aggregate {
+            let kps = &aggregate.average_kps;
+            writeln!(table, "Modes:        {}", by_count(&aggregate.modes)).unwrap();
+            writeln!(table, "Versions:     {}", by_count(&aggregate.versions)).unwrap();
+            writeln!(table, "DAS:          {}", by_count(&aggregate.das)).unwrap();
+            writeln!(table, "ARR:          {}", by_count(&aggregate.arr)).unwrap();
+            writeln!(
+                table,
+                "Average KPS:  {:.2} mean, {:.2} lowest, {:.2} highest, of {} replays with gameplay",
+                kps.mean, kps.min, kps.max, kps.replays
+            )
+            .unwrap();
+        }
+
+        if !self.keys.is_empty() {
+            table.push_str("\nKey            Presses  Hold p50  Hold p90  Hold max\n");
+        }
+
+        for key in &self.keys {
+            let frames = |frames: Option<u64>| frames.map_or("-".to_string(), |frames| frames.to_string());
+
+            writeln!(
+                table,
+                "{:<13} {:>8} {:>9} {:>9} {:>9}",
+                key.key.label(),
+                key.presses,
+                frames(key.hold_p50),
+                frames(key.hold_p90),
+                frames(key.hold_max),
+            )
+            .unwrap();
+        }
+
+        table
+    }
+}
+
+/// Lists values with how often they occur, the most common first, e.g. `3 (2), 4 (1)`.
+fn by_count<T: std::fmt::Display>(counts: &BTreeMap<T, u64>) -> String {
+    let mut sorted: Vec<_> = counts.iter().collect();
+    // Stable, so values with the same count stay in order
+    sorted.sort_by_key(|&(_, count)| Reverse(count));
+
+    let counts: Vec<String> = sorted.iter().map(|(value, count)| format!("{value} ({count})")).collect();
+    match counts.is_empty() {
+        true => "-".to_string(),
+        false => counts.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 50), None);
+        assert_eq!(percentile(&[7], 90), Some(7));
+        assert_eq!(percentile(&[1, 2, 3, 4], 50), Some(2));
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 90), Some(9));
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], 90), Some(10));
+    }
+}
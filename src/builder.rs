@@ -0,0 +1,265 @@
+use alloc::vec::Vec;
+
+use crate::keyset::KeySet;
+use crate::types::*;
+
+/// An error from building a replay with a [`ReplayBuilder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BuilderError {
+    /// An input was added on a frame earlier than a previously added input.
+    OutOfOrder {
+        /// The frame of the input.
+        frame: u64,
+        /// The frame of the previously added input.
+        prev_frame: u64,
+    },
+
+    /// A key was pressed while it was already held.
+    AlreadyHeld {
+        /// The frame of the press.
+        frame: u64,
+        /// The key that was pressed.
+        key: InputEventKey,
+    },
+
+    /// A key was released while it wasn't held.
+    NotHeld {
+        /// The frame of the release.
+        frame: u64,
+        /// The key that was released.
+        key: InputEventKey,
+    },
+}
+
+/// A builder for creating replays input by input.
+///
+/// The builder keeps the inputs sorted and the presses and releases paired up,
+/// so the resulting replay can always be serialized.
+/// Inputs have to be added in chronological order.
+///
+/// The methods can be chained. If an input breaks one of the rules above, it is rejected and
+/// the error is returned by [`build`][ReplayBuilder::build]; later inputs are ignored.
+///
+/// ```
+/// use techmino_replay_toolkit::{InputEventKey, ReplayBuilder};
+///
+/// let replay = ReplayBuilder::new()
+///     .press(180, InputEventKey::MoveLeft)
+///     .tap(185, InputEventKey::HardDrop, 1)
+///     .release(200, InputEventKey::MoveLeft)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(replay.inputs.len(), 4);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ReplayBuilder {
+    inputs: Vec<GameInputEvent>,
+    metadata: GameReplayMetadata,
+    held: KeySet,
+    /// Releases scheduled by [`tap`][ReplayBuilder::tap], sorted by frame.
+    pending_releases: Vec<(u64, InputEventKey)>,
+    /// The frame of the last input added explicitly.
+    frame: u64,
+    error: Option<BuilderError>,
+}
+
+impl ReplayBuilder {
+    /// Creates a builder with no inputs and default metadata.
+    pub fn new() -> ReplayBuilder {
+        Self::default()
+    }
+
+    /// Sets the metadata of the replay.
+    pub fn metadata(&mut self, metadata: GameReplayMetadata) -> &mut Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Adds a key press.
+    ///
+    /// The key must not be held.
+    pub fn press(&mut self, frame: u64, key: InputEventKey) -> &mut Self {
+        if self.advance_to(frame) {
+            if self.held.contains(key) {
+                self.error = Some(BuilderError::AlreadyHeld { frame, key });
+            } else {
+                self.push(frame, InputEventKind::Press, key);
+            }
+        }
+
+        self
+    }
+
+    /// Adds a key release.
+    ///
+    /// The key must be held.
+    pub fn release(&mut self, frame: u64, key: InputEventKey) -> &mut Self {
+        if self.advance_to(frame) {
+            if self.held.contains(key) && !self.is_pending_release(key) {
+                self.push(frame, InputEventKind::Release, key);
+            } else {
+                self.error = Some(BuilderError::NotHeld { frame, key });
+            }
+        }
+
+        self
+    }
+
+    /// Adds a key press, and schedules its release `hold_frames` frames later.
+    ///
+    /// The release is added once an input on or after its frame is added,
+    /// or when the replay is built. Until then, the key counts as held.
+    pub fn tap(&mut self, frame: u64, key: InputEventKey, hold_frames: u64) -> &mut Self {
+        self.press(frame, key);
+
+        if self.error.is_none() {
+            let release_frame = frame.saturating_add(hold_frames);
+            let index = self.pending_releases.partition_point(|&(f, _)| f <= release_frame);
+            self.pending_releases.insert(index, (release_frame, key));
+
+            // Zero-frame taps are released right away
+            self.flush_pending_releases(frame);
+        }
+
+        self
+    }
+
+    /// Builds the replay, releasing any keys scheduled to be released by [`tap`][ReplayBuilder::tap].
+    ///
+    /// Keys that were pressed with [`press`][ReplayBuilder::press] and never released
+    /// stay held at the end of the replay.
+    pub fn build(&mut self) -> Result<GameReplayData, BuilderError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        self.flush_pending_releases(u64::MAX);
+
+        Ok(GameReplayData {
+            inputs: self.inputs.clone(),
+            metadata: self.metadata.clone(),
+            input_mode: None,
+        })
+    }
+
+    /// Checks that an input can be added on the frame, and adds any scheduled releases before it.
+    ///
+    /// Returns whether the input can be added.
+    fn advance_to(&mut self, frame: u64) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+
+        if frame < self.frame {
+            self.error = Some(BuilderError::OutOfOrder { frame, prev_frame: self.frame });
+            return false;
+        }
+
+        self.frame = frame;
+        self.flush_pending_releases(frame);
+
+        true
+    }
+
+    fn flush_pending_releases(&mut self, until_frame: u64) {
+        let count = self.pending_releases.partition_point(|&(f, _)| f <= until_frame);
+
+        for (frame, key) in self.pending_releases.drain(..count).collect::<Vec<_>>() {
+            self.push(frame, InputEventKind::Release, key);
+        }
+    }
+
+    fn is_pending_release(&self, key: InputEventKey) -> bool {
+        self.pending_releases.iter().any(|&(_, k)| k == key)
+    }
+
+    fn push(&mut self, frame: u64, kind: InputEventKind, key: InputEventKey) {
+        match kind {
+            InputEventKind::Press => self.held.insert(key),
+            InputEventKind::Release => self.held.remove(key),
+        };
+
+        self.inputs.push(GameInputEvent { frame, kind, key });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_build_sprint() {
+        let metadata = GameReplayMetadata {
+            player: "TAS".to_string(),
+            version: "V0.17.22".to_string(),
+            mode: "sprint_40l".to_string(),
+            tas_used: Some(true),
+            ..Default::default()
+        };
+
+        let mut builder = ReplayBuilder::new();
+        builder
+            .metadata(metadata)
+            .press(170, MoveLeft)
+            .tap(180, HardDrop, 2)
+            .release(181, MoveLeft)
+            .tap(182, RotateRight, 0)
+            .tap(182, HardDrop, 1)
+            .tap(190, Hold, 3)
+            .press(193, Hold);
+
+        let data = builder.build().unwrap();
+
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(170, MoveLeft),
+                GameInputEvent::press(180, HardDrop),
+                GameInputEvent::release(181, MoveLeft),
+                GameInputEvent::release(182, HardDrop),
+                GameInputEvent::press(182, RotateRight),
+                GameInputEvent::release(182, RotateRight),
+                GameInputEvent::press(182, HardDrop),
+                GameInputEvent::release(183, HardDrop),
+                GameInputEvent::press(190, Hold),
+                GameInputEvent::release(193, Hold),
+                GameInputEvent::press(193, Hold),
+            ]
+        );
+
+        let serialized = data.serialize_to_base64(None).unwrap();
+        let parsed = GameReplayData::try_from_base64(&serialized, None).unwrap();
+        assert_eq!(parsed, data);
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        assert_eq!(
+            ReplayBuilder::new().press(20, MoveLeft).press(10, Hold).build(),
+            Err(BuilderError::OutOfOrder { frame: 10, prev_frame: 20 })
+        );
+
+        assert_eq!(
+            ReplayBuilder::new().press(10, MoveLeft).press(20, MoveLeft).build(),
+            Err(BuilderError::AlreadyHeld { frame: 20, key: MoveLeft })
+        );
+
+        assert_eq!(
+            ReplayBuilder::new().tap(10, MoveLeft, 20).press(20, MoveLeft).build(),
+            Err(BuilderError::AlreadyHeld { frame: 20, key: MoveLeft })
+        );
+
+        assert_eq!(
+            ReplayBuilder::new().tap(10, MoveLeft, 20).release(20, MoveLeft).build(),
+            Err(BuilderError::NotHeld { frame: 20, key: MoveLeft })
+        );
+
+        // Later inputs are ignored once an error has occurred
+        assert_eq!(
+            ReplayBuilder::new().release(10, Hold).release(5, MoveLeft).build(),
+            Err(BuilderError::NotHeld { frame: 10, key: Hold })
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::deserialize::parse_input_slice;
+use crate::types::*;
+use crate::vlq;
+
+/// The version of the schema written by [`GameReplayData::to_msgpack`].
+///
+/// Bump this whenever the schema changes, and keep reading the older versions.
+const SCHEMA_VERSION: u8 = 1;
+
+/// The first version of the cache schema.
+///
+/// The metadata is stored with its JSON field names, and the inputs as a byte string
+/// in the game's input data format, timed absolutely so that unsorted inputs survive.
+///
+/// The metadata is borrowed when writing and owned when reading.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheV1<M> {
+    metadata: M,
+    input_mode: Option<InputParseMode>,
+    #[serde(with = "serde_bytes")]
+    inputs: Vec<u8>,
+}
+
+/// An error from storing or loading a replay with [`GameReplayData::to_msgpack`]
+/// and [`GameReplayData::from_msgpack`].
+#[derive(Debug)]
+pub enum BinaryCacheError {
+    /// The data is empty, so it doesn't even have a version byte.
+    Empty,
+    /// The data was written with a schema version this version of the crate doesn't know,
+    /// e.g. by a newer version of the crate.
+    ///
+    /// Contains the version byte.
+    UnsupportedVersion(u8),
+    /// The replay could not be encoded.
+    ///
+    /// See [`rmp_serde`'s Error type][rmp_serde::encode::Error] for more information.
+    Encode(rmp_serde::encode::Error),
+    /// The data isn't valid MessagePack, or doesn't match the schema.
+    ///
+    /// See [`rmp_serde`'s Error type][rmp_serde::decode::Error] for more information.
+    Decode(rmp_serde::decode::Error),
+    /// The input data is malformed.
+    ///
+    /// Contains the error from parsing the input data, which is either
+    /// [`MalformedVlq`][ReplayParseError::MalformedVlq] or
+    /// [`MalformedInputData`][ReplayParseError::MalformedInputData].
+    MalformedInput(ReplayParseError),
+}
+
+impl From<rmp_serde::encode::Error> for BinaryCacheError {
+    fn from(value: rmp_serde::encode::Error) -> Self {
+        Self::Encode(value)
+    }
+}
+
+impl From<rmp_serde::decode::Error> for BinaryCacheError {
+    fn from(value: rmp_serde::decode::Error) -> Self {
+        Self::Decode(value)
+    }
+}
+
+impl GameReplayData {
+    /// Encodes the parsed replay into a compact binary form, for caching parsed replays.
+    ///
+    /// Loading the replay back with [`from_msgpack`][GameReplayData::from_msgpack] is much faster
+    /// than parsing it again, as it skips the decompression and the VLQ decoding.
+    ///
+    /// The data is a version byte followed by [MessagePack](https://msgpack.org/).
+    /// The schema is stable: data written by older versions of the crate can always be loaded
+    /// by newer ones. The other way around fails with [`BinaryCacheError::UnsupportedVersion`].
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, BinaryCacheError> {
+        let values: Vec<u64> = self
+            .inputs
+            .iter()
+            .flat_map(|input| {
+                let (time, code) = input.to_packed(InputParseMode::Absolute, 0);
+                [time, code]
+            })
+            .collect();
+
+        let cache = CacheV1 {
+            metadata: &self.metadata,
+            input_mode: self.input_mode,
+            inputs: vlq::encode(&values),
+        };
+
+        let mut bytes = vec![SCHEMA_VERSION];
+        rmp_serde::encode::write_named(&mut bytes, &cache)?;
+
+        Ok(bytes)
+    }
+
+    /// Loads a replay encoded with [`to_msgpack`][GameReplayData::to_msgpack].
+    pub fn from_msgpack(data: &[u8]) -> Result<GameReplayData, BinaryCacheError> {
+        let (&version, data) = data.split_first().ok_or(BinaryCacheError::Empty)?;
+
+        let cache: CacheV1<GameReplayMetadata> = match version {
+            1 => rmp_serde::from_slice(data)?,
+            _ => return Err(BinaryCacheError::UnsupportedVersion(version)),
+        };
+
+        let inputs = parse_input_slice(&cache.inputs, InputParseMode::Absolute)
+            .map_err(BinaryCacheError::MalformedInput)?;
+
+        Ok(GameReplayData { inputs, metadata: cache.metadata, input_mode: cache.input_mode })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bad_data() {
+        assert!(matches!(GameReplayData::from_msgpack(&[]), Err(BinaryCacheError::Empty)));
+        assert!(matches!(
+            GameReplayData::from_msgpack(&[2, 0x90]),
+            Err(BinaryCacheError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(GameReplayData::from_msgpack(&[1, 0xc1]), Err(BinaryCacheError::Decode(_))));
+
+        let mut data = GameReplayData::default();
+        data.inputs.push(GameInputEvent {
+            frame: 3,
+            kind: InputEventKind::Press,
+            key: InputEventKey::Hold,
+        });
+        let mut bytes = data.to_msgpack().unwrap();
+
+        // The key code of the only input is the last byte
+        *bytes.last_mut().unwrap() = 0;
+        assert!(matches!(
+            GameReplayData::from_msgpack(&bytes),
+            Err(BinaryCacheError::MalformedInput(ReplayParseError::MalformedInputData { .. }))
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+//! A C API for using the crate from other languages, e.g. from the game's launcher.
+//!
+//! Replays are passed around as opaque [`TrtReplay`] handles, created by the parse functions
+//! and destroyed with [`trt_free_replay`]. Functions that can fail return a [`TrtStatus`], and
+//! a message describing the last failure on the calling thread is available through
+//! [`trt_last_error_message`].
+//!
+//! The C header is `include/techmino_replay_toolkit.h`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::types::*;
+
+/// A parsed replay.
+pub struct TrtReplay(GameReplayData);
+
+/// An input of a replay, as returned by [`trt_replay_input_at`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrtInput {
+    /// The frame the input happened on.
+    pub frame: u64,
+    /// 0 for a press, 1 for a release.
+    pub kind: u8,
+    /// The key code, see [`InputEventKey`].
+    pub key: u8,
+}
+
+/// The result of a C API call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrtStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A pointer argument was null.
+    NullPointer = 1,
+    /// A string argument isn't valid UTF-8.
+    InvalidUtf8 = 2,
+    /// An index argument is out of bounds.
+    IndexOutOfBounds = 3,
+    /// See [`ReplayParseError::Base64DecodeError`].
+    Base64DecodeError = 10,
+    /// See [`ReplayParseError::ZlibDecompressError`].
+    ZlibDecompressError = 11,
+    /// See [`ReplayParseError::MetadataSeparatorNotFound`].
+    MetadataSeparatorNotFound = 12,
+    /// See [`ReplayParseError::MetadataNotUtf8`].
+    MetadataNotUtf8 = 13,
+    /// See [`ReplayParseError::MetadataDeserializeError`].
+    MetadataDeserializeError = 14,
+    /// See [`ReplayParseError::UnknownInputParseMode`] and [`ReplaySerializeError::UnknownInputParseMode`].
+    UnknownInputParseMode = 15,
+    /// See [`ReplayParseError::MalformedInputData`].
+    MalformedInputData = 16,
+    /// See [`ReplayParseError::MalformedVlq`].
+    MalformedVlq = 17,
+    /// See [`ReplayParseError::StrictViolation`].
+    StrictViolation = 18,
+    /// See [`ReplayParseError::EmptyInput`].
+    EmptyInput = 19,
+    /// See [`ReplaySerializeError::UnsortedInput`].
+    UnsortedInput = 20,
+    /// See [`ReplaySerializeError::MetadataSerializeError`].
+    MetadataSerializeError = 21,
+    /// See [`ReplayParseError::NotAReplay`].
+    NotAReplay = 22,
+    /// See [`ReplaySerializeError::UndecodableInputs`].
+    UndecodableInputs = 23,
+    /// See [`ReplaySerializeError::InvalidKeyCode`].
+    InvalidKeyCode = 24,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the message of a failure, and returns its status.
+fn fail(status: TrtStatus, message: String) -> TrtStatus {
+    // Messages come from Debug output, which escapes NUL bytes, but be safe anyway
+    let message = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+
+    status
+}
+
+fn parse_status(error: &ReplayParseError) -> TrtStatus {
+    use ReplayParseError::*;
+
+    match error {
+        ZlibDecompressError(_) => TrtStatus::ZlibDecompressError,
+        Base64DecodeError(_) => TrtStatus::Base64DecodeError,
+        MetadataSeparatorNotFound => TrtStatus::MetadataSeparatorNotFound,
+        MetadataNotUtf8(_) => TrtStatus::MetadataNotUtf8,
+        MetadataDeserializeError { .. } => TrtStatus::MetadataDeserializeError,
+        UnknownInputParseMode(_) => TrtStatus::UnknownInputParseMode,
+        MalformedInputData { .. } => TrtStatus::MalformedInputData,
+        MalformedVlq(_) => TrtStatus::MalformedVlq,
+        StrictViolation(_) => TrtStatus::StrictViolation,
+        EmptyInput => TrtStatus::EmptyInput,
+        NotAReplay { .. } => TrtStatus::NotAReplay,
+    }
+}
+
+fn serialize_status(error: &ReplaySerializeError) -> TrtStatus {
+    use ReplaySerializeError::*;
+
+    match error {
+        UnknownInputParseMode(_) => TrtStatus::UnknownInputParseMode,
+        UnsortedInput { .. } => TrtStatus::UnsortedInput,
+        MetadataSerializeError(_) => TrtStatus::MetadataSerializeError,
+        UndecodableInputs(_) => TrtStatus::UndecodableInputs,
+        InvalidKeyCode { .. } => TrtStatus::InvalidKeyCode,
+    }
+}
+
+/// Stores a parse result into `out`.
+///
+/// # Safety
+///
+/// `out` must be valid for writes.
+unsafe fn finish_parse(result: Result<GameReplayData, ReplayParseError>, out: *mut *mut TrtReplay) -> TrtStatus {
+    match result {
+        Ok(data) => {
+            *out = Box::into_raw(Box::new(TrtReplay(data)));
+            TrtStatus::Ok
+        }
+        Err(error) => fail(parse_status(&error), format!("{error:?}")),
+    }
+}
+
+/// Parses a NUL-terminated base64 replay string.
+///
+/// On success, `*out` is set to a new replay, which must be freed with [`trt_free_replay`].
+///
+/// # Safety
+///
+/// `string` must be null or point to a NUL-terminated string, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn trt_parse_base64(string: *const c_char, out: *mut *mut TrtReplay) -> TrtStatus {
+    if string.is_null() || out.is_null() {
+        return fail(TrtStatus::NullPointer, "A pointer argument is null".to_string());
+    }
+
+    let Ok(string) = CStr::from_ptr(string).to_str() else {
+        return fail(TrtStatus::InvalidUtf8, "The replay string isn't valid UTF-8".to_string());
+    };
+
+    finish_parse(GameReplayData::try_from_base64(string, None), out)
+}
+
+/// Parses a compressed replay, e.g. the contents of a `.rep` file, of `len` bytes.
+///
+/// On success, `*out` is set to a new replay, which must be freed with [`trt_free_replay`].
+///
+/// # Safety
+///
+/// `data` must be null or point to `len` readable bytes, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn trt_parse_compressed(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut TrtReplay,
+) -> TrtStatus {
+    if data.is_null() || out.is_null() {
+        return fail(TrtStatus::NullPointer, "A pointer argument is null".to_string());
+    }
+
+    let data = std::slice::from_raw_parts(data, len);
+
+    finish_parse(GameReplayData::try_from_compressed(data, None), out)
+}
+
+/// Serializes a replay into a base64 string.
+///
+/// On success, `*out` is set to a new NUL-terminated string, which must be freed with [`trt_free_string`].
+///
+/// # Safety
+///
+/// `replay` must be null or a replay from this API that hasn't been freed,
+/// and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn trt_serialize_base64(replay: *const TrtReplay, out: *mut *mut c_char) -> TrtStatus {
+    if replay.is_null() || out.is_null() {
+        return fail(TrtStatus::NullPointer, "A pointer argument is null".to_string());
+    }
+
+    match (*replay).0.serialize_to_base64(None) {
+        Ok(string) => {
+            // Base64 never contains NUL bytes
+            *out = CString::new(string).unwrap_or_default().into_raw();
+            TrtStatus::Ok
+        }
+        Err(error) => fail(serialize_status(&error), format!("{error:?}")),
+    }
+}
+
+/// Returns the number of inputs of a replay, or 0 if `replay` is null.
+///
+/// # Safety
+///
+/// `replay` must be null or a replay from this API that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn trt_replay_input_count(replay: *const TrtReplay) -> usize {
+    match replay.as_ref() {
+        Some(replay) => replay.0.inputs.len(),
+        None => 0,
+    }
+}
+
+/// Copies the input at `index` of a replay into `*out`.
+///
+/// # Safety
+///
+/// `replay` must be null or a replay from this API that hasn't been freed,
+/// and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn trt_replay_input_at(
+    replay: *const TrtReplay,
+    index: usize,
+    out: *mut TrtInput,
+) -> TrtStatus {
+    if replay.is_null() || out.is_null() {
+        return fail(TrtStatus::NullPointer, "A pointer argument is null".to_string());
+    }
+
+    let inputs = &(*replay).0.inputs;
+    let Some(input) = inputs.get(index) else {
+        let message = format!("Index {index} is out of bounds for {} inputs", inputs.len());
+        return fail(TrtStatus::IndexOutOfBounds, message);
+    };
+
+    *out = TrtInput { frame: input.frame, kind: input.kind.into(), key: input.key.into() };
+    TrtStatus::Ok
+}
+
+/// Returns the metadata of a replay as a new NUL-terminated JSON string, which must be freed
+/// with [`trt_free_string`], or null if it fails.
+///
+/// # Safety
+///
+/// `replay` must be null or a replay from this API that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn trt_replay_metadata_json(replay: *const TrtReplay) -> *mut c_char {
+    let Some(replay) = replay.as_ref() else {
+        fail(TrtStatus::NullPointer, "A pointer argument is null".to_string());
+        return ptr::null_mut();
+    };
+
+    match serde_json::to_string(&replay.0.metadata) {
+        // JSON escapes NUL characters in strings, so there are none in the output
+        Ok(json) => CString::new(json).unwrap_or_default().into_raw(),
+        Err(error) => {
+            fail(TrtStatus::MetadataSerializeError, format!("{error:?}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a replay. Does nothing if `replay` is null.
+///
+/// # Safety
+///
+/// `replay` must be null or a replay from this API that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn trt_free_replay(replay: *mut TrtReplay) {
+    if !replay.is_null() {
+        drop(Box::from_raw(replay));
+    }
+}
+
+/// Frees a string returned by this API. Does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string from this API that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn trt_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Returns a message describing the last failed call on this thread, or null if no call has failed.
+///
+/// The message is owned by the library, and stays valid until the next failed call on this thread.
+#[no_mangle]
+pub extern "C" fn trt_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
@@ -0,0 +1,164 @@
+use alloc::vec::Vec;
+
+use crate::types::*;
+use crate::KeyRegistry;
+
+/// The bit of a key code that is set for [`Release`][InputEventKind::Release] events.
+pub const KIND_BIT: u64 = 0b10_0000;
+
+/// The bits of a key code that hold the key.
+pub const KEY_MASK: u64 = 0b1_1111;
+
+const KIND_SHIFT: u32 = KIND_BIT.trailing_zeros();
+
+impl GameInputEvent {
+    /// Packs the event into the pair of values the game stores it as: the time and the key code.
+    ///
+    /// The time is the frame of the event in [`Absolute`][InputParseMode::Absolute] mode,
+    /// and the number of frames since `prev_frame` in [`Relative`][InputParseMode::Relative] mode.
+    /// If the event is before `prev_frame`, the relative time is 0.
+    ///
+    /// The key code holds the key in its lowest 5 bits ([`KEY_MASK`]),
+    /// and the kind in the bit above them ([`KIND_BIT`]).
+    /// The code of an [`Other`][InputEventKey::Other] key isn't checked here; serializing
+    /// a replay rejects the ones outside of 21 to 31.
+    pub fn to_packed(&self, mode: InputParseMode, prev_frame: u64) -> (u64, u64) {
+        let time = match mode {
+            InputParseMode::Relative => self.frame.saturating_sub(prev_frame),
+            InputParseMode::Absolute => self.frame,
+        };
+
+        let code = u8::from(self.key) as u64 | (u8::from(self.kind) as u64) << KIND_SHIFT;
+
+        (time, code)
+    }
+
+    /// Unpacks an event from the time and key code the game stores it as.
+    ///
+    /// This is the inverse of [`to_packed`][GameInputEvent::to_packed]; `prev_frame` is the
+    /// frame of the previous event, which is needed in [`Relative`][InputParseMode::Relative] mode.
+    ///
+    /// Fails with [`MalformedInputData`][ReplayParseError::MalformedInputData] if the key bits
+    /// aren't a known key, if any bit above the kind bit is set, or if the frame overflows.
+    /// As there is no input data to point into, the `offset` and `index` of the error are 0,
+    /// and there are no values around it.
+    pub fn from_packed(
+        time: u64,
+        code: u64,
+        mode: InputParseMode,
+        prev_frame: u64,
+    ) -> Result<Self, ReplayParseError> {
+        unpack(time, code, mode, prev_frame, None)
+    }
+}
+
+/// Unpacks an event.
+///
+/// The errors don't know where the input is, so their `offset` and `index` are 0,
+/// and there are no values around it; see `parse_input_slice`.
+///
+/// The codes the registry has names for are [`Other`][InputEventKey::Other] keys.
+pub(crate) fn unpack(
+    time: u64,
+    code: u64,
+    mode: InputParseMode,
+    prev_frame: u64,
+    keys: Option<&KeyRegistry>,
+) -> Result<GameInputEvent, ReplayParseError> {
+    let frame = match mode {
+        InputParseMode::Relative => time.checked_add(prev_frame),
+        InputParseMode::Absolute => Some(time),
+    };
+
+    let malformed = || ReplayParseError::MalformedInputData {
+        offset: 0,
+        index: 0,
+        time,
+        frame,
+        raw_code: code,
+        key_bits: (code & KEY_MASK) as u8,
+        kind_bit: code & KIND_BIT != 0,
+        values_before: Vec::new(),
+        values_after: Vec::new(),
+    };
+
+    let frame = frame.ok_or_else(malformed)?;
+
+    if code & !(KIND_BIT | KEY_MASK) != 0 {
+        return Err(malformed());
+    }
+
+    let kind = InputEventKind::from(code & KIND_BIT != 0);
+    let key_bits = (code & KEY_MASK) as u8;
+    let key = match InputEventKey::try_from(key_bits) {
+        Ok(key) => key,
+        Err(()) if keys.is_some_and(|keys| keys.name(key_bits).is_some()) => InputEventKey::Other(key_bits),
+        Err(()) => return Err(malformed()),
+    };
+
+    Ok(GameInputEvent { frame, kind, key })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing_round_trip() {
+        let modes = [InputParseMode::Relative, InputParseMode::Absolute];
+        let kinds = [InputEventKind::Press, InputEventKind::Release];
+
+        for code in 1..=20 {
+            let key = InputEventKey::try_from(code).unwrap();
+
+            for kind in kinds {
+                for mode in modes {
+                    let event = GameInputEvent { frame: 200, kind, key };
+                    let (time, packed_code) = event.to_packed(mode, 150);
+
+                    assert_eq!(packed_code & KEY_MASK, code as u64);
+                    assert_eq!(packed_code & KIND_BIT != 0, kind == InputEventKind::Release);
+                    assert_eq!(
+                        time,
+                        match mode {
+                            InputParseMode::Relative => 50,
+                            InputParseMode::Absolute => 200,
+                        }
+                    );
+
+                    let unpacked =
+                        GameInputEvent::from_packed(time, packed_code, mode, 150).unwrap();
+                    assert_eq!(unpacked, event);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let decode = |code| GameInputEvent::from_packed(10, code, InputParseMode::Absolute, 0);
+
+        assert_eq!(
+            decode(33).unwrap(),
+            GameInputEvent {
+                frame: 10,
+                kind: InputEventKind::Release,
+                key: InputEventKey::MoveLeft
+            }
+        );
+
+        for (code, key_bits) in [(0, 0), (21, 21), (32, 0), (63, 31), (65, 1), (u64::MAX, 31)] {
+            assert!(matches!(
+                decode(code),
+                Err(ReplayParseError::MalformedInputData {
+                    offset: 0, frame: Some(10), raw_code, key_bits: k, kind_bit, ..
+                }) if raw_code == code && k == key_bits && kind_bit == (code & KIND_BIT != 0)
+            ));
+        }
+
+        assert!(matches!(
+            GameInputEvent::from_packed(u64::MAX, 1, InputParseMode::Relative, 1),
+            Err(ReplayParseError::MalformedInputData { time: u64::MAX, frame: None, .. })
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+//! The zlib compression of replays.
+//!
+//! [`miniz_oxide`] is used by default. With the `flate2` feature, [`flate2`](https://docs.rs/flate2)
+//! is used instead, with its `zlib-rs` backend, which decompresses considerably faster.
+//!
+//! Both produce valid zlib data that the game can read, but the compressed bytes may differ.
+
+use alloc::vec::Vec;
+
+/// An error from decompressing the replay data with zlib.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecompressError {
+    /// What went wrong.
+    pub kind: DecompressErrorKind,
+    /// The data that was decompressed before the error.
+    pub output: Vec<u8>,
+}
+
+/// The kind of [`DecompressError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DecompressErrorKind {
+    /// The data ends before the end of the compressed stream, e.g. because it was cut off.
+    Truncated,
+    /// The data isn't valid zlib data, e.g. its header or checksum is wrong.
+    Corrupt,
+    /// The decompressed data is larger than the limit.
+    TooLarge,
+}
+
+/// Decompresses a compressed replay, e.g. the contents of a `.rep` file, into the raw replay
+/// without parsing it, e.g. to look into a replay that fails to parse.
+///
+/// On errors, the [`DecompressError`] has the data decompressed before the error.
+pub fn decompress_replay(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decompress(data, usize::MAX)
+}
+
+/// Returns whether two compressed replays decompress to the same bytes.
+///
+/// Different zlib builds compress the same replay into different bytes, so this compares
+/// replays compressed by different tools, e.g. the game and this crate. Data that can't be
+/// decompressed isn't equivalent to anything.
+pub fn compressed_equivalent(a: &[u8], b: &[u8]) -> bool {
+    match (decompress(a, usize::MAX), decompress(b, usize::MAX)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Compresses the data with zlib, at a level from 0 to 10.
+pub(crate) fn compress(data: &[u8], level: u8) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    compress_into(&mut buffer, data, level);
+    buffer
+}
+
+/// Compresses the data with zlib into the buffer, replacing its contents.
+///
+/// This reuses the buffer's allocation.
+#[cfg(not(feature = "flate2"))]
+pub(crate) fn compress_into(buffer: &mut Vec<u8>, mut data: &[u8], level: u8) {
+    use miniz_oxide::deflate::core::{compress, create_comp_flags_from_zip_params, CompressorOxide};
+    use miniz_oxide::deflate::core::{TDEFLFlush, TDEFLStatus};
+
+    // The same as `miniz_oxide::deflate::compress_to_vec_zlib`, but writing into the buffer
+    let flags = create_comp_flags_from_zip_params(level.into(), 1, 0);
+    let mut compressor = CompressorOxide::new(flags);
+
+    buffer.clear();
+    buffer.resize(buffer.capacity().max(data.len() / 2).max(2), 0);
+
+    let mut out_pos = 0;
+    loop {
+        let (status, bytes_in, bytes_out) =
+            compress(&mut compressor, data, &mut buffer[out_pos..], TDEFLFlush::Finish);
+        out_pos += bytes_out;
+
+        match status {
+            TDEFLStatus::Done => break,
+            TDEFLStatus::Okay if bytes_in <= data.len() => {
+                data = &data[bytes_in..];
+
+                if buffer.len() - out_pos < 30 {
+                    buffer.resize(buffer.len() * 2, 0);
+                }
+            }
+            // Only happens with invalid flags or a full output buffer, which are both ruled out above
+            _ => unreachable!("Unexpectedly failed to compress"),
+        }
+    }
+
+    buffer.truncate(out_pos);
+}
+
+/// Compresses the data with zlib into the buffer, replacing its contents.
+///
+/// This reuses the buffer's allocation.
+#[cfg(feature = "flate2")]
+pub(crate) fn compress_into(buffer: &mut Vec<u8>, data: &[u8], level: u8) {
+    use flate2::{Compress, Compression, FlushCompress, Status};
+
+    let mut compressor = Compress::new(Compression::new(level.min(9).into()), true);
+
+    buffer.clear();
+    buffer.reserve(data.len() / 2 + 64);
+
+    loop {
+        let consumed = compressor.total_in() as usize;
+        let status = compressor
+            .compress_vec(&data[consumed..], buffer, FlushCompress::Finish)
+            .expect("Compressing into a buffer can't fail");
+
+        match status {
+            Status::StreamEnd => break,
+            // Out of room in the buffer
+            Status::Ok | Status::BufError => buffer.reserve(buffer.capacity().max(64)),
+        }
+    }
+}
+
+/// Decompresses zlib data, failing if the decompressed data would be larger than `limit` bytes.
+#[cfg(not(feature = "flate2"))]
+pub(crate) fn decompress(mut data: &[u8], limit: usize) -> Result<Vec<u8>, DecompressError> {
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use miniz_oxide::inflate::core::inflate_flags::*;
+    use miniz_oxide::inflate::core::{decompress, DecompressorOxide};
+    use miniz_oxide::inflate::TINFLStatus;
+
+    // The same as `miniz_oxide::inflate::decompress_to_vec_zlib_with_limit`, but that also returns
+    // the unwritten end of its buffer on errors, which isn't decompressed data
+    let flags = TINFL_FLAG_PARSE_ZLIB_HEADER | TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    let mut output = vec![0; data.len().saturating_mul(2).max(64).min(limit)];
+
+    let mut out_pos = 0;
+    loop {
+        let (status, bytes_in, bytes_out) = decompress(&mut decompressor, data, &mut output, out_pos, flags);
+        out_pos += bytes_out;
+
+        let kind = match status {
+            TINFLStatus::Done => {
+                output.truncate(out_pos);
+                return Ok(output);
+            }
+            TINFLStatus::HasMoreOutput if output.len() < limit && bytes_in <= data.len() => {
+                data = &data[bytes_in..];
+                output.resize(output.len().saturating_mul(2).min(limit), 0);
+                continue;
+            }
+            TINFLStatus::HasMoreOutput => DecompressErrorKind::TooLarge,
+            TINFLStatus::FailedCannotMakeProgress | TINFLStatus::NeedsMoreInput => DecompressErrorKind::Truncated,
+            _ => DecompressErrorKind::Corrupt,
+        };
+
+        output.truncate(out_pos);
+        return Err(DecompressError { kind, output });
+    }
+}
+
+/// Decompresses zlib data, failing if the decompressed data would be larger than `limit` bytes.
+#[cfg(feature = "flate2")]
+pub(crate) fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, DecompressError> {
+    use flate2::{Decompress, FlushDecompress, Status};
+
+    let mut decompressor = Decompress::new(true);
+    let mut output = Vec::with_capacity(data.len().saturating_mul(4).clamp(64, limit.max(1)));
+
+    loop {
+        let consumed = decompressor.total_in() as usize;
+        let produced = output.len();
+
+        let status = match decompressor.decompress_vec(&data[consumed..], &mut output, FlushDecompress::Finish) {
+            Ok(status) => status,
+            Err(_) => return Err(DecompressError { kind: DecompressErrorKind::Corrupt, output }),
+        };
+
+        if status == Status::StreamEnd {
+            return Ok(output);
+        }
+
+        if output.len() == output.capacity() {
+            // Out of room in the buffer
+            if output.len() >= limit {
+                return Err(DecompressError { kind: DecompressErrorKind::TooLarge, output });
+            }
+
+            let additional = output.capacity().min(limit - output.len());
+            output.reserve_exact(additional);
+        } else if decompressor.total_in() as usize == consumed && output.len() == produced {
+            // There's room for more output, but no progress, so the input ended early
+            return Err(DecompressError { kind: DecompressErrorKind::Truncated, output });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8 ^ (i / 1000) as u8).collect();
+        let compressed = compress(&data, 6);
+
+        assert_eq!(decompress(&compressed, usize::MAX).unwrap(), data);
+        assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+
+        // Both backends must write zlib data that other zlib implementations can read
+        assert_eq!(miniz_oxide::inflate::decompress_to_vec_zlib(&compressed).unwrap(), data);
+
+        let mut buffer = vec![1; 10];
+        compress_into(&mut buffer, b"", 6);
+        assert_eq!(decompress(&buffer, usize::MAX).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_errors() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8 ^ (i / 1000) as u8).collect();
+        let compressed = compress(&data, 6);
+
+        let error = decompress(&compressed, 100).unwrap_err();
+        assert_eq!(error.kind, DecompressErrorKind::TooLarge);
+
+        let error = decompress(&compressed[..compressed.len() / 2], usize::MAX).unwrap_err();
+        assert_eq!(error.kind, DecompressErrorKind::Truncated);
+        assert!(!error.output.is_empty() && data.starts_with(&error.output));
+
+        let error = decompress(b"not zlib data", usize::MAX).unwrap_err();
+        assert_eq!(error, DecompressError { kind: DecompressErrorKind::Corrupt, output: Vec::new() });
+    }
+
+    #[test]
+    fn test_compressed_equivalent() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8 ^ (i / 1000) as u8).collect();
+        let (fast, best) = (compress(&data, 1), compress(&data, 9));
+        assert_ne!(fast, best);
+
+        assert!(compressed_equivalent(&fast, &best));
+        assert!(!compressed_equivalent(&fast, &compress(&data[1..], 9)));
+        assert!(!compressed_equivalent(b"not zlib data", b"not zlib data"));
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::io::{Read, Write};
+
+use csv::{ReaderBuilder, StringRecord, Trim, Writer};
+
+use crate::types::*;
+
+/// The header row of the CSV format.
+const HEADER: [&str; 3] = ["frame", "kind", "key"];
+
+/// An error from importing input events from CSV.
+#[derive(Debug)]
+pub enum CsvImportError {
+    /// The CSV couldn't be read, e.g. because of an I/O error or invalid UTF-8.
+    Csv(csv::Error),
+    /// A row doesn't have exactly three fields.
+    WrongFieldCount {
+        /// The line the row is on, starting from 1.
+        line: u64,
+        /// The number of fields in the row.
+        count: usize,
+    },
+    /// The frame of a row isn't a non-negative integer.
+    InvalidFrame {
+        /// The line the row is on, starting from 1.
+        line: u64,
+        /// The contents of the field.
+        value: String,
+    },
+    /// The kind of a row is neither the name nor the code of an [`InputEventKind`].
+    InvalidKind {
+        /// The line the row is on, starting from 1.
+        line: u64,
+        /// The contents of the field.
+        value: String,
+    },
+    /// The key of a row is neither the name nor the code of an [`InputEventKey`].
+    InvalidKey {
+        /// The line the row is on, starting from 1.
+        line: u64,
+        /// The contents of the field.
+        value: String,
+    },
+}
+
+impl fmt::Display for CsvImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CsvImportError::*;
+
+        match self {
+            Csv(error) => write!(f, "Couldn't read the CSV: {error}"),
+            WrongFieldCount { line, count } => write!(
+                f,
+                "Line {line} has {count} fields, but it should have 3 (frame, kind, key)"
+            ),
+            InvalidFrame { line, value } => write!(
+                f,
+                "Line {line} has the frame '{value}', which isn't a non-negative integer"
+            ),
+            InvalidKind { line, value } => write!(
+                f,
+                "Line {line} has the kind '{value}', which should be Press, Release, 0 or 1"
+            ),
+            InvalidKey { line, value } => write!(
+                f,
+                "Line {line} has the key '{value}', which isn't a key name or a key code from 1 to 20"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CsvImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvImportError::Csv(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for CsvImportError {
+    fn from(value: csv::Error) -> Self {
+        Self::Csv(value)
+    }
+}
+
+impl GameReplayData {
+    /// Writes the inputs as CSV, with a `frame,kind,key` header row.
+    ///
+    /// The frames are absolute, and the kinds and keys are written by name, e.g. `60,Press,HardDrop`.
+    pub fn inputs_to_csv(&self, w: impl Write) -> std::io::Result<()> {
+        let mut writer = Writer::from_writer(w);
+
+        writer.write_record(HEADER)?;
+        for input in &self.inputs {
+            writer.write_record([
+                input.frame.to_string(),
+                format!("{:?}", input.kind),
+                input.key.name().to_string(),
+            ])?;
+        }
+
+        writer.flush()
+    }
+
+    /// Reads input events from CSV in the format written by [`inputs_to_csv`][GameReplayData::inputs_to_csv].
+    ///
+    /// The kinds and keys can be written either by name (case-insensitively) or by their code,
+    /// e.g. `Release` or `1`, and `HardDrop` or `6`. The header row is optional, whitespace around
+    /// fields is ignored, and the events are returned in the order they're written in.
+    pub fn inputs_from_csv(r: impl Read) -> Result<Vec<GameInputEvent>, CsvImportError> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(r);
+
+        let mut inputs = Vec::new();
+
+        for (index, record) in reader.records().enumerate() {
+            let record = record?;
+
+            if index == 0 && is_header(&record) {
+                continue;
+            }
+
+            inputs.push(parse_record(&record)?);
+        }
+
+        Ok(inputs)
+    }
+}
+
+fn is_header(record: &StringRecord) -> bool {
+    record.len() == HEADER.len()
+        && record.iter().zip(HEADER).all(|(field, name)| field.eq_ignore_ascii_case(name))
+}
+
+fn parse_record(record: &StringRecord) -> Result<GameInputEvent, CsvImportError> {
+    let line = record.position().map_or(0, |p| p.line());
+
+    let [frame, kind, key] = [0, 1, 2].map(|i| record.get(i).unwrap_or_default());
+    if record.len() != HEADER.len() {
+        return Err(CsvImportError::WrongFieldCount { line, count: record.len() });
+    }
+
+    let frame = frame
+        .parse()
+        .map_err(|_| CsvImportError::InvalidFrame { line, value: frame.to_string() })?;
+
+    let kind = InputEventKind::from_name(kind)
+        .or_else(|| kind.parse::<u8>().ok().and_then(|code| code.try_into().ok()))
+        .ok_or_else(|| CsvImportError::InvalidKind { line, value: kind.to_string() })?;
+
+    let key = key
+        .parse::<InputEventKey>()
+        .ok()
+        .or_else(|| key.parse::<u8>().ok().and_then(|code| code.try_into().ok()))
+        .ok_or_else(|| CsvImportError::InvalidKey { line, value: key.to_string() })?;
+
+    Ok(GameInputEvent { frame, kind, key })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_names_and_codes() {
+        let csv = "frame, kind, key\n10,Press,MoveLeft\n12, release ,1\n\n15,0,harddrop\n15,1,6\n";
+
+        assert_eq!(
+            GameReplayData::inputs_from_csv(csv.as_bytes()).unwrap(),
+            [
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::release(12, MoveLeft),
+                GameInputEvent::press(15, HardDrop),
+                GameInputEvent::release(15, HardDrop),
+            ]
+        );
+
+        // The header is optional
+        assert_eq!(
+            GameReplayData::inputs_from_csv("3,Press,Hold".as_bytes()).unwrap(),
+            [GameInputEvent::press(3, Hold)]
+        );
+    }
+
+    #[test]
+    fn test_bad_rows() {
+        let error = |csv: &str| {
+            GameReplayData::inputs_from_csv(csv.as_bytes()).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            error("frame,kind,key\n1,Press,Hold\n2,Press\n"),
+            "Line 3 has 2 fields, but it should have 3 (frame, kind, key)"
+        );
+        assert_eq!(
+            error("-1,Press,Hold\n"),
+            "Line 1 has the frame '-1', which isn't a non-negative integer"
+        );
+        assert_eq!(
+            error("1,Tap,Hold\n"),
+            "Line 1 has the kind 'Tap', which should be Press, Release, 0 or 1"
+        );
+        assert_eq!(
+            error("1,Press,21\n"),
+            "Line 1 has the key '21', which isn't a key name or a key code from 1 to 20"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::keyset::KeySet;
+use crate::types::*;
+
+/// How many events apart the snapshots of the held keys are taken.
+const SNAPSHOT_INTERVAL: usize = 64;
+
+/// A position in a replay that can be moved forwards and backwards, e.g. for a replay viewer.
+///
+/// The cursor sits at the start of a frame: the events on earlier frames have happened,
+/// and the events on the cursor's frame haven't happened yet. It starts at frame 0,
+/// before any event.
+///
+/// Moving forwards applies the events in between. Moving backwards restores the held keys
+/// from the closest snapshot, which is taken every few events, instead of replaying from the start.
+///
+/// The inputs must be sorted by frame. If they aren't, the results are unspecified.
+///
+/// Anomalies are handled like in [`KeyStateTimeline`][crate::KeyStateTimeline]:
+/// pressing a key that is already held, or releasing a key that isn't held, does nothing.
+#[derive(Clone, Debug)]
+pub struct ReplayCursor<'a> {
+    inputs: &'a [GameInputEvent],
+    /// The held keys after every [`SNAPSHOT_INTERVAL`] events, starting with no events.
+    snapshots: Vec<KeySet>,
+    frame: u64,
+    /// The number of events that have happened.
+    index: usize,
+    held: KeySet,
+}
+
+impl<'a> ReplayCursor<'a> {
+    /// Creates a cursor at the start of a replay.
+    pub fn new(data: &'a GameReplayData) -> ReplayCursor<'a> {
+        let inputs = data.inputs.as_slice();
+
+        let mut snapshots = vec![KeySet::new()];
+        let mut held = KeySet::new();
+
+        for chunk in inputs.chunks_exact(SNAPSHOT_INTERVAL) {
+            apply(&mut held, chunk);
+            snapshots.push(held);
+        }
+
+        ReplayCursor { inputs, snapshots, frame: 0, index: 0, held: KeySet::new() }
+    }
+
+    /// Returns the frame the cursor is at.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the keys held at the cursor, i.e. after the events on earlier frames.
+    pub fn current_key_state(&self) -> KeySet {
+        self.held
+    }
+
+    /// Moves the cursor to a frame, in either direction.
+    pub fn seek(&mut self, frame: u64) {
+        let target = self.inputs.partition_point(|i| i.frame < frame);
+
+        if target < self.index || target - self.index > SNAPSHOT_INTERVAL {
+            let snapshot = target / SNAPSHOT_INTERVAL;
+
+            self.index = snapshot * SNAPSHOT_INTERVAL;
+            self.held = self.snapshots[snapshot];
+        }
+
+        apply(&mut self.held, &self.inputs[self.index..target]);
+
+        self.index = target;
+        self.frame = frame;
+    }
+
+    /// Moves the cursor forwards by a number of frames,
+    /// returning the events that happened in between.
+    pub fn advance(&mut self, frames: u64) -> &'a [GameInputEvent] {
+        let start = self.index;
+        self.seek(self.frame.saturating_add(frames));
+
+        &self.inputs[start..self.index]
+    }
+
+    /// Moves the cursor backwards by a number of frames, stopping at frame 0.
+    pub fn rewind(&mut self, frames: u64) {
+        self.seek(self.frame.saturating_sub(frames));
+    }
+}
+
+fn apply(held: &mut KeySet, inputs: &[GameInputEvent]) {
+    for input in inputs {
+        match input.kind {
+            InputEventKind::Press => held.insert(input.key),
+            InputEventKind::Release => held.remove(input.key),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_held_before(inputs: &[GameInputEvent], frame: u64) -> KeySet {
+        let mut held = KeySet::new();
+        apply(&mut held, &inputs[..inputs.iter().take_while(|i| i.frame < frame).count()]);
+        held
+    }
+
+    #[test]
+    fn test_random_scrubbing() {
+        let mut next = crate::tests::helpers::xorshift(0x9E3779B97F4A7C15);
+
+        let mut frame = 0;
+        let inputs = (0..2000)
+            .map(|_| {
+                frame += next() % 5;
+                let value = next();
+                GameInputEvent {
+                    frame,
+                    kind: InputEventKind::from(value & 1 == 1),
+                    key: InputEventKey::try_from((value >> 1) as u8 % 6 + 1).unwrap(),
+                }
+            })
+            .collect();
+        let data = GameReplayData { inputs, ..Default::default() };
+        let last = data.inputs.last().unwrap().frame;
+
+        let mut cursor = ReplayCursor::new(&data);
+        assert_eq!(cursor.current_key_state(), KeySet::new());
+
+        for _ in 0..2000 {
+            let before = cursor.frame();
+            let frames = next() % 300;
+
+            match next() % 3 {
+                0 => cursor.seek(next() % (last + 20)),
+                1 => {
+                    let events = cursor.advance(frames);
+                    let expected: Vec<_> = data
+                        .inputs
+                        .iter()
+                        .copied()
+                        .filter(|i| (before..before + frames).contains(&i.frame))
+                        .collect();
+                    assert_eq!(events, expected);
+                    assert_eq!(cursor.frame(), before + frames);
+                }
+                _ => {
+                    cursor.rewind(frames);
+                    assert_eq!(cursor.frame(), before.saturating_sub(frames));
+                }
+            }
+
+            assert_eq!(
+                cursor.current_key_state(),
+                brute_force_held_before(&data.inputs, cursor.frame()),
+                "Mismatch at frame {}",
+                cursor.frame()
+            );
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use alloc::string::{String, ToString};
+
+use chrono::NaiveDateTime;
+
+use crate::types::*;
+
+/// The format the game writes the replay date in, as seen in replays from V0.17.1 to V0.17.18.
+const DATE_FORMAT: &str = "%Y/%m/%d %H:%M:%S";
+
+/// An error from parsing the date stored within the replay metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateParseError {
+    /// The date string did not match the format the game is known to use.
+    ///
+    /// Contains a [`String`] containing the date string.
+    UnrecognizedFormat(String),
+}
+
+impl GameReplayMetadata {
+    /// Parses the [`date`][GameReplayMetadata::date] field into a timestamp.
+    ///
+    /// The game writes the date in the player's local time with no timezone info,
+    /// so the result is a [`NaiveDateTime`].
+    pub fn parsed_date(&self) -> Result<NaiveDateTime, DateParseError> {
+        NaiveDateTime::parse_from_str(self.date.trim(), DATE_FORMAT)
+            .map_err(|_| DateParseError::UnrecognizedFormat(self.date.clone()))
+    }
+
+    /// Sets the [`date`][GameReplayMetadata::date] field from a timestamp.
+    ///
+    /// The date is written in the format the game uses, e.g. `2024/10/09 14:44:11`.
+    pub fn set_date(&mut self, date: NaiveDateTime) {
+        self.date = date.format(DATE_FORMAT).to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn datetime(ymd: (i32, u32, u32), hms: (u32, u32, u32)) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(ymd.0, ymd.1, ymd.2)
+            .unwrap()
+            .and_hms_opt(hms.0, hms.1, hms.2)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let cases = [
+            // V0.17.1@8fa2
+            ("2022/01/05 20:17:46", Some(datetime((2022, 1, 5), (20, 17, 46)))),
+            // V0.17.17
+            ("2024/10/09 14:44:11", Some(datetime((2024, 10, 9), (14, 44, 11)))),
+            // V0.17.18
+            ("2024/08/13 20:40:13", Some(datetime((2024, 8, 13), (20, 40, 13)))),
+            ("2024/08/13 20:40", None),
+            ("2024/13/01 00:00:00", None),
+            ("yesterday", None),
+            ("", None),
+        ];
+
+        for (input, expected) in cases {
+            let metadata = GameReplayMetadata {
+                date: input.to_string(),
+                ..Default::default()
+            };
+
+            match expected {
+                Some(dt) => assert_eq!(metadata.parsed_date(), Ok(dt)),
+                None => assert_eq!(
+                    metadata.parsed_date(),
+                    Err(DateParseError::UnrecognizedFormat(input.to_string()))
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_date() {
+        let mut metadata = GameReplayMetadata::default();
+        let dt = datetime((2021, 3, 14), (15, 9, 0));
+
+        metadata.set_date(dt);
+
+        assert_eq!(metadata.date, "2021/03/14 15:09:00");
+        assert_eq!(metadata.parsed_date(), Ok(dt));
+    }
+}
@@ -1,8 +1,19 @@
-use base64::engine::general_purpose::STANDARD as B64;
-use base64::Engine;
-use miniz_oxide::inflate;
+use alloc::string::String;
+use alloc::vec::Vec;
 
+use serde_json::error::Category;
+use serde_json::{Map, Value};
+
+use crate::codec::unpack;
+use crate::compression::decompress;
+use crate::detect::reject_non_replay;
+use crate::encoding;
+use crate::lua::LuaParser;
+use crate::strict;
 use crate::types::*;
+use crate::{KeyRegistry, ParseOptions, ParseReport, ParseWarning, ReplayEncoding};
+use crate::{ReplayMetadata, SplitReplay};
+use crate::vlq;
 
 impl GameReplayData {
     /// Parses a base64 string into a game replay.
@@ -10,6 +21,10 @@ impl GameReplayData {
     /// For parsing a replay from the contents of a `.rep` file in the game's `replays` directory,
     /// see [`parse_compressed_bytes`] instead.
     ///
+    /// The string may be mangled in the ways replays shared in chat messages often are:
+    /// whitespace and line breaks anywhere, the URL-safe alphabet (`-` and `_` instead of `+` and `/`),
+    /// missing `=` padding, and a leading `data:...;base64,` prefix are all accepted.
+    ///
     /// `parse_mode` is an optional argument used to specify how you want the inputs to be parsed.  
     /// This is useful for preventing errors from occurring if this function fails to recognize
     /// the game version to automatically infer its parse mode.  
@@ -18,9 +33,7 @@ impl GameReplayData {
         string: &str,
         parse_mode: Option<InputParseMode>,
     ) -> Result<GameReplayData, ReplayParseError> {
-        let data = B64.decode(string)?;
-
-        Ok(Self::try_from_compressed(&data, parse_mode)?)
+        Self::try_from_base64_as(string, parse_mode)
     }
 
     /// Parses a compressed byte array into a game replay.
@@ -37,9 +50,7 @@ impl GameReplayData {
         data: &[u8],
         parse_mode: Option<InputParseMode>,
     ) -> Result<GameReplayData, ReplayParseError> {
-        let data = inflate::decompress_to_vec_zlib(data)?;
-
-        Ok(Self::try_from_raw(&data, parse_mode)?)
+        Self::try_from_compressed_as(data, parse_mode)
     }
 
     /// Parses a raw, uncompressed byte array into a game replay.
@@ -55,27 +66,215 @@ impl GameReplayData {
         data: &[u8],
         parse_mode: Option<InputParseMode>,
     ) -> Result<GameReplayData, ReplayParseError> {
-        let first_newline = match data.iter().position(|&el| el == 10) {
-            Some(loc) => loc,
-            None => return Err(ReplayParseError::MetadataSeparatorNotFound),
+        Self::try_from_raw_as(data, parse_mode)
+    }
+
+    /// Parses a base64 string into a game replay, with the given options.
+    ///
+    /// See [`try_from_base64`][GameReplayData::try_from_base64] and [`ParseOptions`] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_base64", skip_all, fields(bytes = string.len()))
+    )]
+    pub fn try_from_base64_with(
+        string: &str,
+        options: &ParseOptions,
+    ) -> Result<GameReplayData, ReplayParseError> {
+        reject_non_replay(string.as_bytes(), ReplayEncoding::Base64)?;
+        let data = encoding::decode(string)?;
+
+        Self::try_from_compressed_with(&data, options)
+    }
+
+    /// Parses a compressed byte array into a game replay, with the given options.
+    ///
+    /// See [`try_from_compressed`][GameReplayData::try_from_compressed] and [`ParseOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_compressed", skip_all, fields(bytes = data.len()))
+    )]
+    pub fn try_from_compressed_with(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<GameReplayData, ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let data = decompress(data, usize::MAX)?;
+
+        Self::try_from_raw_with(&data, options)
+    }
+
+    /// Parses a raw, uncompressed byte array into a game replay, with the given options.
+    ///
+    /// See [`try_from_raw`][GameReplayData::try_from_raw] and [`ParseOptions`] for more information.
+    pub fn try_from_raw_with(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<GameReplayData, ReplayParseError> {
+        Self::parse_raw_with(data, options).map(|(data, _)| data)
+    }
+
+    /// Parses a raw replay with the options, also returning whether its metadata is a Lua table.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_raw", skip_all, fields(bytes = data.len()))
+    )]
+    fn parse_raw_with(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(GameReplayData, bool), ReplayParseError> {
+        let (data, lua) = match Self::parse_raw(data, options) {
+            Err(error @ ReplayParseError::MetadataDeserializeError { .. }) if options.legacy_lua_metadata => {
+                let split = SplitReplay::from_raw_with(data, options)?;
+                let metadata = lua_metadata(split.metadata_bytes).ok_or(error)??;
+                #[cfg(feature = "tracing")]
+                tracing::warn!("The metadata isn't JSON, but it was read as a Lua table");
+                (Self::from_parts(metadata, split.input_bytes, options)?, true)
+            }
+            data => (data?, false),
         };
 
-        let (metadata_slice, input_slice) = data.split_at(first_newline);
+        if options.strict {
+            strict::check(&data, options)?;
+        }
 
-        let input_slice = &input_slice[1..];
+        Ok((data, lua))
+    }
 
-        let metadata = GameReplayMetadata::try_from(metadata_slice)?;
+    /// Parses a base64 string into a game replay, also reporting non-fatal anomalies.
+    ///
+    /// See [`try_from_raw_with_report`][GameReplayData::try_from_raw_with_report] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_base64", skip_all, fields(bytes = string.len()))
+    )]
+    pub fn try_from_base64_with_report(
+        string: &str,
+        options: &ParseOptions,
+    ) -> Result<(GameReplayData, ParseReport), ReplayParseError> {
+        reject_non_replay(string.as_bytes(), ReplayEncoding::Base64)?;
+        let data = encoding::decode(string)?;
+
+        Self::try_from_compressed_with_report(&data, options)
+    }
 
-        let parse_mode = match parse_mode
-            .or_else(|| InputParseMode::try_infer_from_version(&metadata.version))
-        {
+    /// Parses a compressed byte array into a game replay, also reporting non-fatal anomalies.
+    ///
+    /// See [`try_from_raw_with_report`][GameReplayData::try_from_raw_with_report] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_compressed", skip_all, fields(bytes = data.len()))
+    )]
+    pub fn try_from_compressed_with_report(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(GameReplayData, ParseReport), ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let data = decompress(data, usize::MAX)?;
+
+        Self::try_from_raw_with_report(&data, options)
+    }
+
+    /// Parses a raw, uncompressed byte array into a game replay, also reporting non-fatal anomalies.
+    ///
+    /// This works like [`try_from_raw`][GameReplayData::try_from_raw], but additionally returns a
+    /// [`ParseReport`] listing anything suspicious that didn't stop the replay from being parsed,
+    /// such as duplicate inputs or unknown metadata keys. See [`ParseWarning`] for the full list.
+    pub fn try_from_raw_with_report(
+        data: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(GameReplayData, ParseReport), ReplayParseError> {
+        let (data, lua) = Self::parse_raw_with(data, options)?;
+        let mut report = ParseReport::from_replay(&data);
+        if lua {
+            report.warnings.insert(0, ParseWarning::LegacyLuaMetadata);
+        }
+
+        Ok((data, report))
+    }
+}
+
+impl<M: ReplayMetadata> GameReplayData<M> {
+    /// Parses a base64 string into a game replay with any type of metadata.
+    ///
+    /// See [`try_from_base64`][GameReplayData::try_from_base64] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_base64", skip_all, fields(bytes = string.len()))
+    )]
+    pub fn try_from_base64_as(
+        string: &str,
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<GameReplayData<M>, ReplayParseError> {
+        reject_non_replay(string.as_bytes(), ReplayEncoding::Base64)?;
+        let data = encoding::decode(string)?;
+
+        Self::try_from_compressed_as(&data, parse_mode)
+    }
+
+    /// Parses a compressed byte array into a game replay with any type of metadata.
+    ///
+    /// See [`try_from_compressed`][GameReplayData::try_from_compressed] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_compressed", skip_all, fields(bytes = data.len()))
+    )]
+    pub fn try_from_compressed_as(
+        data: &[u8],
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<GameReplayData<M>, ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let data = decompress(data, usize::MAX)?;
+
+        Self::try_from_raw_as(&data, parse_mode)
+    }
+
+    /// Parses a raw, uncompressed byte array into a game replay with any type of metadata.
+    ///
+    /// See [`try_from_raw`][GameReplayData::try_from_raw] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_raw", skip_all, fields(bytes = data.len()))
+    )]
+    pub fn try_from_raw_as(
+        data: &[u8],
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<GameReplayData<M>, ReplayParseError> {
+        Self::parse_raw(data, &ParseOptions { parse_mode, ..ParseOptions::default() })
+    }
+
+    fn parse_raw(data: &[u8], options: &ParseOptions) -> Result<GameReplayData<M>, ReplayParseError> {
+        let split = SplitReplay::from_raw_with(data, options)?;
+
+        let metadata = M::try_from(split.metadata_bytes)?;
+
+        Self::from_parts(metadata, split.input_bytes, options)
+    }
+
+    /// Parses the input data of a replay whose metadata is already parsed.
+    fn from_parts(
+        metadata: M,
+        input_bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<GameReplayData<M>, ReplayParseError> {
+        // Without inputs, it doesn't matter how they're timed
+        if input_bytes.is_empty() {
+            return Ok(GameReplayData { inputs: Vec::new(), metadata, input_mode: None });
+        }
+
+        let version = metadata.version();
+        let inferred = || version.and_then(InputParseMode::try_infer_from_version);
+        let parse_mode = match options.parse_mode.or_else(inferred) {
             Some(mode) => mode,
-            None => return Err(ReplayParseError::UnknownInputParseMode(metadata.version)),
+            None => {
+                return Err(ReplayParseError::UnknownInputParseMode(String::from(version.unwrap_or_default())))
+            }
         };
 
         Ok(GameReplayData {
-            inputs: parse_input_slice(input_slice, parse_mode)?,
+            inputs: parse_input_slice_with(input_bytes, parse_mode, options.key_registry.as_ref())?,
             metadata,
+            input_mode: None,
         })
     }
 }
@@ -84,63 +283,160 @@ impl TryFrom<&[u8]> for GameReplayMetadata {
     type Error = ReplayParseError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let string = String::from_utf8(Vec::from(value))?;
+        let Ok(string) = core::str::from_utf8(value) else {
+            // The error holds a `FromUtf8Error`, which needs an owned copy of the bytes
+            let error = String::from_utf8(Vec::from(value)).expect_err("the metadata isn't UTF-8");
+            return Err(error.into());
+        };
+
+        serde_json::from_str(string).map_err(|error| metadata_error(error, string))
+    }
+}
+
+/// Parses metadata that's a Lua table, e.g. `{player="Nova",seed=1,...}`.
+///
+/// This is best-effort: no replay with such metadata has been found to check it against.
+/// Returns [`None`] if it isn't a Lua table, and an error if it is one, but doesn't fit.
+fn lua_metadata(bytes: &[u8]) -> Option<Result<GameReplayMetadata, ReplayParseError>> {
+    let text = core::str::from_utf8(bytes).ok()?;
+    let mut value = LuaParser::parse(text).ok()?;
+    if !value.is_object() {
+        return None;
+    }
+
+    // Empty tables are read as lists, but the settings are a table with keys
+    if value["setting"].as_array().is_some_and(Vec::is_empty) {
+        value["setting"] = Value::Object(Map::new());
+    }
+
+    Some(serde_json::from_value(value).map_err(|error| metadata_error(error, text)))
+}
+
+/// The number of bytes of metadata on either side of an error that its snippet shows.
+const SNIPPET_BYTES: usize = 40;
 
-        Ok(serde_json::from_str(&string)?)
+/// Wraps an error from deserializing the metadata, finding where it is in the metadata.
+fn metadata_error(error: serde_json::Error, metadata: &str) -> ReplayParseError {
+    let kind = match error.classify() {
+        Category::Data => MetadataErrorKind::Data,
+        Category::Syntax | Category::Eof | Category::Io => MetadataErrorKind::Syntax,
+    };
+
+    let (line, column) = (error.line(), error.column());
+
+    // serde_json counts columns in bytes, and points at the last byte it read
+    let line_start: usize = metadata.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+    let offset = (line_start + column.saturating_sub(1)).min(metadata.len());
+
+    let mut start = offset.saturating_sub(SNIPPET_BYTES);
+    while !metadata.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (offset + SNIPPET_BYTES).min(metadata.len());
+    while !metadata.is_char_boundary(end) {
+        end += 1;
+    }
+
+    ReplayParseError::MetadataDeserializeError {
+        kind,
+        offset,
+        line,
+        column,
+        snippet: String::from(&metadata[start..end]),
+        error,
     }
 }
 
+/// The number of values before and after a malformed input that its error shows.
+const CONTEXT_VALUES: usize = 4;
+
 pub(crate) fn parse_input_slice(
     input_slice: &[u8],
     parse_mode: InputParseMode,
 ) -> Result<Vec<GameInputEvent>, ReplayParseError> {
-    let values = extract_vlqs(input_slice);
+    parse_input_slice_with(input_slice, parse_mode, None)
+}
 
-    let mut events = Vec::with_capacity(values.len() / 2);
+/// Parses the input data, with the codes the registry has names for as
+/// [`Other`][InputEventKey::Other] keys.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "decode_inputs",
+        level = "debug",
+        skip_all,
+        fields(bytes = input_slice.len(), ?parse_mode, events = tracing::field::Empty)
+    )
+)]
+pub(crate) fn parse_input_slice_with(
+    input_slice: &[u8],
+    parse_mode: InputParseMode,
+    keys: Option<&KeyRegistry>,
+) -> Result<Vec<GameInputEvent>, ReplayParseError> {
+    let mut values = vlq::decode_iter(input_slice);
+
+    // Estimation: an input usually takes 2 or 3 bytes
+    let mut events = Vec::with_capacity(input_slice.len() / 3);
 
-    let mut prev_timestamp = 0;
-    for (position, chunk) in values.chunks_exact(2).enumerate() {
-        let (time, key) = (chunk[0], chunk[1]);
+    let mut prev_frame = 0;
+    loop {
+        let time_offset = values.position();
+        let Some(time) = values.next() else { break };
+        let time = time?;
 
-        let frame = match parse_mode {
-            InputParseMode::Relative => time + prev_timestamp,
-            InputParseMode::Absolute => time,
+        // A trailing time without a key code is ignored
+        let code_offset = values.position();
+        let Some(code) = values.next() else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(offset = time_offset, "Ignored a trailing time without a key code");
+            break;
         };
+        let code = code?;
 
-        let kind = InputEventKind::from(key > 0b100000);
-        let key = InputEventKey::try_from(key as u8 & 0b011111).map_err(|_| {
-            ReplayParseError::MalformedInputData {
-                frame,
-                position: position as u64 * 2,
-                kind: key,
-            }
-        })?;
+        let event = match unpack(time, code, parse_mode, prev_frame, keys) {
+            Ok(event) => event,
+            Err(mut error) => {
+                // Malformed VLQs anywhere in the input data take precedence over malformed inputs
+                if let Some(vlq_error) = values.clone().find_map(Result::err) {
+                    return Err(vlq_error.into());
+                }
 
-        prev_timestamp = frame;
+                if let ReplayParseError::MalformedInputData {
+                    offset,
+                    index,
+                    frame,
+                    values_before,
+                    values_after,
+                    ..
+                } = &mut error
+                {
+                    *offset = if frame.is_some() { code_offset } else { time_offset };
+                    *index = events.len();
 
-        events.push(GameInputEvent { frame, key, kind });
-    }
+                    // Decoded again, as keeping the last few values around would slow down the happy path
+                    let before = events.len() * 2;
+                    let skipped = before.saturating_sub(CONTEXT_VALUES);
+                    *values_before = vlq::decode_iter(input_slice)
+                        .skip(skipped)
+                        .take(before - skipped)
+                        .map_while(Result::ok)
+                        .collect();
+                    *values_after = values.map_while(Result::ok).take(CONTEXT_VALUES).collect();
+                }
 
-    Ok(events)
-}
-
-pub(crate) fn extract_vlqs(vlqs: &[u8]) -> Vec<u64> {
-    let mut numbers = Vec::with_capacity(vlqs.len());
+                return Err(error);
+            }
+        };
 
-    let mut cur_num: u64 = 0;
-    for &vlq in vlqs.iter() {
-        let value = vlq & 0x7F;
-        cur_num <<= 7;
-        cur_num |= value as u64;
+        prev_frame = event.frame;
 
-        let msb = vlq >= 0x80;
-        if !msb {
-            numbers.push(cur_num);
-            cur_num = 0;
-        }
+        events.push(event);
     }
 
-    numbers
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("events", events.len());
+
+    Ok(events)
 }
 
 #[cfg(test)]
@@ -169,7 +465,149 @@ mod tests {
         ];
 
         for (input, expected) in cases {
-            assert_eq!(extract_vlqs(&input), expected);
+            assert_eq!(vlq::decode(&input).unwrap(), expected);
         }
     }
+
+    #[test]
+    fn test_metadata_not_utf8() {
+        let error = GameReplayMetadata::try_from(&b"{\"player\":\"\xff\"}"[..]).unwrap_err();
+
+        match error {
+            ReplayParseError::MetadataNotUtf8(error) => assert_eq!(error.utf8_error().valid_up_to(), 11),
+            other => panic!("Expected MetadataNotUtf8, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_metadata_errors() {
+        use alloc::string::ToString;
+
+        let mut data = GameReplayData::default();
+        data.metadata.version = String::from("V0.17.17");
+        let raw = data.serialize_to_raw(None).unwrap();
+        let metadata = core::str::from_utf8(SplitReplay::from_raw(&raw).unwrap().metadata_bytes).unwrap();
+
+        let trailing_comma = metadata.replacen('}', ",}", 1);
+        let error = GameReplayData::try_from_raw(format!("{trailing_comma}\n").as_bytes(), None).unwrap_err();
+        match &error {
+            ReplayParseError::MetadataDeserializeError { kind, offset, line, snippet, .. } => {
+                assert_eq!(*kind, MetadataErrorKind::Syntax);
+                assert_eq!(*line, 1);
+                // The error is found at the brace after the comma
+                assert_eq!(trailing_comma.as_bytes()[*offset], b'}');
+                assert!(snippet.contains(",}") && snippet.len() <= 81, "{snippet}");
+            }
+            other => panic!("Expected MetadataDeserializeError, got {other:?}"),
+        }
+        let message = error.to_string();
+        let expected = "The metadata isn't valid JSON: trailing comma at line 1, column";
+        assert!(message.starts_with(expected), "{message}");
+
+        let no_version = metadata.replace("\"version\":\"V0.17.17\",", "");
+        assert_ne!(no_version, metadata);
+        let error = GameReplayData::try_from_raw(format!("{no_version}\n").as_bytes(), None).unwrap_err();
+        assert!(matches!(
+            error,
+            ReplayParseError::MetadataDeserializeError { kind: MetadataErrorKind::Data, line: 1, .. }
+        ));
+        let message = error.to_string();
+        let expected = "The metadata is valid JSON, but doesn't fit: missing field `version`";
+        assert!(message.starts_with(expected), "{message}");
+    }
+
+    #[test]
+    fn test_legacy_lua_metadata() {
+        use crate::{ParseOptions, ParseWarning};
+
+        let lua = concat!(
+            r#"{player="Nova \"the\" \92o/",seed=7,version="V0.17.22",date="",mode="custom_clear","#,
+            r#"setting={},mod={{9,2}},private={BAG={5,7},CUSTOMENV={sequence='bag',["end"]=-.5}}}"#,
+            "\n\x0a\x06"
+        );
+        let options = ParseOptions::new().legacy_lua_metadata(true);
+
+        // Only with the option
+        let error = GameReplayData::try_from_raw(lua.as_bytes(), None).unwrap_err();
+        assert!(matches!(error, ReplayParseError::MetadataDeserializeError { .. }));
+
+        let (replay, report) = GameReplayData::try_from_raw_with_report(lua.as_bytes(), &options).unwrap();
+        assert_eq!(report.warnings, [ParseWarning::LegacyLuaMetadata]);
+        assert_eq!(replay.metadata.player, "Nova \"the\" \\o/");
+        assert_eq!(replay.metadata.mods, Some(vec![(9, serde_json::json!(2))]));
+        let private = serde_json::json!({ "BAG": [5, 7], "CUSTOMENV": { "sequence": "bag", "end": -0.5 } });
+        assert_eq!(replay.metadata.private, Some(private));
+        assert_eq!(replay.inputs.len(), 1);
+
+        // JSON metadata isn't reported, and a Lua table that doesn't fit fails like JSON would
+        let json = GameReplayData::default().serialize_to_raw(None).unwrap();
+        let (_, report) = GameReplayData::try_from_raw_with_report(&json, &options).unwrap();
+        assert!(report.is_empty());
+        let cases = [
+            (&b"{player='Nova'}\n"[..], MetadataErrorKind::Data),
+            (b"{das=}\n", MetadataErrorKind::Syntax),
+        ];
+        for (raw, expected) in cases {
+            match GameReplayData::try_from_raw_with(raw, &options).unwrap_err() {
+                ReplayParseError::MetadataDeserializeError { kind, .. } => assert_eq!(kind, expected),
+                other => panic!("Expected MetadataDeserializeError, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_input_section() {
+        use crate::ParseOptions;
+
+        let json = br#"{"player":"Nova","mode":"sprint_10l","version":"V0.17.22","seed":1,"date":"","#;
+        let json = &[&json[..], br#""setting":{}}"#].concat();
+        let options = ParseOptions::new().allow_missing_input_section(true);
+
+        // A linefeed followed by nothing is always a replay without inputs
+        let with_separator = [&json[..], b"\n"].concat();
+        let parsed = GameReplayData::try_from_raw(&with_separator, None).unwrap();
+        assert!(parsed.inputs.is_empty());
+        assert_eq!(GameReplayData::try_from_raw_with(&with_separator, &options).unwrap(), parsed);
+        assert_eq!(parsed.metadata.player, "Nova");
+
+        let serialized = parsed.serialize_to_raw(None).unwrap();
+        assert_eq!(serialized.last(), Some(&b'\n'));
+        assert_eq!(GameReplayData::try_from_raw(&serialized, None).unwrap(), parsed);
+
+        // Without the linefeed, only if the options allow it
+        assert!(matches!(
+            GameReplayData::try_from_raw(json, None),
+            Err(ReplayParseError::MetadataSeparatorNotFound)
+        ));
+        let parsed = GameReplayData::try_from_raw_with(json, &options).unwrap();
+        assert!(parsed.inputs.is_empty());
+
+        let serialized = parsed.serialize_to_compressed(None).unwrap();
+        assert_eq!(GameReplayData::try_from_compressed_with(&serialized, &options).unwrap(), parsed);
+        assert_eq!(GameReplayData::try_from_compressed(&serialized, None).unwrap(), parsed);
+
+        // It doesn't matter how inputs that aren't there are timed
+        let mut unknown_version = parsed;
+        unknown_version.metadata.version = String::from("custom build");
+        let serialized = unknown_version.serialize_to_raw(None).unwrap();
+        assert_eq!(GameReplayData::try_from_raw(&serialized, None).unwrap(), unknown_version);
+    }
+
+    #[test]
+    fn test_input_slice_errors() {
+        use crate::vlq::VlqError;
+
+        // Key bits 0 aren't a key, and the last value is cut off
+        assert!(matches!(
+            parse_input_slice(&[0x05, 0x06, 0x07, 0x00], InputParseMode::Absolute),
+            Err(ReplayParseError::MalformedInputData { offset: 3, index: 1, frame: Some(7), .. })
+        ));
+        assert!(matches!(
+            parse_input_slice(&[0x05, 0x06, 0x07, 0x00, 0x81], InputParseMode::Absolute),
+            Err(ReplayParseError::MalformedVlq(VlqError::Truncated { position: 4 }))
+        ));
+
+        // A trailing time without a key code is ignored
+        assert_eq!(parse_input_slice(&[0x05, 0x06, 0x07], InputParseMode::Absolute).unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,202 @@
+use core::{fmt, str};
+
+use crate::encoding;
+use crate::types::*;
+
+/// The ways a replay can be encoded, as told apart by [`detect_replay_encoding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReplayEncoding {
+    /// The base64 string the game imports and exports,
+    /// parsed with [`GameReplayData::try_from_base64`][crate::GameReplayData::try_from_base64].
+    Base64,
+    /// The zlib-compressed bytes the game saves as `.rep` files,
+    /// parsed with [`GameReplayData::try_from_compressed`][crate::GameReplayData::try_from_compressed].
+    Compressed,
+    /// The uncompressed metadata JSON and input data,
+    /// parsed with [`GameReplayData::try_from_raw`][crate::GameReplayData::try_from_raw].
+    Raw,
+    /// None of the above.
+    Unknown,
+}
+
+/// Guesses how a replay is encoded, e.g. to parse a file or pasted text without being told.
+///
+/// This only looks at the shape of the data, so a result other than [`ReplayEncoding::Unknown`]
+/// doesn't mean that the replay will parse:
+/// - [`Compressed`][ReplayEncoding::Compressed] data starts with a zlib header,
+///   e.g. `0x78 0x9C` as the game writes it;
+/// - [`Raw`][ReplayEncoding::Raw] data starts with the `{` of the metadata JSON
+///   and has the linefeed that ends it;
+/// - [`Base64`][ReplayEncoding::Base64] data is only made of base64 characters,
+///   in any of the forms [`try_from_base64`][crate::GameReplayData::try_from_base64] accepts.
+///
+/// None of the base64 characters can start a zlib header or the metadata JSON,
+/// so the three can't be mistaken for each other.
+///
+/// ```
+/// use techmino_replay_toolkit::{detect_replay_encoding, ReplayEncoding};
+///
+/// assert_eq!(detect_replay_encoding(b"eJzLSM3JyQcABiwCFQ=="), ReplayEncoding::Base64);
+/// assert_eq!(detect_replay_encoding(b"\x78\x9c\xcb\x48\xcd\xc9\xc9\x07\x00"), ReplayEncoding::Compressed);
+/// assert_eq!(detect_replay_encoding(b"{\"version\":\"V0.17.22\"}\n\x0a\x06"), ReplayEncoding::Raw);
+/// assert_eq!(detect_replay_encoding(b"\x89PNG\r\n"), ReplayEncoding::Unknown);
+/// ```
+pub fn detect_replay_encoding(data: &[u8]) -> ReplayEncoding {
+    if data.first() == Some(&b'{') && data.contains(&b'\n') {
+        return ReplayEncoding::Raw;
+    }
+
+    if let [cmf, flg, ..] = *data {
+        // Deflate with a window of at most 32 KiB, and a header checksum
+        if cmf & 0x0F == 8 && cmf >> 4 <= 7 && u16::from_be_bytes([cmf, flg]) % 31 == 0 {
+            return ReplayEncoding::Compressed;
+        }
+    }
+
+    match str::from_utf8(data) {
+        Ok(string) if encoding::looks_like_base64(string) => ReplayEncoding::Base64,
+        _ => ReplayEncoding::Unknown,
+    }
+}
+
+/// What data that isn't a replay most likely is, as reported by
+/// [`ReplayParseError::NotAReplay`].
+///
+/// These are the things that are commonly mistaken for replays, e.g. by users of a website.
+/// Its [`Display`][fmt::Display] form describes it, e.g. `a PNG image`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DetectedFormat {
+    /// JSON text, e.g. replay metadata on its own, or the output of another tool.
+    Json,
+    /// A PNG image, e.g. a screenshot of the game.
+    Png,
+    /// A zip archive, e.g. the game's data export, which has the replays in it.
+    Zip,
+    /// Lua code, e.g. one of the game's save files, which start with `return`.
+    Lua,
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectedFormat::Json => write!(f, "JSON text"),
+            DetectedFormat::Png => write!(f, "a PNG image"),
+            DetectedFormat::Zip => write!(f, "a zip archive"),
+            DetectedFormat::Lua => write!(f, "Lua code"),
+        }
+    }
+}
+
+/// Tells what the data is, if it's one of the things commonly mistaken for replays.
+pub(crate) fn detect_format(data: &[u8]) -> Option<DetectedFormat> {
+    let data = data.strip_prefix("\u{feff}".as_bytes()).unwrap_or(data).trim_ascii_start();
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(DetectedFormat::Png)
+    } else if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        Some(DetectedFormat::Zip)
+    } else if data.starts_with(b"{") || data.starts_with(b"[") {
+        Some(DetectedFormat::Json)
+    } else if ["return", "local ", "--"].iter().any(|start| data.starts_with(start.as_bytes())) {
+        Some(DetectedFormat::Lua)
+    } else {
+        None
+    }
+}
+
+/// Fails early on data that's empty, or that's clearly something other than a replay in the
+/// expected encoding, with an error that tells what it is.
+pub(crate) fn reject_non_replay(data: &[u8], expected: ReplayEncoding) -> Result<(), ReplayParseError> {
+    if data.trim_ascii().is_empty() {
+        return Err(ReplayParseError::EmptyInput);
+    }
+    if detect_replay_encoding(data) == expected {
+        return Ok(());
+    }
+
+    match detect_format(data) {
+        Some(detected) => Err(ReplayParseError::NotAReplay { detected }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::compress;
+
+    #[test]
+    fn test_detect() {
+        let raw = b"{\"version\":\"V0.17.22\",\"player\":\"eJ\"}\n\x0a\x06\x0b\x86";
+        let compressed = compress(raw, 6);
+        let base64 = "eJyrVkpLLUrNKcnMSVWyUrJUsjDS0zYxMkpOzSsqqYSfgn5AFSqUMB0QEyqhGT";
+
+        assert_eq!(detect_replay_encoding(raw), ReplayEncoding::Raw);
+        assert_eq!(detect_replay_encoding(&compressed), ReplayEncoding::Compressed);
+        for level in [0, 1, 9] {
+            assert_eq!(detect_replay_encoding(&compress(raw, level)), ReplayEncoding::Compressed);
+        }
+
+        // Compressed replays in base64 start with `e`, for the 0x78 of the zlib header
+        for string in [base64, "eNoBAAD__w", "xA==", "  eJyrVkpL\nLUrNKcnM\n", "data:;base64,eJyrVkpL"] {
+            assert_eq!(detect_replay_encoding(string.as_bytes()), ReplayEncoding::Base64, "{string:?}");
+        }
+
+        let unknown = [&b""[..], b"  \n", b"==", b"{\"version\":\"V0.17.22\"}", b"\x78\x00", b"eJ+*", b"\xff\xfe"];
+        for data in unknown {
+            assert_eq!(detect_replay_encoding(data), ReplayEncoding::Unknown, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn test_not_a_replay() {
+        use crate::GameReplayData;
+        use alloc::string::ToString;
+
+        for empty in ["", "  \n\t"] {
+            let error = GameReplayData::try_from_base64(empty, None).unwrap_err();
+            assert!(matches!(error, ReplayParseError::EmptyInput), "{error:?}");
+            let error = GameReplayData::try_from_compressed(empty.as_bytes(), None).unwrap_err();
+            assert!(matches!(error, ReplayParseError::EmptyInput), "{error:?}");
+        }
+
+        let cases = [
+            (&b"{\"player\":\"Nova\"}"[..], DetectedFormat::Json),
+            (b"\xef\xbb\xbf  [1, 2]", DetectedFormat::Json),
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", DetectedFormat::Png),
+            (b"PK\x03\x04\x14\0\0\0\x08\0", DetectedFormat::Zip),
+            (b"return{das=10,arr=2}", DetectedFormat::Lua),
+            (b"local replay = {}", DetectedFormat::Lua),
+        ];
+        for (data, expected) in cases {
+            let mut results = vec![GameReplayData::try_from_compressed(data, None)];
+            // Images can't be pasted as text
+            if let Ok(string) = core::str::from_utf8(data) {
+                results.push(GameReplayData::try_from_base64(string, None));
+            }
+
+            for result in results {
+                match result.unwrap_err() {
+                    ReplayParseError::NotAReplay { detected } => assert_eq!(detected, expected, "{data:?}"),
+                    other => panic!("Expected NotAReplay, got {other:?}"),
+                }
+            }
+        }
+
+        let error = ReplayParseError::NotAReplay { detected: DetectedFormat::Png };
+        let expected = "This is a PNG image, not a replay; expected the text the game copies when exporting";
+        assert!(error.to_string().starts_with(expected), "{error}");
+        let error = ReplayParseError::NotAReplay { detected: DetectedFormat::Zip }.to_string();
+        assert!(error.contains("unzip it"), "{error}");
+
+        // Anything else still fails on the decoding, and replays are never mistaken for anything else
+        let error = GameReplayData::try_from_base64("not base64!", None).unwrap_err();
+        assert!(matches!(error, ReplayParseError::Base64DecodeError(_)), "{error:?}");
+        let error = GameReplayData::try_from_compressed(b"\x78\x9c\x01", None).unwrap_err();
+        assert!(matches!(error, ReplayParseError::ZlibDecompressError(_)), "{error:?}");
+        let metadata = GameReplayMetadata { version: "V0.17.22".into(), ..Default::default() };
+        let replay = GameReplayData { metadata, ..Default::default() };
+        assert!(GameReplayData::try_from_base64(&replay.serialize_to_base64(None).unwrap(), None).is_ok());
+    }
+}
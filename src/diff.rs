@@ -0,0 +1,398 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::types::*;
+
+/// The point where the input streams of two replays start to differ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputDivergence {
+    /// The index of the first input event that differs.
+    pub index: usize,
+    /// The frame of the first input event that differs.
+    ///
+    /// If both replays have an event at [`index`][InputDivergence::index],
+    /// this is the earlier of the two frames.
+    pub frame: u64,
+}
+
+/// The differences between two replays.
+///
+/// See [`GameReplayData::diff`] for more information.
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayDiff {
+    /// The paths of the metadata fields that differ, in the style of a JSON pointer
+    /// (e.g. `/setting/das`).
+    ///
+    /// The paths use the field names of the metadata JSON, not the struct field names.
+    pub metadata_differences: Vec<String>,
+    /// The point where the input streams start to differ, if they differ at all.
+    pub first_divergence: Option<InputDivergence>,
+    /// The number of input events that are in the other replay but not in this one.
+    pub added_events: usize,
+    /// The number of input events that are in this replay but not in the other one.
+    pub removed_events: usize,
+    /// Whether the input streams only differ in the order of events on the same frame.
+    pub ordering_only: bool,
+}
+
+/// A setting that differs between two [`PlayerSettings`], found by [`PlayerSettings::diff`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingDiff {
+    /// The name of the setting, as written in the metadata JSON.
+    pub field: String,
+    /// The value in the first settings, or `null` if it isn't set there.
+    pub old: Value,
+    /// The value in the other settings, or `null` if it isn't set there.
+    pub new: Value,
+}
+
+/// A metadata field that differs between two [`GameReplayMetadata`],
+/// found by [`GameReplayMetadata::diff`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataDiff {
+    /// The name of the field, as written in the metadata JSON.
+    /// Settings are prefixed with `setting.`, e.g. `setting.das`.
+    pub field: String,
+    /// The value in the first metadata, or `null` if it isn't there.
+    pub old: Value,
+    /// The value in the other metadata, or `null` if it isn't there.
+    pub new: Value,
+}
+
+impl fmt::Display for SettingDiff {
+    /// Formats the difference like `das: 8 -> 10`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+impl fmt::Display for MetadataDiff {
+    /// Formats the difference like `setting.das: 8 -> 10`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+impl PlayerSettings {
+    /// Lists the settings whose values differ from the other settings, sorted by name.
+    ///
+    /// A setting that isn't set counts as `null`, and nonstandard settings are included.
+    /// Numbers are compared by value, so `1` and `1.0` are the same.
+    pub fn diff(&self, other: &PlayerSettings) -> Vec<SettingDiff> {
+        let ours = serde_json::to_value(self).unwrap_or(Value::Null);
+        let theirs = serde_json::to_value(other).unwrap_or(Value::Null);
+
+        diff_fields(&ours, &theirs)
+            .into_iter()
+            .map(|(field, old, new)| SettingDiff { field, old, new })
+            .collect()
+    }
+}
+
+impl GameReplayMetadata {
+    /// Lists the fields whose values differ from the other metadata, sorted by name,
+    /// with the differing settings listed in place of the `setting` field.
+    ///
+    /// See [`PlayerSettings::diff`] for how the values are compared. Other nested values,
+    /// like the [`private`][GameReplayMetadata::private] data, are compared as a whole.
+    pub fn diff(&self, other: &GameReplayMetadata) -> Vec<MetadataDiff> {
+        let ours = serde_json::to_value(self).unwrap_or(Value::Null);
+        let theirs = serde_json::to_value(other).unwrap_or(Value::Null);
+
+        let mut diffs = Vec::new();
+        for (field, old, new) in diff_fields(&ours, &theirs) {
+            if field == "setting" {
+                let settings = diff_fields(&old, &new).into_iter().map(|(field, old, new)| MetadataDiff {
+                    field: format!("setting.{field}"),
+                    old,
+                    new,
+                });
+                diffs.extend(settings);
+            } else {
+                diffs.push(MetadataDiff { field, old, new });
+            }
+        }
+
+        diffs
+    }
+}
+
+impl ReplayDiff {
+    /// Returns whether the two replays are the same.
+    pub fn is_empty(&self) -> bool {
+        self.metadata_differences.is_empty() && self.first_divergence.is_none()
+    }
+}
+
+impl fmt::Display for ReplayDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "The replays are identical");
+        }
+
+        if self.metadata_differences.is_empty() {
+            writeln!(f, "Metadata: identical")?;
+        } else {
+            writeln!(
+                f,
+                "Metadata: {} difference(s) at {}",
+                self.metadata_differences.len(),
+                self.metadata_differences.join(", ")
+            )?;
+        }
+
+        match self.first_divergence {
+            None => write!(f, "Inputs: identical"),
+            Some(InputDivergence { index, frame }) => {
+                write!(
+                    f,
+                    "Inputs: first divergence at index {index} (frame {frame}), \
+                    {} added, {} removed",
+                    self.added_events, self.removed_events
+                )?;
+
+                if self.ordering_only {
+                    write!(f, " (only the order of same-frame events differs)")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl GameReplayData {
+    /// Compares this replay to another replay.
+    ///
+    /// "Added" and "removed" events are counted from the perspective of this replay,
+    /// i.e. added events are the ones only the other replay has.
+    pub fn diff(&self, other: &GameReplayData) -> ReplayDiff {
+        let mut diff = ReplayDiff::default();
+
+        let ours = serde_json::to_value(&self.metadata).unwrap_or(Value::Null);
+        let theirs = serde_json::to_value(&other.metadata).unwrap_or(Value::Null);
+        diff_values(&ours, &theirs, &mut String::new(), &mut diff.metadata_differences);
+
+        let (a, b) = (&self.inputs, &other.inputs);
+
+        let index = a.iter().zip(b.iter()).position(|(x, y)| x != y);
+        let index = match index {
+            Some(i) => i,
+            None if a.len() == b.len() => return diff,
+            None => a.len().min(b.len()),
+        };
+
+        let frame = match (a.get(index), b.get(index)) {
+            (Some(x), Some(y)) => x.frame.min(y.frame),
+            (Some(x), None) | (None, Some(x)) => x.frame,
+            (None, None) => unreachable!(),
+        };
+
+        diff.first_divergence = Some(InputDivergence { index, frame });
+
+        let mut counts: BTreeMap<(u64, InputEventKind, InputEventKey), isize> = BTreeMap::new();
+        for event in a {
+            *counts.entry((event.frame, event.kind, event.key)).or_default() += 1;
+        }
+        for event in b {
+            *counts.entry((event.frame, event.kind, event.key)).or_default() -= 1;
+        }
+
+        for count in counts.into_values() {
+            if count > 0 {
+                diff.removed_events += count as usize;
+            } else {
+                diff.added_events += (-count) as usize;
+            }
+        }
+
+        diff.ordering_only = diff.added_events == 0
+            && diff.removed_events == 0
+            && a.iter().zip(b.iter()).all(|(x, y)| x.frame == y.frame);
+
+        diff
+    }
+}
+
+fn diff_values(a: &Value, b: &Value, path: &mut String, out: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let len = path.len();
+                path.push('/');
+                // Escape according to RFC 6901
+                path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_values(x, y, path, out),
+                    _ => out.push(path.clone()),
+                }
+
+                path.truncate(len);
+            }
+        }
+        (a, b) if a != b => out.push(path.clone()),
+        _ => {}
+    }
+}
+
+/// The fields of two JSON objects whose values differ, as `(field, ours, theirs)`, sorted by name.
+///
+/// Missing fields count as `null`, and numbers are compared by value.
+fn diff_fields(a: &Value, b: &Value) -> Vec<(String, Value, Value)> {
+    let empty = Map::new();
+    let (a, b) = (a.as_object().unwrap_or(&empty), b.as_object().unwrap_or(&empty));
+
+    let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = a.get(key).cloned().unwrap_or(Value::Null);
+            let new = b.get(key).cloned().unwrap_or(Value::Null);
+            let same = match (&old, &new) {
+                (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+                (x, y) => x == y,
+            };
+
+            (!same).then(|| (key.clone(), old, new))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_metadata_diff() {
+        let a = GameReplayData::default();
+        let mut b = GameReplayData::default();
+
+        assert!(a.diff(&b).is_empty());
+
+        b.metadata.player = "Nova".to_string();
+        b.metadata.setting.das = Some(8);
+        b.metadata.nonstandard.insert("a/b".to_string(), Value::Bool(true));
+
+        let diff = a.diff(&b);
+
+        assert_eq!(diff.metadata_differences, vec!["/a~1b", "/player", "/setting/das"]);
+        assert_eq!(diff.first_divergence, None);
+    }
+
+    #[test]
+    fn test_settings_diff() {
+        let a = PlayerSettings { das: Some(8), arr: Some(0), ..Default::default() };
+        let mut b = a.clone();
+        assert_eq!(a.diff(&b), []);
+
+        b.das = Some(10);
+        b.irscut = Some(3);
+        b.arr = None;
+        // The same value, written differently
+        let mut a = a;
+        a.ghost = Some(1.into());
+        b.ghost = serde_json::Number::from_f64(1.0);
+        a.grid = serde_json::Number::from_f64(0.4);
+        b.grid = serde_json::Number::from_f64(0.25);
+        b.nonstandard.insert("skinSet".to_string(), Value::from("neon"));
+
+        let diff = a.diff(&b);
+        let diff: Vec<String> = diff.iter().map(|diff| diff.to_string()).collect();
+        assert_eq!(
+            diff,
+            [
+                "arr: 0 -> null",
+                "das: 8 -> 10",
+                "grid: 0.4 -> 0.25",
+                "irscut: null -> 3",
+                r#"skinSet: null -> "neon""#,
+            ]
+        );
+
+        let das = &a.diff(&b)[1];
+        assert_eq!((das.field.as_str(), &das.old, &das.new), ("das", &Value::from(8), &Value::from(10)));
+        let json = serde_json::to_string(das).unwrap();
+        assert_eq!(json, r#"{"field":"das","old":8,"new":10}"#);
+    }
+
+    #[test]
+    fn test_metadata_field_diff() {
+        let a = GameReplayMetadata::default();
+        let mut b = GameReplayMetadata::default();
+        assert_eq!(a.diff(&b), []);
+
+        b.player = "Nova".to_string();
+        b.setting.das = Some(8);
+        b.private = Some(serde_json::json!({ "BAG": [1, 2] }));
+        b.nonstandard.insert("device".to_string(), Value::from("phone"));
+
+        let diff: Vec<String> = a.diff(&b).iter().map(|diff| diff.to_string()).collect();
+        assert_eq!(
+            diff,
+            [
+                r#"device: null -> "phone""#,
+                r#"player: "" -> "Nova""#,
+                r#"private: null -> {"BAG":[1,2]}"#,
+                "setting.das: null -> 8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_diff() {
+        let base = vec![
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::press(20, HardDrop),
+            GameInputEvent::release(20, MoveLeft),
+            GameInputEvent::release(30, HardDrop),
+        ];
+
+        let a = replay(base.clone());
+
+        let mut reordered = base.clone();
+        reordered.swap(1, 2);
+        let diff = a.diff(&replay(reordered));
+        assert_eq!(diff.first_divergence, Some(InputDivergence { index: 1, frame: 20 }));
+        assert_eq!((diff.added_events, diff.removed_events), (0, 0));
+        assert!(diff.ordering_only);
+
+        let mut edited = base.clone();
+        edited[3].frame = 25;
+        edited.push(GameInputEvent::press(40, Hold));
+        let diff = a.diff(&replay(edited));
+        assert_eq!(diff.first_divergence, Some(InputDivergence { index: 3, frame: 25 }));
+        assert_eq!((diff.added_events, diff.removed_events), (2, 1));
+        assert!(!diff.ordering_only);
+
+        let truncated = replay(base[..2].to_vec());
+        let diff = a.diff(&truncated);
+        assert_eq!(diff.first_divergence, Some(InputDivergence { index: 2, frame: 20 }));
+        assert_eq!((diff.added_events, diff.removed_events), (0, 2));
+        assert!(!diff.ordering_only);
+
+        assert_eq!(
+            diff.to_string(),
+            "Metadata: identical\n\
+            Inputs: first divergence at index 2 (frame 20), 0 added, 2 removed"
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::keyset::KeySet;
+use crate::types::*;
+
+/// An error from splicing two replays together with [`GameReplayData::splice`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpliceError {
+    /// The spliced replay would start before the last input of the original replay.
+    Overlap {
+        /// The frame the spliced replay would start at.
+        at_frame: u64,
+        /// The frame of the last input of the original replay.
+        last_frame: u64,
+    },
+
+    /// Shifting the frames of the spliced replay would overflow a [`u64`].
+    FrameOverflow,
+
+    /// The spliced replay presses a key that is still held at the end of the original replay.
+    KeyHeldAtSeam {
+        /// The key that is held.
+        key: InputEventKey,
+    },
+
+    /// The spliced replay releases a key before pressing it, but the key isn't held
+    /// at the end of the original replay.
+    KeyNotHeldAtSeam {
+        /// The key that isn't held.
+        key: InputEventKey,
+    },
+}
+
+/// An error from transforming the frames of a replay with
+/// [`scale_time`][GameReplayData::scale_time] or [`offset_frames`][GameReplayData::offset_frames].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransformError {
+    /// The denominator of the time scale is zero.
+    ZeroDenominator,
+
+    /// The new frame of an input would overflow a [`u64`].
+    FrameOverflow {
+        /// The index of the input.
+        index: usize,
+        /// The original frame of the input.
+        frame: u64,
+    },
+
+    /// The new frame of an input would be negative.
+    NegativeFrame {
+        /// The index of the input.
+        index: usize,
+        /// The original frame of the input.
+        frame: u64,
+    },
+}
+
+impl GameReplayData {
+    /// Inserts an input, keeping the inputs sorted by frame.
+    ///
+    /// The input is placed after any existing inputs on the same frame.
+    /// Returns the index the input was placed at.
+    ///
+    /// This expects the inputs to already be sorted;
+    /// see [`sort_inputs`][GameReplayData::sort_inputs].
+    pub fn insert_input(&mut self, event: GameInputEvent) -> usize {
+        let index = self.inputs.partition_point(|i| i.frame <= event.frame);
+        self.inputs.insert(index, event);
+        index
+    }
+
+    /// Removes all the inputs within a range of frames, returning them.
+    ///
+    /// This expects the inputs to already be sorted;
+    /// see [`sort_inputs`][GameReplayData::sort_inputs].
+    ///
+    /// Note that this can leave keys held forever or released without being pressed.
+    /// To avoid that, see
+    /// [`remove_inputs_in_range_with_fixup`][GameReplayData::remove_inputs_in_range_with_fixup].
+    pub fn remove_inputs_in_range(&mut self, frames: Range<u64>) -> Vec<GameInputEvent> {
+        let (start, end) = self.index_range(frames);
+        self.inputs.drain(start..end).collect()
+    }
+
+    /// Removes all the inputs within a range of frames, returning them, and keeps the
+    /// held keys consistent around the removed range.
+    ///
+    /// Keys that were held going into the range are released at the start of the range,
+    /// and keys that were held coming out of the range are pressed again at the end of it,
+    /// so every remaining release still has a matching press.
+    /// The returned inputs don't include the added ones.
+    ///
+    /// This expects the inputs to already be sorted;
+    /// see [`sort_inputs`][GameReplayData::sort_inputs].
+    pub fn remove_inputs_in_range_with_fixup(&mut self, frames: Range<u64>) -> Vec<GameInputEvent> {
+        let (start, end) = self.index_range(frames.clone());
+
+        let held_before = self.held_keys_before(start);
+        let held_after = self.held_keys_before(end);
+
+        let removed = self.inputs.drain(start..end).collect();
+
+        let releases = held_before.iter().map(|key| GameInputEvent {
+            frame: frames.start,
+            kind: InputEventKind::Release,
+            key,
+        });
+        let presses = held_after.iter().map(|key| GameInputEvent {
+            frame: frames.end,
+            kind: InputEventKind::Press,
+            key,
+        });
+
+        self.inputs.splice(start..start, releases.chain(presses));
+
+        removed
+    }
+
+    /// Removes all the inputs after a frame, and releases the keys still held on that frame.
+    ///
+    /// Inputs on the frame itself are kept. The releases are added after them,
+    /// so no key is held at the end of the replay.
+    ///
+    /// This expects the inputs to already be sorted;
+    /// see [`sort_inputs`][GameReplayData::sort_inputs].
+    pub fn truncate_at_frame(&mut self, frame: u64) {
+        let end = self.inputs.partition_point(|i| i.frame <= frame);
+        self.inputs.truncate(end);
+
+        let held = self.held_keys_before(end);
+        self.inputs.extend(held.iter().map(|key| GameInputEvent {
+            frame,
+            kind: InputEventKind::Release,
+            key,
+        }));
+    }
+
+    /// Appends the inputs of another replay, shifted so that its frame 0 lands on `at_frame`.
+    ///
+    /// Note that the other replay's countdown is kept, so its gameplay starts on
+    /// `at_frame + GAME_START_FRAME`. To line up the start of its gameplay with a certain frame
+    /// instead, subtract [`GAME_START_FRAME`][crate::GAME_START_FRAME] from that frame.
+    ///
+    /// The metadata of this replay is kept as is. Both replays are expected to have sorted inputs.
+    ///
+    /// This fails without changing anything if the other replay would start before the last
+    /// input of this replay, if the shifted frames would overflow, or if the held keys
+    /// don't line up at the seam (see [`SpliceError`]).
+    pub fn splice(&mut self, other: &GameReplayData, at_frame: u64) -> Result<(), SpliceError> {
+        let last_frame = self.last_frame().unwrap_or(0);
+        if at_frame < last_frame {
+            return Err(SpliceError::Overlap { at_frame, last_frame });
+        }
+
+        let shifted = other
+            .inputs
+            .iter()
+            .map(|i| {
+                Some(GameInputEvent {
+                    frame: i.frame.checked_add(at_frame)?,
+                    ..*i
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SpliceError::FrameOverflow)?;
+
+        let held = self.held_keys_before(self.inputs.len());
+        let mut seen = KeySet::new();
+
+        for input in &other.inputs {
+            if !seen.insert(input.key) {
+                continue;
+            }
+
+            match (input.kind, held.contains(input.key)) {
+                (InputEventKind::Press, true) => {
+                    return Err(SpliceError::KeyHeldAtSeam { key: input.key })
+                }
+                (InputEventKind::Release, false) => {
+                    return Err(SpliceError::KeyNotHeldAtSeam { key: input.key })
+                }
+                _ => {}
+            }
+        }
+
+        self.inputs.extend(shifted);
+
+        Ok(())
+    }
+
+    /// Multiplies the frame of every input by `numerator / denominator`, rounding to the nearest frame.
+    ///
+    /// For example, a scale of `2 / 1` makes the replay play twice as slow.
+    /// Halves are rounded up. Rounding can move inputs onto the same frame,
+    /// but never changes their order.
+    ///
+    /// This fails without changing anything if the denominator is zero,
+    /// or if a new frame would overflow.
+    pub fn scale_time(&mut self, numerator: u64, denominator: u64) -> Result<(), TransformError> {
+        if denominator == 0 {
+            return Err(TransformError::ZeroDenominator);
+        }
+
+        let scale = |frame: u64| {
+            let scaled = (frame as u128 * numerator as u128 + denominator as u128 / 2)
+                / denominator as u128;
+            u64::try_from(scaled).ok()
+        };
+
+        self.map_frames(scale, |index, frame| TransformError::FrameOverflow { index, frame })
+    }
+
+    /// Shifts the frame of every input by `delta` frames.
+    ///
+    /// This fails without changing anything if an input would end up on a negative frame,
+    /// or if a new frame would overflow.
+    pub fn offset_frames(&mut self, delta: i64) -> Result<(), TransformError> {
+        let shift = |frame: u64| frame.checked_add_signed(delta);
+
+        self.map_frames(shift, |index, frame| match delta < 0 {
+            true => TransformError::NegativeFrame { index, frame },
+            false => TransformError::FrameOverflow { index, frame },
+        })
+    }
+
+    /// Maps the frame of every input, or changes nothing if any of the mappings fail.
+    fn map_frames(
+        &mut self,
+        f: impl Fn(u64) -> Option<u64>,
+        error: impl Fn(usize, u64) -> TransformError,
+    ) -> Result<(), TransformError> {
+        let frames = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, i)| f(i.frame).ok_or_else(|| error(index, i.frame)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (input, frame) in self.inputs.iter_mut().zip(frames) {
+            input.frame = frame;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps only the inputs for which the predicate returns `true`.
+    ///
+    /// The order of the remaining inputs is kept, so sorted inputs stay sorted.
+    pub fn retain_inputs(&mut self, f: impl FnMut(&GameInputEvent) -> bool) {
+        self.inputs.retain(f);
+    }
+
+    /// Replaces the key of every input with the key the function returns for it.
+    ///
+    /// Mapping two keys onto the same key can leave it pressed while it's already held;
+    /// see [`validate`][GameReplayData::validate].
+    pub fn remap_keys(&mut self, mut f: impl FnMut(InputEventKey) -> InputEventKey) {
+        for input in &mut self.inputs {
+            input.key = f(input.key);
+        }
+    }
+
+    /// Finds the keys held after processing the inputs before the index.
+    pub(crate) fn held_keys_before(&self, index: usize) -> KeySet {
+        let mut held = KeySet::new();
+
+        for input in &self.inputs[..index] {
+            match input.kind {
+                InputEventKind::Press => held.insert(input.key),
+                InputEventKind::Release => held.remove(input.key),
+            };
+        }
+
+        held
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_insert_input() {
+        let mut data = replay(vec![
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::press(20, HardDrop),
+            GameInputEvent::release(20, HardDrop),
+            GameInputEvent::release(30, MoveLeft),
+        ]);
+
+        assert_eq!(data.insert_input(GameInputEvent::press(5, Hold)), 0);
+        assert_eq!(data.insert_input(GameInputEvent::release(40, Hold)), 5);
+        assert_eq!(data.insert_input(GameInputEvent::press(20, RotateLeft)), 4);
+        assert_eq!(data.insert_input(GameInputEvent::release(15, RotateLeft)), 2);
+
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(5, Hold),
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::release(15, RotateLeft),
+                GameInputEvent::press(20, HardDrop),
+                GameInputEvent::release(20, HardDrop),
+                GameInputEvent::press(20, RotateLeft),
+                GameInputEvent::release(30, MoveLeft),
+                GameInputEvent::release(40, Hold),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_inputs_in_range() {
+        let inputs = vec![
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::press(20, HardDrop),
+            GameInputEvent::release(25, HardDrop),
+            GameInputEvent::press(28, Hold),
+            GameInputEvent::release(30, MoveLeft),
+            GameInputEvent::release(40, Hold),
+        ];
+
+        let mut data = replay(inputs.clone());
+        let removed = data.remove_inputs_in_range(20..30);
+        assert_eq!(removed, inputs[1..4]);
+        assert_eq!(data.inputs, [inputs[0], inputs[4], inputs[5]]);
+
+        let mut data = replay(inputs.clone());
+        let removed = data.remove_inputs_in_range_with_fixup(15..30);
+        assert_eq!(removed, inputs[1..4]);
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::release(15, MoveLeft),
+                GameInputEvent::press(30, MoveLeft),
+                GameInputEvent::press(30, Hold),
+                GameInputEvent::release(30, MoveLeft),
+                GameInputEvent::release(40, Hold),
+            ]
+        );
+        assert!(data.validate().iter().all(|issue| !matches!(
+            issue,
+            crate::ValidationIssue::ReleaseWithoutPress { .. }
+                | crate::ValidationIssue::PressWhileHeld { .. }
+        )));
+
+        let mut data = replay(inputs.clone());
+        data.retain_inputs(|i| i.key != HardDrop);
+        assert_eq!(data.inputs.len(), 4);
+
+        data.remap_keys(|key| if key == MoveLeft { MoveRight } else { key });
+        let keys: Vec<_> = data.inputs.iter().map(|i| i.key).collect();
+        assert_eq!(keys, [MoveRight, Hold, MoveRight, Hold]);
+    }
+
+    #[test]
+    fn test_truncate_and_splice() {
+        let metadata = GameReplayMetadata {
+            version: "V0.17.22".to_string(),
+            ..Default::default()
+        };
+
+        let mut first = GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(100, MoveLeft),
+                GameInputEvent::press(190, HardDrop),
+                GameInputEvent::release(190, HardDrop),
+                GameInputEvent::press(200, Hold),
+                GameInputEvent::release(300, MoveLeft),
+                GameInputEvent::release(310, Hold),
+            ],
+            metadata: metadata.clone(),
+            input_mode: None,
+        };
+
+        first.truncate_at_frame(250);
+        assert_eq!(
+            first.inputs[3..],
+            [
+                GameInputEvent::press(200, Hold),
+                GameInputEvent::release(250, MoveLeft),
+                GameInputEvent::release(250, Hold),
+            ]
+        );
+
+        let second = GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(170, RotateLeft),
+                GameInputEvent::release(180, RotateLeft),
+                GameInputEvent::press(200, HardDrop),
+                GameInputEvent::release(200, HardDrop),
+            ],
+            metadata,
+            input_mode: None,
+        };
+
+        assert_eq!(
+            first.clone().splice(&second, 100),
+            Err(SpliceError::Overlap { at_frame: 100, last_frame: 250 })
+        );
+        assert_eq!(
+            first.clone().splice(&second, u64::MAX - 100),
+            Err(SpliceError::FrameOverflow)
+        );
+
+        first.splice(&second, 300).unwrap();
+        assert_eq!(first.inputs.len(), 10);
+        assert_eq!(first.inputs[6], GameInputEvent::press(470, RotateLeft));
+        assert_eq!(first.last_frame(), Some(500));
+        assert_eq!(first.validate(), vec![]);
+
+        let serialized = first.serialize_to_base64(None).unwrap();
+        assert_eq!(GameReplayData::try_from_base64(&serialized, None).unwrap(), first);
+
+        let mut holding = replay(vec![GameInputEvent::press(10, RotateLeft)]);
+        assert_eq!(
+            holding.splice(&second, 20),
+            Err(SpliceError::KeyHeldAtSeam { key: RotateLeft })
+        );
+
+        let releasing = replay(vec![GameInputEvent::release(5, MoveLeft)]);
+        assert_eq!(
+            replay(vec![]).splice(&releasing, 0),
+            Err(SpliceError::KeyNotHeldAtSeam { key: MoveLeft })
+        );
+        holding.inputs[0].key = MoveLeft;
+        assert_eq!(holding.splice(&releasing, 20), Ok(()));
+    }
+
+    #[test]
+    fn test_scale_and_offset() {
+        let inputs = vec![
+            GameInputEvent::press(0, MoveLeft),
+            GameInputEvent::release(3, MoveLeft),
+            GameInputEvent::press(4, HardDrop),
+            GameInputEvent::release(5, HardDrop),
+            GameInputEvent::press(181, Hold),
+            GameInputEvent::release(181, Hold),
+        ];
+
+        let mut data = replay(inputs.clone());
+        data.metadata.version = "V0.17.22".to_string();
+
+        data.scale_time(2, 1).unwrap();
+        let frames: Vec<_> = data.inputs.iter().map(|i| i.frame).collect();
+        assert_eq!(frames, [0, 6, 8, 10, 362, 362]);
+
+        data.offset_frames(60).unwrap();
+        assert_eq!(data.inputs[0].frame, 60);
+        assert_eq!(data.last_frame(), Some(422));
+
+        let serialized = data.serialize_to_base64(None).unwrap();
+        assert_eq!(GameReplayData::try_from_base64(&serialized, None).unwrap(), data);
+
+        let mut data = replay(inputs.clone());
+        data.scale_time(1, 2).unwrap();
+        let frames: Vec<_> = data.inputs.iter().map(|i| i.frame).collect();
+        assert_eq!(frames, [0, 2, 2, 3, 91, 91]);
+
+        let mut data = replay(inputs.clone());
+        assert_eq!(data.scale_time(1, 0), Err(TransformError::ZeroDenominator));
+        assert_eq!(
+            data.scale_time(u64::MAX, 1),
+            Err(TransformError::FrameOverflow { index: 1, frame: 3 })
+        );
+        assert_eq!(
+            data.offset_frames(-4),
+            Err(TransformError::NegativeFrame { index: 0, frame: 0 })
+        );
+        data.offset_frames(i64::MAX).unwrap();
+        assert_eq!(
+            data.offset_frames(i64::MAX),
+            Err(TransformError::FrameOverflow { index: 1, frame: 3 + i64::MAX as u64 })
+        );
+        assert_eq!(data.inputs[0].frame, i64::MAX as u64);
+    }
+}
@@ -0,0 +1,142 @@
+//! The base64 text form of replays, as copied from the game's export button.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{STANDARD as B64, URL_SAFE_NO_PAD};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::{DecodeError, Engine};
+
+use crate::SerializeOptions;
+
+/// The standard alphabet, with or without padding.
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Decodes a base64 replay string into the compressed replay without parsing it,
+/// e.g. to look into a replay that fails to parse.
+///
+/// This accepts the same mangled strings as
+/// [`GameReplayData::try_from_base64`][crate::GameReplayData::try_from_base64].
+/// To decompress the result, see [`decompress_replay`][crate::decompress_replay].
+pub fn decode_base64_replay(string: &str) -> Result<Vec<u8>, DecodeError> {
+    decode(string)
+}
+
+/// Decodes a base64 replay string, tolerating the ways it tends to get mangled when shared.
+///
+/// This ignores ASCII whitespace anywhere in the string and a leading `data:...;base64,` prefix,
+/// accepts the URL-safe alphabet (`-` and `_`) as well as the standard one, and doesn't require
+/// the `=` padding.
+///
+/// The offsets in the errors are into the string after removing the whitespace and prefix.
+pub(crate) fn decode(string: &str) -> Result<Vec<u8>, DecodeError> {
+    let string = strip_data_url(string).as_bytes();
+
+    let needs_cleanup = string.iter().any(|&byte| matches!(byte, b'-' | b'_') || byte.is_ascii_whitespace());
+    let string: Cow<[u8]> = if needs_cleanup {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("The base64 has whitespace or URL-safe characters, which were cleaned up");
+
+        let cleaned = string
+            .iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .map(|&byte| match byte {
+                b'-' => b'+',
+                b'_' => b'/',
+                byte => byte,
+            })
+            .collect();
+
+        Cow::Owned(cleaned)
+    } else {
+        Cow::Borrowed(string)
+    };
+
+    LENIENT.decode(string)
+}
+
+/// Encodes replay bytes into base64, following the base64 options of [`SerializeOptions`].
+pub(crate) fn encode(bytes: &[u8], options: &SerializeOptions) -> String {
+    let string = if options.url_safe_base64 { URL_SAFE_NO_PAD.encode(bytes) } else { B64.encode(bytes) };
+
+    let width = options.base64_line_width;
+    if width == 0 || string.len() <= width {
+        return string;
+    }
+
+    let mut wrapped = String::with_capacity(string.len() + string.len() / width);
+    for (i, line) in string.as_bytes().chunks(width).enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+        wrapped.push_str(core::str::from_utf8(line).expect("base64 is ASCII"));
+    }
+
+    wrapped
+}
+
+/// Whether the string looks like something [`decode`] accepts, without decoding it.
+///
+/// This only checks the characters: whitespace, a `data:` URL prefix, both alphabets, and `=` padding
+/// at the end. The string must have at least one other character.
+pub(crate) fn looks_like_base64(string: &str) -> bool {
+    let string = strip_data_url(string).trim_end_matches(|c: char| c == '=' || c.is_ascii_whitespace());
+
+    let mut characters = string.bytes().filter(|byte| !byte.is_ascii_whitespace()).peekable();
+    characters.peek().is_some()
+        && characters.all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'+' | b'/' | b'-' | b'_'))
+}
+
+/// Removes a leading `data:` URL prefix, e.g. `data:application/octet-stream;base64,`.
+fn strip_data_url(string: &str) -> &str {
+    let trimmed = string.trim_start();
+
+    match trimmed.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("data:") => {
+            trimmed.split_once(',').map_or(string, |(_, data)| data)
+        }
+        _ => string,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_lenient() {
+        let expected = b"\xfb\xff\x00 replay";
+
+        for string in [
+            "+/8AIHJlcGxheQ==",
+            "+/8AIHJlcGxheQ",
+            "-_8AIHJlcGxheQ==",
+            " +/8AIHJl\r\ncGxh eQ==\n",
+            "data:application/octet-stream;base64,+/8AIHJlcGxheQ==",
+            "DATA:;base64,\n-_8AIHJl\ncGxheQ",
+        ] {
+            assert_eq!(decode(string).unwrap(), expected, "{string:?}");
+        }
+
+        assert!(decode("+/8A*HJlcGxheQ==").is_err());
+        assert!(decode("data:,+/8A*HJlcGxheQ==").is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let bytes = b"\xfb\xff\x00 replay";
+        let encode = |options: SerializeOptions| encode(bytes, &options);
+
+        assert_eq!(encode(SerializeOptions::new()), "+/8AIHJlcGxheQ==");
+        assert_eq!(encode(SerializeOptions::new().url_safe_base64(true)), "-_8AIHJlcGxheQ");
+        assert_eq!(encode(SerializeOptions::new().base64_line_width(5)), "+/8AI\nHJlcG\nxheQ=\n=");
+        assert_eq!(encode(SerializeOptions::new().base64_line_width(16)), "+/8AIHJlcGxheQ==");
+        let options = SerializeOptions::new().base64_line_width(8).url_safe_base64(true);
+        assert_eq!(encode(options), "-_8AIHJl\ncGxheQ");
+    }
+}
@@ -0,0 +1,339 @@
+//! The serialized form of the errors, for returning them from web APIs.
+
+use alloc::string::ToString;
+use core::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+
+use crate::types::*;
+use crate::vlq::VlqError;
+use crate::{DecompressErrorKind, StrictViolation};
+
+impl ReplayParseError {
+    /// The name of the error variant, e.g. `MalformedInputData`.
+    ///
+    /// The codes are a stable contract, e.g. for telling errors apart in a browser: a code is
+    /// never renamed or reused for another error, though new errors get new codes. They are:
+    /// `ZlibDecompressError`, `Base64DecodeError`, `MetadataSeparatorNotFound`, `MetadataNotUtf8`,
+    /// `MetadataDeserializeError`, `UnknownInputParseMode`, `MalformedInputData`, `MalformedVlq`,
+    /// `StrictViolation`, `EmptyInput` and `NotAReplay`.
+    ///
+    /// The error is serialized as its code, the fields of the variant as a `detail` object with
+    /// camelCase keys, and its [`Display`][fmt::Display] form as a `message`:
+    ///
+    /// ```text
+    /// {"code":"MalformedVlq","detail":{"kind":"Truncated","position":12},"message":"..."}
+    /// ```
+    ///
+    /// The keys of the details are stable like the codes, but the messages may change.
+    pub fn code(&self) -> &'static str {
+        use ReplayParseError::*;
+
+        match self {
+            ZlibDecompressError(_) => "ZlibDecompressError",
+            Base64DecodeError(_) => "Base64DecodeError",
+            MetadataSeparatorNotFound => "MetadataSeparatorNotFound",
+            MetadataNotUtf8(_) => "MetadataNotUtf8",
+            MetadataDeserializeError { .. } => "MetadataDeserializeError",
+            UnknownInputParseMode(_) => "UnknownInputParseMode",
+            MalformedInputData { .. } => "MalformedInputData",
+            MalformedVlq(_) => "MalformedVlq",
+            StrictViolation(_) => "StrictViolation",
+            EmptyInput => "EmptyInput",
+            NotAReplay { .. } => "NotAReplay",
+        }
+    }
+
+    /// The fields of the error, for its serialized form.
+    fn detail(&self) -> Value {
+        use ReplayParseError::*;
+
+        match self {
+            ZlibDecompressError(error) => {
+                let kind = match error.kind {
+                    DecompressErrorKind::Truncated => "Truncated",
+                    DecompressErrorKind::Corrupt => "Corrupt",
+                    DecompressErrorKind::TooLarge => "TooLarge",
+                };
+                json!({ "kind": kind, "decompressedBytes": error.output.len() })
+            }
+            Base64DecodeError(error) => json!({ "error": error.to_string() }),
+            MetadataSeparatorNotFound | EmptyInput => json!({}),
+            MetadataNotUtf8(error) => json!({ "validUpTo": error.utf8_error().valid_up_to() }),
+            MetadataDeserializeError { kind, offset, line, column, snippet, .. } => {
+                let kind = match kind {
+                    MetadataErrorKind::Syntax => "Syntax",
+                    MetadataErrorKind::Data => "Data",
+                };
+                json!({ "kind": kind, "offset": offset, "line": line, "column": column, "snippet": snippet })
+            }
+            UnknownInputParseMode(version) => json!({ "version": version }),
+            MalformedInputData {
+                offset,
+                index,
+                time,
+                frame,
+                raw_code,
+                key_bits,
+                kind_bit,
+                values_before,
+                values_after,
+            } => json!({
+                "offset": offset,
+                "index": index,
+                "time": time,
+                "frame": frame,
+                "rawCode": raw_code,
+                "keyBits": key_bits,
+                "kindBit": kind_bit,
+                "valuesBefore": values_before,
+                "valuesAfter": values_after,
+            }),
+            MalformedVlq(VlqError::Truncated { position }) => {
+                json!({ "kind": "Truncated", "position": position })
+            }
+            MalformedVlq(VlqError::Overflow { position }) => {
+                json!({ "kind": "Overflow", "position": position })
+            }
+            StrictViolation(violation) => violation_detail(violation),
+            NotAReplay { detected } => json!({ "detected": alloc::format!("{detected:?}") }),
+        }
+    }
+}
+
+/// The detail of a [`StrictViolation`], with the name of the violation as `violation`.
+fn violation_detail(violation: &StrictViolation) -> Value {
+    use StrictViolation::*;
+
+    match violation {
+        FrameTooLate { index, frame, max_frame } => {
+            json!({ "violation": "FrameTooLate", "index": index, "frame": frame, "maxFrame": max_frame })
+        }
+        TooManyInputs { count, max_inputs } => {
+            json!({ "violation": "TooManyInputs", "count": count, "maxInputs": max_inputs })
+        }
+        DuplicateInput { index, event } => {
+            json!({ "violation": "DuplicateInput", "index": index, "event": event })
+        }
+        SettingOutOfRange { field, value, min, max } => json!({
+            "violation": "SettingOutOfRange",
+            "field": field,
+            "value": value,
+            "min": min,
+            "max": max,
+        }),
+        UnknownMetadataKey { key } => json!({ "violation": "UnknownMetadataKey", "key": key }),
+    }
+}
+
+impl ReplaySerializeError {
+    /// The name of the error variant, e.g. `UnsortedInput`.
+    ///
+    /// The codes are a stable contract like [those of parse errors][ReplayParseError::code], and
+    /// the errors are serialized in the same shape. They are: `UnknownInputParseMode`,
+    /// `UnsortedInput`, `MetadataSerializeError`, `UndecodableInputs` and `InvalidKeyCode`.
+    pub fn code(&self) -> &'static str {
+        use ReplaySerializeError::*;
+
+        match self {
+            UnknownInputParseMode(_) => "UnknownInputParseMode",
+            UnsortedInput { .. } => "UnsortedInput",
+            MetadataSerializeError(_) => "MetadataSerializeError",
+            UndecodableInputs(_) => "UndecodableInputs",
+            InvalidKeyCode { .. } => "InvalidKeyCode",
+        }
+    }
+
+    /// The fields of the error, for its serialized form.
+    fn detail(&self) -> Value {
+        use ReplaySerializeError::*;
+
+        match self {
+            UnknownInputParseMode(version) => json!({ "version": version }),
+            UnsortedInput { first_unsorted_index, prev_time, unsorted_time } => json!({
+                "firstUnsortedIndex": first_unsorted_index,
+                "prevTime": prev_time,
+                "unsortedTime": unsorted_time,
+            }),
+            MetadataSerializeError(error) => json!({ "error": error.to_string() }),
+            UndecodableInputs(error) => json!({ "error": error }),
+            InvalidKeyCode { index, code } => json!({ "code": code, "index": index }),
+        }
+    }
+}
+
+impl fmt::Display for ReplaySerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ReplaySerializeError::*;
+
+        match self {
+            UnknownInputParseMode(version) => {
+                write!(f, "Can't tell how to time the inputs from the version '{version}'")
+            }
+            UnsortedInput { first_unsorted_index, prev_time, unsorted_time } => write!(
+                f,
+                "Input {first_unsorted_index} is on frame {unsorted_time}, before the frame {prev_time} \
+                 of the input before it"
+            ),
+            MetadataSerializeError(error) => write!(f, "The metadata can't be written as JSON: {error}"),
+            UndecodableInputs(error) => write!(f, "The inputs can't be decoded to re-time them: {error}"),
+            InvalidKeyCode { index, code } => {
+                write!(f, "Input {index} has the key code {code}, but other keys have to be from 21 to 31")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplaySerializeError {}
+
+/// Serializes an error as its code, detail and message.
+fn serialize_error<S: Serializer>(
+    serializer: S,
+    code: &'static str,
+    detail: Value,
+    message: &dyn fmt::Display,
+) -> Result<S::Ok, S::Error> {
+    let mut error = serializer.serialize_struct("Error", 3)?;
+    error.serialize_field("code", code)?;
+    error.serialize_field("detail", &detail)?;
+    error.serialize_field("message", &message.to_string())?;
+    error.end()
+}
+
+/// Serialized as `{"code": ..., "detail": {...}, "message": ...}`, see [`ReplayParseError::code`].
+impl Serialize for ReplayParseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), self.detail(), self)
+    }
+}
+
+/// Serialized as `{"code": ..., "detail": {...}, "message": ...}`, see [`ReplaySerializeError::code`].
+impl Serialize for ReplaySerializeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(serializer, self.code(), self.detail(), self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use crate::{DecompressError, DetectedFormat, GameInputEvent};
+
+    /// Checks the serialized error without its message, whatever the order of the keys.
+    fn assert_snapshot<E: Serialize + fmt::Debug>(error: &E, expected: &str) {
+        let mut json = serde_json::to_value(error).unwrap();
+        let message = json.as_object_mut().unwrap().remove("message").unwrap();
+        assert!(message.as_str().is_some_and(|message| !message.is_empty()));
+
+        assert_eq!(json, serde_json::from_str::<Value>(expected).unwrap(), "{error:?}");
+    }
+
+    #[test]
+    fn test_parse_error_snapshots() {
+        let metadata_error = GameReplayData::try_from_raw(b"{,}\n", None).unwrap_err();
+        let utf8_error = String::from_utf8(Vec::from(&b"ab\xff"[..])).unwrap_err();
+        let event = GameInputEvent::press(3, InputEventKey::Hold);
+        let violation = crate::StrictViolation::DuplicateInput { index: 1, event };
+
+        let cases = [
+            (
+                ReplayParseError::ZlibDecompressError(DecompressError {
+                    kind: DecompressErrorKind::Truncated,
+                    output: Vec::from(&b"{"[..]),
+                }),
+                r#"{"code":"ZlibDecompressError","detail":{"decompressedBytes":1,"kind":"Truncated"}}"#,
+            ),
+            (
+                ReplayParseError::Base64DecodeError(base64::DecodeError::InvalidLength(5)),
+                r#"{"code":"Base64DecodeError","detail":{"error":"Invalid input length: 5"}}"#,
+            ),
+            (
+                ReplayParseError::MetadataSeparatorNotFound,
+                r#"{"code":"MetadataSeparatorNotFound","detail":{}}"#,
+            ),
+            (
+                ReplayParseError::MetadataNotUtf8(utf8_error),
+                r#"{"code":"MetadataNotUtf8","detail":{"validUpTo":2}}"#,
+            ),
+            (
+                metadata_error,
+                concat!(
+                    r#"{"code":"MetadataDeserializeError","detail":{"column":2,"kind":"Syntax","line":1,"#,
+                    r#""offset":1,"snippet":"{,}"}}"#,
+                ),
+            ),
+            (
+                ReplayParseError::UnknownInputParseMode("V0.1".into()),
+                r#"{"code":"UnknownInputParseMode","detail":{"version":"V0.1"}}"#,
+            ),
+            (
+                ReplayParseError::MalformedInputData {
+                    offset: 2,
+                    index: 1,
+                    time: 4,
+                    frame: Some(4),
+                    raw_code: 31,
+                    key_bits: 31,
+                    kind_bit: false,
+                    values_before: vec![1, 6],
+                    values_after: vec![],
+                },
+                concat!(
+                    r#"{"code":"MalformedInputData","detail":{"frame":4,"index":1,"keyBits":31,"#,
+                    r#""kindBit":false,"offset":2,"rawCode":31,"time":4,"#,
+                    r#""valuesAfter":[],"valuesBefore":[1,6]}}"#,
+                ),
+            ),
+            (
+                ReplayParseError::MalformedVlq(VlqError::Overflow { position: 7 }),
+                r#"{"code":"MalformedVlq","detail":{"kind":"Overflow","position":7}}"#,
+            ),
+            (
+                ReplayParseError::StrictViolation(violation),
+                concat!(
+                    r#"{"code":"StrictViolation","detail":{"event":{"frame":3,"key":"Hold","kind":"Press"},"#,
+                    r#""index":1,"violation":"DuplicateInput"}}"#,
+                ),
+            ),
+            (ReplayParseError::EmptyInput, r#"{"code":"EmptyInput","detail":{}}"#),
+            (
+                ReplayParseError::NotAReplay { detected: DetectedFormat::Png },
+                r#"{"code":"NotAReplay","detail":{"detected":"Png"}}"#,
+            ),
+        ];
+
+        for (error, expected) in cases {
+            assert_snapshot(&error, expected);
+        }
+    }
+
+    #[test]
+    fn test_serialize_error_snapshots() {
+        let unsorted =
+            ReplaySerializeError::UnsortedInput { first_unsorted_index: 2, prev_time: 9, unsorted_time: 4 };
+        assert_snapshot(
+            &unsorted,
+            r#"{"code":"UnsortedInput","detail":{"firstUnsortedIndex":2,"prevTime":9,"unsortedTime":4}}"#,
+        );
+        assert_eq!(unsorted.to_string(), "Input 2 is on frame 4, before the frame 9 of the input before it");
+
+        let unknown = ReplaySerializeError::UnknownInputParseMode("V0.1".into());
+        assert_snapshot(&unknown, r#"{"code":"UnknownInputParseMode","detail":{"version":"V0.1"}}"#);
+
+        let json_error = serde_json::from_str::<Value>("{").unwrap_err();
+        let error = ReplaySerializeError::MetadataSerializeError(json_error);
+        assert_eq!(error.code(), "MetadataSerializeError");
+        let json = serde_json::to_value(&error).unwrap();
+        assert!(json["detail"]["error"].as_str().unwrap().starts_with("EOF"), "{json}");
+
+        let invalid = ReplaySerializeError::InvalidKeyCode { index: 3, code: 40 };
+        assert_snapshot(&invalid, r#"{"code":"InvalidKeyCode","detail":{"code":40,"index":3}}"#);
+        let message = "Input 3 has the key code 40, but other keys have to be from 21 to 31";
+        assert_eq!(invalid.to_string(), message);
+    }
+}
@@ -0,0 +1,103 @@
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::canonical_metadata_json;
+use crate::types::*;
+use crate::vlq;
+
+impl GameReplayData {
+    /// Computes a SHA-256 hash that identifies the replay, e.g. for finding duplicate uploads.
+    ///
+    /// The hash is computed from a canonical form of the replay, so it doesn't depend on
+    /// how the replay was stored: the metadata is encoded with [`canonical_metadata_json`]
+    /// (which sorts the keys and normalizes the numbers), and the inputs are sorted with
+    /// [`sort_inputs_canonical`][GameReplayData::sort_inputs_canonical] and timed absolutely.
+    /// [`input_mode`][GameReplayData::input_mode] is ignored.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let json = canonical_metadata_json(&self.metadata)
+            .expect("the metadata can always be converted to JSON");
+
+        let mut hasher = Sha256::new();
+        hasher.update(json.as_bytes());
+        hasher.update([10]);
+        hasher.update(self.canonical_input_bytes());
+
+        hasher.finalize().into()
+    }
+
+    /// Computes a SHA-256 hash of only the inputs of the replay.
+    ///
+    /// Two replays with the same inputs have the same input fingerprint, even if their metadata differs.
+    /// See [`fingerprint`][GameReplayData::fingerprint] for more information.
+    pub fn fingerprint_inputs_only(&self) -> [u8; 32] {
+        Sha256::digest(self.canonical_input_bytes()).into()
+    }
+
+    fn canonical_input_bytes(&self) -> Vec<u8> {
+        let mut inputs = self.inputs.clone();
+        inputs.sort();
+
+        // Packed like the serializer does, but without rejecting keys it can't write,
+        // so that every replay has a fingerprint
+        let mut bytes = Vec::new();
+        for input in &inputs {
+            let (time, code) = input.to_packed(InputParseMode::Absolute, 0);
+            vlq::encode_value_into(&mut bytes, time);
+            vlq::encode_value_into(&mut bytes, code);
+        }
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    fn replay() -> GameReplayData {
+        GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::press(10, HardDrop),
+                GameInputEvent::release(20, MoveLeft),
+                GameInputEvent::release(20, HardDrop),
+            ],
+            metadata: GameReplayMetadata {
+                version: "V0.17.22".to_string(),
+                setting: PlayerSettings {
+                    ghost: serde_json::Number::from_f64(0.3),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            input_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let data = replay();
+
+        // Differences that don't change the replay's meaning
+        let mut equivalent = replay();
+        equivalent.inputs.swap(0, 1);
+        equivalent.metadata.setting.ghost = serde_json::Number::from_f64(0.1 + 0.2);
+        equivalent.input_mode = Some(InputParseMode::Relative);
+
+        assert_eq!(data.fingerprint(), equivalent.fingerprint());
+
+        let mut renamed = replay();
+        renamed.metadata.player = "Nova".to_string();
+
+        assert_ne!(data.fingerprint(), renamed.fingerprint());
+        assert_eq!(data.fingerprint_inputs_only(), renamed.fingerprint_inputs_only());
+
+        let mut edited = replay();
+        edited.inputs[3].frame = 21;
+
+        assert_ne!(data.fingerprint(), edited.fingerprint());
+        assert_ne!(data.fingerprint_inputs_only(), edited.fingerprint_inputs_only());
+    }
+}
@@ -0,0 +1,73 @@
+//! Sample replays for testing, with the replay data they're expected to parse into.
+//!
+//! These are the replays this crate tests itself against, embedded into the crate,
+//! so that other crates can test against them too.
+//!
+//! ```
+//! use techmino_replay_toolkit::{fixtures, GameReplayData};
+//!
+//! for fixture in fixtures::all() {
+//!     let (Some(base64), Some(expected)) = (fixture.base64, fixture.expected) else { continue };
+//!     assert_eq!(GameReplayData::try_from_base64(base64, None).unwrap(), expected);
+//! }
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::types::*;
+
+/// A sample replay.
+///
+/// A fixture has at least one serialized form or its expected data, and usually both.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+    /// The name of the fixture, e.g. `someinputs`.
+    pub name: &'static str,
+    /// The replay as base64, as the game exports it.
+    pub base64: Option<&'static str>,
+    /// The replay as compressed bytes, as the game saves it.
+    pub binary: Option<&'static [u8]>,
+    /// The replay data the serialized forms parse into.
+    pub expected: Option<GameReplayData>,
+}
+
+/// The fixtures as stored: the name, the base64, the compressed bytes and the RON of the expected data.
+type StoredFixture = (&'static str, Option<&'static str>, Option<&'static [u8]>, Option<&'static str>);
+
+const FIXTURES: [StoredFixture; 3] = [
+    (
+        "earlyinput",
+        Some(include_str!("tests/cases/earlyinput.b64.rep")),
+        None,
+        Some(include_str!("tests/cases/earlyinput.ron")),
+    ),
+    (
+        "huge",
+        Some(include_str!("tests/cases/huge.b64.rep")),
+        None,
+        Some(include_str!("tests/cases/huge.ron")),
+    ),
+    (
+        "someinputs",
+        Some(include_str!("tests/cases/someinputs.b64.rep")),
+        None,
+        Some(include_str!("tests/cases/someinputs.ron")),
+    ),
+];
+
+/// All the fixtures, sorted by name.
+///
+/// The expected data is parsed on every call, which takes a moment for the larger fixtures.
+pub fn all() -> Vec<Fixture> {
+    FIXTURES
+        .into_iter()
+        .map(|(name, base64, binary, ron)| Fixture {
+            name,
+            base64,
+            binary,
+            expected: ron.map(|ron| {
+                ron::from_str(ron).unwrap_or_else(|error| panic!("Invalid RON in fixture {name}: {error}"))
+            }),
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+use crate::types::*;
+
+/// The frame in which the game starts.
+///
+/// The frames before this happen during the game start countdown.
+pub const GAME_START_FRAME: u64 = 180;
+
+/// The number of frames the game runs per second.
+pub const FRAMES_PER_SECOND: u64 = 60;
+
+impl GameInputEvent {
+    /// The frame this event occurred in, relative to the end of the countdown.
+    ///
+    /// Returns [`None`] if the event occurred during the countdown.
+    pub fn gameplay_frame(&self) -> Option<u64> {
+        self.frame.checked_sub(GAME_START_FRAME)
+    }
+}
+
+impl GameReplayData {
+    /// The frame of the last input in the replay.
+    ///
+    /// Returns [`None`] if there are no inputs.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.inputs.iter().map(|i| i.frame).max()
+    }
+
+    /// The number of frames between each input and the next one, in the order of the inputs.
+    ///
+    /// Yields one gap fewer than there are inputs, and 0 for inputs on the same frame.
+    /// The inputs are expected to be sorted; an input on an earlier frame than the one
+    /// before it also gives a gap of 0.
+    pub fn frame_gaps(&self) -> impl Iterator<Item = u64> + '_ {
+        self.inputs.windows(2).map(|pair| pair[1].frame.saturating_sub(pair[0].frame))
+    }
+
+    /// The duration of the replay in frames, including the countdown.
+    ///
+    /// The replay format has no end marker, so this is measured until the last input.
+    pub fn duration_frames(&self) -> u64 {
+        self.last_frame().unwrap_or(0)
+    }
+
+    /// The duration of the replay in seconds, including the countdown.
+    ///
+    /// The replay format has no end marker, so this is measured until the last input.
+    pub fn duration_secs(&self) -> f64 {
+        self.duration_frames() as f64 / FRAMES_PER_SECOND as f64
+    }
+
+    /// The duration of the replay in seconds, excluding the countdown.
+    ///
+    /// This is zero if the replay ends before the countdown does.
+    pub fn gameplay_duration_secs(&self) -> f64 {
+        self.duration_frames().saturating_sub(GAME_START_FRAME) as f64 / FRAMES_PER_SECOND as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    /// A replay of hard drops on the frames.
+    fn drops(frames: &[u64]) -> GameReplayData {
+        replay(frames.iter().map(|&frame| GameInputEvent::press(frame, HardDrop)).collect())
+    }
+
+    #[test]
+    fn test_frame_gaps() {
+        assert_eq!(drops(&[]).frame_gaps().count(), 0);
+        assert_eq!(drops(&[62]).frame_gaps().count(), 0);
+
+        let gaps: Vec<_> = drops(&[62, 180, 180, 1500, 1200]).frame_gaps().collect();
+        assert_eq!(gaps, [118, 0, 1320, 0]);
+    }
+
+    #[test]
+    fn test_durations() {
+        let empty = drops(&[]);
+        assert_eq!(empty.last_frame(), None);
+        assert_eq!(empty.duration_frames(), 0);
+        assert_eq!(empty.duration_secs(), 0.0);
+        assert_eq!(empty.gameplay_duration_secs(), 0.0);
+
+        let countdown_only = drops(&[30, 120]);
+        assert_eq!(countdown_only.last_frame(), Some(120));
+        assert_eq!(countdown_only.duration_secs(), 2.0);
+        assert_eq!(countdown_only.gameplay_duration_secs(), 0.0);
+
+        let sprint = drops(&[62, 180, 1500, 1200]);
+        assert_eq!(sprint.last_frame(), Some(1500));
+        assert_eq!(sprint.duration_frames(), 1500);
+        assert_eq!(sprint.duration_secs(), 25.0);
+        assert_eq!(sprint.gameplay_duration_secs(), 22.0);
+    }
+
+    #[test]
+    fn test_gameplay_frame() {
+        let event = |frame| GameInputEvent::release(frame, MoveLeft);
+
+        assert_eq!(event(0).gameplay_frame(), None);
+        assert_eq!(event(179).gameplay_frame(), None);
+        assert_eq!(event(180).gameplay_frame(), Some(0));
+        assert_eq!(event(245).gameplay_frame(), Some(65));
+    }
+}
@@ -0,0 +1,162 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use serde_json::{Map, Number, Value};
+
+use crate::types::*;
+
+/// Versions whose input timing is known, so that the generated replays can be serialized.
+const VERSIONS: [&str; 4] = ["V0.17.22", "V0.17.21", "V0.17.17", "V0.16.4"];
+
+/// A JSON value that survives a round trip through the metadata JSON unchanged.
+///
+/// Nulls are left out, as a null `private` field is read back as a missing one.
+fn json_value(u: &mut Unstructured) -> Result<Value> {
+    Ok(match u.int_in_range(0..=3)? {
+        0 => Value::Bool(u.arbitrary()?),
+        1 => Value::from(u.arbitrary::<i64>()?),
+        2 => Value::from(u.arbitrary::<String>()?),
+        _ => Value::Number(opacity(u)?.unwrap_or_else(|| 0.into())),
+    })
+}
+
+/// A map of nonstandard keys, which are prefixed so they can't be mistaken for standard ones.
+fn nonstandard(u: &mut Unstructured) -> Result<Map<String, Value>> {
+    let mut map = Map::new();
+    for _ in 0..u.int_in_range(0..=3)? {
+        let key: String = u.arbitrary()?;
+        map.insert(format!("x-{key}"), json_value(u)?);
+    }
+
+    Ok(map)
+}
+
+/// A float in hundredths, which are read back exactly, unlike floats with more digits.
+fn opacity(u: &mut Unstructured) -> Result<Option<Number>> {
+    let hundredths: Option<u8> = u.arbitrary()?;
+    Ok(hundredths.and_then(|hundredths| Number::from_f64(f64::from(hundredths) / 100.0)))
+}
+
+impl<'a> Arbitrary<'a> for PlayerSettings {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PlayerSettings {
+            atk_fx: u.arbitrary()?,
+            clear_fx: u.arbitrary()?,
+            drop_fx: u.arbitrary()?,
+            lock_fx: u.arbitrary()?,
+            move_fx: u.arbitrary()?,
+            shake_fx: u.arbitrary()?,
+            splash_fx: u.arbitrary()?,
+            das: u.arbitrary()?,
+            arr: u.arbitrary()?,
+            sddas: u.arbitrary()?,
+            sdarr: u.arbitrary()?,
+            dascut: u.arbitrary()?,
+            irscut: u.arbitrary()?,
+            dropcut: u.arbitrary()?,
+            irs: u.arbitrary()?,
+            ihs: u.arbitrary()?,
+            ims: u.arbitrary()?,
+            rs: u.arbitrary()?,
+            bag_line: u.arbitrary()?,
+            block: u.arbitrary()?,
+            center: opacity(u)?,
+            face: u.arbitrary()?,
+            ghost: opacity(u)?,
+            grid: opacity(u)?,
+            high_cam: u.arbitrary()?,
+            next_pos: u.arbitrary()?,
+            score: u.arbitrary()?,
+            skin: u.arbitrary()?,
+            smooth: u.arbitrary()?,
+            swap: u.arbitrary()?,
+            text: u.arbitrary()?,
+            warn: u.arbitrary()?,
+            ft_lock: u.arbitrary()?,
+            nonstandard: nonstandard(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GameReplayMetadata {
+    /// Generates metadata whose version is one with a known input timing.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let private = match u.arbitrary()? {
+            true => Some(Value::Object(nonstandard(u)?)),
+            false => None,
+        };
+        let mods = match u.arbitrary()? {
+            true => {
+                let count = u.int_in_range(0..=3)?;
+                Some((0..count).map(|_| Ok((u.arbitrary()?, json_value(u)?))).collect::<Result<_>>()?)
+            }
+            false => None,
+        };
+
+        Ok(GameReplayMetadata {
+            tas_used: u.arbitrary()?,
+            private,
+            player: u.arbitrary()?,
+            seed: u.arbitrary()?,
+            version: u.choose(&VERSIONS)?.to_string(),
+            date: u.arbitrary()?,
+            mods,
+            mode: u.arbitrary()?,
+            setting: u.arbitrary()?,
+            nonstandard: nonstandard(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GameReplayData {
+    /// Generates a replay that can be serialized, i.e. with its inputs sorted by frame.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut inputs: Vec<GameInputEvent> = u.arbitrary()?;
+        inputs.sort_by_key(|input| input.frame);
+
+        Ok(GameReplayData { inputs, metadata: u.arbitrary()?, input_mode: u.arbitrary()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic bytes for the generators, from a xorshift generator.
+    fn byte_soup(seed: u64, len: usize) -> Vec<u8> {
+        let mut next = crate::tests::helpers::xorshift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+        (0..len).map(|_| next() as u8).collect()
+    }
+
+    #[test]
+    fn test_arbitrary_replays_round_trip() {
+        for seed in 0..500 {
+            let bytes = byte_soup(seed, 4096);
+            let replay: GameReplayData = Unstructured::new(&bytes).arbitrary().unwrap();
+            assert!(replay.inputs.is_sorted_by_key(|input| input.frame));
+
+            let raw = replay.serialize_to_raw(None).unwrap();
+            let mut parsed = GameReplayData::try_from_raw(&raw, replay.input_mode).unwrap();
+            parsed.input_mode = replay.input_mode;
+            assert_eq!(parsed, replay, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_byte_soup_does_not_panic() {
+        for seed in 0..2000 {
+            let len = (seed as usize * 7) % 512;
+            let mut bytes = byte_soup(seed, len);
+            let _ = GameReplayData::try_from_raw(&bytes, None);
+            let _ = GameReplayData::try_from_compressed(&bytes, None);
+
+            // Soup after a valid zlib header and a metadata-like prefix gets further into the parser
+            bytes.splice(0..0, *b"{\"version\":\"V0.17.22\"}\n");
+            let _ = GameReplayData::try_from_raw(&bytes, None);
+            bytes.splice(0..0, [0x78, 0x9C]);
+            let _ = GameReplayData::try_from_compressed(&bytes, None);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde_json::Value;
+
+use crate::types::*;
+
+/// Encodes the metadata into JSON the way the game's JSON encoder does.
+///
+/// The game's encoder writes no whitespace, formats numbers like Lua's `%.14g` (so `1.0`
+/// is written as `1`, and `0.1 + 0.2` as `0.3`), escapes only the characters JSON requires, and leaves
+/// out keys without a value, as Lua tables can't hold `nil`s.
+///
+/// The order the game writes keys in depends on the layout of Lua's hash tables,
+/// which isn't stable even between runs of the game, so it can't be reproduced.
+/// Instead, the keys of every object are sorted, which makes the output deterministic.
+pub fn canonical_metadata_json(metadata: &GameReplayMetadata) -> Result<String, ReplaySerializeError> {
+    let value = serde_json::to_value(metadata)?;
+
+    let mut json = String::new();
+    write_value(&mut json, &value);
+
+    Ok(json)
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) if i.unsigned_abs() < 100_000_000_000_000 => write!(out, "{i}").unwrap(),
+                (_, Some(f)) => write_lua_number(out, f),
+                _ => write!(out, "{n}").unwrap(),
+            }
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, value);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().filter(|(_, v)| !v.is_null()).collect();
+            entries.sort_by_key(|&(key, _)| key);
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' || c == '\u{7f}' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes a number like C's `%.14g`.
+fn write_lua_number(out: &mut String, value: f64) {
+    const PRECISION: i32 = 14;
+
+    if value == 0.0 {
+        out.push('0');
+        return;
+    }
+
+    // `{:e}` rounds to the requested number of significant digits, which gives the exponent
+    let scientific = format!("{:.*e}", (PRECISION - 1) as usize, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if (-4..PRECISION).contains(&exponent) {
+        let decimals = (PRECISION - 1 - exponent) as usize;
+        out.push_str(trim_fraction(&format!("{value:.decimals$}")));
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        write!(out, "{}e{sign}{:02}", trim_fraction(mantissa), exponent.abs()).unwrap();
+    }
+}
+
+/// Removes trailing zeros after the decimal point, and the point itself if nothing is left after it.
+fn trim_fraction(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lua_number(value: f64) -> String {
+        let mut out = String::new();
+        write_lua_number(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn test_lua_numbers() {
+        let cases = [
+            (0.0, "0"),
+            (1.0, "1"),
+            (0.3, "0.3"),
+            (0.1 + 0.2, "0.3"),
+            (0.6999999, "0.6999999"),
+            (-2.5, "-2.5"),
+            (1e14, "1e+14"),
+            (123456789012345.0, "1.2345678901234e+14"),
+            (0.0001, "0.0001"),
+            (0.00001234, "1.234e-05"),
+            (1.0 / 3.0, "0.33333333333333"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(lua_number(value), expected, "formatting {value}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let mut metadata = GameReplayMetadata {
+            player: "Nova \"\\\u{1}".to_string(),
+            version: "V0.17.22".to_string(),
+            mods: Some(vec![]),
+            ..Default::default()
+        };
+        metadata.setting.ghost = serde_json::Number::from_f64(1.0);
+        metadata.setting.grid = serde_json::Number::from_f64(0.3);
+        metadata.setting.das = Some(8);
+
+        assert_eq!(
+            canonical_metadata_json(&metadata).unwrap(),
+            r#"{"date":"","mod":[],"mode":"","player":"Nova \"\\\u0001","seed":0,"setting":{"das":8,"ghost":1,"grid":0.3},"version":"V0.17.22"}"#
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+
+/// An input event as a line of JSON.
+///
+/// External tools depend on these field names, so they must not change.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonlInput {
+    frame: u64,
+    kind: InputEventKind,
+    key: InputEventKey,
+    /// Derived from the frame, so it's ignored when importing.
+    #[serde(default, skip_deserializing)]
+    gameplay_frame: Option<u64>,
+}
+
+/// An error from importing input events from JSON Lines.
+#[derive(Debug)]
+pub enum JsonlImportError {
+    /// The input couldn't be read.
+    Io(std::io::Error),
+    /// A line isn't a valid input event.
+    InvalidLine {
+        /// The line, starting from 1.
+        line: usize,
+        /// The error from parsing the line.
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for JsonlImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonlImportError::Io(error) => write!(f, "Couldn't read the input: {error}"),
+            JsonlImportError::InvalidLine { line, error } => {
+                write!(f, "Line {line} isn't a valid input event: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonlImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonlImportError::Io(error) => Some(error),
+            JsonlImportError::InvalidLine { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for JsonlImportError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl GameReplayData {
+    /// Writes the inputs as [JSON Lines](https://jsonlines.org/), one object per input:
+    ///
+    /// ```text
+    /// {"frame":62,"kind":"Press","key":"HardDrop","gameplayFrame":null}
+    /// {"frame":180,"kind":"Press","key":"RotateRight","gameplayFrame":0}
+    /// ```
+    ///
+    /// `gameplayFrame` is the frame relative to the game start (see [`GameInputEvent::gameplay_frame`]),
+    /// or `null` for inputs during the countdown.
+    ///
+    /// The field names and order are stable, so other tools can rely on them.
+    pub fn inputs_to_jsonl(&self, mut w: impl Write) -> std::io::Result<()> {
+        for input in &self.inputs {
+            let line = JsonlInput {
+                frame: input.frame,
+                kind: input.kind,
+                key: input.key,
+                gameplay_frame: input.gameplay_frame(),
+            };
+
+            serde_json::to_writer(&mut w, &line)?;
+            w.write_all(b"\n")?;
+        }
+
+        w.flush()
+    }
+
+    /// Reads input events from JSON Lines in the format written by
+    /// [`inputs_to_jsonl`][GameReplayData::inputs_to_jsonl].
+    ///
+    /// `gameplayFrame` is optional and ignored, as it's derived from `frame`. Empty lines are skipped,
+    /// and the events are returned in the order they're written in.
+    pub fn inputs_from_jsonl(r: impl Read) -> Result<Vec<GameInputEvent>, JsonlImportError> {
+        let mut inputs = Vec::new();
+
+        for (index, line) in BufReader::new(r).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let input: JsonlInput = serde_json::from_str(&line)
+                .map_err(|error| JsonlImportError::InvalidLine { line: index + 1, error })?;
+
+            inputs.push(GameInputEvent { frame: input.frame, kind: input.kind, key: input.key });
+        }
+
+        Ok(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_import() {
+        let jsonl = r#"{"frame":179,"kind":"Press","key":"Hold","gameplayFrame":null}
+
+{"key":"Hold","kind":"Release","frame":200}
+"#;
+
+        assert_eq!(
+            GameReplayData::inputs_from_jsonl(jsonl.as_bytes()).unwrap(),
+            [
+                GameInputEvent::press(179, Hold),
+                GameInputEvent::release(200, Hold),
+            ]
+        );
+
+        let error = GameReplayData::inputs_from_jsonl(&b"{\"frame\":1,\"kind\":\"Press\"}\n{}"[..]);
+        match error {
+            Err(JsonlImportError::InvalidLine { line: 1, .. }) => {}
+            other => panic!("Expected an invalid first line, got {other:?}"),
+        }
+    }
+}
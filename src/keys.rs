@@ -0,0 +1,365 @@
+use alloc::string::String;
+use core::fmt;
+use core::ptr;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+
+/// What a key is for, as grouped by [`InputEventKey::category`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum KeyCategory {
+    /// The keys that move the piece sideways, including the instant ones.
+    Movement,
+    /// The keys that rotate the piece.
+    Rotation,
+    /// The keys that move the piece down, from soft drops to hard drops.
+    Drop,
+    /// The hold key.
+    Hold,
+    /// The two function keys, which do different things in different modes.
+    Function,
+    /// The zangi-move keys, which move the piece to a wall and drop it in one press.
+    Misc,
+    /// The keys added by forks of the game, see [`InputEventKey::Other`].
+    Other,
+}
+
+/// An error from parsing an [`InputEventKey`] from a string that isn't the name of a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyParseError {
+    /// The string that was parsed.
+    pub name: String,
+}
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' isn't a key", self.name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyParseError {}
+
+impl InputEventKey {
+    /// All the keys, in the order of their key codes.
+    pub const ALL: [InputEventKey; 20] = {
+        use InputEventKey::*;
+
+        [
+            MoveLeft, MoveRight, RotateRight, RotateLeft, Rotate180, HardDrop, SoftDrop, Hold, Function1,
+            Function2, InstantLeft, InstantRight, SonicDrop, Down1, Down4, Down10, LeftDrop, RightDrop,
+            LeftZangi, RightZangi,
+        ]
+    };
+
+    /// What the key is for. See [`KeyCategory`] for which keys are in which category.
+    pub fn category(self) -> KeyCategory {
+        use InputEventKey::*;
+
+        match self {
+            MoveLeft | MoveRight | InstantLeft | InstantRight => KeyCategory::Movement,
+            RotateRight | RotateLeft | Rotate180 => KeyCategory::Rotation,
+            HardDrop | SoftDrop | SonicDrop | Down1 | Down4 | Down10 => KeyCategory::Drop,
+            Hold => KeyCategory::Hold,
+            Function1 | Function2 => KeyCategory::Function,
+            LeftDrop | RightDrop | LeftZangi | RightZangi => KeyCategory::Misc,
+            Other(_) => KeyCategory::Other,
+        }
+    }
+
+    /// Whether the key moves the piece sideways, i.e. is in [`KeyCategory::Movement`].
+    pub fn is_movement(self) -> bool {
+        self.category() == KeyCategory::Movement
+    }
+
+    /// Whether the key rotates the piece, i.e. is in [`KeyCategory::Rotation`].
+    pub fn is_rotation(self) -> bool {
+        self.category() == KeyCategory::Rotation
+    }
+
+    /// Whether the key moves the piece down, i.e. is in [`KeyCategory::Drop`].
+    ///
+    /// The zangi-move keys aren't included, as they're in [`KeyCategory::Misc`].
+    pub fn is_drop(self) -> bool {
+        self.category() == KeyCategory::Drop
+    }
+
+    /// The name of the key, e.g. `HardDrop`, as written in the text and CSV formats.
+    ///
+    /// This is the name of the variant, and for [`Other`][InputEventKey::Other] keys, the name in
+    /// the [installed][KeyRegistry::install] registry, or `Key` and the code, e.g. `Key21`.
+    pub fn name(self) -> &'static str {
+        match self {
+            InputEventKey::Other(code) => other_name(code),
+            key => key.variant_name(),
+        }
+    }
+
+    /// The name of the variant, e.g. `HardDrop`.
+    fn variant_name(self) -> &'static str {
+        use InputEventKey::*;
+
+        match self {
+            MoveLeft => "MoveLeft",
+            MoveRight => "MoveRight",
+            RotateRight => "RotateRight",
+            RotateLeft => "RotateLeft",
+            Rotate180 => "Rotate180",
+            HardDrop => "HardDrop",
+            SoftDrop => "SoftDrop",
+            Hold => "Hold",
+            Function1 => "Function1",
+            Function2 => "Function2",
+            InstantLeft => "InstantLeft",
+            InstantRight => "InstantRight",
+            SonicDrop => "SonicDrop",
+            Down1 => "Down1",
+            Down4 => "Down4",
+            Down10 => "Down10",
+            LeftDrop => "LeftDrop",
+            RightDrop => "RightDrop",
+            LeftZangi => "LeftZangi",
+            RightZangi => "RightZangi",
+            Other(_) => "Other",
+        }
+    }
+
+    /// The name of the key the game shows in the key config, e.g. `Hard Drop`.
+    ///
+    /// [`Other`][InputEventKey::Other] keys have the same label as [`name`][InputEventKey::name].
+    pub fn label(self) -> &'static str {
+        use InputEventKey::*;
+
+        match self {
+            MoveLeft => "Move Left",
+            MoveRight => "Move Right",
+            RotateRight => "Rotate Right",
+            RotateLeft => "Rotate Left",
+            Rotate180 => "Rotate 180",
+            HardDrop => "Hard Drop",
+            SoftDrop => "Soft Drop",
+            Hold => "Hold",
+            Function1 => "Function 1",
+            Function2 => "Function 2",
+            InstantLeft => "Instant Left",
+            InstantRight => "Instant Right",
+            SonicDrop => "Sonic Drop",
+            Down1 => "Down 1",
+            Down4 => "Down 4",
+            Down10 => "Down 10",
+            LeftDrop => "Left Drop",
+            RightDrop => "Right Drop",
+            LeftZangi => "Left Zangi",
+            RightZangi => "Right Zangi",
+            Other(code) => other_name(code),
+        }
+    }
+}
+
+/// The name of an [`Other`][InputEventKey::Other] key, from the installed registry if it has one.
+fn other_name(code: u8) -> &'static str {
+    KeyRegistry::installed()
+        .and_then(|registry| registry.name(code))
+        .or_else(|| FALLBACK_NAMES.get(code.wrapping_sub(FIRST_OTHER_CODE) as usize).copied())
+        .unwrap_or("Other")
+}
+
+/// The lowest key code that isn't a key of the game.
+const FIRST_OTHER_CODE: u8 = 21;
+
+/// The names of the [`Other`][InputEventKey::Other] keys the registry has no name for.
+const FALLBACK_NAMES: [&str; 11] =
+    ["Key21", "Key22", "Key23", "Key24", "Key25", "Key26", "Key27", "Key28", "Key29", "Key30", "Key31"];
+
+/// The registry [`KeyRegistry::install`] installed, if any.
+static INSTALLED: AtomicPtr<KeyRegistry> = AtomicPtr::new(ptr::null_mut());
+
+/// Names for the key codes 21 to 31, which some forks of the game use for keys of their own,
+/// e.g. extra function keys.
+///
+/// Parsing with a registry, see [`ParseOptions::key_registry`][crate::ParseOptions::key_registry],
+/// reads the codes it has names for as [`InputEventKey::Other`] keys instead of failing on them,
+/// and serializing writes the codes back.
+///
+/// The names are shown by [`InputEventKey::name`] and [`label`][InputEventKey::label], and parsed
+/// by [`FromStr`], e.g. by the text and CSV formats, once the registry is
+/// [installed][KeyRegistry::install] for the whole program:
+///
+/// ```
+/// use techmino_replay_toolkit::{InputEventKey, KeyRegistry};
+///
+/// static KEYS: KeyRegistry = KeyRegistry::new().with_key(21, "Retry");
+/// KEYS.install();
+///
+/// assert_eq!(InputEventKey::Other(21).label(), "Retry");
+/// assert_eq!("retry".parse(), Ok(InputEventKey::Other(21)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyRegistry {
+    /// The names of the codes from 21 on.
+    names: [Option<&'static str>; 11],
+}
+
+impl KeyRegistry {
+    /// Creates a registry without any names.
+    pub const fn new() -> KeyRegistry {
+        KeyRegistry { names: [None; 11] }
+    }
+
+    /// Names a key code, replacing the name it had.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code isn't from 21 to 31, as the lower ones are keys of the game and the
+    /// higher ones don't fit in the key bits of an input.
+    pub const fn with_key(mut self, code: u8, name: &'static str) -> KeyRegistry {
+        assert!(code >= FIRST_OTHER_CODE && code <= 31, "the key code isn't from 21 to 31");
+
+        self.names[(code - FIRST_OTHER_CODE) as usize] = Some(name);
+        self
+    }
+
+    /// The name of a key code, if the registry has one.
+    pub fn name(&self, code: u8) -> Option<&'static str> {
+        let index = code.checked_sub(FIRST_OTHER_CODE)?;
+        self.names.get(index as usize).copied().flatten()
+    }
+
+    /// The key code with a name, ignoring case, if the registry has one.
+    pub fn code(&self, name: &str) -> Option<u8> {
+        let matches = |other: &Option<&str>| other.is_some_and(|other| other.eq_ignore_ascii_case(name));
+        let index = self.names.iter().position(matches)?;
+        Some(index as u8 + FIRST_OTHER_CODE)
+    }
+
+    /// Makes this the registry whose names are shown and parsed, for the whole program.
+    ///
+    /// It replaces the registry installed before, if any.
+    pub fn install(&'static self) {
+        INSTALLED.store(ptr::from_ref(self).cast_mut(), Ordering::Release);
+    }
+
+    /// The installed registry, if any.
+    pub fn installed() -> Option<&'static KeyRegistry> {
+        // Only `install` stores into it, from a `'static` reference
+        unsafe { INSTALLED.load(Ordering::Acquire).as_ref() }
+    }
+}
+
+impl FromStr for InputEventKey {
+    type Err = KeyParseError;
+
+    /// Finds the key with a name or a [label][InputEventKey::label], ignoring case,
+    /// e.g. `HardDrop`, `harddrop` or `Hard Drop`.
+    ///
+    /// The names of the [installed][KeyRegistry::install] registry are found too,
+    /// and `Key21` to `Key31` are the [`Other`][InputEventKey::Other] keys with those codes.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let registered = || KeyRegistry::installed().and_then(|registry| registry.code(name));
+        let fallback = || {
+            let index = FALLBACK_NAMES.iter().position(|other| other.eq_ignore_ascii_case(name))?;
+            Some(index as u8 + FIRST_OTHER_CODE)
+        };
+
+        Self::ALL
+            .into_iter()
+            .find(|key| name.eq_ignore_ascii_case(key.name()) || name.eq_ignore_ascii_case(key.label()))
+            .or_else(|| registered().or_else(fallback).map(InputEventKey::Other))
+            .ok_or_else(|| KeyParseError { name: name.into() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::ToString;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_all_keys() {
+        for (index, key) in InputEventKey::ALL.into_iter().enumerate() {
+            assert_eq!(u8::from(key) as usize, index + 1);
+        }
+        assert_eq!(InputEventKey::try_from(21), Err(()));
+    }
+
+    #[test]
+    fn test_categories() {
+        use KeyCategory::*;
+
+        let categories = InputEventKey::ALL.map(InputEventKey::category);
+        assert_eq!(
+            categories,
+            [
+                Movement, Movement, Rotation, Rotation, Rotation, Drop, Drop, Hold, Function, Function,
+                Movement, Movement, Drop, Drop, Drop, Drop, Misc, Misc, Misc, Misc,
+            ]
+        );
+
+        for key in InputEventKey::ALL {
+            assert_eq!(key.is_movement(), key.category() == Movement);
+            assert_eq!(key.is_rotation(), key.category() == Rotation);
+            assert_eq!(key.is_drop(), key.category() == Drop);
+        }
+        assert!(InstantRight.is_movement() && Rotate180.is_rotation() && Down10.is_drop());
+        assert!(!LeftZangi.is_drop() && !InputEventKey::Hold.is_movement());
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        for key in InputEventKey::ALL {
+            let name = format!("{key:?}");
+            assert_eq!(name.parse(), Ok(key));
+            assert_eq!(name.to_lowercase().parse(), Ok(key));
+            assert_eq!(name.to_uppercase().parse(), Ok(key));
+            assert_eq!(key.label().parse(), Ok(key));
+            assert_eq!(key.label().to_lowercase().parse(), Ok(key));
+        }
+
+        assert_eq!("Instant Right".parse(), Ok(InstantRight));
+        assert_eq!("rotate 180".parse(), Ok(Rotate180));
+        assert_eq!("function1".parse(), Ok(Function1));
+
+        let error = "Jump".parse::<InputEventKey>().unwrap_err();
+        assert_eq!(error, KeyParseError { name: "Jump".to_string() });
+        assert_eq!(error.to_string(), "'Jump' isn't a key");
+        assert!(" HardDrop".parse::<InputEventKey>().is_err());
+        assert!("Hard  Drop".parse::<InputEventKey>().is_err());
+    }
+
+    #[test]
+    fn test_key_registry() {
+        use crate::{ParseOptions, ReplayParseError};
+
+        static KEYS: KeyRegistry = KeyRegistry::new().with_key(21, "Retry");
+        assert_eq!((KEYS.name(21), KEYS.name(22), KEYS.name(6)), (Some("Retry"), None, None));
+        assert_eq!((KEYS.code("RETRY"), KEYS.code("Key21")), (Some(21), None));
+
+        // A press of the key with the code 21 on frame 10
+        let metadata = br#"{"player":"","seed":0,"version":"V0.17.22","date":"","mode":"","setting":{}}"#;
+        let raw = [&metadata[..], b"\n\x0a\x15"].concat();
+        let error = GameReplayData::try_from_raw(&raw, None).unwrap_err();
+        assert!(matches!(error, ReplayParseError::MalformedInputData { key_bits: 21, .. }), "{error:?}");
+        let options = ParseOptions::new().key_registry(KEYS);
+        let data = GameReplayData::try_from_raw_with(&raw, &options).unwrap();
+        assert_eq!(data.inputs[0].key, Other(21));
+        assert!(data.serialize_to_raw(None).unwrap().ends_with(b"\n\x0a\x15"));
+
+        // Unregistered keys still have names of their own
+        assert_eq!((Other(22).name(), Other(22).label()), ("Key22", "Key22"));
+        assert_eq!("key22".parse(), Ok(Other(22)));
+
+        KEYS.install();
+        assert_eq!((Other(21).name(), Other(21).label()), ("Retry", "Retry"));
+        assert_eq!(Other(21).category(), KeyCategory::Other);
+        assert_eq!("retry".parse(), Ok(Other(21)));
+
+        let text = data.to_text().unwrap();
+        assert!(text.ends_with("[inputs]\n10 press Retry\n"), "{text}");
+        assert_eq!(GameReplayData::from_text(&text).unwrap(), data);
+    }
+}
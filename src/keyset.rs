@@ -0,0 +1,231 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{BitAnd, BitOr, Sub};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::types::*;
+
+/// A set of [`InputEventKey`]s, e.g. the keys held at a certain frame.
+///
+/// This is a small bitset, so it's cheap to copy and compare. Each key is stored in the bit
+/// of its key code, e.g. [`MoveLeft`][InputEventKey::MoveLeft] in bit 1, and
+/// [`Other`][InputEventKey::Other] keys in the bits 21 to 31. Bit 0 is reserved, as there's no
+/// key with the code 0.
+///
+/// It's serialized as a list of key names, e.g. `["MoveLeft","HardDrop"]`, and displayed
+/// as the key names separated by `+`, e.g. `MoveLeft+HardDrop`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct KeySet(u32);
+
+impl KeySet {
+    /// Creates an empty set.
+    pub const fn new() -> KeySet {
+        KeySet(0)
+    }
+
+    /// The bits that keys are stored in.
+    const KEY_BITS: u32 = !1;
+
+    /// Creates a set from its bits, e.g. from [`bits`][KeySet::bits] passed over FFI.
+    ///
+    /// Returns [`None`] if any reserved bit is set.
+    pub const fn from_bits(bits: u32) -> Option<KeySet> {
+        match bits & !Self::KEY_BITS {
+            0 => Some(KeySet(bits)),
+            _ => None,
+        }
+    }
+
+    /// The bits of the set, one for each key code in it.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    fn bit(key: InputEventKey) -> u32 {
+        // `Other` keys with codes that don't fit in the key bits of an input aren't stored
+        1u32.checked_shl(u8::from(key).into()).unwrap_or(0)
+    }
+
+    /// Adds a key to the set.
+    ///
+    /// Returns whether the key was newly inserted.
+    pub fn insert(&mut self, key: InputEventKey) -> bool {
+        let inserted = !self.contains(key);
+        self.0 |= Self::bit(key);
+        inserted
+    }
+
+    /// Removes a key from the set.
+    ///
+    /// Returns whether the key was in the set.
+    pub fn remove(&mut self, key: InputEventKey) -> bool {
+        let removed = self.contains(key);
+        self.0 &= !Self::bit(key);
+        removed
+    }
+
+    /// Returns whether the key is in the set.
+    pub fn contains(&self, key: InputEventKey) -> bool {
+        self.0 & Self::bit(key) != 0
+    }
+
+    /// Returns the number of keys in the set.
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterates over the keys in the set, in order of their key code.
+    pub fn iter(&self) -> impl Iterator<Item = InputEventKey> {
+        let bits = self.0;
+
+        (0..u32::BITS as u8)
+            .filter(move |code| bits & (1 << code) != 0)
+            .filter_map(|code| match code {
+                0 => None,
+                code => Some(InputEventKey::try_from(code).unwrap_or(InputEventKey::Other(code))),
+            })
+    }
+
+    /// The keys in either set.
+    pub const fn union(self, other: KeySet) -> KeySet {
+        KeySet(self.0 | other.0)
+    }
+
+    /// The keys in both sets.
+    pub const fn intersection(self, other: KeySet) -> KeySet {
+        KeySet(self.0 & other.0)
+    }
+
+    /// The keys in this set but not in the other one.
+    pub const fn difference(self, other: KeySet) -> KeySet {
+        KeySet(self.0 & !other.0)
+    }
+}
+
+impl BitOr for KeySet {
+    type Output = KeySet;
+
+    fn bitor(self, other: KeySet) -> KeySet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for KeySet {
+    type Output = KeySet;
+
+    fn bitand(self, other: KeySet) -> KeySet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for KeySet {
+    type Output = KeySet;
+
+    fn sub(self, other: KeySet) -> KeySet {
+        self.difference(other)
+    }
+}
+
+impl fmt::Display for KeySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, key) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_str("+")?;
+            }
+            f.write_str(key.name())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for KeySet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<InputEventKey>::deserialize(deserializer).map(KeySet::from_iter)
+    }
+}
+
+impl FromIterator<InputEventKey> for KeySet {
+    fn from_iter<T: IntoIterator<Item = InputEventKey>>(iter: T) -> Self {
+        let mut set = KeySet::new();
+
+        for key in iter {
+            set.insert(key);
+        }
+
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_every_key() {
+        let mut all = KeySet::new();
+
+        for key in InputEventKey::ALL {
+            let mut set = KeySet::new();
+            assert!(set.insert(key));
+            assert!(!set.insert(key));
+            assert!(set.contains(key));
+            assert_eq!((set.len(), set.bits()), (1, 1 << u8::from(key)));
+            assert_eq!(set.iter().collect::<Vec<_>>(), [key]);
+            assert_eq!(KeySet::from_bits(set.bits()), Some(set));
+            assert_eq!(set.to_string(), format!("{key:?}"));
+
+            all = all | set;
+            assert!(set.remove(key));
+            assert!(set.is_empty());
+        }
+
+        assert_eq!(all.len(), InputEventKey::ALL.len());
+        assert_eq!(all.iter().collect::<Vec<_>>(), InputEventKey::ALL);
+        assert_eq!(KeySet::from_bits(all.bits()), Some(all));
+
+        // Bit 0 could only hold a key with the code 0, and the bits above the game's keys hold `Other` keys
+        assert_eq!(KeySet::from_bits(1), None);
+        for code in [21, 31] {
+            let set = KeySet::from_bits(1 << code).unwrap();
+            assert_eq!(set.iter().collect::<Vec<_>>(), [Other(code)]);
+        }
+        assert!(!KeySet::from_iter([Other(32)]).contains(Other(32)));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left = KeySet::from_iter([MoveLeft, HardDrop, Hold]);
+        let right = KeySet::from_iter([HardDrop, RotateLeft]);
+
+        assert_eq!(left | right, KeySet::from_iter([MoveLeft, RotateLeft, HardDrop, Hold]));
+        assert_eq!(left & right, KeySet::from_iter([HardDrop]));
+        assert_eq!(left - right, KeySet::from_iter([MoveLeft, Hold]));
+        assert_eq!(left.union(right), left | right);
+        assert_eq!(left.to_string(), "MoveLeft+HardDrop+Hold");
+        assert_eq!(KeySet::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_serde() {
+        let set = KeySet::from_iter([Hold, MoveLeft]);
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, r#"["MoveLeft","Hold"]"#);
+        assert_eq!(serde_json::from_str::<KeySet>(&json).unwrap(), set);
+        assert!(serde_json::from_str::<KeySet>(r#"["Jump"]"#).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+
+use crate::compression::{compress, decompress};
+use crate::detect::reject_non_replay;
+use crate::deserialize::parse_input_slice;
+use crate::encoding;
+use crate::serialize::encode_inputs;
+use crate::types::*;
+use crate::{RecompressionFidelity, ReplayEncoding, ReplayMetadata, SerializeOptions, SplitReplay};
+
+/// A replay whose inputs are only decoded when they're first accessed.
+///
+/// Until the inputs are borrowed mutably, the original input data is kept, and serializing the
+/// replay copies it verbatim instead of re-encoding the inputs. This makes metadata edits
+/// (e.g. renaming the player) cheap for long replays, and guarantees that the input data comes
+/// out byte for byte the same, even if it has oddities that re-encoding would normalize.
+///
+/// ```
+/// use techmino_replay_toolkit::{GameReplayData, LazyReplayData};
+///
+/// # let replay = GameReplayData {
+/// #     metadata: techmino_replay_toolkit::GameReplayMetadata {
+/// #         version: "V0.17.22".to_string(),
+/// #         ..Default::default()
+/// #     },
+/// #     ..Default::default()
+/// # };
+/// # let base64 = replay.serialize_to_base64(None).unwrap();
+/// let mut replay = LazyReplayData::try_from_base64(&base64, None).unwrap();
+/// replay.metadata.player = "Nova".to_string();
+///
+/// // The inputs aren't decoded or re-encoded here
+/// let renamed = replay.serialize_to_base64(None).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct LazyReplayData {
+    /// The metadata of the replay.
+    pub metadata: GameReplayMetadata,
+    /// The original input data, as long as the inputs haven't been borrowed mutably.
+    raw_inputs: Option<Vec<u8>>,
+    /// The mode the original input data is timed in.
+    parse_mode: InputParseMode,
+    inputs: OnceCell<Vec<GameInputEvent>>,
+    /// The compressed bytes the replay was parsed from and the metadata parsed from them,
+    /// as long as the inputs haven't been borrowed mutably.
+    original: Option<(Vec<u8>, GameReplayMetadata)>,
+}
+
+impl LazyReplayData {
+    /// Parses the metadata of a base64 string, keeping the input data for later.
+    ///
+    /// See [`GameReplayData::try_from_base64`] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_base64", skip_all, fields(bytes = string.len(), lazy = true))
+    )]
+    pub fn try_from_base64(
+        string: &str,
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<LazyReplayData, ReplayParseError> {
+        reject_non_replay(string.as_bytes(), ReplayEncoding::Base64)?;
+        let data = encoding::decode(string)?;
+
+        Self::try_from_compressed(&data, parse_mode)
+    }
+
+    /// Parses the metadata of a compressed byte array, keeping the input data for later.
+    ///
+    /// The compressed bytes are kept too, for serializing with
+    /// [`RecompressionFidelity::Exact`][crate::RecompressionFidelity::Exact].
+    ///
+    /// See [`GameReplayData::try_from_compressed`] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "try_from_compressed", skip_all, fields(bytes = data.len(), lazy = true))
+    )]
+    pub fn try_from_compressed(
+        data: &[u8],
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<LazyReplayData, ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let raw = decompress(data, usize::MAX)?;
+
+        let mut replay = Self::try_from_raw(&raw, parse_mode)?;
+        replay.original = Some((data.to_vec(), replay.metadata.clone()));
+
+        Ok(replay)
+    }
+
+    /// Parses the metadata of a raw, uncompressed byte array, keeping the input data for later.
+    ///
+    /// The input parse mode is resolved right away, so this fails in the same cases as
+    /// [`GameReplayData::try_from_raw`] except for malformed input data,
+    /// which is only detected when the inputs are accessed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "try_from_raw",
+            skip_all,
+            fields(bytes = data.len(), lazy = true, parse_mode = tracing::field::Empty)
+        )
+    )]
+    pub fn try_from_raw(
+        data: &[u8],
+        parse_mode: Option<InputParseMode>,
+    ) -> Result<LazyReplayData, ReplayParseError> {
+        let split = SplitReplay::from_raw(data)?;
+
+        let metadata = GameReplayMetadata::try_from(split.metadata_bytes)?;
+
+        let parse_mode = match parse_mode
+            .or_else(|| InputParseMode::try_infer_from_version(&metadata.version))
+        {
+            Some(mode) => mode,
+            None => return Err(ReplayParseError::UnknownInputParseMode(metadata.version)),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("parse_mode", tracing::field::debug(parse_mode));
+
+        Ok(LazyReplayData {
+            metadata,
+            raw_inputs: Some(split.input_bytes.to_vec()),
+            parse_mode,
+            inputs: OnceCell::new(),
+            original: None,
+        })
+    }
+
+    /// Returns the inputs, decoding them if they haven't been decoded yet.
+    pub fn inputs(&self) -> Result<&[GameInputEvent], ReplayParseError> {
+        if let Some(inputs) = self.inputs.get() {
+            return Ok(inputs);
+        }
+
+        let raw = self.raw_inputs.as_deref().unwrap_or_default();
+        let inputs = parse_input_slice(raw, self.parse_mode)?;
+
+        Ok(self.inputs.get_or_init(|| inputs))
+    }
+
+    /// Returns the inputs for editing, decoding them if they haven't been decoded yet.
+    ///
+    /// The original input data is dropped, so from then on, serializing the replay re-encodes the inputs.
+    pub fn inputs_mut(&mut self) -> Result<&mut Vec<GameInputEvent>, ReplayParseError> {
+        self.inputs()?;
+        self.raw_inputs = None;
+        self.original = None;
+
+        Ok(self.inputs.get_mut().expect("the inputs were just decoded"))
+    }
+
+    /// Returns whether the original input data is still kept, i.e. the inputs haven't been borrowed mutably.
+    pub fn has_raw_inputs(&self) -> bool {
+        self.raw_inputs.is_some()
+    }
+
+    /// The compressed bytes the replay was parsed from, as long as it's unmodified,
+    /// i.e. its metadata is the same and its inputs haven't been borrowed mutably.
+    ///
+    /// Returns [`None`] for replays that weren't parsed from compressed data.
+    pub fn original_compressed(&self) -> Option<&[u8]> {
+        match &self.original {
+            Some((compressed, metadata)) if *metadata == self.metadata => Some(compressed),
+            _ => None,
+        }
+    }
+
+    /// Decodes the inputs and turns this into a [`GameReplayData`].
+    pub fn into_data(self) -> Result<GameReplayData, ReplayParseError> {
+        self.inputs()?;
+
+        Ok(GameReplayData {
+            inputs: self.inputs.into_inner().unwrap_or_default(),
+            metadata: self.metadata,
+            input_mode: Some(self.parse_mode),
+        })
+    }
+
+    /// Serialize into a raw, uncompressed byte array.
+    ///
+    /// If the original input data is still kept and is timed in the target input mode, it is copied as is.
+    /// Otherwise, the inputs are re-encoded like in [`GameReplayData::serialize_to_raw`].
+    /// Input data that can't be decoded is also copied as is, but can't be re-timed into another
+    /// mode, which fails with [`ReplaySerializeError::UndecodableInputs`].
+    ///
+    /// `input_mode` is an optional argument used to specify how the inputs should be timed.
+    /// If it's [`None`], it is inferred from the game version, falling back to the mode
+    /// the replay was parsed with.
+    pub fn serialize_to_raw(&self, input_mode: Option<InputParseMode>) -> Result<Vec<u8>, ReplaySerializeError> {
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_raw_with(&options)
+    }
+
+    /// Serialize into a raw, uncompressed byte array, with the given options.
+    ///
+    /// See [`serialize_to_raw`][LazyReplayData::serialize_to_raw] and [`SerializeOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "serialize_to_raw", skip_all, fields(lazy = true))
+    )]
+    pub fn serialize_to_raw_with(&self, options: &SerializeOptions) -> Result<Vec<u8>, ReplaySerializeError> {
+        let input_mode = self.input_mode(options);
+
+        let mut buffer = Vec::new();
+        self.metadata.write_bytes(&mut buffer, options)?;
+        buffer.push(10);
+
+        match &self.raw_inputs {
+            Some(raw) if input_mode == self.parse_mode => buffer.extend_from_slice(raw),
+            _ => {
+                let inputs = self.inputs().map_err(ReplaySerializeError::UndecodableInputs)?;
+                encode_inputs(&mut buffer, inputs, input_mode)?;
+            }
+        }
+
+        Ok(buffer)
+    }
+
+    /// Serialize into a compressed byte array used by the game.
+    ///
+    /// See [`serialize_to_raw`][LazyReplayData::serialize_to_raw] for more information.
+    pub fn serialize_to_compressed(
+        &self,
+        input_mode: Option<InputParseMode>,
+    ) -> Result<Vec<u8>, ReplaySerializeError> {
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_compressed_with(&options)
+    }
+
+    /// Serialize into a compressed byte array used by the game, with the given options.
+    ///
+    /// With [`RecompressionFidelity::Exact`], an unmodified replay whose inputs are timed like they
+    /// were is written as the [original compressed bytes][LazyReplayData::original_compressed].
+    ///
+    /// See [`serialize_to_raw`][LazyReplayData::serialize_to_raw] and [`SerializeOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "serialize_to_compressed", skip_all, fields(lazy = true))
+    )]
+    pub fn serialize_to_compressed_with(
+        &self,
+        options: &SerializeOptions,
+    ) -> Result<Vec<u8>, ReplaySerializeError> {
+        let exact = options.recompression == RecompressionFidelity::Exact;
+        if let Some(original) = self.original_compressed().filter(|_| exact) {
+            if self.input_mode(options) == self.parse_mode {
+                return Ok(original.to_vec());
+            }
+        }
+
+        let raw_bytes = self.serialize_to_raw_with(options)?;
+
+        Ok(compress(&raw_bytes, 6))
+    }
+
+    /// Serialize into a copiable text-based base64 format.
+    ///
+    /// See [`serialize_to_raw`][LazyReplayData::serialize_to_raw] for more information.
+    pub fn serialize_to_base64(
+        &self,
+        input_mode: Option<InputParseMode>,
+    ) -> Result<String, ReplaySerializeError> {
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_base64_with(&options)
+    }
+
+    /// Serialize into a copiable text-based base64 format, with the given options.
+    ///
+    /// See [`serialize_to_compressed_with`][LazyReplayData::serialize_to_compressed_with] and
+    /// [`SerializeOptions`] for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "serialize_to_base64", skip_all, fields(lazy = true))
+    )]
+    pub fn serialize_to_base64_with(
+        &self,
+        options: &SerializeOptions,
+    ) -> Result<String, ReplaySerializeError> {
+        let bytes = self.serialize_to_compressed_with(options)?;
+
+        Ok(encoding::encode(&bytes, options))
+    }
+
+    /// How the inputs are timed when serializing with the options.
+    fn input_mode(&self, options: &SerializeOptions) -> InputParseMode {
+        options
+            .input_mode
+            .or_else(|| InputParseMode::try_infer_from_version(&self.metadata.version))
+            .unwrap_or(self.parse_mode)
+    }
+}
+
+impl From<GameReplayData> for LazyReplayData {
+    fn from(value: GameReplayData) -> Self {
+        let parse_mode = value
+            .input_mode
+            .or_else(|| InputParseMode::try_infer_from_version(&value.metadata.version))
+            .unwrap_or(InputParseMode::Absolute);
+
+        LazyReplayData {
+            metadata: value.metadata,
+            raw_inputs: None,
+            parse_mode,
+            inputs: OnceCell::from(value.inputs),
+            original: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    fn raw_replay() -> Vec<u8> {
+        let metadata = br#"{"player":"Nova","seed":0,"version":"V0.17.22","date":"","mode":"","setting":{}}"#;
+
+        // The second VLQ is padded with a redundant leading 0x80 byte,
+        // which re-encoding would drop
+        SplitReplay::reassemble(metadata, &[10, 0x80, 6, 11, 6 | 0b10_0000])
+    }
+
+    #[test]
+    fn test_untouched_inputs_are_copied() {
+        let raw = raw_replay();
+        let mut data = LazyReplayData::try_from_raw(&raw, None).unwrap();
+
+        data.metadata.player = "Lumi".to_string();
+        assert_eq!(
+            data.inputs().unwrap(),
+            [
+                GameInputEvent::press(10, HardDrop),
+                GameInputEvent::release(11, HardDrop),
+            ]
+        );
+
+        let serialized = data.serialize_to_raw(None).unwrap();
+        let split = SplitReplay::from_raw(&serialized).unwrap();
+        assert_eq!(split.input_bytes, SplitReplay::from_raw(&raw).unwrap().input_bytes);
+        assert_eq!(GameReplayData::try_from_raw(&serialized, None).unwrap().metadata.player, "Lumi");
+
+        // Re-encoding in a different mode decodes the inputs
+        let relative = data.serialize_to_raw(Some(InputParseMode::Relative)).unwrap();
+        assert_eq!(SplitReplay::from_raw(&relative).unwrap().input_bytes, [10, 6, 1, 6 | 0b10_0000]);
+    }
+
+    #[test]
+    fn test_edited_inputs_are_reencoded() {
+        let mut data = LazyReplayData::try_from_raw(&raw_replay(), None).unwrap();
+
+        data.inputs_mut().unwrap().push(GameInputEvent::press(12, Hold));
+        assert!(!data.has_raw_inputs());
+
+        let serialized = data.serialize_to_raw(None).unwrap();
+        assert_eq!(
+            SplitReplay::from_raw(&serialized).unwrap().input_bytes,
+            [10, 6, 11, 6 | 0b10_0000, 12, 8]
+        );
+
+        let data = data.into_data().unwrap();
+        assert_eq!(data.inputs.len(), 3);
+    }
+
+    #[test]
+    fn test_undecodable_inputs() {
+        let metadata = br#"{"player":"","seed":0,"version":"V0.17.22","date":"","mode":"","setting":{}}"#;
+        // A truncated VLQ, which isn't decoded until the inputs are needed
+        let raw = SplitReplay::reassemble(metadata, &[10, 6, 0x80]);
+        let data = LazyReplayData::try_from_raw(&raw, None).unwrap();
+
+        assert!(data.serialize_to_raw(None).unwrap().ends_with(b"\n\n\x06\x80"));
+        let error = data.serialize_to_raw(Some(InputParseMode::Relative)).unwrap_err();
+        assert!(matches!(error, ReplaySerializeError::UndecodableInputs(ReplayParseError::MalformedVlq(_))));
+    }
+
+    #[test]
+    fn test_exact_recompression() {
+        // Compressed differently than this crate does
+        let original = compress(&raw_replay(), 1);
+        let exact = SerializeOptions::new().recompression(RecompressionFidelity::Exact);
+
+        let mut data = LazyReplayData::try_from_compressed(&original, None).unwrap();
+        assert_eq!(data.original_compressed(), Some(&original[..]));
+        assert_eq!(data.serialize_to_compressed_with(&exact).unwrap(), original);
+        assert_ne!(data.serialize_to_compressed(None).unwrap(), original);
+        let relative = exact.clone().input_mode(InputParseMode::Relative);
+        assert_ne!(data.serialize_to_compressed_with(&relative).unwrap(), original);
+
+        // Editing the metadata and changing it back keeps the original bytes
+        data.metadata.player = "Lumi".to_string();
+        assert_eq!(data.original_compressed(), None);
+        let renamed = data.serialize_to_compressed_with(&exact).unwrap();
+        assert_eq!(GameReplayData::try_from_compressed(&renamed, None).unwrap().metadata.player, "Lumi");
+        data.metadata.player = "Nova".to_string();
+        assert_eq!(data.serialize_to_compressed_with(&exact).unwrap(), original);
+
+        // Borrowing the inputs mutably drops them for good
+        data.inputs_mut().unwrap();
+        assert_eq!(data.original_compressed(), None);
+        assert_ne!(data.serialize_to_compressed_with(&exact).unwrap(), original);
+        assert_eq!(LazyReplayData::try_from_raw(&raw_replay(), None).unwrap().original_compressed(), None);
+    }
+}
@@ -0,0 +1,124 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::*;
+
+/// A replay in the shape of [`GameReplayData::to_legacy_json`].
+#[derive(Serialize, Deserialize)]
+struct LegacyReplay<M> {
+    inputs: Vec<LegacyInput>,
+    metadata: M,
+}
+
+/// An input with the game's numeric codes.
+#[derive(Serialize, Deserialize)]
+struct LegacyInput {
+    frame: u64,
+    #[serde(rename = "type")]
+    kind: u8,
+    key: u8,
+}
+
+impl GameReplayData {
+    /// Encodes the replay as JSON with the inputs as numeric codes, a shape modeled on the output
+    /// of the JavaScript [`techmino-replay-parser`](https://github.com/techmino-hub/techmino-replay-parser):
+    ///
+    /// ```text
+    /// {"inputs":[{"frame":62,"type":0,"key":6}],"metadata":{"mod":[],"player":"Nova",...}}
+    /// ```
+    ///
+    /// The inputs are a flat array of objects with the game's numeric codes:
+    /// `type` is 0 for presses and 1 for releases, and `key` is the key code (see [`InputEventKey`]).
+    /// The metadata has the keys the game writes, e.g. `mod` rather than `mods`, and leaves out
+    /// missing values instead of writing `null`s.
+    ///
+    /// [`input_mode`][GameReplayData::input_mode] isn't part of this shape, so it's left out.
+    ///
+    /// The output hasn't been compared with the JavaScript parser's, so tools written against
+    /// that parser may still need adjusting.
+    pub fn to_legacy_json(&self) -> Result<String, ReplaySerializeError> {
+        let mut metadata = serde_json::to_value(&self.metadata)?;
+        remove_nulls(&mut metadata);
+
+        let replay = LegacyReplay {
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| LegacyInput {
+                    frame: input.frame,
+                    kind: input.kind.into(),
+                    key: input.key.into(),
+                })
+                .collect(),
+            metadata,
+        };
+
+        Ok(serde_json::to_string(&replay)?)
+    }
+
+    /// Decodes a replay from JSON in the shape written by
+    /// [`to_legacy_json`][GameReplayData::to_legacy_json].
+    ///
+    /// Fails if the JSON doesn't have that shape, or if an input has an unknown `type` or `key` code.
+    pub fn from_legacy_json(json: &str) -> Result<GameReplayData, serde_json::Error> {
+        let replay: LegacyReplay<GameReplayMetadata> = serde_json::from_str(json)?;
+
+        let inputs = replay
+            .inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let unknown = |field, code| {
+                    serde_json::Error::custom(format!("inputs[{index}] has an unknown {field} {code}"))
+                };
+
+                let kind = InputEventKind::try_from(input.kind).map_err(|_| unknown("type", input.kind))?;
+                let key = InputEventKey::try_from(input.key).map_err(|_| unknown("key", input.key))?;
+
+                Ok(GameInputEvent { frame: input.frame, kind, key })
+            })
+            .collect::<Result<_, serde_json::Error>>()?;
+
+        Ok(GameReplayData { inputs, metadata: replay.metadata, input_mode: None })
+    }
+}
+
+/// Removes the `null` values of every object within a value.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_codes() {
+        let json = |kind: u8, key: u8| {
+            format!(
+                r#"{{"inputs":[{{"frame":1,"type":0,"key":6}},{{"frame":2,"type":{kind},"key":{key}}}],
+                "metadata":{{"player":"","seed":0,"version":"","date":"","mode":"","setting":{{}}}}}}"#
+            )
+        };
+
+        assert!(GameReplayData::from_legacy_json(&json(1, 6)).is_ok());
+
+        let error = GameReplayData::from_legacy_json(&json(2, 6)).unwrap_err();
+        assert_eq!(error.to_string(), "inputs[1] has an unknown type 2");
+
+        let error = GameReplayData::from_legacy_json(&json(1, 21)).unwrap_err();
+        assert_eq!(error.to_string(), "inputs[1] has an unknown key 21");
+    }
+}
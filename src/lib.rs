@@ -9,11 +9,198 @@
 // TODO: Improve crate-level docs and more tests
 
 #![warn(missing_docs)]
+// Tests always have `std`, as the test harness needs it
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 mod deserialize;
 mod serialize;
 mod types;
 pub use types::*;
 
+mod keys;
+pub use keys::{KeyCategory, KeyParseError, KeyRegistry};
+
+mod compression;
+pub use compression::{compressed_equivalent, decompress_replay, DecompressError, DecompressErrorKind};
+
+mod encoding;
+pub use encoding::decode_base64_replay;
+
+mod json;
+pub use json::canonical_metadata_json;
+
+mod metadata;
+pub use metadata::{RawMetadata, ReplayMetadata};
+
+mod codec;
+pub use codec::{KEY_MASK, KIND_BIT};
+
+mod frames;
+pub use frames::{FRAMES_PER_SECOND, GAME_START_FRAME};
+
+mod summary;
+pub use summary::{ReplaySummary, DEFAULT_DISPLAYED_INPUTS};
+
+mod size;
+pub use size::SizeReport;
+
+mod presses;
+pub use presses::{KeyPress, KeyPressAnomaly, KeyPressPairing};
+
+mod keyset;
+pub use keyset::KeySet;
+
+mod timeline;
+pub use timeline::KeyStateTimeline;
+
+mod cursor;
+pub use cursor::ReplayCursor;
+
+mod diff;
+pub use diff::{InputDivergence, MetadataDiff, ReplayDiff, SettingDiff};
+
+mod text;
+pub use text::{TextParseError, TextParseErrorKind};
+
+#[cfg(feature = "std")]
+mod jsonl;
+#[cfg(feature = "std")]
+pub use jsonl::JsonlImportError;
+
+mod legacy;
+
+mod options;
+pub use options::{AnonymizeOptions, ParseOptions, RecompressionFidelity, SerializeOptions};
+
+mod report;
+pub use report::{ParseReport, ParseWarning};
+
+mod split;
+pub use split::SplitReplay;
+
+mod detect;
+pub use detect::{detect_replay_encoding, DetectedFormat, ReplayEncoding};
+
+mod error_codes;
+
+mod annotations;
+pub use annotations::ANNOTATIONS_KEY;
+
+mod segments;
+pub use segments::{Segment, SEGMENTS_KEY};
+
+mod annotate;
+pub use annotate::{annotate_input_bytes, InputAnnotation, InputValueMeaning, MalformedInputValue};
+
+mod lazy;
+pub use lazy::LazyReplayData;
+
+mod recover;
+pub use recover::{DataLoss, RecoveryResult};
+
+mod semantic_eq;
+pub use semantic_eq::SemanticEqOptions;
+
+mod validate;
+pub use validate::{ValidationIssue, MAX_PLAUSIBLE_FRAME, MAX_PLAUSIBLE_INPUTS};
+
+mod settings;
+pub use settings::SettingsIssue;
+
+mod settings_file;
+pub use settings_file::SettingsParseError;
+
+mod lua;
+
+mod pieces;
+pub use pieces::PieceId;
+
+mod strict;
+pub use strict::StrictViolation;
+
+mod private;
+pub use private::{CustomClearData, CustomPuzzleData};
+
+mod mods;
+pub use mods::GameMod;
+
+mod modes;
+pub use modes::{mode_info, ModeCategory, ModeInfo, MODES};
+
+mod repair;
+pub use repair::{RepairAction, RepairPolicy, RepairReport};
+
+mod query;
+
+mod edit;
+pub use edit::{SpliceError, TransformError};
+
+mod anonymize;
+
+mod builder;
+pub use builder::{BuilderError, ReplayBuilder};
+
+mod batch;
+pub use batch::parse_many;
+#[cfg(feature = "rayon")]
+pub use batch::par_parse_many;
+#[cfg(feature = "std")]
+pub use batch::parse_replay_dir;
+
+#[cfg(feature = "std")]
+mod locate;
+#[cfg(feature = "std")]
+pub use locate::{locate_replay_dir, replay_dir_candidates};
+
+pub mod analysis;
+pub mod stats;
+pub mod verify;
+pub mod vlq;
+
+#[cfg(feature = "fingerprint")]
+mod fingerprint;
+
+#[cfg(feature = "binary-cache")]
+mod cache;
+#[cfg(feature = "binary-cache")]
+pub use cache::BinaryCacheError;
+
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(feature = "schema")]
+pub use schema::export_schema;
+
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "archive")]
+pub use archive::{ArchiveEntry, ArchiveError, ReplayArchive};
+
+#[cfg(feature = "csv")]
+mod csv_io;
+#[cfg(feature = "csv")]
+pub use csv_io::CsvImportError;
+
+#[cfg(any(feature = "fixtures", test))]
+pub mod fixtures;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "chrono")]
+mod date;
+#[cfg(feature = "chrono")]
+pub use date::DateParseError;
+
 #[cfg(test)]
 mod tests;
\ No newline at end of file
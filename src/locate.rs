@@ -0,0 +1,80 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// The name of the game's save directory, its LÖVE identity.
+const IDENTITY: &str = "Techmino";
+
+/// Finds the directory the game saves replays in on this system, if it exists.
+///
+/// The game is built on [LÖVE](https://love2d.org), which keeps saves in a directory named after
+/// the game inside a per-user data directory, e.g. `%APPDATA%\LOVE\Techmino\replay` on Windows,
+/// `~/.local/share/love/Techmino/replay` on Linux and
+/// `~/Library/Application Support/LOVE/Techmino/replay` on macOS.
+/// Standalone builds of the game leave out the `LOVE` directory, so both are checked.
+///
+/// See [`replay_dir_candidates`] for the directories that are checked.
+pub fn locate_replay_dir() -> Option<PathBuf> {
+    replay_dir_candidates().into_iter().find(|path| path.is_dir())
+}
+
+/// The directories the game may save replays in on this system, most likely first.
+///
+/// The directories don't necessarily exist; see [`locate_replay_dir`].
+pub fn replay_dir_candidates() -> Vec<PathBuf> {
+    candidates(env::consts::OS, |name| env::var_os(name))
+}
+
+fn candidates(os: &str, var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let var = |name: &str| var(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let home = var("HOME");
+
+    // The data directory, and the name of LÖVE's directory inside it
+    let (data, love) = match os {
+        "windows" => (var("APPDATA"), "LOVE"),
+        "macos" => (home.map(|home| home.join("Library/Application Support")), "LOVE"),
+        _ => (var("XDG_DATA_HOME").or_else(|| home.map(|home| home.join(".local/share"))), "love"),
+    };
+
+    let Some(data) = data else { return Vec::new() };
+
+    vec![data.join(love).join(IDENTITY).join("replay"), data.join(IDENTITY).join("replay")]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter().find(|(var, _)| *var == name).map(|(_, value)| OsString::from(value))
+            }
+        };
+
+        assert_eq!(
+            candidates("windows", env(&[("APPDATA", r"C:\Users\nova\AppData\Roaming")])),
+            [
+                PathBuf::from(r"C:\Users\nova\AppData\Roaming").join("LOVE/Techmino/replay"),
+                PathBuf::from(r"C:\Users\nova\AppData\Roaming").join("Techmino/replay"),
+            ]
+        );
+        assert_eq!(
+            candidates("linux", env(&[("HOME", "/home/nova"), ("XDG_DATA_HOME", "")])),
+            [
+                PathBuf::from("/home/nova/.local/share/love/Techmino/replay"),
+                PathBuf::from("/home/nova/.local/share/Techmino/replay"),
+            ]
+        );
+        assert_eq!(
+            candidates("linux", env(&[("HOME", "/home/nova"), ("XDG_DATA_HOME", "/data")]))[0],
+            PathBuf::from("/data/love/Techmino/replay")
+        );
+        assert_eq!(
+            candidates("macos", env(&[("HOME", "/Users/nova")]))[0],
+            PathBuf::from("/Users/nova/Library/Application Support/LOVE/Techmino/replay")
+        );
+        assert!(candidates("windows", env(&[("HOME", "/home/nova")])).is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+//! Lua table literals, the form the game serializes its data in.
+//!
+//! Tables are written from and read into JSON values, so that anything with a JSON form can be
+//! converted: objects become tables with keys, arrays become tables with positional values.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde_json::{Map, Number, Value};
+
+use crate::types::*;
+use crate::SettingsParseError;
+
+/// The words Lua reserves, which can't be written as bare keys.
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+impl GameReplayMetadata {
+    /// The metadata as a Lua table literal, e.g. `{player="NOT_A_ROBOT",mode="sprint_10l",...}`,
+    /// with the same keys as its JSON form.
+    ///
+    /// The mods are a list of `{id,value}` pairs, i.e. with numeric keys, and the private data is
+    /// written as nested tables. Keys without a value are left out, as Lua doesn't tell them apart
+    /// from missing ones. Each key is on its own line, indented with tabs, and there's no `return`,
+    /// so the table can be put anywhere in a script.
+    ///
+    /// Numbers are written so that Lua reads them back as the same values. Note that LuaJIT,
+    /// which the game runs on, has no integers, so seeds above 2<sup>53</sup> lose precision.
+    pub fn to_lua_table(&self) -> String {
+        to_lua_table(&serde_json::to_value(self).unwrap_or(Value::Null))
+    }
+}
+
+impl PlayerSettings {
+    /// The settings as a Lua table literal, without the `return` that
+    /// [`try_from_settings_file`][PlayerSettings::try_from_settings_file] accepts before it.
+    ///
+    /// See [`GameReplayMetadata::to_lua_table`] for how the values are written.
+    pub fn to_lua_table(&self) -> String {
+        to_lua_table(&serde_json::to_value(self).unwrap_or(Value::Null))
+    }
+}
+
+/// A JSON value as a Lua table literal, or as a Lua constant if it isn't an object or an array.
+pub(crate) fn to_lua_table(value: &Value) -> String {
+    let mut lua = String::new();
+    write_value(&mut lua, value, 0);
+
+    lua
+}
+
+fn write_value(lua: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Null => lua.push_str("nil"),
+        Value::Bool(value) => lua.push_str(if *value { "true" } else { "false" }),
+        // serde_json writes the shortest form that reads back as the same float, e.g. `0.4` or `1e-7`,
+        // which is also valid Lua
+        Value::Number(number) => lua.push_str(&number.to_string()),
+        Value::String(string) => write_string(lua, string),
+        Value::Array(values) => {
+            // Arrays of constants, e.g. skins, are kept on one line
+            if values.iter().all(|value| !value.is_array() && !value.is_object()) {
+                lua.push('{');
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        lua.push(',');
+                    }
+                    write_value(lua, value, depth + 1);
+                }
+                lua.push('}');
+                return;
+            }
+
+            write_table(lua, values.iter().map(|value| (None, value)), depth);
+        }
+        // A key set to `nil` is the same as a missing one in Lua
+        Value::Object(map) => {
+            let fields = map.iter().filter(|(_, value)| !value.is_null());
+            write_table(lua, fields.map(|(key, value)| (Some(key.as_str()), value)), depth);
+        }
+    }
+}
+
+/// A table with a field per line, and positional values where there's no key.
+fn write_table<'a, I>(lua: &mut String, fields: I, depth: usize)
+where
+    I: Iterator<Item = (Option<&'a str>, &'a Value)>,
+{
+    lua.push('{');
+
+    let mut empty = true;
+    for (key, value) in fields {
+        empty = false;
+        lua.push('\n');
+        lua.extend(core::iter::repeat_n('\t', depth + 1));
+
+        match key {
+            Some(key) if is_name(key) => lua.push_str(key),
+            Some(key) => {
+                lua.push('[');
+                write_string(lua, key);
+                lua.push(']');
+            }
+            None => {}
+        }
+        if key.is_some() {
+            lua.push('=');
+        }
+        write_value(lua, value, depth + 1);
+        lua.push(',');
+    }
+
+    if !empty {
+        lua.push('\n');
+        lua.extend(core::iter::repeat_n('\t', depth));
+    }
+    lua.push('}');
+}
+
+/// Whether the key can be written bare, e.g. `das=` rather than `["das"]=`.
+fn is_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+
+    starts_with_letter && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&key)
+}
+
+/// A double-quoted string literal. Text that isn't ASCII is kept as is, as Lua strings are bytes.
+fn write_string(lua: &mut String, string: &str) {
+    lua.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => lua.push_str("\\\""),
+            '\\' => lua.push_str("\\\\"),
+            '\n' => lua.push_str("\\n"),
+            '\r' => lua.push_str("\\r"),
+            '\t' => lua.push_str("\\t"),
+            // Always three digits, so that a digit after it isn't read as part of the escape
+            c if c.is_ascii_control() => {
+                let _ = write!(lua, "\\{:03}", c as u8);
+            }
+            c => lua.push(c),
+        }
+    }
+    lua.push('"');
+}
+
+/// A parser for the Lua tables the game serializes its data as, read into JSON values.
+///
+/// Only the constants the game writes are supported: tables, strings, numbers, booleans and `nil`.
+/// A table with only positional values becomes an array, and any other table an object,
+/// with the positions as keys.
+pub(crate) struct LuaParser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> LuaParser<'a> {
+    pub(crate) fn parse(text: &'a str) -> Result<Value, SettingsParseError> {
+        let mut parser = LuaParser { text, offset: 0 };
+
+        parser.skip_whitespace();
+        if parser.rest().starts_with("return") {
+            parser.offset += "return".len();
+        }
+        let value = parser.value()?;
+
+        parser.skip_whitespace();
+        match parser.rest() {
+            "" => Ok(value),
+            _ => Err(parser.error("the end of the file")),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn error(&self, expected: &'static str) -> SettingsParseError {
+        SettingsParseError::InvalidLua { offset: self.offset, expected }
+    }
+
+    /// Skips whitespace and `--` comments.
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.offset += rest.len() - trimmed.len();
+
+            if !trimmed.starts_with("--") {
+                return;
+            }
+            self.offset += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Consumes the character if it's next, after any whitespace.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let eaten = self.rest().starts_with(c);
+        if eaten {
+            self.offset += c.len_utf8();
+        }
+
+        eaten
+    }
+
+    fn value(&mut self) -> Result<Value, SettingsParseError> {
+        self.skip_whitespace();
+        let rest = self.rest();
+
+        match rest.chars().next() {
+            Some('{') => self.table(),
+            Some('"' | '\'') => self.string().map(Value::String),
+            Some('-' | '.' | '0'..='9') => self.number(),
+            _ => {
+                let word = self.name().ok_or(self.error("a value"))?;
+                match word {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "nil" => Ok(Value::Null),
+                    _ => {
+                        self.offset -= word.len();
+                        Err(self.error("a value"))
+                    }
+                }
+            }
+        }
+    }
+
+    /// An identifier, e.g. a key or `true`.
+    fn name(&mut self) -> Option<&'a str> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+
+        self.offset += len;
+        Some(&rest[..len])
+    }
+
+    fn table(&mut self) -> Result<Value, SettingsParseError> {
+        self.eat('{');
+
+        let mut positional = Vec::new();
+        let mut keyed = Map::new();
+        while !self.eat('}') {
+            self.skip_whitespace();
+            let start = self.offset;
+
+            let key = if self.eat('[') {
+                let key = match self.value()? {
+                    Value::String(key) => key,
+                    key @ Value::Number(_) => key.to_string(),
+                    _ => return Err(self.error("a string or number key")),
+                };
+                if !self.eat(']') {
+                    return Err(self.error("']'"));
+                }
+                Some(key)
+            } else {
+                self.name().map(String::from)
+            };
+
+            match key {
+                Some(key) if self.eat('=') => {
+                    let value = self.value()?;
+                    keyed.insert(key, value);
+                }
+                // A bare `true`, `false` or `nil` is a value, not a key
+                _ => {
+                    self.offset = start;
+                    positional.push(self.value()?);
+                }
+            }
+
+            if !self.eat(',') && !self.eat(';') {
+                if self.eat('}') {
+                    break;
+                }
+                return Err(self.error("',' or '}'"));
+            }
+        }
+
+        if keyed.is_empty() {
+            return Ok(Value::Array(positional));
+        }
+        for (index, value) in positional.into_iter().enumerate() {
+            keyed.insert((index + 1).to_string(), value);
+        }
+
+        Ok(Value::Object(keyed))
+    }
+
+    fn string(&mut self) -> Result<String, SettingsParseError> {
+        let quote = self.rest().chars().next().ok_or(self.error("a string"))?;
+        self.offset += 1;
+
+        let mut string = String::new();
+        loop {
+            let rest = self.rest();
+            let mut chars = rest.chars();
+            match chars.next() {
+                None | Some('\n') => return Err(self.error("the end of the string")),
+                Some(c) if c == quote => {
+                    self.offset += 1;
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    let escaped = match chars.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(c @ ('\\' | '"' | '\'' | '\n')) => c,
+                        Some(c) if c.is_ascii_digit() => {
+                            // Up to three decimal digits, e.g. `\65`
+                            let digits = rest[1..].bytes().take(3).take_while(u8::is_ascii_digit).count();
+                            let code: u8 = rest[1..1 + digits].parse().map_err(|_| self.error("a byte"))?;
+                            self.offset += 1 + digits;
+                            string.push(char::from(code));
+                            continue;
+                        }
+                        _ => return Err(self.error("an escape sequence")),
+                    };
+                    self.offset += 1 + escaped.len_utf8();
+                    string.push(escaped);
+                }
+                Some(c) => {
+                    self.offset += c.len_utf8();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, SettingsParseError> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(index, c)| {
+                let sign = (c == '-' || c == '+') && (index == 0 || rest[..index].ends_with(['e', 'E']));
+                !(c.is_ascii_alphanumeric() || c == '.' || sign)
+            })
+            .map_or(rest.len(), |(index, _)| index);
+        let literal = &rest[..len];
+
+        let number = if let Ok(integer) = literal.parse::<i64>() {
+            Number::from(integer)
+        } else if let Some(hex) = literal.strip_prefix("0x").or_else(|| literal.strip_prefix("0X")) {
+            i64::from_str_radix(hex, 16).map(Number::from).map_err(|_| self.error("a number"))?
+        } else {
+            let float: f64 = literal.parse().map_err(|_| self.error("a number"))?;
+            Number::from_f64(float).ok_or(self.error("a finite number"))?
+        };
+
+        self.offset += len;
+        Ok(Value::Number(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lua_tables() {
+        use SettingsParseError::InvalidLua;
+
+        let lua = r#"return{ -- A comment
+            das=8, ["RS"]='SRS', face={0,1,2;3}, grid=.25, bgm=-1e-1,
+            nested={ [1]="a", ["b c"]="\"quoted\"\n\65", true, nil },
+        }"#;
+        let value = LuaParser::parse(lua).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "das": 8, "RS": "SRS", "face": [0, 1, 2, 3], "grid": 0.25, "bgm": -0.1,
+                "nested": { "1": true, "b c": "\"quoted\"\nA", "2": null },
+            })
+        );
+
+        assert_eq!(LuaParser::parse("{}"), Ok(json!([])));
+        assert_eq!(LuaParser::parse("return{das=0x10}"), Ok(json!({ "das": 16 })));
+        for (lua, offset) in [("return{das=}", 11), ("return{das=1 arr=2}", 13), ("{} x", 3), ("{'a}", 4)] {
+            let error = LuaParser::parse(lua).unwrap_err();
+            assert!(matches!(error, InvalidLua { offset: at, .. } if at == offset), "{lua}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_write_lua_tables() {
+        let mut metadata = GameReplayMetadata {
+            player: "Nova \"the\"\\\nÜber\u{7}1".into(),
+            mods: Some(vec![(9, json!(2)), (0, json!(true))]),
+            private: Some(json!({ "BAG": [5, 7], "CUSTOMENV": { "end": 0.1, "2nd": "x", "none": null } })),
+            ..Default::default()
+        };
+        metadata.setting.grid = serde_json::Number::from_f64(0.4);
+        metadata.setting.rs = Some("TRS".into());
+
+        let lua = metadata.to_lua_table();
+        assert!(lua.starts_with("{\n\t"), "{lua}");
+        assert!(lua.contains("\n\tplayer=\"Nova \\\"the\\\"\\\\\\nÜber\\0071\",\n"), "{lua}");
+        assert!(lua.contains("\n\tmod={\n\t\t{9,2},\n\t\t{0,true},\n\t},\n"), "{lua}");
+        assert!(lua.contains("\n\t\tBAG={5,7},\n\t\tCUSTOMENV={\n\t\t\t[\""), "{lua}");
+        for field in ["\n\t\t\t[\"end\"]=0.1,\n", "\n\t\t\t[\"2nd\"]=\"x\",\n"] {
+            assert!(lua.contains(field), "{lua}");
+        }
+        assert!(!lua.contains("none") && !lua.contains("nil"), "{lua}");
+        assert!(lua.contains("\t\tgrid=0.4,\n") && lua.contains("\t\tRS=\"TRS\",\n"), "{lua}");
+        assert!(lua.ends_with(",\n}"));
+
+        // Lua reads it back as the same values, except for the missing ones
+        let parsed: GameReplayMetadata = serde_json::from_value(LuaParser::parse(&lua).unwrap()).unwrap();
+        metadata.private.as_mut().unwrap()["CUSTOMENV"].as_object_mut().unwrap().remove("none");
+        assert_eq!(parsed, metadata);
+
+        let settings = PlayerSettings { das: Some(4), skin: Some(vec![1, 2]), ..Default::default() };
+        assert_eq!(settings.to_lua_table(), "{\n\tdas=4,\n\tskin={1,2},\n}");
+        assert_eq!(PlayerSettings::default().to_lua_table(), "{}");
+    }
+}
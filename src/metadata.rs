@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+
+use serde::Deserialize;
+
+use crate::types::*;
+use crate::{canonical_metadata_json, SerializeOptions};
+
+/// A type that can be stored as the metadata of a [`GameReplayData`].
+///
+/// The metadata is the part of the replay before the separator, which the game writes as JSON.
+/// It's parsed with [`TryFrom<&[u8]>`] and written back with [`write_bytes`][ReplayMetadata::write_bytes].
+///
+/// [`GameReplayMetadata`] parses the JSON into its fields, while [`RawMetadata`] keeps the bytes untouched.
+pub trait ReplayMetadata: for<'a> TryFrom<&'a [u8], Error = ReplayParseError> {
+    /// The game version string, used to infer the [`InputParseMode`] of the inputs.
+    ///
+    /// Returns [`None`] if the version isn't known.
+    fn version(&self) -> Option<&str>;
+
+    /// Appends the metadata bytes to the buffer.
+    ///
+    /// The bytes must not contain a linefeed, as that would end the metadata early.
+    fn write_bytes(&self, buffer: &mut Vec<u8>, options: &SerializeOptions) -> Result<(), ReplaySerializeError>;
+
+    /// Estimates how many bytes [`write_bytes`][ReplayMetadata::write_bytes] writes.
+    ///
+    /// Only used for reserving space, so it doesn't need to be exact.
+    /// The default estimate is the size of a typical metadata JSON written by the game.
+    fn estimated_len(&self) -> usize {
+        512
+    }
+}
+
+impl ReplayMetadata for GameReplayMetadata {
+    fn version(&self) -> Option<&str> {
+        Some(&self.version)
+    }
+
+    fn write_bytes(&self, buffer: &mut Vec<u8>, options: &SerializeOptions) -> Result<(), ReplaySerializeError> {
+        if options.canonical_json {
+            buffer.extend_from_slice(canonical_metadata_json(self)?.as_bytes());
+            return Ok(());
+        }
+
+        // Without `std`, there's no `io::Write` to encode into the buffer directly
+        #[cfg(feature = "std")]
+        serde_json::to_writer(buffer, self)?;
+        #[cfg(not(feature = "std"))]
+        buffer.extend_from_slice(&serde_json::to_vec(self)?);
+
+        Ok(())
+    }
+}
+
+/// Replay metadata kept as the bytes it was parsed from, without decoding the JSON.
+///
+/// This is useful for tools that only care about the inputs, as it skips deserializing
+/// the metadata, and guarantees that the metadata is written back byte for byte:
+///
+/// ```
+/// use techmino_replay_toolkit::{GameReplayData, InputParseMode, RawMetadata};
+///
+/// let raw = b"{\"version\":\"V0.17.22\",\"note\":\"kept as is\"}\n\x0a\x06";
+/// let replay: GameReplayData<RawMetadata> = GameReplayData::try_from_raw_as(raw, None).unwrap();
+///
+/// assert_eq!(replay.metadata.version(), Some("V0.17.22"));
+/// assert_eq!(replay.serialize_to_raw(None).unwrap(), raw);
+/// ```
+///
+/// The `canonical_json` option of [`SerializeOptions`] doesn't apply, since the bytes are never re-encoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RawMetadata(pub Vec<u8>);
+
+impl RawMetadata {
+    /// The `version` field of the metadata JSON.
+    ///
+    /// Returns [`None`] if the metadata isn't a JSON object with a plain string `version` field.
+    /// This only looks for the field, and doesn't decode the rest of the JSON.
+    pub fn version(&self) -> Option<&str> {
+        #[derive(Deserialize)]
+        struct VersionOnly<'a> {
+            version: &'a str,
+        }
+
+        serde_json::from_slice::<VersionOnly>(&self.0).ok().map(|v| v.version)
+    }
+}
+
+impl TryFrom<&[u8]> for RawMetadata {
+    type Error = ReplayParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Ok(RawMetadata(Vec::from(value)))
+    }
+}
+
+impl ReplayMetadata for RawMetadata {
+    fn version(&self) -> Option<&str> {
+        RawMetadata::version(self)
+    }
+
+    fn write_bytes(&self, buffer: &mut Vec<u8>, _options: &SerializeOptions) -> Result<(), ReplaySerializeError> {
+        buffer.extend_from_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn estimated_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_version() {
+        let version = |json: &[u8]| RawMetadata(json.to_vec()).version().map(String::from);
+
+        let json = br#"{"mode":"sprint_10l","version":"V0.17.1@8fa2"}"#;
+        assert_eq!(version(json).as_deref(), Some("V0.17.1@8fa2"));
+        assert_eq!(version(br#"{"mode":"sprint_10l"}"#), None);
+        assert_eq!(version(br#"{"version":17}"#), None);
+        assert_eq!(version(b"not json"), None);
+
+        let error = GameReplayData::<RawMetadata>::try_from_raw_as(b"{}\n\x0a\x06", None).unwrap_err();
+        assert!(matches!(error, ReplayParseError::UnknownInputParseMode(version) if version.is_empty()));
+    }
+}
@@ -0,0 +1,237 @@
+//! The modes of the game, by the codenames stored in [`mode`][GameReplayMetadata::mode].
+
+use crate::types::*;
+
+/// The kind of a [mode][ModeInfo], like the groups of the game's mode map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ModeCategory {
+    /// Clearing or sending a number of lines as fast as possible.
+    Sprint,
+    /// Clearing lines as the game speeds up.
+    Marathon,
+    /// Clearing lines at the highest gravity.
+    Master,
+    /// Clearing garbage lines.
+    Dig,
+    /// Surviving incoming garbage.
+    Survivor,
+    /// Fighting a bot, or surviving and sending attacks.
+    Battle,
+    /// Fighting many bots at once.
+    Royale,
+    /// Practicing a technique, e.g. perfect clears or T-spins.
+    Practice,
+    /// Playing with the stack hidden.
+    Invisible,
+    /// Playing with the rules of old games.
+    Classic,
+    /// Placing pieces to a beat.
+    Rhythm,
+    /// Playing without pressure, e.g. without a time limit.
+    Casual,
+    /// Playing a game set up by the player.
+    Custom,
+    /// Any other mode.
+    Other,
+}
+
+/// What this crate knows about a mode of the game, found by [`mode_info`].
+///
+/// The names, categories and targets are this crate's own descriptions, not text taken from the
+/// game, and may not match what the game shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModeInfo {
+    /// The codename stored in replays, e.g. `sprint_40l`.
+    pub code: &'static str,
+    /// The English name of the mode, e.g. `Sprint 40L`.
+    pub name: &'static str,
+    /// The kind of mode.
+    pub category: ModeCategory,
+    /// What the player has to do, e.g. `Clear 40 lines`.
+    pub target: &'static str,
+}
+
+impl ModeInfo {
+    const fn new(
+        code: &'static str,
+        name: &'static str,
+        category: ModeCategory,
+        target: &'static str,
+    ) -> Self {
+        ModeInfo { code, name, category, target }
+    }
+}
+
+/// The modes of the game, by codename.
+///
+/// Modes of mods of the game aren't included. Only `sprint_10l` and `marathon_inf` are seen in the
+/// replays this crate is tested with; the other codenames haven't been checked against replays.
+/// Which versions of the game have which modes isn't recorded.
+pub static MODES: &[ModeInfo] = {
+    use ModeCategory::*;
+
+    &[
+        ModeInfo::new("sprint_10l", "Sprint 10L", Sprint, "Clear 10 lines"),
+        ModeInfo::new("sprint_20l", "Sprint 20L", Sprint, "Clear 20 lines"),
+        ModeInfo::new("sprint_40l", "Sprint 40L", Sprint, "Clear 40 lines"),
+        ModeInfo::new("sprint_100l", "Sprint 100L", Sprint, "Clear 100 lines"),
+        ModeInfo::new("sprint_400l", "Sprint 400L", Sprint, "Clear 400 lines"),
+        ModeInfo::new("sprint_1000l", "Sprint 1000L", Sprint, "Clear 1000 lines"),
+        ModeInfo::new("sprintPenta", "Sprint Penta", Sprint, "Clear 40 lines of pentominoes"),
+        ModeInfo::new("sprintMPH", "Sprint MPH", Sprint, "Clear 40 lines without previews"),
+        ModeInfo::new("sprint123", "Sprint 123", Sprint, "Clear 40 lines of tiny pieces"),
+        ModeInfo::new("sprintAtk", "Sprint Attack", Sprint, "Send 100 lines of attack"),
+        ModeInfo::new("sprintEff", "Sprint Efficiency", Sprint, "Send the most attack in 40 lines"),
+        ModeInfo::new("marathon_n", "Marathon Normal", Marathon, "Clear 200 lines as the speed rises"),
+        ModeInfo::new("marathon_h", "Marathon Hard", Marathon, "Clear 200 lines at high speed"),
+        ModeInfo::new("marathon_inf", "Marathon Infinite", Marathon, "Clear lines for as long as possible"),
+        ModeInfo::new("master_n", "Master Normal", Master, "Clear lines at 20G"),
+        ModeInfo::new("master_h", "Master Hard", Master, "Clear lines at 20G with shrinking delays"),
+        ModeInfo::new("master_final", "Master Final", Master, "Clear lines at 20G with the shortest delays"),
+        ModeInfo::new("master_ph", "Master Phantasm", Master, "Clear lines at 20G with the stack fading"),
+        ModeInfo::new("master_ex", "Master EX", Master, "Clear lines at 20G for a grade"),
+        ModeInfo::new("master_instinct", "Master Instinct", Master, "Play 20G with the piece hidden"),
+        ModeInfo::new("dig_10l", "Dig 10L", Dig, "Clear 10 lines of garbage"),
+        ModeInfo::new("dig_40l", "Dig 40L", Dig, "Clear 40 lines of garbage"),
+        ModeInfo::new("dig_100l", "Dig 100L", Dig, "Clear 100 lines of garbage"),
+        ModeInfo::new("dig_400l", "Dig 400L", Dig, "Clear 400 lines of garbage"),
+        ModeInfo::new("dig_h", "Dig Hard", Dig, "Survive rising garbage"),
+        ModeInfo::new("dig_u", "Dig Ultimate", Dig, "Survive fast rising garbage"),
+        ModeInfo::new("survivor_e", "Survivor Easy", Survivor, "Survive incoming garbage"),
+        ModeInfo::new("survivor_n", "Survivor Normal", Survivor, "Survive incoming garbage"),
+        ModeInfo::new("survivor_h", "Survivor Hard", Survivor, "Survive incoming garbage"),
+        ModeInfo::new("survivor_l", "Survivor Lunatic", Survivor, "Survive incoming garbage"),
+        ModeInfo::new("survivor_u", "Survivor Ultimate", Survivor, "Survive incoming garbage"),
+        ModeInfo::new("attacker_h", "Attacker Hard", Battle, "Send attacks through a defending opponent"),
+        ModeInfo::new("attacker_u", "Attacker Ultimate", Battle, "Send attacks through a defending opponent"),
+        ModeInfo::new("defender_n", "Defender Normal", Battle, "Survive growing attacks"),
+        ModeInfo::new("defender_l", "Defender Lunatic", Battle, "Survive growing attacks"),
+        ModeInfo::new("solo_e", "Battle Easy", Battle, "Defeat the bot"),
+        ModeInfo::new("solo_n", "Battle Normal", Battle, "Defeat the bot"),
+        ModeInfo::new("solo_h", "Battle Hard", Battle, "Defeat the bot"),
+        ModeInfo::new("solo_l", "Battle Lunatic", Battle, "Defeat the bot"),
+        ModeInfo::new("solo_u", "Battle Ultimate", Battle, "Defeat the bot"),
+        ModeInfo::new("round_e", "Turn-Based Easy", Battle, "Defeat the bot, taking turns"),
+        ModeInfo::new("round_n", "Turn-Based Normal", Battle, "Defeat the bot, taking turns"),
+        ModeInfo::new("round_h", "Turn-Based Hard", Battle, "Defeat the bot, taking turns"),
+        ModeInfo::new("round_l", "Turn-Based Lunatic", Battle, "Defeat the bot, taking turns"),
+        ModeInfo::new("round_u", "Turn-Based Ultimate", Battle, "Defeat the bot, taking turns"),
+        ModeInfo::new("techmino49_e", "Techmino 49 Easy", Royale, "Be the last of 49 players standing"),
+        ModeInfo::new("techmino49_h", "Techmino 49 Hard", Royale, "Be the last of 49 players standing"),
+        ModeInfo::new("techmino49_u", "Techmino 49 Ultimate", Royale, "Be the last of 49 players standing"),
+        ModeInfo::new("techmino99_e", "Techmino 99 Easy", Royale, "Be the last of 99 players standing"),
+        ModeInfo::new("techmino99_h", "Techmino 99 Hard", Royale, "Be the last of 99 players standing"),
+        ModeInfo::new("techmino99_u", "Techmino 99 Ultimate", Royale, "Be the last of 99 players standing"),
+        ModeInfo::new("tech_n", "Tech Normal", Practice, "Clear lines without breaking back-to-back"),
+        ModeInfo::new("tech_n_plus", "Tech Normal+", Practice, "Clear lines with spins only"),
+        ModeInfo::new("tech_h", "Tech Hard", Practice, "Clear lines without breaking back-to-back"),
+        ModeInfo::new("tech_h_plus", "Tech Hard+", Practice, "Clear lines with spins only"),
+        ModeInfo::new("tech_l", "Tech Lunatic", Practice, "Clear lines without breaking back-to-back"),
+        ModeInfo::new("tech_l_plus", "Tech Lunatic+", Practice, "Clear lines with spins only"),
+        ModeInfo::new("tech_finesse", "Tech Finesse", Practice, "Clear lines without finesse faults"),
+        ModeInfo::new("tech_finesse_f", "Tech Finesse+", Practice, "Clear lines without any faults"),
+        ModeInfo::new("pc_n", "PC Challenge Normal", Practice, "Get the most perfect clears in 100 lines"),
+        ModeInfo::new("pc_h", "PC Challenge Hard", Practice, "Get the most perfect clears in 100 lines"),
+        ModeInfo::new("pc_l", "PC Challenge Lunatic", Practice, "Get the most perfect clears in 100 lines"),
+        ModeInfo::new("pc_inf", "PC Challenge Infinite", Practice, "Keep getting perfect clears"),
+        ModeInfo::new("pctrain_n", "PC Training Normal", Practice, "Practice perfect clear setups"),
+        ModeInfo::new("pctrain_l", "PC Training Lunatic", Practice, "Practice perfect clear setups"),
+        ModeInfo::new("c4wtrain_n", "C4W Training Normal", Practice, "Keep a combo going in a 4-wide well"),
+        ModeInfo::new("c4wtrain_l", "C4W Training Lunatic", Practice, "Keep a combo going in a 4-wide well"),
+        ModeInfo::new("tsd_e", "TSD Challenge Easy", Practice, "Chain T-spin doubles"),
+        ModeInfo::new("tsd_h", "TSD Challenge Hard", Practice, "Chain T-spin doubles"),
+        ModeInfo::new("tsd_u", "TSD Challenge Ultimate", Practice, "Chain T-spin doubles"),
+        ModeInfo::new("blind_e", "Invisible Easy", Invisible, "Clear lines with the stack hidden"),
+        ModeInfo::new("blind_n", "Invisible Normal", Invisible, "Clear lines with the stack hidden"),
+        ModeInfo::new("blind_h", "Invisible Hard", Invisible, "Clear lines with the stack hidden"),
+        ModeInfo::new("blind_l", "Invisible Lunatic", Invisible, "Clear lines with the stack hidden"),
+        ModeInfo::new("blind_u", "Invisible Ultimate", Invisible, "Clear lines with the stack hidden"),
+        ModeInfo::new("classic_e", "Classic Easy", Classic, "Clear lines with classic rules"),
+        ModeInfo::new("classic_h", "Classic Hard", Classic, "Clear lines with classic rules"),
+        ModeInfo::new("classic_l", "Classic Lunatic", Classic, "Clear lines with classic rules"),
+        ModeInfo::new("classic_u", "Classic Ultimate", Classic, "Clear lines with classic rules"),
+        ModeInfo::new("rhythm_e", "Rhythm Easy", Rhythm, "Place pieces to the beat"),
+        ModeInfo::new("rhythm_h", "Rhythm Hard", Rhythm, "Place pieces to the beat"),
+        ModeInfo::new("rhythm_u", "Rhythm Ultimate", Rhythm, "Place pieces to the beat"),
+        ModeInfo::new("zen", "Zen", Casual, "Clear 200 lines without a time limit"),
+        ModeInfo::new("ultra", "Ultra", Casual, "Score as much as possible in 2 minutes"),
+        ModeInfo::new("infinite", "Infinite", Casual, "Play without a goal"),
+        ModeInfo::new("infinite_dig", "Infinite: Dig", Casual, "Dig through garbage without a goal"),
+        ModeInfo::new("backfire_n", "Backfire Normal", Other, "Survive your own attacks"),
+        ModeInfo::new("backfire_h", "Backfire Hard", Other, "Survive your own attacks"),
+        ModeInfo::new("backfire_l", "Backfire Lunatic", Other, "Survive your own attacks"),
+        ModeInfo::new("backfire_u", "Backfire Ultimate", Other, "Survive your own attacks"),
+        ModeInfo::new("big_n", "Big Normal", Other, "Clear lines with big pieces"),
+        ModeInfo::new("big_h", "Big Hard", Other, "Clear lines with big pieces"),
+        ModeInfo::new("drought_n", "Drought Normal", Other, "Clear lines without I pieces"),
+        ModeInfo::new("drought_l", "Drought Lunatic", Other, "Clear lines with the worst pieces"),
+        ModeInfo::new("strategy_e", "Strategy Easy", Other, "Play against the clock"),
+        ModeInfo::new("strategy_h", "Strategy Hard", Other, "Play against the clock"),
+        ModeInfo::new("strategy_u", "Strategy Ultimate", Other, "Play against the clock"),
+        ModeInfo::new("secret_grade", "Secret Grade", Other, "Build a zigzag staircase in the stack"),
+        ModeInfo::new("custom_clear", "Custom Game", Custom, "Clear the lines set up by the player"),
+        ModeInfo::new("custom_puzzle", "Custom Puzzle", Custom, "Build the pattern set up by the player"),
+    ]
+};
+
+/// Finds a mode by its codename, e.g. `sprint_40l`.
+///
+/// Returns `None` for modes this crate doesn't know about, e.g. those of mods of the game.
+pub fn mode_info(code: &str) -> Option<&'static ModeInfo> {
+    MODES.iter().find(|mode| mode.code == code)
+}
+
+impl GameReplayMetadata {
+    /// What this crate knows about the mode of the replay, see [`mode_info`].
+    pub fn mode_info(&self) -> Option<&'static ModeInfo> {
+        mode_info(&self.mode)
+    }
+
+    /// The English name of the mode of the replay, e.g. `Sprint 40L`, or its codename
+    /// if it's [unknown][mode_info].
+    pub fn mode_display_name(&self) -> &str {
+        self.mode_info().map_or(&self.mode, |mode| mode.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_mode_info() {
+        let name = |code| mode_info(code).map(|mode| mode.name);
+        assert_eq!(name("sprint_40l"), Some("Sprint 40L"));
+        assert_eq!(name("sprint_1000l"), Some("Sprint 1000L"));
+        assert_eq!(name("sprintPenta"), Some("Sprint Penta"));
+        assert_eq!(name("marathon_inf"), Some("Marathon Infinite"));
+        assert_eq!(name("master_ph"), Some("Master Phantasm"));
+        assert_eq!(name("dig_100l"), Some("Dig 100L"));
+        assert_eq!(name("solo_l"), Some("Battle Lunatic"));
+        assert_eq!(name("techmino99_u"), Some("Techmino 99 Ultimate"));
+        assert_eq!(name("tech_h_plus"), Some("Tech Hard+"));
+        assert_eq!(name("pc_inf"), Some("PC Challenge Infinite"));
+        assert_eq!(name("blind_u"), Some("Invisible Ultimate"));
+        assert_eq!(name("zen"), Some("Zen"));
+        assert_eq!(name("Sprint_40L"), None);
+        assert_eq!(name("wtf_mode"), None);
+
+        let sprint = mode_info("sprint_40l").unwrap();
+        assert_eq!((sprint.category, sprint.target), (ModeCategory::Sprint, "Clear 40 lines"));
+        assert_eq!(mode_info("custom_puzzle").unwrap().category, ModeCategory::Custom);
+
+        let codes: BTreeSet<_> = MODES.iter().map(|mode| mode.code).collect();
+        assert_eq!(codes.len(), MODES.len(), "a codename is listed twice");
+    }
+
+    #[test]
+    fn test_mode_display_name() {
+        let mut metadata = GameReplayMetadata { mode: "sprint_40l".to_string(), ..Default::default() };
+        assert_eq!(metadata.mode_display_name(), "Sprint 40L");
+
+        metadata.mode = "a_modded_mode".to_string();
+        assert_eq!(metadata.mode_display_name(), "a_modded_mode");
+    }
+}
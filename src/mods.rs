@@ -0,0 +1,184 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::types::*;
+
+/// A mod of the game, as stored by its ID in [`mods`][GameReplayMetadata::mods].
+///
+/// The IDs are meant to be the positions of the mods in the game's mod menu, starting at 0, but
+/// the names haven't been checked against the game or against replays that use mods, so they may
+/// not match what the game calls each ID. Only the IDs are stored in replays, and they round-trip
+/// unchanged either way.
+///
+/// Mods compare, order and hash by their IDs, so an [`Unknown`][GameMod::Unknown] mod built by
+/// hand with a known ID is the same as the known mod.
+#[derive(Clone, Copy, Debug)]
+pub enum GameMod {
+    /// Changes the number of next pieces shown.
+    Next,
+    /// Changes the number of hold slots, or disables hold.
+    Hold,
+    /// Hides the next pieces.
+    HideNext,
+    /// Allows holding any number of times per piece.
+    InfiniteHold,
+    /// Hides the falling piece.
+    HideBlock,
+    /// Hides the ghost piece.
+    HideGhost,
+    /// Hides the blocks of the board after they lock.
+    Hidden,
+    /// Hides parts of the board.
+    HideBoard,
+    /// Flips or rotates how the board is drawn.
+    FlipBoard,
+    /// Changes the drop delay, i.e. the gravity.
+    DropDelay,
+    /// Changes the lock delay.
+    LockDelay,
+    /// Changes the spawn delay.
+    WaitDelay,
+    /// Changes the line clear delay.
+    FallDelay,
+    /// Changes the number of lives.
+    Life,
+    /// Ends the game when the back-to-back chain is broken.
+    ForceB2b,
+    /// Ends the game on a finesse fault.
+    ForceFinesse,
+    /// Removes the DAS and ARR, so the piece moves instantly.
+    Tele,
+    /// Disables rotation.
+    NoRotation,
+    /// Disables moving sideways.
+    NoMove,
+    /// Changes how the piece sequence is generated.
+    CustomSequence,
+    /// Changes how fast garbage rises.
+    PushSpeed,
+    /// Draws the blocks with the bone skin.
+    BoneBlock,
+    /// A mod ID this crate doesn't know about.
+    ///
+    /// [`GameMod::from`] only uses this for IDs past the known ones.
+    Unknown(u64),
+}
+
+impl PartialEq for GameMod {
+    fn eq(&self, other: &Self) -> bool {
+        u64::from(*self) == u64::from(*other)
+    }
+}
+
+impl Eq for GameMod {}
+
+impl PartialOrd for GameMod {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GameMod {
+    fn cmp(&self, other: &Self) -> Ordering {
+        u64::from(*self).cmp(&u64::from(*other))
+    }
+}
+
+impl Hash for GameMod {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        u64::from(*self).hash(state);
+    }
+}
+
+impl GameMod {
+    /// All the known mods, in the order of their IDs.
+    pub const KNOWN: [GameMod; 22] = {
+        use GameMod::*;
+
+        [
+            Next, Hold, HideNext, InfiniteHold, HideBlock, HideGhost, Hidden, HideBoard, FlipBoard, DropDelay,
+            LockDelay, WaitDelay, FallDelay, Life, ForceB2b, ForceFinesse, Tele, NoRotation, NoMove,
+            CustomSequence, PushSpeed, BoneBlock,
+        ]
+    };
+}
+
+impl From<u64> for GameMod {
+    /// The known mod with the ID, or [`Unknown`][GameMod::Unknown] if there's none.
+    fn from(id: u64) -> Self {
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| Self::KNOWN.get(index).copied())
+            .unwrap_or(GameMod::Unknown(id))
+    }
+}
+
+impl From<GameMod> for u64 {
+    fn from(value: GameMod) -> Self {
+        match value {
+            GameMod::Unknown(id) => id,
+            known => GameMod::KNOWN.iter().position(|other| same_variant(*other, known)).unwrap() as u64,
+        }
+    }
+}
+
+/// Compares known mods without going through their IDs, which [`PartialEq`] does.
+fn same_variant(a: GameMod, b: GameMod) -> bool {
+    core::mem::discriminant(&a) == core::mem::discriminant(&b)
+}
+
+impl GameReplayMetadata {
+    /// The [`mods`][GameReplayMetadata::mods] of the replay, with the IDs as [`GameMod`]s.
+    ///
+    /// Returns an empty list if the replay has no mods.
+    pub fn mods_typed(&self) -> Vec<(GameMod, serde_json::Value)> {
+        let mods = self.mods.iter().flatten();
+        mods.map(|(id, value)| (GameMod::from(*id), value.clone())).collect()
+    }
+
+    /// Whether the replay uses the mod, with any value.
+    pub fn has_mod(&self, game_mod: GameMod) -> bool {
+        self.mod_value(game_mod).is_some()
+    }
+
+    /// The value of the mod in the replay, or [`None`] if the replay doesn't use it.
+    pub fn mod_value(&self, game_mod: GameMod) -> Option<&serde_json::Value> {
+        let id = u64::from(game_mod);
+        self.mods.iter().flatten().find(|(other, _)| *other == id).map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_ids() {
+        for (id, game_mod) in GameMod::KNOWN.into_iter().enumerate() {
+            assert_eq!(GameMod::from(id as u64), game_mod);
+            assert_eq!(u64::from(game_mod), id as u64);
+        }
+
+        assert_eq!(GameMod::from(22), GameMod::Unknown(22));
+        assert_eq!(u64::from(GameMod::Unknown(u64::MAX)), u64::MAX);
+        assert_eq!(GameMod::from(9), GameMod::DropDelay);
+    }
+
+    #[test]
+    fn test_unknown_with_known_id() {
+        use std::collections::HashSet;
+
+        // Built by hand rather than with `From`
+        let by_hand = GameMod::Unknown(9);
+        assert_eq!(by_hand, GameMod::DropDelay);
+        assert_eq!(by_hand.cmp(&GameMod::DropDelay), Ordering::Equal);
+        assert!(GameMod::Unknown(8) < GameMod::DropDelay && GameMod::DropDelay < GameMod::Unknown(10));
+        assert_eq!(HashSet::from([by_hand, GameMod::DropDelay]).len(), 1);
+        assert_ne!(GameMod::Unknown(22), GameMod::BoneBlock);
+
+        let mods = Some(vec![(9, serde_json::json!(8))]);
+        let metadata = GameReplayMetadata { mods, ..Default::default() };
+        assert!(metadata.has_mod(by_hand));
+    }
+}
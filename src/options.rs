@@ -0,0 +1,275 @@
+use alloc::string::{String, ToString};
+
+use crate::types::*;
+use crate::{KeyRegistry, MAX_PLAUSIBLE_FRAME, MAX_PLAUSIBLE_INPUTS};
+
+/// Options for parsing replays.
+///
+/// The options are set by chaining the methods:
+///
+/// ```
+/// use techmino_replay_toolkit::{InputParseMode, ParseOptions};
+///
+/// let options = ParseOptions::new().parse_mode(InputParseMode::Absolute);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub(crate) parse_mode: Option<InputParseMode>,
+    pub(crate) allow_missing_input_section: bool,
+    pub(crate) strict: bool,
+    pub(crate) max_frame: u64,
+    pub(crate) max_inputs: usize,
+    pub(crate) legacy_lua_metadata: bool,
+    pub(crate) key_registry: Option<KeyRegistry>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            parse_mode: None,
+            allow_missing_input_section: false,
+            strict: false,
+            max_frame: MAX_PLAUSIBLE_FRAME,
+            max_inputs: MAX_PLAUSIBLE_INPUTS,
+            legacy_lua_metadata: false,
+            key_registry: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default options.
+    pub fn new() -> ParseOptions {
+        Self::default()
+    }
+
+    /// Sets how the inputs should be parsed, instead of inferring it from the game version.
+    ///
+    /// For more information, see [`InputParseMode`].
+    pub fn parse_mode(mut self, mode: InputParseMode) -> Self {
+        self.parse_mode = Some(mode);
+        self
+    }
+
+    /// Sets whether a replay that ends right after the metadata, without the linefeed
+    /// that separates it from the inputs, is parsed as a replay without inputs,
+    /// e.g. for metadata-only exports of some tools.
+    ///
+    /// Otherwise, such replays fail with
+    /// [`MetadataSeparatorNotFound`][ReplayParseError::MetadataSeparatorNotFound].
+    /// A replay that ends right after the linefeed always has no inputs. Disabled by default.
+    pub fn allow_missing_input_section(mut self, enabled: bool) -> Self {
+        self.allow_missing_input_section = enabled;
+        self
+    }
+
+    /// Sets whether replays that parse, but are implausible, should fail to parse,
+    /// e.g. for verifying replays submitted to a leaderboard.
+    ///
+    /// Strict parsing fails with a [`StrictViolation`][ReplayParseError::StrictViolation] on:
+    /// - an input later than the [`max_frame`][ParseOptions::max_frame],
+    /// - more inputs than the [`max_inputs`][ParseOptions::max_inputs],
+    /// - an input identical to the one before it,
+    /// - a setting outside the range the game allows,
+    /// - a metadata key this crate doesn't know about, outside of the settings.
+    ///
+    /// See [`StrictViolation`][crate::StrictViolation] for more information. Disabled by default.
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Sets the latest frame an input can be on in [`strict`][ParseOptions::strict] mode.
+    ///
+    /// [`MAX_PLAUSIBLE_FRAME`] by default.
+    pub fn max_frame(mut self, frame: u64) -> Self {
+        self.max_frame = frame;
+        self
+    }
+
+    /// Sets the most inputs a replay can have in [`strict`][ParseOptions::strict] mode.
+    ///
+    /// [`MAX_PLAUSIBLE_INPUTS`] by default.
+    pub fn max_inputs(mut self, count: usize) -> Self {
+        self.max_inputs = count;
+        self
+    }
+
+    /// Sets whether metadata that isn't JSON may be a Lua table, e.g. `{player="Nova",seed=1,...}`.
+    ///
+    /// Reading such metadata is best-effort, as no replay with it has been found to check it against.
+    /// The table has to be on one line, like the JSON, and empty tables are read as empty lists,
+    /// except for the settings. The report of
+    /// [`try_from_raw_with_report`][GameReplayData::try_from_raw_with_report] has a
+    /// [`LegacyLuaMetadata`][crate::ParseWarning::LegacyLuaMetadata] warning for such replays.
+    /// Disabled by default.
+    pub fn legacy_lua_metadata(mut self, enabled: bool) -> Self {
+        self.legacy_lua_metadata = enabled;
+        self
+    }
+
+    /// Sets names for the key codes 21 to 31, which some forks of the game use for keys of their own.
+    ///
+    /// Inputs with the codes the registry has names for are parsed as
+    /// [`Other`][InputEventKey::Other] keys, instead of failing with
+    /// [`MalformedInputData`][ReplayParseError::MalformedInputData]. For the names to be shown
+    /// and parsed, e.g. by the text format, the registry also has to be
+    /// [installed][KeyRegistry::install]. Not set by default.
+    pub fn key_registry(mut self, registry: KeyRegistry) -> Self {
+        self.key_registry = Some(registry);
+        self
+    }
+}
+
+/// Options for serializing replays.
+///
+/// The options are set by chaining the methods:
+///
+/// ```
+/// use techmino_replay_toolkit::{InputParseMode, SerializeOptions};
+///
+/// let options = SerializeOptions::new()
+///     .input_mode(InputParseMode::Absolute)
+///     .canonical_json(true)
+///     .base64_line_width(76);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    pub(crate) input_mode: Option<InputParseMode>,
+    pub(crate) canonical_json: bool,
+    pub(crate) base64_line_width: usize,
+    pub(crate) url_safe_base64: bool,
+    pub(crate) recompression: RecompressionFidelity,
+}
+
+impl SerializeOptions {
+    /// Creates the default options.
+    pub fn new() -> SerializeOptions {
+        Self::default()
+    }
+
+    /// Sets how the inputs should be timed, instead of using
+    /// [`GameReplayData::input_mode`] or inferring it from the game version.
+    ///
+    /// For more information, see [`InputParseMode`].
+    pub fn input_mode(mut self, mode: InputParseMode) -> Self {
+        self.input_mode = Some(mode);
+        self
+    }
+
+    /// Sets whether the metadata should be encoded like the game's JSON encoder does.
+    ///
+    /// See [`canonical_metadata_json`][crate::canonical_metadata_json] for more information.
+    /// Disabled by default.
+    pub fn canonical_json(mut self, enabled: bool) -> Self {
+        self.canonical_json = enabled;
+        self
+    }
+
+    /// Sets the number of characters after which base64 output is wrapped onto a new line,
+    /// e.g. for pasting into forum posts.
+    ///
+    /// Lines are separated by `\n`, and every line except the last is exactly `width` characters.
+    /// 0, the default, disables wrapping, like the game's exported strings.
+    /// The parsing functions of this crate ignore the line breaks.
+    pub fn base64_line_width(mut self, width: usize) -> Self {
+        self.base64_line_width = width;
+        self
+    }
+
+    /// Sets whether base64 output should use the URL-safe alphabet without padding,
+    /// e.g. for share links.
+    ///
+    /// This replaces `+` and `/` with `-` and `_`, and leaves out the trailing `=`.
+    /// The game exports the standard alphabet, but the parsing functions of this crate
+    /// accept both. Disabled by default.
+    pub fn url_safe_base64(mut self, enabled: bool) -> Self {
+        self.url_safe_base64 = enabled;
+        self
+    }
+
+    /// Sets whether a replay parsed from compressed data keeps its original compressed bytes
+    /// while it's unmodified.
+    ///
+    /// Only [`LazyReplayData`][crate::LazyReplayData] keeps the original bytes, so this doesn't
+    /// change how a [`GameReplayData`] is serialized. See [`RecompressionFidelity`] for more
+    /// information. [`Content`][RecompressionFidelity::Content] by default.
+    pub fn recompression(mut self, fidelity: RecompressionFidelity) -> Self {
+        self.recompression = fidelity;
+        self
+    }
+}
+
+/// How a replay parsed from compressed data is compressed again when it's serialized.
+///
+/// Replays are compressed with zlib, and different zlib builds compress the same data into
+/// different bytes, so a replay compressed again by this crate can differ from the game's
+/// original byte for byte, even though it decompresses to the same content.
+/// [`compressed_equivalent`][crate::compressed_equivalent] compares replays by their content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RecompressionFidelity {
+    /// The original compressed bytes are written back as long as the replay is unmodified and
+    /// the inputs are timed like they were, so that it comes out byte for byte the same.
+    /// Otherwise, the replay is compressed again.
+    Exact,
+    /// The replay is always compressed again.
+    #[default]
+    Content,
+}
+
+/// Options for [`GameReplayData::anonymize`].
+///
+/// The options are set by chaining the methods:
+///
+/// ```
+/// use techmino_replay_toolkit::AnonymizeOptions;
+///
+/// let options = AnonymizeOptions::new().player("Someone").blank_date(true);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnonymizeOptions {
+    pub(crate) player: String,
+    pub(crate) blank_date: bool,
+    pub(crate) clear_seed: bool,
+}
+
+impl Default for AnonymizeOptions {
+    fn default() -> Self {
+        AnonymizeOptions {
+            player: "Anonymous".to_string(),
+            blank_date: false,
+            clear_seed: false,
+        }
+    }
+}
+
+impl AnonymizeOptions {
+    /// Creates the default options.
+    pub fn new() -> AnonymizeOptions {
+        Self::default()
+    }
+
+    /// Sets the name that replaces the player's name. `Anonymous` by default.
+    pub fn player(mut self, player: impl Into<String>) -> Self {
+        self.player = player.into();
+        self
+    }
+
+    /// Sets whether the date should be replaced with an empty string,
+    /// instead of only removing the time of day.
+    ///
+    /// Disabled by default.
+    pub fn blank_date(mut self, enabled: bool) -> Self {
+        self.blank_date = enabled;
+        self
+    }
+
+    /// Sets whether the seed should be set to 0.
+    ///
+    /// The seed decides the order of the pieces, so without it the replay won't play back
+    /// the same way in the game. Disabled by default.
+    pub fn clear_seed(mut self, enabled: bool) -> Self {
+        self.clear_seed = enabled;
+        self
+    }
+}
@@ -0,0 +1,134 @@
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::PIECE_COUNT;
+use crate::types::*;
+
+/// A piece of the game, in the order of the per-piece settings
+/// [`face`][PlayerSettings::face] and [`skin`][PlayerSettings::skin].
+///
+/// The names are the ones the game uses, with the number of blocks appended where
+/// a tetromino and a pentomino would share a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum PieceId {
+    // Tetrominoes
+    Z,
+    S,
+    J,
+    L,
+    T,
+    O,
+    I,
+
+    // Pentominoes
+    Z5,
+    S5,
+    P,
+    Q,
+    F,
+    E,
+    T5,
+    U,
+    V,
+    W,
+    X,
+    J5,
+    L5,
+    R,
+    Y,
+    N,
+    H,
+    I5,
+
+    // Trominoes, domino and monomino
+    I3,
+    C,
+    I2,
+    O1,
+}
+
+impl PieceId {
+    /// All the pieces, in the order of the per-piece settings.
+    pub const ALL: [PieceId; PIECE_COUNT] = {
+        use PieceId::*;
+
+        [
+            Z, S, J, L, T, O, I, Z5, S5, P, Q, F, E, T5, U, V, W, X, J5, L5, R, Y, N, H, I5, I3, C, I2, O1,
+        ]
+    };
+
+    /// The index of the piece in the per-piece settings.
+    ///
+    /// The game's own piece IDs, e.g. in [`CustomClearData::bag`][crate::CustomClearData::bag],
+    /// are one more than this.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl PlayerSettings {
+    /// The starting orientation of a piece, or [`None`] if the [`face`][PlayerSettings::face]
+    /// setting is missing or too short.
+    pub fn face_of(&self, piece: PieceId) -> Option<u64> {
+        self.face.as_ref()?.get(piece.index()).copied()
+    }
+
+    /// The color of a piece, or [`None`] if the [`skin`][PlayerSettings::skin]
+    /// setting is missing or too short.
+    pub fn skin_of(&self, piece: PieceId) -> Option<u64> {
+        self.skin.as_ref()?.get(piece.index()).copied()
+    }
+
+    /// Sets the starting orientation of a piece.
+    ///
+    /// If the [`face`][PlayerSettings::face] setting is missing or too short, it's filled up
+    /// with the game's default of 0 first.
+    pub fn set_face_of(&mut self, piece: PieceId, face: u64) {
+        set_piece_value(&mut self.face, &[0; PIECE_COUNT], piece, face);
+    }
+
+    /// Sets the color of a piece.
+    ///
+    /// If the [`skin`][PlayerSettings::skin] setting is missing or too short, it's filled up
+    /// with 0 first. The game's default colors aren't known here, so the other pieces should be
+    /// given their colors too in that case.
+    pub fn set_skin_of(&mut self, piece: PieceId, skin: u64) {
+        set_piece_value(&mut self.skin, &[0; PIECE_COUNT], piece, skin);
+    }
+}
+
+fn set_piece_value(values: &mut Option<Vec<u64>>, defaults: &[u64; PIECE_COUNT], piece: PieceId, value: u64) {
+    let values = values.get_or_insert_with(Vec::new);
+    if values.len() < PIECE_COUNT {
+        values.extend_from_slice(&defaults[values.len()..]);
+    }
+
+    values[piece.index()] = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_piece_values() {
+        let mut settings = PlayerSettings::default();
+        assert_eq!(settings.skin_of(PieceId::T), None);
+
+        settings.set_skin_of(PieceId::T, 16);
+        settings.set_face_of(PieceId::O1, 2);
+        assert_eq!(settings.skin_of(PieceId::T), Some(16));
+        assert_eq!(settings.skin_of(PieceId::I), Some(0));
+        assert_eq!(settings.face_of(PieceId::O1), Some(2));
+        assert_eq!(settings.face_of(PieceId::Z), Some(0));
+        assert_eq!(settings.validate(), []);
+
+        // Values that are already there are kept
+        settings.face = Some(vec![3; 7]);
+        settings.set_face_of(PieceId::I5, 1);
+        let face = settings.face.as_ref().unwrap();
+        assert_eq!((face.len(), face[6], face[7], face[PieceId::I5.index()]), (PIECE_COUNT, 3, 0, 1));
+    }
+}
@@ -0,0 +1,273 @@
+use alloc::collections::{btree_map::Entry, BTreeMap};
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+
+/// A span of frames in which a key was held down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPress {
+    /// The key that was held.
+    pub key: InputEventKey,
+    /// The frame in which the key was pressed.
+    pub start_frame: u64,
+    /// The frame in which the key was released.
+    ///
+    /// This is [`None`] if the key was still held at the end of the replay.
+    pub end_frame: Option<u64>,
+}
+
+impl KeyPress {
+    /// The number of frames the key was held for.
+    ///
+    /// Returns [`None`] if the key was still held at the end of the replay.
+    /// A press that ends before it starts, e.g. one built by hand, lasts 0 frames.
+    pub fn duration(&self) -> Option<u64> {
+        self.end_frame.map(|end| end.saturating_sub(self.start_frame))
+    }
+}
+
+/// An input event that couldn't be paired up into a [`KeyPress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyPressAnomaly {
+    /// A key was released without being pressed beforehand.
+    ///
+    /// The release is skipped.
+    UnpairedRelease {
+        /// The index of the release event in the replay's inputs.
+        index: usize,
+        /// The frame of the release event.
+        frame: u64,
+        /// The key that was released.
+        key: InputEventKey,
+    },
+
+    /// A key was pressed while it was already held.
+    ///
+    /// The game can't produce this, but corrupted or edited replays can.
+    /// The press is skipped, so the key is treated as held since its first press.
+    RepeatedPress {
+        /// The index of the press event in the replay's inputs.
+        index: usize,
+        /// The frame of the press event.
+        frame: u64,
+        /// The key that was pressed.
+        key: InputEventKey,
+    },
+}
+
+/// The result of pairing up a replay's press and release events.
+///
+/// See [`GameReplayData::pair_key_presses`] for more information.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct KeyPressPairing {
+    /// The key presses, in the order they were pressed.
+    pub presses: Vec<KeyPress>,
+    /// The input events that couldn't be paired up, in the order they happened.
+    pub anomalies: Vec<KeyPressAnomaly>,
+}
+
+impl GameReplayData {
+    /// Pairs each key press with its matching release.
+    ///
+    /// Releases with no preceding press and presses of keys that are already held are skipped.
+    /// Use [`pair_key_presses`][GameReplayData::pair_key_presses] to find out about those.
+    pub fn key_presses(&self) -> Vec<KeyPress> {
+        self.pair_key_presses().presses
+    }
+
+    /// Pairs each key press with its matching release, reporting any events that
+    /// couldn't be paired up.
+    ///
+    /// The inputs don't need to be sorted; they're processed in order of their frame,
+    /// with events on the same frame keeping their original order.
+    pub fn pair_key_presses(&self) -> KeyPressPairing {
+        let mut order: Vec<usize> = (0..self.inputs.len()).collect();
+        order.sort_by_key(|&i| self.inputs[i].frame);
+
+        let mut pairing = KeyPressPairing::default();
+        // Maps the held keys to their index in `pairing.presses`
+        let mut held: BTreeMap<InputEventKey, usize> = BTreeMap::new();
+
+        for index in order {
+            let GameInputEvent { frame, kind, key } = self.inputs[index];
+
+            match kind {
+                InputEventKind::Press => match held.entry(key) {
+                    Entry::Occupied(_) => pairing
+                        .anomalies
+                        .push(KeyPressAnomaly::RepeatedPress { index, frame, key }),
+                    Entry::Vacant(entry) => {
+                        entry.insert(pairing.presses.len());
+                        pairing.presses.push(KeyPress {
+                            key,
+                            start_frame: frame,
+                            end_frame: None,
+                        });
+                    }
+                },
+                InputEventKind::Release => match held.remove(&key) {
+                    Some(press) => pairing.presses[press].end_frame = Some(frame),
+                    None => pairing
+                        .anomalies
+                        .push(KeyPressAnomaly::UnpairedRelease { index, frame, key }),
+                },
+            }
+        }
+
+        pairing
+    }
+
+    /// Creates a replay from a list of key presses.
+    ///
+    /// Each [`KeyPress`] is expanded into a press event and, if it has an end frame,
+    /// a release event. The resulting inputs are sorted by frame.
+    /// On the same frame, releases of keys that were held from an earlier frame come first,
+    /// followed by the presses in the order they were given.
+    ///
+    /// A key press that ends before it starts is clamped to end on the frame it starts on,
+    /// so it becomes a press and release on the same frame instead of a key held forever.
+    pub fn from_key_presses(metadata: GameReplayMetadata, presses: &[KeyPress]) -> GameReplayData {
+        let mut presses = presses.to_vec();
+        presses.sort_by_key(|p| p.start_frame);
+
+        // The second element is used to place releases before presses on the same frame
+        let mut events = Vec::with_capacity(presses.len() * 2);
+
+        for press in presses {
+            events.push((
+                1,
+                GameInputEvent {
+                    frame: press.start_frame,
+                    kind: InputEventKind::Press,
+                    key: press.key,
+                },
+            ));
+
+            if let Some(end_frame) = press.end_frame {
+                let end_frame = end_frame.max(press.start_frame);
+                let rank = if end_frame > press.start_frame { 0 } else { 1 };
+
+                events.push((
+                    rank,
+                    GameInputEvent {
+                        frame: end_frame,
+                        kind: InputEventKind::Release,
+                        key: press.key,
+                    },
+                ));
+            }
+        }
+
+        events.sort_by_key(|&(rank, event)| (event.frame, rank));
+
+        GameReplayData {
+            inputs: events.into_iter().map(|(_, event)| event).collect(),
+            metadata,
+            input_mode: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    fn press(key: InputEventKey, start_frame: u64, end_frame: Option<u64>) -> KeyPress {
+        KeyPress { key, start_frame, end_frame }
+    }
+
+    #[test]
+    fn test_pair_key_presses() {
+        let data = GameReplayData {
+            inputs: vec![
+                GameInputEvent::release(10, Hold),
+                GameInputEvent::press(20, MoveLeft),
+                GameInputEvent::press(25, MoveLeft),
+                GameInputEvent::press(30, HardDrop),
+                GameInputEvent::release(30, HardDrop),
+                GameInputEvent::release(40, MoveLeft),
+                GameInputEvent::press(50, RotateLeft),
+            ],
+            ..Default::default()
+        };
+
+        let pairing = data.pair_key_presses();
+
+        assert_eq!(
+            pairing.presses,
+            vec![
+                press(MoveLeft, 20, Some(40)),
+                press(HardDrop, 30, Some(30)),
+                press(RotateLeft, 50, None),
+            ]
+        );
+        assert_eq!(
+            pairing.anomalies,
+            vec![
+                KeyPressAnomaly::UnpairedRelease { index: 0, frame: 10, key: Hold },
+                KeyPressAnomaly::RepeatedPress { index: 2, frame: 25, key: MoveLeft },
+            ]
+        );
+        assert_eq!(data.key_presses(), pairing.presses);
+    }
+
+    #[test]
+    fn test_from_key_presses() {
+        let presses = [
+            press(MoveLeft, 10, Some(20)),
+            press(HardDrop, 20, Some(20)),
+            press(MoveLeft, 0, Some(10)),
+            press(RotateRight, 15, None),
+        ];
+
+        let data = GameReplayData::from_key_presses(GameReplayMetadata::default(), &presses);
+
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(0, MoveLeft),
+                GameInputEvent::release(10, MoveLeft),
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::press(15, RotateRight),
+                GameInputEvent::release(20, MoveLeft),
+                GameInputEvent::press(20, HardDrop),
+                GameInputEvent::release(20, HardDrop),
+            ]
+        );
+
+        let mut sorted = presses.to_vec();
+        sorted.sort_by_key(|p| p.start_frame);
+        assert_eq!(data.key_presses(), sorted);
+    }
+
+    #[test]
+    fn test_from_key_presses_ending_before_start() {
+        let presses = [press(Hold, 30, Some(25)), press(MoveLeft, 30, Some(40))];
+        let data = GameReplayData::from_key_presses(GameReplayMetadata::default(), &presses);
+
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(30, Hold),
+                GameInputEvent::release(30, Hold),
+                GameInputEvent::press(30, MoveLeft),
+                GameInputEvent::release(40, MoveLeft),
+            ]
+        );
+        let pairing = data.pair_key_presses();
+        assert_eq!(pairing.anomalies, vec![]);
+        assert_eq!(pairing.presses[0], press(Hold, 30, Some(30)));
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(press(Hold, 30, Some(45)).duration(), Some(15));
+        assert_eq!(press(Hold, 30, Some(25)).duration(), Some(0));
+        assert_eq!(press(Hold, 30, None).duration(), None);
+    }
+}
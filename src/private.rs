@@ -0,0 +1,92 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+
+/// The [`private`][GameReplayMetadata::private] data of a `custom_clear` replay,
+/// i.e. the custom game the player set up.
+///
+/// See [`GameReplayMetadata::custom_clear_data`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub struct CustomClearData {
+    /// The starting boards, one per page of the field editor, each a list of rows from the bottom up.
+    ///
+    /// Each cell is a block color, or 0 if it's empty.
+    #[serde(rename = "FIELD", skip_serializing_if = "Option::is_none")]
+    pub field: Option<Vec<Vec<Vec<i64>>>>,
+
+    /// The custom piece sequence, as piece IDs, e.g. 1 for Z and 7 for I.
+    #[serde(rename = "BAG", skip_serializing_if = "Option::is_none")]
+    pub bag: Option<Vec<u64>>,
+
+    /// The clears the player has to do, in order, as mission IDs.
+    #[serde(rename = "MISSION", skip_serializing_if = "Option::is_none")]
+    pub mission: Option<Vec<u64>>,
+
+    /// The rules of the custom game, e.g. `drop`, `lock` and `nextCount`.
+    #[serde(rename = "CUSTOMENV", skip_serializing_if = "Option::is_none")]
+    pub custom_env: Option<serde_json::Map<String, serde_json::Value>>,
+
+    /// Additional data, if any, that may not be standard.
+    #[serde(flatten)]
+    pub nonstandard: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The [`private`][GameReplayMetadata::private] data of a `custom_puzzle` replay,
+/// i.e. the puzzle the player set up.
+///
+/// The game saves the same data as for `custom_clear`, but the field is the shape to build
+/// instead of the starting board. See [`GameReplayMetadata::custom_puzzle_data`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub struct CustomPuzzleData {
+    /// The shapes to build, one per page of the field editor, each a list of rows from the bottom up.
+    ///
+    /// Each cell is a block color, or 0 if it's empty.
+    #[serde(rename = "FIELD", skip_serializing_if = "Option::is_none")]
+    pub field: Option<Vec<Vec<Vec<i64>>>>,
+
+    /// The custom piece sequence, as piece IDs, e.g. 1 for Z and 7 for I.
+    #[serde(rename = "BAG", skip_serializing_if = "Option::is_none")]
+    pub bag: Option<Vec<u64>>,
+
+    /// The clears the player has to do, in order, as mission IDs.
+    #[serde(rename = "MISSION", skip_serializing_if = "Option::is_none")]
+    pub mission: Option<Vec<u64>>,
+
+    /// The rules of the custom game, e.g. `drop`, `lock` and `nextCount`.
+    #[serde(rename = "CUSTOMENV", skip_serializing_if = "Option::is_none")]
+    pub custom_env: Option<serde_json::Map<String, serde_json::Value>>,
+
+    /// Additional data, if any, that may not be standard.
+    #[serde(flatten)]
+    pub nonstandard: serde_json::Map<String, serde_json::Value>,
+}
+
+impl GameReplayMetadata {
+    /// Decodes the [`private`][GameReplayMetadata::private] field into a type,
+    /// e.g. [`CustomClearData`].
+    ///
+    /// Returns [`None`] if the replay has no private data. The mode isn't checked, see
+    /// [`custom_clear_data`][GameReplayMetadata::custom_clear_data] and
+    /// [`custom_puzzle_data`][GameReplayMetadata::custom_puzzle_data] for that.
+    pub fn private_as<T: DeserializeOwned>(&self) -> Option<Result<T, serde_json::Error>> {
+        self.private.as_ref().map(|private| T::deserialize(private))
+    }
+
+    /// Decodes the [`private`][GameReplayMetadata::private] field of a `custom_clear` replay.
+    ///
+    /// Returns [`None`] if the replay is of another mode, or has no private data.
+    pub fn custom_clear_data(&self) -> Option<Result<CustomClearData, serde_json::Error>> {
+        (self.mode == "custom_clear").then(|| self.private_as()).flatten()
+    }
+
+    /// Decodes the [`private`][GameReplayMetadata::private] field of a `custom_puzzle` replay.
+    ///
+    /// Returns [`None`] if the replay is of another mode, or has no private data.
+    pub fn custom_puzzle_data(&self) -> Option<Result<CustomPuzzleData, serde_json::Error>> {
+        (self.mode == "custom_puzzle").then(|| self.private_as()).flatten()
+    }
+}
@@ -0,0 +1,145 @@
+//! Bindings for using the crate from Python, as the `techmino_replay_toolkit` module.
+//!
+//! ```python
+//! import pandas as pd
+//! import techmino_replay_toolkit as trt
+//!
+//! replay = trt.parse_file("game.rep")
+//! print(replay.metadata["player"])
+//! inputs = pd.DataFrame(replay.inputs_dict())
+//! ```
+//!
+//! Build the module with [maturin](https://www.maturin.rs/), e.g. `maturin develop --features python`.
+
+use std::path::PathBuf;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::types::*;
+
+create_exception!(
+    techmino_replay_toolkit,
+    ReplayError,
+    PyValueError,
+    "A replay couldn't be parsed or serialized."
+);
+
+/// A parsed replay.
+#[pyclass(name = "Replay", module = "techmino_replay_toolkit")]
+pub struct PyReplay(GameReplayData);
+
+#[pymethods]
+impl PyReplay {
+    /// The inputs, as a list of `(frame, kind, key)` tuples, e.g. `(180, "Press", "MoveLeft")`.
+    #[getter]
+    fn inputs(&self) -> Vec<(u64, String, String)> {
+        self.0
+            .inputs
+            .iter()
+            .map(|input| (input.frame, format!("{:?}", input.kind), input.key.name().to_string()))
+            .collect()
+    }
+
+    /// The inputs, as a dict of `frame`, `kind` and `key` lists, for `pandas.DataFrame(...)`.
+    fn inputs_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let inputs = &self.0.inputs;
+        let frames: Vec<u64> = inputs.iter().map(|input| input.frame).collect();
+        let kinds: Vec<String> = inputs.iter().map(|input| format!("{:?}", input.kind)).collect();
+        let keys: Vec<String> = inputs.iter().map(|input| input.key.name().to_string()).collect();
+
+        let dict = PyDict::new(py);
+        dict.set_item("frame", frames)?;
+        dict.set_item("kind", kinds)?;
+        dict.set_item("key", keys)?;
+
+        Ok(dict)
+    }
+
+    /// The metadata, as a dict with the game's JSON keys, e.g. `mod` rather than `mods`.
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let json = serde_json::to_string(&self.0.metadata)
+            .map_err(|error| ReplayError::new_err(format!("{error:?}")))?;
+
+        py.import("json")?.call_method1("loads", (json,))
+    }
+
+    /// Serializes the replay into a base64 string.
+    fn to_base64(&self) -> PyResult<String> {
+        self.0.serialize_to_base64(None).map_err(serialize_error)
+    }
+
+    /// Serializes the replay into compressed bytes, the contents of a `.rep` file.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self.0.serialize_to_compressed(None).map_err(serialize_error)?;
+
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Writes the replay to a `.rep` file.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        let bytes = self.0.serialize_to_compressed(None).map_err(serialize_error)?;
+
+        std::fs::write(path, bytes).map_err(|error| PyOSError::new_err(error.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Replay of {:?} by {:?} with {} inputs>",
+            self.0.metadata.mode,
+            self.0.metadata.player,
+            self.0.inputs.len()
+        )
+    }
+}
+
+/// Parses a base64 replay string.
+#[pyfunction]
+fn parse_base64(string: &str) -> PyResult<PyReplay> {
+    GameReplayData::try_from_base64(string, None).map(PyReplay).map_err(parse_error)
+}
+
+/// Parses compressed replay bytes, e.g. the contents of a `.rep` file.
+#[pyfunction]
+fn parse_bytes(data: &[u8]) -> PyResult<PyReplay> {
+    GameReplayData::try_from_compressed(data, None).map(PyReplay).map_err(parse_error)
+}
+
+/// Parses a replay file, either compressed or base64-encoded.
+#[pyfunction]
+fn parse_file(path: PathBuf) -> PyResult<PyReplay> {
+    let data = std::fs::read(path).map_err(|error| PyOSError::new_err(error.to_string()))?;
+
+    match GameReplayData::try_from_compressed(&data, None) {
+        Err(ReplayParseError::ZlibDecompressError(_)) if data.is_ascii() => {
+            // Exported replays are often saved as their base64 text
+            let string = String::from_utf8_lossy(&data);
+            GameReplayData::try_from_base64(string.trim(), None).map(PyReplay).map_err(parse_error)
+        }
+        result => result.map(PyReplay).map_err(parse_error),
+    }
+}
+
+fn parse_error(error: ReplayParseError) -> PyErr {
+    ReplayError::new_err(format!("{error:?}"))
+}
+
+fn serialize_error(error: ReplaySerializeError) -> PyErr {
+    ReplayError::new_err(format!("{error:?}"))
+}
+
+/// The `techmino_replay_toolkit` Python module.
+#[pymodule]
+#[pyo3(name = "techmino_replay_toolkit")]
+pub fn techmino_replay_toolkit(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyReplay>()?;
+    m.add("ReplayError", m.py().get_type::<ReplayError>())?;
+    m.add_function(wrap_pyfunction!(parse_base64, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_file, m)?)?;
+
+    Ok(())
+}
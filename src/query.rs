@@ -0,0 +1,109 @@
+use core::ops::{Bound, RangeBounds};
+
+use crate::types::*;
+
+impl GameReplayData {
+    /// Returns the inputs within a range of frames.
+    ///
+    /// This uses a binary search, so the inputs must be sorted by frame.
+    /// If they aren't, the result is unspecified.
+    pub fn inputs_in_range(&self, frames: impl RangeBounds<u64>) -> &[GameInputEvent] {
+        let (start, end) = self.index_range(frames);
+        &self.inputs[start..end]
+    }
+
+    /// Returns an iterator over the inputs of a key.
+    pub fn inputs_for_key(&self, key: InputEventKey) -> impl Iterator<Item = &GameInputEvent> {
+        self.inputs.iter().filter(move |i| i.key == key)
+    }
+
+    /// Returns the first input on or after a frame.
+    ///
+    /// This uses a binary search, so the inputs must be sorted by frame.
+    /// If they aren't, the result is unspecified.
+    pub fn first_input_at_or_after(&self, frame: u64) -> Option<&GameInputEvent> {
+        self.inputs_in_range(frame..).first()
+    }
+
+    /// Finds the range of indices of the inputs within a range of frames.
+    pub(crate) fn index_range(&self, frames: impl RangeBounds<u64>) -> (usize, usize) {
+        let start = match frames.start_bound() {
+            Bound::Included(&s) => self.inputs.partition_point(|i| i.frame < s),
+            Bound::Excluded(&s) => self.inputs.partition_point(|i| i.frame <= s),
+            Bound::Unbounded => 0,
+        };
+
+        let rest = &self.inputs[start..];
+        let len = match frames.end_bound() {
+            Bound::Included(&e) => rest.partition_point(|i| i.frame <= e),
+            Bound::Excluded(&e) => rest.partition_point(|i| i.frame < e),
+            Bound::Unbounded => rest.len(),
+        };
+
+        (start, start + len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates a sorted replay with some frames having several inputs, and some having none.
+    fn generated_replay() -> GameReplayData {
+        let mut next = crate::tests::helpers::xorshift(0x2545F4914F6CDD1D);
+
+        let mut frame = 0;
+        let inputs = (0..5000)
+            .map(|_| {
+                frame += next() % 4;
+                let value = next();
+                GameInputEvent {
+                    frame,
+                    kind: InputEventKind::from(value & 1 == 1),
+                    key: InputEventKey::try_from((value >> 1) as u8 % 20 + 1).unwrap(),
+                }
+            })
+            .collect();
+
+        GameReplayData { inputs, ..Default::default() }
+    }
+
+    #[test]
+    fn test_queries_match_naive_filter() {
+        let data = generated_replay();
+        let last = data.inputs.last().unwrap().frame;
+
+        let naive = |f: &dyn Fn(u64) -> bool| -> Vec<GameInputEvent> {
+            data.inputs.iter().copied().filter(|i| f(i.frame)).collect()
+        };
+
+        for start in (0..last + 10).step_by(37) {
+            for len in [0, 1, 2, 5, 100] {
+                let end = start + len;
+
+                assert_eq!(data.inputs_in_range(start..end), naive(&|f| (start..end).contains(&f)));
+                assert_eq!(data.inputs_in_range(start..=end), naive(&|f| (start..=end).contains(&f)));
+                assert_eq!(data.inputs_in_range(..end), naive(&|f| f < end));
+                assert_eq!(data.inputs_in_range(start..), naive(&|f| f >= start));
+                assert_eq!(
+                    data.inputs_in_range((Bound::Excluded(start), Bound::Included(end))),
+                    naive(&|f| f > start && f <= end)
+                );
+            }
+
+            assert_eq!(
+                data.first_input_at_or_after(start),
+                data.inputs.iter().find(|i| i.frame >= start)
+            );
+        }
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = data.inputs_in_range(100..50);
+        assert!(reversed.is_empty());
+
+        let hard_drops: Vec<_> = data.inputs_for_key(InputEventKey::HardDrop).collect();
+        let naive_hard_drops: Vec<_> =
+            data.inputs.iter().filter(|i| i.key == InputEventKey::HardDrop).collect();
+        assert_eq!(hard_drops, naive_hard_drops);
+    }
+}
@@ -0,0 +1,201 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::codec::unpack;
+use crate::compression::decompress;
+use crate::types::*;
+use crate::vlq;
+use crate::{ParseOptions, SplitReplay};
+
+/// A part of a replay that couldn't be recovered by
+/// [`try_recover_from_compressed`][GameReplayData::try_recover_from_compressed].
+#[derive(Debug)]
+pub enum DataLoss {
+    /// The compressed data is cut off or corrupted, so only part of it could be decompressed.
+    Decompression {
+        /// The number of bytes that could be decompressed.
+        decompressed_bytes: usize,
+    },
+
+    /// The metadata couldn't be parsed, so nothing could be recovered.
+    ///
+    /// This is also the case if the separator between the metadata and the inputs is missing,
+    /// as the metadata is most likely cut off.
+    Metadata(ReplayParseError),
+
+    /// The mode in which to parse the inputs could not be inferred from the version string,
+    /// so only the metadata could be recovered.
+    ///
+    /// Contains the version string. To fix this, consider passing in the input parse mode
+    /// explicitly with [`ParseOptions::parse_mode`].
+    UnknownInputParseMode(String),
+
+    /// The input data stops being decodable partway through.
+    Inputs {
+        /// The position of the first byte that couldn't be decoded, counted from the start of the input data.
+        position: usize,
+        /// The number of inputs that were decoded before it.
+        recovered_inputs: usize,
+    },
+}
+
+/// The outcome of [`try_recover_from_compressed`][GameReplayData::try_recover_from_compressed].
+#[derive(Debug)]
+pub struct RecoveryResult {
+    /// The recovered replay, or [`None`] if not even the metadata could be recovered.
+    pub data: Option<GameReplayData>,
+    /// The parts of the replay that were lost, in the order they were found.
+    ///
+    /// If this is empty, the whole replay was recovered.
+    pub losses: Vec<DataLoss>,
+}
+
+impl RecoveryResult {
+    /// Returns whether the whole replay was recovered.
+    pub fn is_complete(&self) -> bool {
+        self.data.is_some() && self.losses.is_empty()
+    }
+}
+
+impl GameReplayData {
+    /// Recovers as much as possible from a compressed replay that may be cut off or corrupted,
+    /// e.g. because the game was closed while saving it.
+    ///
+    /// Unlike [`try_from_compressed`][GameReplayData::try_from_compressed], this doesn't fail at the
+    /// first problem. The data is decompressed as far as possible, and if the metadata is intact,
+    /// all the inputs before the damaged part are decoded.
+    /// The [`RecoveryResult`] describes where data was lost.
+    pub fn try_recover_from_compressed(data: &[u8], options: &ParseOptions) -> RecoveryResult {
+        match decompress(data, usize::MAX) {
+            Ok(data) => Self::try_recover_from_raw(&data, options),
+            Err(error) => {
+                let mut result = Self::try_recover_from_raw(&error.output, options);
+                result.losses.insert(
+                    0,
+                    DataLoss::Decompression { decompressed_bytes: error.output.len() },
+                );
+                result
+            }
+        }
+    }
+
+    /// Recovers as much as possible from a raw, uncompressed replay that may be cut off or corrupted.
+    ///
+    /// See [`try_recover_from_compressed`][GameReplayData::try_recover_from_compressed] for more information.
+    pub fn try_recover_from_raw(data: &[u8], options: &ParseOptions) -> RecoveryResult {
+        let mut losses = Vec::new();
+
+        let split = match SplitReplay::from_raw_with(data, options) {
+            Ok(split) => split,
+            Err(error) => {
+                losses.push(DataLoss::Metadata(error));
+                return RecoveryResult { data: None, losses };
+            }
+        };
+
+        let metadata = match GameReplayMetadata::try_from(split.metadata_bytes) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                losses.push(DataLoss::Metadata(error));
+                return RecoveryResult { data: None, losses };
+            }
+        };
+
+        let parse_mode = options
+            .parse_mode
+            .or_else(|| InputParseMode::try_infer_from_version(&metadata.version));
+
+        let inputs = match parse_mode {
+            _ if split.input_bytes.is_empty() => Vec::new(),
+            Some(mode) => {
+                let (inputs, loss) = recover_inputs(split.input_bytes, mode);
+                losses.extend(loss);
+                inputs
+            }
+            None => {
+                losses.push(DataLoss::UnknownInputParseMode(metadata.version.clone()));
+                Vec::new()
+            }
+        };
+
+        RecoveryResult {
+            data: Some(GameReplayData { inputs, metadata, input_mode: None }),
+            losses,
+        }
+    }
+}
+
+/// Decodes the inputs until the first one that can't be decoded.
+fn recover_inputs(input_slice: &[u8], mode: InputParseMode) -> (Vec<GameInputEvent>, Option<DataLoss>) {
+    let mut inputs = Vec::new();
+    let mut values = vlq::decode_iter(input_slice);
+    let mut prev_frame = 0;
+
+    loop {
+        let position = values.position();
+
+        let pair = match (values.next(), values.next()) {
+            (None, _) => return (inputs, None),
+            (Some(Ok(time)), Some(Ok(code))) => (time, code),
+            _ => {
+                let loss = DataLoss::Inputs { position, recovered_inputs: inputs.len() };
+                return (inputs, Some(loss));
+            }
+        };
+
+        match unpack(pair.0, pair.1, mode, prev_frame, None) {
+            Ok(event) => {
+                prev_frame = event.frame;
+                inputs.push(event);
+            }
+            Err(_) => {
+                let loss = DataLoss::Inputs { position, recovered_inputs: inputs.len() };
+                return (inputs, Some(loss));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_inputs() {
+        // Press and release of HardDrop, then a time with no key code
+        let bytes = [10, 6, 0, 6 | 0b10_0000, 0x81, 0x00];
+
+        let (inputs, loss) = recover_inputs(&bytes, InputParseMode::Relative);
+        assert_eq!(inputs.len(), 2);
+        assert!(matches!(loss, Some(DataLoss::Inputs { position: 4, recovered_inputs: 2 })));
+
+        // A truncated VLQ
+        let (inputs, loss) = recover_inputs(&bytes[..5], InputParseMode::Relative);
+        assert_eq!(inputs.len(), 2);
+        assert!(matches!(loss, Some(DataLoss::Inputs { position: 4, recovered_inputs: 2 })));
+
+        // An invalid key code
+        let (inputs, loss) = recover_inputs(&[10, 6, 0, 0], InputParseMode::Relative);
+        assert_eq!(inputs.len(), 1);
+        assert!(matches!(loss, Some(DataLoss::Inputs { position: 2, recovered_inputs: 1 })));
+
+        let (inputs, loss) = recover_inputs(&bytes[..4], InputParseMode::Relative);
+        assert_eq!(inputs[1].frame, 10);
+        assert!(loss.is_none());
+    }
+
+    #[test]
+    fn test_recover_garbage() {
+        let options = ParseOptions::new();
+
+        for len in 0..64 {
+            let garbage: Vec<u8> = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+
+            let result = GameReplayData::try_recover_from_compressed(&garbage, &options);
+            assert!(result.data.is_none());
+            assert!(!result.losses.is_empty());
+
+            GameReplayData::try_recover_from_raw(&garbage, &options);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyset::KeySet;
+use crate::types::*;
+
+/// Chooses which fixes [`GameReplayData::repair`] applies.
+///
+/// The fixes are applied in the order the fields are listed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairPolicy {
+    /// Moves inputs that are earlier than the input before them forward to that input's frame.
+    ///
+    /// This is meant for replays whose frames were converted from relative timing
+    /// and went backwards by mistake. It keeps the order of the inputs,
+    /// at the cost of changing their timing.
+    /// Disabled by default.
+    pub clamp_backwards_frames: bool,
+    /// Sorts the inputs by frame, keeping the order of inputs on the same frame.
+    pub sort: bool,
+    /// Removes events that repeat the previous event of their key on the same frame,
+    /// e.g. a second press of a key without a release in between.
+    ///
+    /// A press, release and press of a key on one frame is kept, as the key is tapped and held.
+    pub remove_duplicates: bool,
+    /// Removes releases of keys that aren't held.
+    pub remove_unpaired_releases: bool,
+    /// Removes the events of keys added by forks of the game, i.e. [`Other`][InputEventKey::Other]
+    /// keys, so that the replay only has keys the game knows.
+    /// Disabled by default.
+    pub strip_unknown_keys: bool,
+}
+
+impl Default for RepairPolicy {
+    fn default() -> Self {
+        RepairPolicy {
+            clamp_backwards_frames: false,
+            sort: true,
+            remove_duplicates: true,
+            remove_unpaired_releases: true,
+            strip_unknown_keys: false,
+        }
+    }
+}
+
+/// A change made by [`GameReplayData::repair`].
+///
+/// All indices refer to the position of the input before the replay was repaired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "action")]
+pub enum RepairAction {
+    /// The frame of an input was moved forward.
+    #[serde(rename_all = "camelCase")]
+    ClampedFrame {
+        /// The index of the input.
+        index: usize,
+        /// The original frame of the input.
+        from_frame: u64,
+        /// The new frame of the input.
+        to_frame: u64,
+    },
+    /// The inputs were sorted.
+    #[serde(rename_all = "camelCase")]
+    Sorted {
+        /// The number of inputs whose position changed.
+        moved_inputs: usize,
+    },
+    /// A duplicate input was removed.
+    RemovedDuplicate {
+        /// The index of the input.
+        index: usize,
+        /// The input that was removed.
+        event: GameInputEvent,
+    },
+    /// A release of a key that wasn't held was removed.
+    RemovedUnpairedRelease {
+        /// The index of the input.
+        index: usize,
+        /// The input that was removed.
+        event: GameInputEvent,
+    },
+    /// An event of a key added by a fork of the game was removed.
+    RemovedUnknownKey {
+        /// The index of the input.
+        index: usize,
+        /// The input that was removed.
+        event: GameInputEvent,
+    },
+}
+
+/// A list of the changes made by [`GameReplayData::repair`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// The changes made, in the order they were made.
+    pub actions: Vec<RepairAction>,
+}
+
+impl RepairReport {
+    /// Returns whether the replay was left unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl GameReplayData {
+    /// Fixes common defects in the inputs of the replay.
+    ///
+    /// This is meant for replays that fail to serialize, or that [`validate`][GameReplayData::validate]
+    /// reports issues for, e.g. because they were assembled by a script.
+    /// See [`RepairPolicy`] for the fixes that can be applied.
+    pub fn repair(&mut self, policy: RepairPolicy) -> RepairReport {
+        let mut report = RepairReport::default();
+
+        // Keep track of the original indices so the report can refer to them
+        let mut inputs: Vec<(usize, GameInputEvent)> =
+            self.inputs.iter().copied().enumerate().collect();
+
+        if policy.clamp_backwards_frames {
+            let mut prev_frame = 0;
+
+            for (index, event) in inputs.iter_mut() {
+                if event.frame < prev_frame {
+                    report.actions.push(RepairAction::ClampedFrame {
+                        index: *index,
+                        from_frame: event.frame,
+                        to_frame: prev_frame,
+                    });
+                    event.frame = prev_frame;
+                }
+
+                prev_frame = event.frame;
+            }
+        }
+
+        if policy.sort {
+            inputs.sort_by_key(|(_, event)| event.frame);
+
+            let moved_inputs = inputs
+                .iter()
+                .enumerate()
+                .filter(|(position, (index, _))| position != index)
+                .count();
+
+            if moved_inputs > 0 {
+                report.actions.push(RepairAction::Sorted { moved_inputs });
+            }
+        }
+
+        if policy.remove_duplicates {
+            let mut kept: Vec<(usize, GameInputEvent)> = Vec::with_capacity(inputs.len());
+            // The index in `kept` where the current frame's inputs start
+            let mut frame_start = 0;
+
+            for (index, event) in inputs {
+                if kept.last().is_some_and(|(_, last)| last.frame != event.frame) {
+                    frame_start = kept.len();
+                }
+
+                if repeats_previous(kept[frame_start..].iter().map(|(_, e)| e), &event) {
+                    report.actions.push(RepairAction::RemovedDuplicate { index, event });
+                } else {
+                    kept.push((index, event));
+                }
+            }
+
+            inputs = kept;
+        }
+
+        if policy.remove_unpaired_releases {
+            let mut held = KeySet::new();
+
+            inputs.retain(|&(index, event)| match event.kind {
+                InputEventKind::Press => {
+                    held.insert(event.key);
+                    true
+                }
+                InputEventKind::Release => {
+                    let paired = held.remove(event.key);
+                    if !paired {
+                        report.actions.push(RepairAction::RemovedUnpairedRelease { index, event });
+                    }
+                    paired
+                }
+            });
+        }
+
+        if policy.strip_unknown_keys {
+            inputs.retain(|&(index, event)| {
+                let unknown = matches!(event.key, InputEventKey::Other(_));
+                if unknown {
+                    report.actions.push(RepairAction::RemovedUnknownKey { index, event });
+                }
+                !unknown
+            });
+        }
+
+        self.inputs = inputs.into_iter().map(|(_, event)| event).collect();
+
+        report
+    }
+}
+
+/// Returns whether an event is the same as the previous event of its key among the earlier events
+/// on its frame, e.g. a second press of a key without a release in between.
+///
+/// Only the key's previous event matters, so a press after a press and release isn't a repeat.
+pub(crate) fn repeats_previous<'a>(
+    earlier: impl DoubleEndedIterator<Item = &'a GameInputEvent>,
+    event: &GameInputEvent,
+) -> bool {
+    earlier.rev().find(|e| e.key == event.key) == Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    fn only(policy: impl FnOnce(&mut RepairPolicy)) -> RepairPolicy {
+        let mut p = RepairPolicy {
+            clamp_backwards_frames: false,
+            sort: false,
+            remove_duplicates: false,
+            remove_unpaired_releases: false,
+            strip_unknown_keys: false,
+        };
+        policy(&mut p);
+        p
+    }
+
+    #[test]
+    fn test_repair_sort() {
+        let mut data = replay(vec![
+            GameInputEvent::press(20, MoveLeft),
+            GameInputEvent::press(10, HardDrop),
+            GameInputEvent::release(10, HardDrop),
+            GameInputEvent::release(30, MoveLeft),
+        ]);
+
+        assert!(data.serialize_to_base64(None).is_err());
+
+        let report = data.repair(only(|p| p.sort = true));
+
+        assert_eq!(report.actions, vec![RepairAction::Sorted { moved_inputs: 3 }]);
+        assert_eq!(
+            data.inputs,
+            vec![
+                GameInputEvent::press(10, HardDrop),
+                GameInputEvent::release(10, HardDrop),
+                GameInputEvent::press(20, MoveLeft),
+                GameInputEvent::release(30, MoveLeft),
+            ]
+        );
+
+        let serialized = data.serialize_to_base64(None).unwrap();
+        let reparsed = GameReplayData::try_from_base64(&serialized, None).unwrap();
+        assert_eq!(reparsed, data);
+
+        assert!(data.repair(RepairPolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn test_repair_clamp() {
+        let mut data = replay(vec![
+            GameInputEvent::press(20, MoveLeft),
+            GameInputEvent::release(10, MoveLeft),
+            GameInputEvent::press(30, HardDrop),
+        ]);
+
+        let report = data.repair(only(|p| p.clamp_backwards_frames = true));
+
+        assert_eq!(
+            report.actions,
+            vec![RepairAction::ClampedFrame { index: 1, from_frame: 10, to_frame: 20 }]
+        );
+        assert_eq!(data.inputs[1], GameInputEvent::release(20, MoveLeft));
+    }
+
+    #[test]
+    fn test_repair_duplicates() {
+        let mut data = replay(vec![
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::press(10, HardDrop),
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::release(20, MoveLeft),
+            GameInputEvent::press(30, MoveLeft),
+        ]);
+
+        let report = data.repair(only(|p| p.remove_duplicates = true));
+
+        assert_eq!(
+            report.actions,
+            vec![RepairAction::RemovedDuplicate { index: 2, event: GameInputEvent::press(10, MoveLeft) }]
+        );
+        assert_eq!(data.inputs.len(), 4);
+
+        // Tapping a key and pressing it again on the same frame leaves it held
+        let tap_and_hold = vec![
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::release(10, MoveLeft),
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::press(10, MoveLeft),
+        ];
+        let mut data = replay(tap_and_hold.clone());
+        let report = data.repair(only(|p| p.remove_duplicates = true));
+
+        assert_eq!(
+            report.actions,
+            vec![RepairAction::RemovedDuplicate { index: 3, event: GameInputEvent::press(10, MoveLeft) }]
+        );
+        assert_eq!(data.inputs, tap_and_hold[..3]);
+    }
+
+    #[test]
+    fn test_repair_unknown_keys() {
+        let mut data = replay(vec![
+            GameInputEvent::press(10, Other(21)),
+            GameInputEvent::press(10, HardDrop),
+            GameInputEvent::release(12, Other(21)),
+            GameInputEvent::release(12, HardDrop),
+        ]);
+
+        assert!(data.clone().repair(RepairPolicy::default()).is_empty());
+        let report = data.repair(only(|p| p.strip_unknown_keys = true));
+
+        assert_eq!(
+            report.actions,
+            vec![
+                RepairAction::RemovedUnknownKey { index: 0, event: GameInputEvent::press(10, Other(21)) },
+                RepairAction::RemovedUnknownKey { index: 2, event: GameInputEvent::release(12, Other(21)) },
+            ]
+        );
+        assert_eq!(data.inputs, [GameInputEvent::press(10, HardDrop), GameInputEvent::release(12, HardDrop)]);
+    }
+
+    #[test]
+    fn test_repair_unpaired_releases() {
+        let mut data = replay(vec![
+            GameInputEvent::release(10, Hold),
+            GameInputEvent::press(20, MoveLeft),
+            GameInputEvent::release(30, MoveLeft),
+            GameInputEvent::release(40, MoveLeft),
+        ]);
+
+        let report = data.repair(only(|p| p.remove_unpaired_releases = true));
+
+        assert_eq!(
+            report.actions,
+            vec![
+                RepairAction::RemovedUnpairedRelease { index: 0, event: GameInputEvent::release(10, Hold) },
+                RepairAction::RemovedUnpairedRelease {
+                    index: 3,
+                    event: GameInputEvent::release(40, MoveLeft)
+                },
+            ]
+        );
+        assert!(data.validate().is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::repair::repeats_previous;
+use crate::types::*;
+use crate::validate::settings_issues;
+use crate::segments::TOOLKIT_KEYS;
+use crate::ValidationIssue;
+
+/// A non-fatal anomaly found while parsing a replay.
+///
+/// The warnings are found in the parsed replay, so they refer to inputs by their index in
+/// [`inputs`][GameReplayData::inputs] rather than by their position in the replay's bytes.
+/// See [`GameReplayData::try_from_raw_with_report`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "warning")]
+pub enum ParseWarning {
+    /// An input is on an earlier frame than the input before it.
+    ///
+    /// In [`Absolute`][InputParseMode::Absolute] mode, the game stores the frames as they are,
+    /// so nothing stops them from going backwards.
+    #[serde(rename_all = "camelCase")]
+    UnsortedInput {
+        /// The index of the input.
+        index: usize,
+        /// The frame of the input before it.
+        prev_frame: u64,
+        /// The frame of the input.
+        frame: u64,
+    },
+
+    /// An input is identical to the previous input of its key on the same frame,
+    /// e.g. a second press of a key without a release in between.
+    DuplicateInput {
+        /// The index of the duplicate input.
+        index: usize,
+        /// The duplicate input.
+        event: GameInputEvent,
+    },
+
+    /// The metadata contains a key this crate doesn't know about.
+    ///
+    /// Its value is kept in the `nonstandard` map it was found in.
+    UnknownMetadataKey {
+        /// The path of the key, in the style of a JSON pointer (e.g. `/setting/foo`).
+        path: String,
+    },
+
+    /// A setting has a value outside the range the game allows.
+    ///
+    /// Holds the [`ValidationIssue::SettingOutOfRange`] that [`GameReplayData::validate`] reports.
+    SettingOutOfRange(ValidationIssue),
+
+    /// The metadata is a Lua table rather than JSON.
+    ///
+    /// Only parsed with [`legacy_lua_metadata`][crate::ParseOptions::legacy_lua_metadata].
+    LegacyLuaMetadata,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseWarning::*;
+
+        match self {
+            UnsortedInput { index, prev_frame, frame } => write!(
+                f,
+                "Input #{index} is on frame {frame}, which is before the previous input's frame {prev_frame}"
+            ),
+            DuplicateInput { index, event } => write!(
+                f,
+                "Input #{index} ({:?} {:?} on frame {}) is a duplicate",
+                event.kind, event.key, event.frame
+            ),
+            UnknownMetadataKey { path } => write!(f, "Unknown metadata key at {path}"),
+            SettingOutOfRange(issue) => write!(f, "{issue}"),
+            LegacyLuaMetadata => {
+                write!(f, "The metadata is a Lua table rather than JSON")
+            }
+        }
+    }
+}
+
+/// The non-fatal anomalies found while parsing a replay.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ParseReport {
+    /// The anomalies found, with the input warnings in the order of the inputs.
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseReport {
+    /// Returns whether no anomalies were found.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Creates a report of the anomalies in a parsed replay.
+    pub(crate) fn from_replay(data: &GameReplayData) -> ParseReport {
+        let mut warnings = Vec::new();
+
+        // The index in `inputs` where the current frame's inputs start
+        let mut frame_start = 0;
+
+        for (index, window) in data.inputs.windows(2).enumerate() {
+            let (prev, event) = (window[0], window[1]);
+            let index = index + 1;
+
+            if event.frame < prev.frame {
+                warnings.push(ParseWarning::UnsortedInput {
+                    index,
+                    prev_frame: prev.frame,
+                    frame: event.frame,
+                });
+            }
+
+            if event.frame != prev.frame {
+                frame_start = index;
+            } else if repeats_previous(data.inputs[frame_start..index].iter(), &event) {
+                warnings.push(ParseWarning::DuplicateInput { index, event });
+            }
+        }
+
+        let metadata = &data.metadata;
+        let mut paths: Vec<String> = metadata
+            .nonstandard
+            .keys()
+            .filter(|key| !TOOLKIT_KEYS.contains(&key.as_str()))
+            .map(|key| format!("/{key}"))
+            .chain(metadata.setting.nonstandard.keys().map(|key| format!("/setting/{key}")))
+            .collect();
+        paths.sort();
+
+        warnings.extend(paths.into_iter().map(|path| ParseWarning::UnknownMetadataKey { path }));
+
+        warnings.extend(
+            settings_issues(&metadata.setting)
+                .into_iter()
+                .filter(|issue| matches!(issue, ValidationIssue::SettingOutOfRange { .. }))
+                .map(ParseWarning::SettingOutOfRange),
+        );
+
+        ParseReport { warnings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use InputEventKey::*;
+
+    fn parse(data: &GameReplayData) -> ParseReport {
+        let raw = data.serialize_to_raw(None).unwrap();
+        let (parsed, report) =
+            GameReplayData::try_from_raw_with_report(&raw, &ParseOptions::new()).unwrap();
+
+        assert_eq!(&parsed, data);
+        report
+    }
+
+    fn replay() -> GameReplayData {
+        GameReplayData {
+            inputs: vec![GameInputEvent::press(10, HardDrop), GameInputEvent::release(11, HardDrop)],
+            metadata: GameReplayMetadata {
+                version: "V0.17.22".to_string(),
+                ..Default::default()
+            },
+            input_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_no_warnings() {
+        assert!(parse(&replay()).is_empty());
+    }
+
+    #[test]
+    fn test_unsorted_input() {
+        // The serializer refuses unsorted inputs, so put the bytes together by hand
+        let mut raw = br#"{"player":"","seed":0,"version":"V0.17.22","date":"","mode":"","setting":{}}"#.to_vec();
+        raw.push(b'\n');
+        raw.extend([20, 6, 10, 6 | 0b10_0000]);
+
+        let (_, report) =
+            GameReplayData::try_from_raw_with_report(&raw, &ParseOptions::new()).unwrap();
+
+        assert_eq!(
+            report.warnings,
+            vec![ParseWarning::UnsortedInput { index: 1, prev_frame: 20, frame: 10 }]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_input() {
+        let mut data = replay();
+        data.inputs.insert(1, GameInputEvent::press(10, HardDrop));
+
+        assert_eq!(
+            parse(&data).warnings,
+            vec![ParseWarning::DuplicateInput { index: 1, event: GameInputEvent::press(10, HardDrop) }]
+        );
+
+        // Tapping a key and pressing it again on the same frame isn't a duplicate
+        let mut data = replay();
+        data.inputs.insert(1, GameInputEvent::release(10, HardDrop));
+        data.inputs.insert(2, GameInputEvent::press(10, HardDrop));
+        assert!(parse(&data).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_metadata_key() {
+        let mut data = replay();
+        data.metadata.setting.nonstandard.insert("newSetting".to_string(), true.into());
+
+        assert_eq!(
+            parse(&data).warnings,
+            vec![ParseWarning::UnknownMetadataKey { path: "/setting/newSetting".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_setting_out_of_range() {
+        let mut data = replay();
+        data.metadata.setting.arr = Some(16);
+
+        assert_eq!(
+            parse(&data).warnings,
+            vec![ParseWarning::SettingOutOfRange(ValidationIssue::SettingOutOfRange {
+                field: "arr".to_string(),
+                value: 16.0,
+                min: 0.0,
+                max: 15.0
+            })]
+        );
+
+        let warning = &parse(&data).warnings[0];
+        assert_eq!(warning.to_string(), "Setting 'arr' is 16, which is outside the range 0 to 15");
+        let json = serde_json::to_value(warning).unwrap();
+        assert_eq!((&json["warning"], &json["field"]), (&"settingOutOfRange".into(), &"arr".into()));
+        assert_eq!(&serde_json::from_value::<ParseWarning>(json).unwrap(), warning);
+    }
+}
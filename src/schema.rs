@@ -0,0 +1,15 @@
+use alloc::string::String;
+
+use crate::types::*;
+
+/// The [JSON Schema](https://json-schema.org/) of a [`GameReplayData`] written as JSON,
+/// pretty-printed.
+///
+/// The schema uses the names the serde attributes give the fields, e.g. `tasUsed` and `mod`,
+/// so it describes the JSON that [`serde_json`] reads and writes for a replay.
+/// It can be used to check replay JSON edited by hand before it's parsed.
+pub fn export_schema() -> String {
+    let schema = schemars::schema_for!(GameReplayData);
+
+    serde_json::to_string_pretty(&schema).expect("a schema is always valid JSON")
+}
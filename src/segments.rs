@@ -0,0 +1,209 @@
+//! Named ranges of frames of a replay, e.g. to practice a part of it on its own.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::*;
+use crate::{ANNOTATIONS_KEY, GAME_START_FRAME};
+
+/// The nonstandard metadata key the [segments][GameReplayData::segments] of a replay are stored
+/// under.
+pub const SEGMENTS_KEY: &str = "trtSegments";
+
+/// The nonstandard metadata keys this crate keeps its own data under, which the game ignores.
+pub(crate) const TOOLKIT_KEYS: [&str; 2] = [ANNOTATIONS_KEY, SEGMENTS_KEY];
+
+/// A named range of frames of a replay, e.g. `opener` for frames 180 to 420.
+///
+/// Both frames are included. See [`GameReplayData::segments`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    /// The name of the segment, unique within a replay.
+    pub name: String,
+    /// The first frame of the segment.
+    pub start_frame: u64,
+    /// The last frame of the segment.
+    pub end_frame: u64,
+}
+
+impl GameReplayData {
+    /// The named ranges of frames of the replay, in the order they were added.
+    ///
+    /// They're stored in the [`nonstandard`][GameReplayMetadata::nonstandard] metadata under
+    /// [`SEGMENTS_KEY`], as an array of objects like `{"name":"opener","startFrame":180,"endFrame":420}`,
+    /// which the game ignores.
+    ///
+    /// Entries that aren't segments are skipped.
+    pub fn segments(&self) -> Vec<Segment> {
+        let Some(Value::Array(entries)) = self.metadata.nonstandard.get(SEGMENTS_KEY) else {
+            return Vec::new();
+        };
+
+        entries.iter().filter_map(|entry| Segment::deserialize(entry).ok()).collect()
+    }
+
+    /// Adds a segment, replacing the one with the same name, which is returned.
+    ///
+    /// A replaced segment keeps its place, new ones are added at the end.
+    pub fn add_segment(&mut self, segment: Segment) -> Option<Segment> {
+        let entry = self.metadata.nonstandard.entry(SEGMENTS_KEY).or_insert_with(|| Value::Array(Vec::new()));
+        if !entry.is_array() {
+            *entry = Value::Array(Vec::new());
+        }
+        let entries = entry.as_array_mut().expect("the segments are an array");
+
+        let value = serde_json::to_value(&segment).expect("segments serialize to JSON");
+        let index = entries.iter().position(|entry| entry["name"] == segment.name.as_str());
+        match index {
+            Some(index) => {
+                let previous = core::mem::replace(&mut entries[index], value);
+                Segment::deserialize(previous).ok()
+            }
+            None => {
+                entries.push(value);
+                None
+            }
+        }
+    }
+
+    /// Removes the segment with a name, returning it.
+    ///
+    /// Once the last segment is removed, so is the [`SEGMENTS_KEY`] in the metadata.
+    pub fn remove_segment(&mut self, name: &str) -> Option<Segment> {
+        let nonstandard = &mut self.metadata.nonstandard;
+        let entries = nonstandard.get_mut(SEGMENTS_KEY)?.as_array_mut()?;
+
+        let index = entries.iter().position(|entry| entry["name"] == name)?;
+        let removed = entries.remove(index);
+        if entries.is_empty() {
+            // `Map::remove` would move the last key in its place with the `preserve-order` feature
+            nonstandard.retain(|key, _| key != SEGMENTS_KEY);
+        }
+
+        Segment::deserialize(removed).ok()
+    }
+
+    /// Cuts the segment with a name out into a replay of its own, e.g. to practice that part.
+    ///
+    /// The inputs of the segment are moved so that its first frame lands on
+    /// [`GAME_START_FRAME`], right after the countdown. Keys held going into the segment are
+    /// pressed on its first frame, and keys still held at its end are released on its last frame,
+    /// like [`remove_inputs_in_range_with_fixup`][GameReplayData::remove_inputs_in_range_with_fixup]
+    /// and [`truncate_at_frame`][GameReplayData::truncate_at_frame] do, so the replay is playable.
+    ///
+    /// The metadata is kept, except that the new replay has no segments, and only the
+    /// [annotations][GameReplayData::annotations] within the segment, moved along with the inputs.
+    /// Note that the game state at the start of the segment isn't recreated: the sub-replay
+    /// starts on an empty board, with the seed of the whole replay.
+    ///
+    /// A segment that ends before it starts gives a replay without inputs.
+    /// Returns `None` if there's no segment with the name. This expects the inputs to already be
+    /// sorted; see [`sort_inputs`][GameReplayData::sort_inputs].
+    pub fn extract_segment(&self, name: &str) -> Option<GameReplayData> {
+        let segment = self.segments().into_iter().find(|segment| segment.name == name)?;
+        let (start, end) = (segment.start_frame, segment.end_frame);
+        let rebase = |frame: u64| (frame - start).saturating_add(GAME_START_FRAME);
+
+        let mut data = self.clone();
+        data.truncate_at_frame(end);
+        data.remove_inputs_in_range_with_fixup(0..start);
+        for input in &mut data.inputs {
+            input.frame = rebase(input.frame);
+        }
+
+        data.metadata.nonstandard.retain(|key, _| !TOOLKIT_KEYS.contains(&key.as_str()));
+        for (frame, text) in self.annotations() {
+            if (start..=end).contains(&frame) {
+                data.set_annotation(rebase(frame), text);
+            }
+        }
+
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use InputEventKey::*;
+
+    fn segment(name: &str, start_frame: u64, end_frame: u64) -> Segment {
+        Segment { name: name.to_string(), start_frame, end_frame }
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut data = GameReplayData::default();
+        assert!(data.segments().is_empty());
+
+        assert_eq!(data.add_segment(segment("opener", 180, 420)), None);
+        assert_eq!(data.add_segment(segment("midgame", 421, 900)), None);
+        assert_eq!(data.add_segment(segment("opener", 180, 400)), Some(segment("opener", 180, 420)));
+        assert_eq!(data.segments(), [segment("opener", 180, 400), segment("midgame", 421, 900)]);
+        assert_eq!(
+            data.metadata.nonstandard[SEGMENTS_KEY][0],
+            serde_json::json!({"name": "opener", "startFrame": 180, "endFrame": 400})
+        );
+
+        // Entries written by something else are skipped, but kept
+        data.metadata.nonstandard[SEGMENTS_KEY].as_array_mut().unwrap().push("later".into());
+        assert_eq!(data.segments().len(), 2);
+        assert_eq!(data.remove_segment("later"), None);
+
+        assert_eq!(data.remove_segment("opener"), Some(segment("opener", 180, 400)));
+        assert_eq!(data.remove_segment("opener"), None);
+        assert_eq!(data.remove_segment("midgame"), Some(segment("midgame", 421, 900)));
+        data.metadata.nonstandard[SEGMENTS_KEY].as_array_mut().unwrap().clear();
+        data.add_segment(segment("all", 0, u64::MAX));
+        data.remove_segment("all");
+        assert!(data.metadata.nonstandard.is_empty());
+    }
+
+    #[test]
+    fn test_extract_segment() {
+        let mut data = GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(200, MoveLeft),
+                GameInputEvent::press(210, SoftDrop),
+                GameInputEvent::release(300, MoveLeft),
+                GameInputEvent::press(310, HardDrop),
+                GameInputEvent::release(311, HardDrop),
+                GameInputEvent::press(320, RotateRight),
+                GameInputEvent::release(400, RotateRight),
+                GameInputEvent::release(400, SoftDrop),
+            ],
+            metadata: GameReplayMetadata { version: "V0.17.22".to_string(), ..Default::default() },
+            ..Default::default()
+        };
+        data.add_segment(segment("middle", 250, 350));
+        data.set_annotation(310, "the drop");
+        data.set_annotation(360, "after the segment");
+
+        assert_eq!(data.extract_segment("end"), None);
+        let middle = data.extract_segment("middle").unwrap();
+
+        // MoveLeft and SoftDrop are held at the start, SoftDrop and RotateRight at the end
+        assert_eq!(
+            middle.inputs,
+            [
+                GameInputEvent::press(180, MoveLeft),
+                GameInputEvent::press(180, SoftDrop),
+                GameInputEvent::release(230, MoveLeft),
+                GameInputEvent::press(240, HardDrop),
+                GameInputEvent::release(241, HardDrop),
+                GameInputEvent::press(250, RotateRight),
+                GameInputEvent::release(280, RotateRight),
+                GameInputEvent::release(280, SoftDrop),
+            ]
+        );
+        assert_eq!(middle.validate(), []);
+        assert!(middle.segments().is_empty());
+        assert_eq!(middle.annotations().into_iter().collect::<Vec<_>>(), [(240, "the drop".to_string())]);
+    }
+}
@@ -0,0 +1,192 @@
+use serde_json::Value;
+
+use crate::types::*;
+
+/// Chooses which differences [`GameReplayData::semantically_eq`] overlooks.
+///
+/// By default, nothing is overlooked, so two replays are only equal if they hold the same data.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct SemanticEqOptions {
+    /// Ignores the metadata keys this crate doesn't know about,
+    /// i.e. the `nonstandard` maps of the metadata and the settings.
+    pub ignore_nonstandard: bool,
+    /// The largest difference between two non-integer numbers in the metadata that still
+    /// counts as equal. Integers, like the seed, always have to be equal.
+    ///
+    /// Defaults to 0, i.e. numbers have to be equal.
+    pub float_tolerance: f64,
+    /// Ignores the order of inputs on the same frame.
+    pub ignore_same_frame_order: bool,
+}
+
+impl GameReplayData {
+    /// Compares this replay to another replay, overlooking the differences chosen in the options.
+    ///
+    /// Unlike `==`, this ignores [`input_mode`][GameReplayData::input_mode], as it only affects
+    /// how the replay is serialized.
+    pub fn semantically_eq(&self, other: &GameReplayData, options: SemanticEqOptions) -> bool {
+        if !self.metadata.semantically_eq(&other.metadata, options) {
+            return false;
+        }
+
+        if options.ignore_same_frame_order {
+            let sorted = |data: &GameReplayData| {
+                let mut inputs = data.inputs.clone();
+                inputs.sort();
+                inputs
+            };
+
+            sorted(self) == sorted(other)
+        } else {
+            self.inputs == other.inputs
+        }
+    }
+}
+
+impl GameReplayMetadata {
+    /// Compares this metadata to other metadata, overlooking the differences chosen in the options.
+    ///
+    /// See [`GameReplayData::semantically_eq`] for more information.
+    pub fn semantically_eq(&self, other: &GameReplayMetadata, options: SemanticEqOptions) -> bool {
+        let (a, b) = (self.to_comparable(options), other.to_comparable(options));
+
+        values_eq(&a, &b, options.float_tolerance)
+    }
+
+    fn to_comparable(&self, options: SemanticEqOptions) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+
+        if options.ignore_nonstandard {
+            if let Some(map) = value.as_object_mut() {
+                for key in self.nonstandard.keys() {
+                    map.remove(key);
+                }
+            }
+
+            if let Some(map) = value.get_mut("setting").and_then(Value::as_object_mut) {
+                for key in self.setting.nonstandard.keys() {
+                    map.remove(key);
+                }
+            }
+        }
+
+        value
+    }
+}
+
+fn values_eq(a: &Value, b: &Value, tolerance: f64) -> bool {
+    match (a, b) {
+        // Integers like seeds are compared exactly, as they may not fit in a float
+        (Value::Number(x), Value::Number(y)) if x.is_f64() && y.is_f64() => {
+            let (x, y) = (x.as_f64().unwrap_or_default(), y.as_f64().unwrap_or_default());
+            x == y || (x - y).abs() <= tolerance
+        }
+        (Value::Number(x), Value::Number(y)) => x == y,
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| values_eq(x, y, tolerance))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, x)| y.get(key).is_some_and(|y| values_eq(x, y, tolerance)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Number;
+    use InputEventKey::*;
+
+    fn replay() -> GameReplayData {
+        GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(10, MoveLeft),
+                GameInputEvent::press(10, HardDrop),
+                GameInputEvent::release(20, MoveLeft),
+                GameInputEvent::release(20, HardDrop),
+            ],
+            metadata: GameReplayMetadata {
+                version: "V0.17.22".to_string(),
+                setting: PlayerSettings {
+                    ghost: Number::from_f64(0.3),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            input_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_strict() {
+        let options = SemanticEqOptions::default();
+
+        let mut other = replay();
+        assert!(replay().semantically_eq(&other, options));
+
+        other.input_mode = Some(InputParseMode::Relative);
+        assert!(replay().semantically_eq(&other, options));
+
+        other.metadata.player = "Nova".to_string();
+        assert!(!replay().semantically_eq(&other, options));
+    }
+
+    #[test]
+    fn test_ignore_nonstandard() {
+        let options = SemanticEqOptions { ignore_nonstandard: true, ..Default::default() };
+
+        let mut other = replay();
+        other.metadata.nonstandard.insert("extra".to_string(), true.into());
+        other.metadata.setting.nonstandard.insert("extra".to_string(), 1.into());
+
+        assert!(!replay().semantically_eq(&other, SemanticEqOptions::default()));
+        assert!(replay().semantically_eq(&other, options));
+        assert!(other.semantically_eq(&replay(), options));
+    }
+
+    #[test]
+    fn test_float_tolerance() {
+        let options = SemanticEqOptions { float_tolerance: 1e-9, ..Default::default() };
+
+        let mut other = replay();
+        other.metadata.setting.ghost = Number::from_f64(0.1 + 0.2);
+
+        assert!(!replay().semantically_eq(&other, SemanticEqOptions::default()));
+        assert!(replay().semantically_eq(&other, options));
+
+        other.metadata.setting.ghost = Number::from_f64(0.31);
+        assert!(!replay().semantically_eq(&other, options));
+    }
+
+    #[test]
+    fn test_float_tolerance_ignores_integers() {
+        let options = SemanticEqOptions { float_tolerance: 1.0, ..Default::default() };
+
+        let mut a = replay();
+        let mut b = replay();
+        a.metadata.seed = (1 << 53) + 1;
+        b.metadata.seed = 1 << 53;
+
+        // Both seeds are the same float, but they aren't the same seed
+        assert_eq!(a.metadata.seed as f64, b.metadata.seed as f64);
+        assert!(!a.semantically_eq(&b, options));
+        assert!(!a.semantically_eq(&b, SemanticEqOptions::default()));
+    }
+
+    #[test]
+    fn test_ignore_same_frame_order() {
+        let options = SemanticEqOptions { ignore_same_frame_order: true, ..Default::default() };
+
+        let mut other = replay();
+        other.inputs.swap(0, 1);
+
+        assert!(!replay().semantically_eq(&other, SemanticEqOptions::default()));
+        assert!(replay().semantically_eq(&other, options));
+
+        other.inputs[2].frame = 21;
+        assert!(!replay().semantically_eq(&other, options));
+    }
+}
@@ -1,20 +1,92 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::compression::compress_into;
+use crate::encoding;
 use crate::types::*;
-use base64::engine::general_purpose::STANDARD as B64;
-use base64::Engine;
-use miniz_oxide::deflate::compress_to_vec_zlib as compress;
+use crate::vlq;
+use crate::{ReplayMetadata, SerializeOptions};
 
 // TODO: Add tests
 
-impl GameReplayData {
+impl<M> GameReplayData<M> {
 
     /// Sort the inputs so that they are sorted by time.
     /// 
     /// This can be necessary sometimes as serializing the replay (e.g., into base64)
     /// requires that the inputs are sorted for the algorithm to work properly.
+    /// 
+    /// The sort is stable, so inputs on the same frame keep the order they were inserted in.  
+    /// For an order that doesn't depend on insertion order, see
+    /// [`sort_inputs_canonical`][GameReplayData::sort_inputs_canonical].
     pub fn sort_inputs(&mut self) {
         self.inputs.sort_by_key(|i| i.frame);
     }
 
+    /// Sort the inputs by time, ordering inputs on the same frame in a canonical way.
+    /// 
+    /// Inputs on the same frame are ordered with releases before presses, and then by key code,
+    /// which is the [`Ord`] of [`GameInputEvent`]. This way, two replays with the same inputs
+    /// serialize to the same bytes, no matter which order the inputs were inserted in.
+    /// 
+    /// Note that this also reorders a key that is pressed and released on the same frame
+    /// so that it is released before it is pressed, which leaves it held.
+    /// If that matters, use [`sort_inputs`][GameReplayData::sort_inputs] instead.
+    pub fn sort_inputs_canonical(&mut self) {
+        self.inputs.sort();
+    }
+
+    /// Sort the inputs canonically and remove duplicate inputs.
+    /// 
+    /// See [`sort_inputs_canonical`][GameReplayData::sort_inputs_canonical] for the order used.
+    /// Inputs that are identical to another input (same frame, kind and key) are removed.
+    pub fn canonicalize(&mut self) {
+        self.sort_inputs_canonical();
+        self.inputs.dedup();
+    }
+
+    /// Sets the input timing to serialize the replay with.
+    ///
+    /// The inputs are always stored with absolute frame numbers in memory, so this doesn't
+    /// change them. Instead, it sets [`input_mode`][GameReplayData::input_mode], which the
+    /// `serialize_to_*` functions use when no input parse mode is passed in explicitly.
+    ///
+    /// Note that the game decides how to parse the inputs based on the version string.
+    /// To make a replay playable in versions that use the target timing, consider also calling
+    /// [`set_version_for_mode`][GameReplayData::set_version_for_mode].
+    pub fn convert_timing(&mut self, target: InputParseMode) {
+        self.input_mode = Some(target);
+    }
+}
+
+impl GameReplayData {
+
+    /// Changes the version string, if needed, so that the game parses the inputs
+    /// using the given input parse mode.
+    ///
+    /// If the version already implies the given mode, it is kept as is.
+    /// Otherwise, it is set to `V0.17.22` for absolute timing, or `V0.17.21` for relative timing.
+    ///
+    /// Returns whether the version was changed.
+    pub fn set_version_for_mode(&mut self, mode: InputParseMode) -> bool {
+        if InputParseMode::try_infer_from_version(&self.metadata.version) == Some(mode) {
+            return false;
+        }
+
+        let start = InputParseMode::ABSOLUTE_TIMING_START;
+
+        self.metadata.version = match mode {
+            InputParseMode::Absolute => format!("V{start}"),
+            InputParseMode::Relative => format!("V{}.{}.{}", start.major, start.minor, start.patch - 1),
+        };
+
+        true
+    }
+}
+
+impl<M: ReplayMetadata> GameReplayData<M> {
+
     /// Serialize into a raw, uncompressed byte array.
     /// 
     /// This function serializes the GameReplayData into a raw, uncompressed byte array.
@@ -25,52 +97,88 @@ impl GameReplayData {
     /// For serializing the data into a copiable text/base64 format, use
     /// [`serialize_to_base64`][GameReplayData::serialize_to_base64] instead.
     /// 
+    /// `input_mode` is an optional argument used to specify how the inputs should be timed.  
+    /// If it's [`None`], [`input_mode`][GameReplayData::input_mode] is used, and if that is also
+    /// [`None`], it is inferred from the game version.
+    /// 
     /// Note that the serialization algorithm requires that the inputs in the replay are sorted to time.  
     /// If this isn't always the case, consider calling [`sort_inputs`][GameReplayData::sort_inputs] before calling this function,
     /// otherwise an [`UnsortedInput`][ReplaySerializeError::UnsortedInput] error will be returned.
     pub fn serialize_to_raw(&self, input_mode: Option<InputParseMode>) -> Result<Vec<u8>, ReplaySerializeError> {
-        let input_mode = match input_mode
-            .or_else(|| InputParseMode::try_infer_from_version(&self.metadata.version))
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_raw_with(&options)
+    }
+
+    /// Serialize into a raw, uncompressed byte array, with the given options.
+    ///
+    /// See [`serialize_to_raw`][GameReplayData::serialize_to_raw] and [`SerializeOptions`]
+    /// for more information.
+    pub fn serialize_to_raw_with(&self, options: &SerializeOptions) -> Result<Vec<u8>, ReplaySerializeError> {
+        let mut buffer = Vec::with_capacity(self.estimated_raw_size());
+        self.serialize_raw_into(&mut buffer, options)?;
+
+        Ok(buffer)
+    }
+
+    /// Serialize into a raw, uncompressed byte array, reusing the given buffer.
+    ///
+    /// The buffer is cleared before the replay is written into it, so that one buffer can be
+    /// reused for serializing many replays without allocating a new one every time.
+    /// Returns the number of bytes written, which is the new length of the buffer.
+    ///
+    /// See [`serialize_to_raw`][GameReplayData::serialize_to_raw] and [`SerializeOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "serialize_to_raw",
+            skip_all,
+            fields(inputs = self.inputs.len(), bytes = tracing::field::Empty)
+        )
+    )]
+    pub fn serialize_raw_into(
+        &self,
+        buffer: &mut Vec<u8>,
+        options: &SerializeOptions,
+    ) -> Result<usize, ReplaySerializeError> {
+        buffer.clear();
+
+        let version = self.metadata.version();
+        let input_mode = match options
+            .input_mode
+            .or(self.input_mode)
+            .or_else(|| version.and_then(InputParseMode::try_infer_from_version))
         {
             Some(mode) => mode,
+            // Without inputs, it doesn't matter how they're timed
+            None if self.inputs.is_empty() => InputParseMode::Absolute,
             None => {
                 return Err(ReplaySerializeError::UnknownInputParseMode(
-                    self.metadata.version.clone(),
+                    String::from(version.unwrap_or_default()),
                 ))
             }
         };
 
-        let json = serde_json::to_string(&self.metadata)?;
-
-        let mut buffer = Vec::from(json.as_bytes());
-
-        let inputs = &self.inputs;
-
-        if let Some(u) = get_first_unsorted(&inputs) {
-            return Err(u);
-        }
-
-        let mut bytes = Vec::with_capacity(inputs.len() * 2);
-
-        let mut prev_time = 0;
-        for input in inputs {
-            let key = u8::from(input.key) | (u8::from(input.kind) << 5);
+        self.metadata.write_bytes(buffer, options)?;
 
-            let time = match input_mode {
-                InputParseMode::Relative => input.frame - prev_time,
-                InputParseMode::Absolute => input.frame,
-            };
+        buffer.push(10);
+        encode_inputs(buffer, &self.inputs, input_mode)?;
 
-            prev_time = input.frame;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("bytes", buffer.len());
 
-            bytes.push(key as u64);
-            bytes.push(time);
-        }
-        
-        buffer.push(10);
-        append_vlqs(&mut buffer, &bytes);
+        Ok(buffer.len())
+    }
 
-        Ok(buffer)
+    /// Estimates the size of the raw, uncompressed form of the replay in bytes,
+    /// e.g. for reserving a buffer for [`serialize_raw_into`][GameReplayData::serialize_raw_into].
+    ///
+    /// The estimate is usually a bit larger than the actual size, but it may also be smaller.
+    pub fn estimated_raw_size(&self) -> usize {
+        // Estimation: the key code takes 1 byte, and the time usually takes 1 or 2
+        self.metadata.estimated_len() + 1 + self.inputs.len() * 3
     }
     
     /// Serialize into a compressed byte array used by the game.
@@ -89,9 +197,51 @@ impl GameReplayData {
         &self,
         input_mode: Option<InputParseMode>,
     ) -> Result<Vec<u8>, ReplaySerializeError> {
-        let raw_bytes = self.serialize_to_raw(input_mode)?;
-    
-        Ok(compress(&raw_bytes, 10))
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_compressed_with(&options)
+    }
+
+    /// Serialize into a compressed byte array used by the game, with the given options.
+    ///
+    /// See [`serialize_to_compressed`][GameReplayData::serialize_to_compressed] and [`SerializeOptions`]
+    /// for more information.
+    pub fn serialize_to_compressed_with(&self, options: &SerializeOptions) -> Result<Vec<u8>, ReplaySerializeError> {
+        let mut buffer = Vec::new();
+        self.serialize_compressed_into(&mut buffer, options)?;
+
+        Ok(buffer)
+    }
+
+    /// Serialize into a compressed byte array used by the game, reusing the given buffer.
+    ///
+    /// The buffer is cleared before the replay is written into it, so that one buffer can be
+    /// reused for serializing many replays without allocating a new one every time.
+    /// Returns the number of bytes written, which is the new length of the buffer.
+    ///
+    /// See [`serialize_to_compressed`][GameReplayData::serialize_to_compressed] and [`SerializeOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "serialize_to_compressed",
+            skip_all,
+            fields(inputs = self.inputs.len(), bytes = tracing::field::Empty)
+        )
+    )]
+    pub fn serialize_compressed_into(
+        &self,
+        buffer: &mut Vec<u8>,
+        options: &SerializeOptions,
+    ) -> Result<usize, ReplaySerializeError> {
+        let raw_bytes = self.serialize_to_raw_with(options)?;
+        compress_into(buffer, &raw_bytes, 6);
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("bytes", buffer.len());
+
+        Ok(buffer.len())
     }
     
     /// Serialize into a copiable text-based base64 format.
@@ -109,13 +259,77 @@ impl GameReplayData {
         &self,
         input_mode: Option<InputParseMode>,
     ) -> Result<String, ReplaySerializeError> {
-        let bytes = self.serialize_to_compressed(input_mode)?;
-    
-        Ok(B64.encode(&bytes))
+        let mut options = SerializeOptions::new();
+        options.input_mode = input_mode;
+
+        self.serialize_to_base64_with(&options)
+    }
+
+    /// Serialize into a copiable text-based base64 format, with the given options.
+    ///
+    /// See [`serialize_to_base64`][GameReplayData::serialize_to_base64] and [`SerializeOptions`]
+    /// for more information.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "serialize_to_base64",
+            skip_all,
+            fields(inputs = self.inputs.len(), bytes = tracing::field::Empty)
+        )
+    )]
+    pub fn serialize_to_base64_with(&self, options: &SerializeOptions) -> Result<String, ReplaySerializeError> {
+        let bytes = self.serialize_to_compressed_with(options)?;
+        let string = encoding::encode(&bytes, options);
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("bytes", string.len());
+
+        Ok(string)
     }
 }
 
-fn get_first_unsorted(inputs: &[GameInputEvent]) -> Option<ReplaySerializeError> {
+/// Appends the VLQ-encoded inputs to the buffer.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "encode_inputs",
+        level = "debug",
+        skip_all,
+        fields(events = inputs.len(), ?input_mode)
+    )
+)]
+pub(crate) fn encode_inputs(
+    buffer: &mut Vec<u8>,
+    inputs: &[GameInputEvent],
+    input_mode: InputParseMode,
+) -> Result<(), ReplaySerializeError> {
+    if let Some(u) = get_first_unsorted(inputs) {
+        return Err(u);
+    }
+    if let Some(invalid) = get_first_invalid_key(inputs) {
+        return Err(invalid);
+    }
+
+    // Estimation: the key code takes 1 byte, and the time usually takes 1 or 2
+    buffer.reserve(inputs.len() * 3);
+
+    let mut prev_frame = 0;
+    for input in inputs {
+        let (time, key) = input.to_packed(input_mode, prev_frame);
+
+        prev_frame = input.frame;
+
+        vlq::encode_value_into(buffer, time);
+        vlq::encode_value_into(buffer, key);
+    }
+
+    Ok(())
+}
+
+/// Finds the first input on an earlier frame than the one before it.
+///
+/// Only the frames matter, as inputs on the same frame can be serialized in any order.
+pub(crate) fn get_first_unsorted(inputs: &[GameInputEvent]) -> Option<ReplaySerializeError> {
     for (index, window) in inputs.windows(2).enumerate() {
         let prev = window[0];
         let cur = window[1];
@@ -132,52 +346,19 @@ fn get_first_unsorted(inputs: &[GameInputEvent]) -> Option<ReplaySerializeError>
     None
 }
 
-fn _create_vlqs(values: &[u64]) -> Vec<u8> {
-    // Estimation: most values need around 2 bytes
-    let mut vlqs = Vec::with_capacity(values.len() * 2);
-
-    // u64 is up to 9 VLQ bytes
-    let mut vlq = Vec::with_capacity(9);
-    for &value in values {
-        vlq.clear();
-        let mut value = value;
-
-        vlq.push((value & 0x7F) as u8);
-        value >>= 7;
-
-        while value > 0 {
-            vlq.push(((value & 0x7F) | 0x80) as u8);
-            value >>= 7;
+/// Finds the first input with an [`Other`][InputEventKey::Other] key that doesn't fit
+/// in the codes left for other keys.
+fn get_first_invalid_key(inputs: &[GameInputEvent]) -> Option<ReplaySerializeError> {
+    inputs.iter().enumerate().find_map(|(index, input)| match input.key {
+        InputEventKey::Other(code) if !(21..=31).contains(&code) => {
+            Some(ReplaySerializeError::InvalidKeyCode { index, code })
         }
-
-        vlq.reverse();
-        vlqs.append(&mut vlq);
-    }
-
-    vlqs
+        _ => None,
+    })
 }
 
-fn append_vlqs(buffer: &mut Vec<u8>, values: &[u64]) {
-    // Estimation: most values need around 2 bytes
-    buffer.reserve(values.len() * 2 + 1);
-    
-    // u64 is up to 9 VLQ bytes
-    let mut vlq = Vec::with_capacity(9);
-    for &value in values {
-        vlq.clear();
-        let mut value = value;
-
-        vlq.push((value & 0x7F) as u8);
-        value >>= 7;
-
-        while value > 0 {
-            vlq.push(((value & 0x7F) | 0x80) as u8);
-            value >>= 7;
-        }
-
-        vlq.reverse();
-        buffer.append(&mut vlq);
-    }
+fn _create_vlqs(values: &[u64]) -> Vec<u8> {
+    vlq::encode(values)
 }
 
 #[cfg(test)]
@@ -233,11 +414,173 @@ mod tests {
 
         for (expected, values) in cases {
             let mut vec = Vec::new();
-            append_vlqs(&mut vec, &values);
+            vlq::encode_into(&mut vec, &values);
             assert_eq!(vec, expected);
         }
     }
 
+    #[test]
+    fn test_convert_timing() {
+        use InputEventKey::*;
+
+        let mut data = GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(62, HardDrop),
+                GameInputEvent::release(62, HardDrop),
+                GameInputEvent::press(200, MoveLeft),
+                GameInputEvent::release(215, MoveLeft),
+            ],
+            metadata: GameReplayMetadata {
+                version: "V0.17.21".to_string(),
+                ..Default::default()
+            },
+            input_mode: None,
+        };
+
+        let relative = data.serialize_to_raw(None).unwrap();
+
+        data.convert_timing(InputParseMode::Absolute);
+        assert_eq!(data.metadata.version, "V0.17.21");
+        assert_eq!(
+            data.serialize_to_raw(None).unwrap(),
+            data.serialize_to_raw(Some(InputParseMode::Absolute)).unwrap()
+        );
+
+        assert!(data.set_version_for_mode(InputParseMode::Absolute));
+        assert!(!data.set_version_for_mode(InputParseMode::Absolute));
+        assert_eq!(data.metadata.version, "V0.17.22");
+
+        let absolute = data.serialize_to_base64(None).unwrap();
+        let reparsed = GameReplayData::try_from_base64(&absolute, None).unwrap();
+
+        assert_eq!(reparsed.inputs, data.inputs);
+        assert_eq!(reparsed.metadata, data.metadata);
+        assert_ne!(data.serialize_to_raw(None).unwrap(), relative);
+
+        data.convert_timing(InputParseMode::Relative);
+        assert!(data.set_version_for_mode(InputParseMode::Relative));
+        assert_eq!(data.metadata.version, "V0.17.21");
+        assert_eq!(data.serialize_to_raw(None).unwrap(), relative);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        use InputEventKey::*;
+
+        let inputs = [
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::release(20, MoveLeft),
+            GameInputEvent::press(20, RotateLeft),
+            GameInputEvent::press(20, Hold),
+            GameInputEvent::release(30, RotateLeft),
+            GameInputEvent::release(30, Hold),
+        ];
+
+        let permutations = [
+            [0, 1, 2, 3, 4, 5],
+            [5, 4, 3, 2, 1, 0],
+            [3, 2, 1, 0, 5, 4],
+            [2, 0, 4, 1, 5, 3],
+        ];
+
+        let mut outputs = permutations.iter().map(|permutation| {
+            let mut data = GameReplayData {
+                inputs: permutation.iter().map(|&i| inputs[i]).collect(),
+                metadata: GameReplayMetadata {
+                    version: "V0.17.22".to_string(),
+                    ..Default::default()
+                },
+                input_mode: None,
+            };
+
+            // Duplicates should be removed
+            data.inputs.push(inputs[permutation[0]]);
+            data.canonicalize();
+
+            assert_eq!(data.inputs.len(), inputs.len());
+            assert_eq!(data.inputs[1..4], [inputs[1], inputs[2], inputs[3]]);
+
+            data.serialize_to_raw(None).unwrap()
+        });
+
+        let first = outputs.next().unwrap();
+        for output in outputs {
+            assert_eq!(output, first);
+        }
+    }
+
+    #[test]
+    fn test_input_ordering() {
+        use core::cmp::Ordering;
+        use InputEventKey::*;
+
+        let sorted = [
+            GameInputEvent::press(10, MoveLeft),
+            GameInputEvent::release(20, MoveLeft),
+            GameInputEvent::release(20, HardDrop),
+            GameInputEvent::press(20, RotateLeft),
+            GameInputEvent::press(20, Hold),
+            GameInputEvent::release(30, RotateLeft),
+            GameInputEvent::release(30, Hold),
+            GameInputEvent::press(u64::MAX, Function1),
+        ];
+
+        // Rotations of a reversed list, so every input is moved somewhere else
+        for shift in 0..sorted.len() {
+            let mut inputs: Vec<_> = sorted.iter().rev().copied().collect();
+            inputs.rotate_left(shift);
+
+            let mut canonical = GameReplayData {
+                inputs: inputs.clone(),
+                metadata: GameReplayMetadata { version: "V0.17.22".to_string(), ..Default::default() },
+                input_mode: None,
+            };
+            canonical.sort_inputs_canonical();
+
+            inputs.sort();
+            assert_eq!(inputs, sorted);
+            assert_eq!(inputs, canonical.inputs);
+            assert!(canonical.serialize_to_raw(None).is_ok());
+        }
+
+        assert_eq!(sorted.binary_search(&GameInputEvent::press(20, Hold)), Ok(4));
+        assert_eq!(sorted.binary_search(&GameInputEvent::press(20, HardDrop)), Err(4));
+        assert!(GameInputEvent::release(5, Hold) > GameInputEvent::press(4, Hold));
+
+        // Only equal events compare as equal, even with a code that a known key has
+        let other = GameInputEvent::press(20, Other(8));
+        assert_ne!(other, GameInputEvent::press(20, Hold));
+        assert_eq!(other.cmp(&GameInputEvent::press(20, Hold)), Ordering::Greater);
+        assert_eq!(other.cmp(&GameInputEvent::press(20, RightDrop)), Ordering::Less);
+        assert_eq!(other.cmp(&GameInputEvent::press(20, Other(8))), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_invalid_other_keys() {
+        use InputEventKey::*;
+
+        let mut data = GameReplayData {
+            inputs: vec![GameInputEvent::press(10, Other(21)), GameInputEvent::release(20, Other(31))],
+            metadata: GameReplayMetadata { version: "V0.17.22".to_string(), ..Default::default() },
+            input_mode: None,
+        };
+        assert!(data.serialize_to_raw(None).is_ok());
+
+        // Past the key bits, the code would set the kind bit
+        data.inputs.push(GameInputEvent::press(30, Other(40)));
+        assert!(matches!(
+            data.serialize_to_raw(None),
+            Err(ReplaySerializeError::InvalidKeyCode { index: 2, code: 40 })
+        ));
+
+        // A code of the game's own keys would be written as RotateRight
+        data.inputs[2] = GameInputEvent::press(30, Other(3));
+        assert!(matches!(
+            data.serialize_to_raw(None),
+            Err(ReplaySerializeError::InvalidKeyCode { index: 2, code: 3 })
+        ));
+    }
+
     #[test]
     fn test_input_slice_parse() {
         use crate::deserialize::parse_input_slice;
@@ -0,0 +1,287 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::*;
+
+/// The number of pieces in the game, i.e. the length of [`face`][PlayerSettings::face]
+/// and [`skin`][PlayerSettings::skin].
+pub(crate) const PIECE_COUNT: usize = 29;
+
+/// A setting with a value the game doesn't allow, found by [`PlayerSettings::validate`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "issue")]
+pub enum SettingsIssue {
+    /// A setting has a value outside the range the game allows.
+    OutOfRange {
+        /// The name of the setting, as written in the metadata JSON.
+        field: String,
+        /// The value of the setting.
+        value: f64,
+        /// The lowest value the game allows.
+        min: f64,
+        /// The highest value the game allows.
+        max: f64,
+    },
+
+    /// A per-piece setting doesn't have one value for each piece.
+    WrongLength {
+        /// The name of the setting, as written in the metadata JSON.
+        field: String,
+        /// The number of values.
+        len: usize,
+        /// The number of pieces.
+        expected: usize,
+    },
+}
+
+impl fmt::Display for SettingsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsIssue::OutOfRange { field, value, min, max } => write!(
+                f,
+                "Setting '{field}' is {value}, which is outside the range {min} to {max}"
+            ),
+            SettingsIssue::WrongLength { field, len, expected } => write!(
+                f,
+                "Setting '{field}' has {len} values, but there are {expected} pieces"
+            ),
+        }
+    }
+}
+
+impl PlayerSettings {
+    /// The settings this crate assumes a new player of the game starts with.
+    ///
+    /// They aren't taken from the game's source, so they may not be the game's actual defaults;
+    /// use [`diff_from`][PlayerSettings::diff_from] with other defaults if they're known.
+    /// Only some of the settings stored in replays are filled in; the rest are [`None`].
+    pub fn assumed_defaults() -> PlayerSettings {
+        let opacity = |value| serde_json::Number::from_f64(value);
+
+        PlayerSettings {
+            atk_fx: Some(2),
+            clear_fx: Some(2),
+            drop_fx: Some(2),
+            lock_fx: Some(2),
+            move_fx: Some(2),
+            shake_fx: Some(2),
+            splash_fx: Some(2),
+            das: Some(10),
+            arr: Some(2),
+            sddas: Some(0),
+            sdarr: Some(2),
+            dascut: Some(0),
+            dropcut: Some(0),
+            irs: Some(true),
+            ihs: Some(true),
+            ims: Some(true),
+            rs: Some("TRS".to_string()),
+            bag_line: Some(false),
+            block: Some(true),
+            center: Some(1.into()),
+            face: Some(vec![0; PIECE_COUNT]),
+            ghost: opacity(0.3),
+            grid: opacity(0.16),
+            high_cam: Some(true),
+            next_pos: Some(true),
+            score: Some(true),
+            smooth: Some(true),
+            swap: Some(true),
+            text: Some(true),
+            warn: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Checks the settings against the ranges the game allows, returning every setting outside them.
+    ///
+    /// The ranges are the ones documented on the fields. Settings that aren't set aren't checked.
+    pub fn validate(&self) -> Vec<SettingsIssue> {
+        let integers = [
+            ("atkFX", self.atk_fx, 5),
+            ("clearFX", self.clear_fx, 5),
+            ("dropFX", self.drop_fx, 5),
+            ("lockFX", self.lock_fx, 5),
+            ("moveFX", self.move_fx, 5),
+            ("shakeFX", self.shake_fx, 5),
+            ("splashFX", self.splash_fx, 5),
+            ("das", self.das, 20),
+            ("arr", self.arr, 15),
+            ("sddas", self.sddas, 10),
+            ("sdarr", self.sdarr, 4),
+            ("dascut", self.dascut, 20),
+            ("irscut", self.irscut, 20),
+            ("dropcut", self.dropcut, 10),
+        ];
+
+        let opacities = [
+            ("center", self.center_f64()),
+            ("ghost", self.ghost_f64()),
+            ("grid", self.grid_f64()),
+        ];
+
+        let integers = integers
+            .into_iter()
+            .filter_map(|(field, value, max)| Some((field, value? as f64, 0.0, max as f64)));
+        let opacities = opacities
+            .into_iter()
+            .filter_map(|(field, value)| Some((field, value?, 0.0, 1.0)));
+
+        let out_of_range = integers
+            .chain(opacities)
+            .filter(|&(_, value, min, max)| !(min..=max).contains(&value))
+            .map(|(field, value, min, max)| SettingsIssue::OutOfRange {
+                field: field.to_string(),
+                value,
+                min,
+                max,
+            });
+
+        let wrong_length = [("face", &self.face), ("skin", &self.skin)]
+            .into_iter()
+            .filter_map(|(field, values)| Some((field, values.as_ref()?.len())))
+            .filter(|&(_, len)| len != PIECE_COUNT)
+            .map(|(field, len)| SettingsIssue::WrongLength {
+                field: field.to_string(),
+                len,
+                expected: PIECE_COUNT,
+            });
+
+        out_of_range.chain(wrong_length).collect()
+    }
+
+    /// The settings that differ from the [assumed defaults][PlayerSettings::assumed_defaults],
+    /// see [`diff_from`][PlayerSettings::diff_from].
+    pub fn diff_from_default(&self) -> Vec<(String, Value)> {
+        self.diff_from(&Self::assumed_defaults())
+    }
+
+    /// The settings that differ from the defaults, as their names in the metadata JSON
+    /// and their values, e.g. `("das", 4)`, sorted by name.
+    ///
+    /// Settings that aren't set here or in the defaults are left out.
+    /// Numbers are compared by value, so `1` and `1.0` are the same.
+    pub fn diff_from(&self, defaults: &PlayerSettings) -> Vec<(String, Value)> {
+        let (Ok(Value::Object(settings)), Ok(Value::Object(defaults))) =
+            (serde_json::to_value(self), serde_json::to_value(defaults))
+        else {
+            return Vec::new();
+        };
+
+        let mut diff: Vec<_> = settings
+            .into_iter()
+            .filter(|(field, value)| match (value, defaults.get(field)) {
+                (Value::Null, _) | (_, None | Some(Value::Null)) => false,
+                (Value::Number(a), Some(Value::Number(b))) => a.as_f64() != b.as_f64(),
+                (value, Some(default)) => value != default,
+            })
+            .collect();
+        diff.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_settings() {
+        assert_eq!(PlayerSettings::default().validate(), []);
+        assert_eq!(PlayerSettings::assumed_defaults().validate(), []);
+
+        let out_of_range = |field: &str, value: f64, max: f64| SettingsIssue::OutOfRange {
+            field: field.to_string(),
+            value,
+            min: 0.0,
+            max,
+        };
+
+        let settings = PlayerSettings {
+            atk_fx: Some(6),
+            clear_fx: Some(6),
+            drop_fx: Some(6),
+            lock_fx: Some(6),
+            move_fx: Some(6),
+            shake_fx: Some(6),
+            splash_fx: Some(6),
+            das: Some(21),
+            arr: Some(16),
+            sddas: Some(11),
+            sdarr: Some(5),
+            dascut: Some(21),
+            irscut: Some(21),
+            dropcut: Some(11),
+            center: serde_json::Number::from_f64(1.5),
+            ghost: serde_json::Number::from_f64(-0.5),
+            grid: Some(2.into()),
+            face: Some(vec![0; 28]),
+            skin: Some(vec![1; 30]),
+            ..PlayerSettings::assumed_defaults()
+        };
+
+        assert_eq!(
+            settings.validate(),
+            [
+                out_of_range("atkFX", 6.0, 5.0),
+                out_of_range("clearFX", 6.0, 5.0),
+                out_of_range("dropFX", 6.0, 5.0),
+                out_of_range("lockFX", 6.0, 5.0),
+                out_of_range("moveFX", 6.0, 5.0),
+                out_of_range("shakeFX", 6.0, 5.0),
+                out_of_range("splashFX", 6.0, 5.0),
+                out_of_range("das", 21.0, 20.0),
+                out_of_range("arr", 16.0, 15.0),
+                out_of_range("sddas", 11.0, 10.0),
+                out_of_range("sdarr", 5.0, 4.0),
+                out_of_range("dascut", 21.0, 20.0),
+                out_of_range("irscut", 21.0, 20.0),
+                out_of_range("dropcut", 11.0, 10.0),
+                out_of_range("center", 1.5, 1.0),
+                out_of_range("ghost", -0.5, 1.0),
+                out_of_range("grid", 2.0, 1.0),
+                SettingsIssue::WrongLength { field: "face".to_string(), len: 28, expected: 29 },
+                SettingsIssue::WrongLength { field: "skin".to_string(), len: 30, expected: 29 },
+            ]
+        );
+
+        let edges = PlayerSettings {
+            das: Some(20),
+            arr: Some(0),
+            ghost: Some(1.into()),
+            ..Default::default()
+        };
+        assert_eq!(edges.validate(), []);
+        let message = "Setting 'skin' has 30 values, but there are 29 pieces";
+        assert_eq!(settings.validate()[18].to_string(), message);
+    }
+
+    #[test]
+    fn test_diff_from_default() {
+        assert_eq!(PlayerSettings::default().diff_from_default(), []);
+        assert_eq!(PlayerSettings::assumed_defaults().diff_from_default(), []);
+
+        let mut settings = PlayerSettings::assumed_defaults();
+        settings.das = Some(4);
+        settings.arr = Some(0);
+        settings.center = serde_json::Number::from_f64(1.0);
+        settings.irscut = Some(3);
+        settings.nonstandard.insert("newSetting".to_string(), json!(true));
+
+        assert_eq!(
+            settings.diff_from_default(),
+            [("arr".to_string(), json!(0)), ("das".to_string(), json!(4))]
+        );
+
+        // Against other defaults, only the settings both have are compared
+        let defaults = PlayerSettings { das: Some(4), irscut: Some(0), ..Default::default() };
+        assert_eq!(settings.diff_from(&defaults), [("irscut".to_string(), json!(3))]);
+    }
+}
@@ -0,0 +1,103 @@
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str;
+
+use crate::lua::LuaParser;
+use crate::types::*;
+
+/// An error from parsing a settings file with [`PlayerSettings::try_from_settings_file`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SettingsParseError {
+    /// The file isn't valid UTF-8.
+    InvalidUtf8,
+    /// The file is neither JSON nor a Lua table.
+    ///
+    /// Contains the message of the JSON parser.
+    InvalidJson(String),
+    /// The file is a Lua table, i.e. it starts with `return`, but it's malformed.
+    InvalidLua {
+        /// The byte offset in the file where the table stopped making sense.
+        offset: usize,
+        /// A description of what was expected there.
+        expected: &'static str,
+    },
+    /// The file isn't a table of settings, or one of the settings has the wrong type,
+    /// e.g. `das` is a string.
+    ///
+    /// Contains the message of the settings deserializer.
+    InvalidSettings(String),
+}
+
+impl fmt::Display for SettingsParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsParseError::InvalidUtf8 => write!(f, "The settings file isn't valid UTF-8"),
+            SettingsParseError::InvalidJson(message) => {
+                write!(f, "The settings file is neither JSON nor a Lua table: {message}")
+            }
+            SettingsParseError::InvalidLua { offset, expected } => {
+                let message = "The Lua table in the settings file is malformed";
+                write!(f, "{message} at byte {offset}: expected {expected}")
+            }
+            SettingsParseError::InvalidSettings(message) => write!(f, "The settings are invalid: {message}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SettingsParseError {}
+
+impl PlayerSettings {
+    /// Parses a file of the player's settings, written either as JSON or as a Lua table
+    /// (`return{das=10,...}`), with the same keys as in the metadata of replays.
+    ///
+    /// This is best-effort: it hasn't been checked against settings files saved by the game,
+    /// so where the game keeps the file and which of the two forms it writes aren't assumed.
+    ///
+    /// Settings that replays don't record, e.g. the volume and the language, are dropped rather
+    /// than kept as [`nonstandard`][PlayerSettings::nonstandard], so that the result can be
+    /// compared with the settings of a replay with [`diff`][PlayerSettings::diff].
+    pub fn try_from_settings_file(bytes: &[u8]) -> Result<PlayerSettings, SettingsParseError> {
+        let text = str::from_utf8(bytes).map_err(|_| SettingsParseError::InvalidUtf8)?;
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+        let value = match text.trim_start().strip_prefix("return") {
+            Some(_) => LuaParser::parse(text)?,
+            None => match serde_json::from_str(text) {
+                Ok(value) => value,
+                // A bare table, without the `return`
+                Err(error) => {
+                    LuaParser::parse(text).map_err(|_| SettingsParseError::InvalidJson(error.to_string()))?
+                }
+            },
+        };
+
+        let mut settings = serde_json::from_value::<PlayerSettings>(value)
+            .map_err(|error| SettingsParseError::InvalidSettings(error.to_string()))?;
+        settings.nonstandard.clear();
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_file_errors() {
+        use SettingsParseError::*;
+
+        let parse = PlayerSettings::try_from_settings_file;
+        assert_eq!(parse(b"\xff"), Err(InvalidUtf8));
+        assert!(matches!(parse(b"{\"das\":"), Err(InvalidJson(_))));
+        assert!(matches!(parse(b"return{das="), Err(InvalidLua { offset: 11, .. })));
+        assert!(matches!(parse(b"return{das='fast'}"), Err(InvalidSettings(_))));
+        assert!(matches!(parse(b"[1, 2]"), Err(InvalidSettings(_))));
+
+        // Bare tables are read too, and settings replays don't record are dropped
+        let settings = parse(b"{das=4,lang='en'}").unwrap();
+        assert_eq!(settings, PlayerSettings { das: Some(4), ..Default::default() });
+        assert_eq!(parse(b"\xef\xbb\xbf{\"RS\": \"SRS\"}").unwrap().rs.as_deref(), Some("SRS"));
+    }
+}
@@ -0,0 +1,114 @@
+//! How big the parts of a replay are, and how well it compresses.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::compression::{compress, decompress};
+use crate::detect::reject_non_replay;
+use crate::types::*;
+use crate::{vlq, ReplayEncoding, SplitReplay};
+
+/// The sizes of the parts of a replay in bytes, and how well it compresses.
+///
+/// The raw replay is the metadata JSON, a linefeed, and the VLQ-encoded input data, so
+/// `metadata_bytes + 1 + input_bytes` is its length, as returned by
+/// [`raw_bytes`][SizeReport::raw_bytes].
+///
+/// Created by [`GameReplayData::size_report`] or, for the replay as it was saved,
+/// [`SizeReport::from_compressed`].
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeReport {
+    /// The length of the metadata JSON.
+    pub metadata_bytes: usize,
+    /// The length of the VLQ-encoded input data.
+    pub input_bytes: usize,
+    /// The number of input events.
+    pub events: usize,
+    /// The average length of an input event in the input data, or 0 without inputs.
+    pub bytes_per_event: f64,
+    /// The length of the compressed replay, as the game saves it.
+    pub compressed_bytes: usize,
+    /// The length of the compressed replay divided by the length of the raw replay.
+    pub compression_ratio: f64,
+}
+
+impl SizeReport {
+    /// Measures a compressed replay, e.g. the contents of a `.rep` file, as it is.
+    ///
+    /// Unlike [`GameReplayData::size_report`], nothing is re-encoded, so this measures the
+    /// compression the replay was actually saved with. Neither the metadata nor the inputs
+    /// are parsed, only the input data is split into values to count the events.
+    pub fn from_compressed(data: &[u8]) -> Result<SizeReport, ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let raw = decompress(data, usize::MAX)?;
+        let split = SplitReplay::from_raw(&raw)?;
+
+        let mut values = 0;
+        for value in vlq::decode_iter(split.input_bytes) {
+            value?;
+            values += 1;
+        }
+
+        // A trailing time without a key code isn't an event
+        Ok(SizeReport::new(split, values / 2, data.len()))
+    }
+
+    fn new(split: SplitReplay, events: usize, compressed_bytes: usize) -> SizeReport {
+        let mut report = SizeReport {
+            metadata_bytes: split.metadata_bytes.len(),
+            input_bytes: split.input_bytes.len(),
+            events,
+            bytes_per_event: 0.0,
+            compressed_bytes,
+            compression_ratio: 0.0,
+        };
+
+        if events > 0 {
+            report.bytes_per_event = report.input_bytes as f64 / events as f64;
+        }
+        report.compression_ratio = compressed_bytes as f64 / report.raw_bytes() as f64;
+
+        report
+    }
+
+    /// The length of the raw replay: the metadata JSON, the linefeed after it, and the input data.
+    pub fn raw_bytes(&self) -> usize {
+        self.metadata_bytes + 1 + self.input_bytes
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Metadata:   {} bytes", self.metadata_bytes)?;
+        writeln!(
+            f,
+            "Input data: {} bytes ({} events, {:.2} bytes per event)",
+            self.input_bytes, self.events, self.bytes_per_event
+        )?;
+        writeln!(f, "Raw:        {} bytes", self.raw_bytes())?;
+        writeln!(
+            f,
+            "Compressed: {} bytes ({:.1}% of the raw size)",
+            self.compressed_bytes,
+            self.compression_ratio * 100.0
+        )
+    }
+}
+
+impl GameReplayData {
+    /// Measures the parts of the replay as it would be serialized, and how well it compresses.
+    ///
+    /// The replay is serialized and compressed like
+    /// [`serialize_to_compressed`][GameReplayData::serialize_to_compressed] does, so this fails
+    /// in the same cases. For the sizes of a replay as it was saved, which may have been
+    /// compressed differently, see [`SizeReport::from_compressed`].
+    pub fn size_report(&self) -> Result<SizeReport, ReplaySerializeError> {
+        let raw = self.serialize_to_raw(None)?;
+        let compressed = compress(&raw, 6);
+        let split = SplitReplay::from_raw(&raw).expect("serialized replays have a separator");
+
+        Ok(SizeReport::new(split, self.inputs.len(), compressed.len()))
+    }
+}
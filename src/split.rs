@@ -0,0 +1,106 @@
+use alloc::vec::Vec;
+
+use crate::types::*;
+use crate::ParseOptions;
+
+/// A raw, uncompressed replay split into its metadata and input data, without parsing either.
+///
+/// This is useful for tools that only change one of the two parts,
+/// e.g. fixing the player name in the metadata JSON without decoding the inputs.
+///
+/// ```
+/// use techmino_replay_toolkit::SplitReplay;
+///
+/// let raw = b"{\"player\":\"Nova\"}\n\x0a\x06";
+/// let split = SplitReplay::from_raw(raw).unwrap();
+/// assert_eq!(split.metadata_bytes, b"{\"player\":\"Nova\"}");
+/// assert_eq!(split.input_bytes, b"\x0a\x06");
+///
+/// let patched = SplitReplay::reassemble(b"{\"player\":\"Nova2\"}", split.input_bytes);
+/// assert_eq!(patched, b"{\"player\":\"Nova2\"}\n\x0a\x06");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SplitReplay<'a> {
+    /// The metadata JSON.
+    pub metadata_bytes: &'a [u8],
+    /// The VLQ-encoded input data.
+    pub input_bytes: &'a [u8],
+}
+
+impl<'a> SplitReplay<'a> {
+    /// Splits a raw, uncompressed replay at the separator between the metadata and the input data.
+    ///
+    /// The separator is a linefeed character, or a byte with a decimal value of `10`.
+    pub fn from_raw(data: &'a [u8]) -> Result<Self, ReplayParseError> {
+        let first_newline = match data.iter().position(|&el| el == 10) {
+            Some(loc) => loc,
+            None => return Err(ReplayParseError::MetadataSeparatorNotFound),
+        };
+
+        let (metadata_bytes, input_bytes) = data.split_at(first_newline);
+
+        Ok(SplitReplay {
+            metadata_bytes,
+            input_bytes: &input_bytes[1..],
+        })
+    }
+
+    /// Splits a raw replay like [`from_raw`][SplitReplay::from_raw], but with the whole replay as
+    /// the metadata if there's no separator and the options allow it.
+    pub(crate) fn from_raw_with(data: &'a [u8], options: &ParseOptions) -> Result<Self, ReplayParseError> {
+        match SplitReplay::from_raw(data) {
+            Err(ReplayParseError::MetadataSeparatorNotFound) if options.allow_missing_input_section => {
+                Ok(SplitReplay { metadata_bytes: data, input_bytes: &[] })
+            }
+            split => split,
+        }
+    }
+
+    /// Joins the metadata JSON and the input data into a raw, uncompressed replay.
+    pub fn reassemble(metadata: &[u8], inputs: &[u8]) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(metadata.len() + 1 + inputs.len());
+        buffer.extend_from_slice(metadata);
+        buffer.push(10);
+        buffer.extend_from_slice(inputs);
+        buffer
+    }
+
+    /// Joins the two parts back into a raw, uncompressed replay.
+    pub fn to_raw(&self) -> Vec<u8> {
+        Self::reassemble(self.metadata_bytes, self.input_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    fn raw_replay(player: &str, inputs: Vec<GameInputEvent>) -> Vec<u8> {
+        let mut data = replay(inputs);
+        data.metadata.player = player.to_string();
+        data.serialize_to_raw(None).unwrap()
+    }
+
+    #[test]
+    fn test_swap_metadata() {
+        let a = raw_replay("Nova", vec![GameInputEvent::press(200, HardDrop)]);
+        let b = raw_replay("Lumi", vec![GameInputEvent::release(1000, Hold)]);
+
+        let (split_a, split_b) = (SplitReplay::from_raw(&a).unwrap(), SplitReplay::from_raw(&b).unwrap());
+        assert_eq!(split_a.to_raw(), a);
+
+        let swapped = SplitReplay::reassemble(split_b.metadata_bytes, split_a.input_bytes);
+        assert_eq!(SplitReplay::from_raw(&swapped).unwrap().input_bytes, split_a.input_bytes);
+
+        let parsed = GameReplayData::try_from_raw(&swapped, None).unwrap();
+        assert_eq!(parsed.metadata.player, "Lumi");
+        assert_eq!(parsed.inputs, vec![GameInputEvent::press(200, HardDrop)]);
+
+        assert!(matches!(
+            SplitReplay::from_raw(b"{}"),
+            Err(ReplayParseError::MetadataSeparatorNotFound)
+        ));
+    }
+}
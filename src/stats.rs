@@ -0,0 +1,268 @@
+//! Statistics about the inputs of a replay.
+//!
+//! See [`ReplayStats`] for more information.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+use crate::{KeyPressAnomaly, FRAMES_PER_SECOND, GAME_START_FRAME};
+
+/// Determines what to do with keys that are still held at the end of the replay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum UnpairedPressPolicy {
+    /// Presses with no matching release are left out of the hold duration statistics.
+    #[default]
+    Ignore,
+    /// Presses with no matching release are treated as if they were released on
+    /// the frame of the last input in the replay.
+    ClampToLastFrame,
+}
+
+/// Options for computing [`ReplayStats`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsOptions {
+    /// What to do with keys that are still held at the end of the replay.
+    ///
+    /// See [`UnpairedPressPolicy`] for more information.
+    pub unpaired_press_policy: UnpairedPressPolicy,
+}
+
+/// Statistics about how long a key was held for, measured in frames.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldDurationStats {
+    /// The number of press-release pairs the statistics are made from.
+    pub count: u64,
+    /// The sum of all the hold durations.
+    pub total: u64,
+    /// The shortest hold duration.
+    pub min: u64,
+    /// The longest hold duration.
+    pub max: u64,
+    /// The average hold duration.
+    pub mean: f64,
+}
+
+impl HoldDurationStats {
+    fn add(&mut self, duration: u64) {
+        if self.count == 0 {
+            self.min = duration;
+            self.max = duration;
+        } else {
+            self.min = self.min.min(duration);
+            self.max = self.max.max(duration);
+        }
+
+        self.count += 1;
+        self.total += duration;
+        self.mean = self.total as f64 / self.count as f64;
+    }
+}
+
+/// Statistics about the inputs of a replay.
+///
+/// Key presses made during the countdown (before frame 180) are included in
+/// [`total_presses`][ReplayStats::total_presses] and
+/// [`presses_per_key`][ReplayStats::presses_per_key], and are additionally counted in
+/// [`countdown_presses`][ReplayStats::countdown_presses] and
+/// [`countdown_presses_per_key`][ReplayStats::countdown_presses_per_key].
+/// The keys-per-second values only take the presses after the countdown into account.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayStats {
+    /// The total number of key presses in the replay.
+    pub total_presses: u64,
+    /// The number of key presses in the replay for each key.
+    ///
+    /// Keys that were never pressed are left out.
+    pub presses_per_key: BTreeMap<InputEventKey, u64>,
+
+    /// The number of key presses made during the countdown.
+    pub countdown_presses: u64,
+    /// The number of key presses made during the countdown for each key.
+    ///
+    /// Keys that were never pressed during the countdown are left out.
+    pub countdown_presses_per_key: BTreeMap<InputEventKey, u64>,
+
+    /// The average number of key presses per second after the countdown,
+    /// up until the last input of the replay.
+    pub average_kps: f64,
+    /// The highest number of key presses within any one-second (60 frame) window
+    /// after the countdown.
+    pub peak_kps: f64,
+
+    /// Statistics about how long each key was held for.
+    ///
+    /// Keys that were never pressed and released are left out.
+    pub hold_durations: BTreeMap<InputEventKey, HoldDurationStats>,
+
+    /// The number of presses of a key that was already held.
+    ///
+    /// See [`KeyPressAnomaly::RepeatedPress`] for more information.
+    /// These presses are counted towards the press counts, but the hold duration
+    /// is measured from the first press.
+    pub repeated_presses: u64,
+    /// The number of presses that have no matching release at the end of the replay.
+    pub unpaired_presses: u64,
+    /// The number of releases that have no matching press before them.
+    ///
+    /// These are left out of the hold duration statistics.
+    pub unpaired_releases: u64,
+}
+
+impl ReplayStats {
+    /// Computes the statistics of a replay using the default [`StatsOptions`].
+    pub fn from_replay(replay: &GameReplayData) -> ReplayStats {
+        Self::from_replay_with(replay, StatsOptions::default())
+    }
+
+    /// Computes the statistics of a replay.
+    ///
+    /// The inputs don't need to be sorted. Hold durations are derived from
+    /// [`GameReplayData::pair_key_presses`].
+    pub fn from_replay_with(replay: &GameReplayData, options: StatsOptions) -> ReplayStats {
+        let mut stats = ReplayStats::default();
+        let mut gameplay_presses = Vec::new();
+
+        for input in &replay.inputs {
+            if input.kind != InputEventKind::Press {
+                continue;
+            }
+
+            stats.total_presses += 1;
+            *stats.presses_per_key.entry(input.key).or_default() += 1;
+
+            if input.frame < GAME_START_FRAME {
+                stats.countdown_presses += 1;
+                *stats.countdown_presses_per_key.entry(input.key).or_default() += 1;
+            } else {
+                gameplay_presses.push(input.frame);
+            }
+        }
+
+        gameplay_presses.sort_unstable();
+
+        let last_frame = replay.duration_frames();
+        let pairing = replay.pair_key_presses();
+
+        for anomaly in pairing.anomalies {
+            match anomaly {
+                KeyPressAnomaly::RepeatedPress { .. } => stats.repeated_presses += 1,
+                KeyPressAnomaly::UnpairedRelease { .. } => stats.unpaired_releases += 1,
+            }
+        }
+
+        for press in pairing.presses {
+            let duration = match (press.duration(), options.unpaired_press_policy) {
+                (Some(duration), _) => duration,
+                (None, policy) => {
+                    stats.unpaired_presses += 1;
+
+                    match policy {
+                        UnpairedPressPolicy::Ignore => continue,
+                        UnpairedPressPolicy::ClampToLastFrame => last_frame - press.start_frame,
+                    }
+                }
+            };
+
+            stats.hold_durations.entry(press.key).or_default().add(duration);
+        }
+
+        let gameplay_frames = last_frame.saturating_sub(GAME_START_FRAME);
+        if gameplay_frames > 0 {
+            stats.average_kps = gameplay_presses.len() as f64 * FRAMES_PER_SECOND as f64
+                / gameplay_frames as f64;
+        }
+
+        stats.peak_kps = peak_presses_in_window(&gameplay_presses, FRAMES_PER_SECOND) as f64;
+
+        stats
+    }
+}
+
+/// Finds the highest number of presses within any window of `window` frames.
+///
+/// Expects `press_frames` to be sorted.
+pub(crate) fn peak_presses_in_window(press_frames: &[u64], window: u64) -> usize {
+    let mut peak = 0;
+    let mut start = 0;
+
+    for (end, &frame) in press_frames.iter().enumerate() {
+        while frame - press_frames[start] >= window {
+            start += 1;
+        }
+
+        peak = peak.max(end - start + 1);
+    }
+
+    peak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_replay_stats() {
+        let data = replay(vec![
+            GameInputEvent::press(100, MoveLeft),
+            GameInputEvent::release(110, MoveLeft),
+            GameInputEvent::press(180, HardDrop),
+            GameInputEvent::release(182, HardDrop),
+            GameInputEvent::press(190, HardDrop),
+            GameInputEvent::release(190, HardDrop),
+            GameInputEvent::release(200, Hold),
+            GameInputEvent::press(210, MoveRight),
+            GameInputEvent::press(215, MoveRight),
+            GameInputEvent::release(240, MoveRight),
+            GameInputEvent::press(260, RotateLeft),
+            GameInputEvent::press(300, HardDrop),
+        ]);
+
+        let stats = ReplayStats::from_replay(&data);
+
+        assert_eq!(stats.total_presses, 7);
+        assert_eq!(stats.presses_per_key[&HardDrop], 3);
+        assert_eq!(stats.presses_per_key[&MoveRight], 2);
+        assert_eq!(stats.presses_per_key.get(&Hold), None);
+        assert_eq!(stats.countdown_presses, 1);
+        assert_eq!(stats.countdown_presses_per_key[&MoveLeft], 1);
+        assert_eq!(stats.repeated_presses, 1);
+        assert_eq!(stats.unpaired_presses, 2);
+        assert_eq!(stats.unpaired_releases, 1);
+
+        // 6 presses in 120 frames after the countdown
+        assert_eq!(stats.average_kps, 3.0);
+        // Frames 180 to 239 contain 4 presses
+        assert_eq!(stats.peak_kps, 4.0);
+
+        let hard_drop = &stats.hold_durations[&HardDrop];
+        assert_eq!((hard_drop.count, hard_drop.min, hard_drop.max), (2, 0, 2));
+        assert_eq!(hard_drop.mean, 1.0);
+        assert_eq!(stats.hold_durations[&MoveRight].max, 30);
+        assert_eq!(stats.hold_durations.get(&RotateLeft), None);
+
+        let clamped = ReplayStats::from_replay_with(
+            &data,
+            StatsOptions {
+                unpaired_press_policy: UnpairedPressPolicy::ClampToLastFrame,
+            },
+        );
+
+        assert_eq!(clamped.hold_durations[&RotateLeft].total, 40);
+        assert_eq!(clamped.hold_durations[&HardDrop].count, 3);
+    }
+
+    #[test]
+    fn test_empty_replay_stats() {
+        let stats = ReplayStats::from_replay(&GameReplayData::default());
+
+        assert_eq!(stats, ReplayStats::default());
+    }
+}
@@ -0,0 +1,215 @@
+use alloc::string::String;
+use core::fmt;
+
+use crate::types::*;
+use crate::validate::settings_issues;
+use crate::segments::TOOLKIT_KEYS;
+use crate::{ParseOptions, ValidationIssue};
+
+/// Why a replay failed to parse in [`strict`][ParseOptions::strict] mode.
+///
+/// See [`ReplayParseError::StrictViolation`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrictViolation {
+    /// An input is on a frame later than the [`max_frame`][ParseOptions::max_frame].
+    ///
+    /// Only the first such input is reported.
+    FrameTooLate {
+        /// The index of the input.
+        index: usize,
+        /// The frame of the input.
+        frame: u64,
+        /// The latest frame allowed.
+        max_frame: u64,
+    },
+
+    /// The replay has more inputs than the [`max_inputs`][ParseOptions::max_inputs].
+    TooManyInputs {
+        /// The number of inputs.
+        count: usize,
+        /// The most inputs allowed.
+        max_inputs: usize,
+    },
+
+    /// An input is identical to the input right before it, frame included.
+    DuplicateInput {
+        /// The index of the second of the two inputs.
+        index: usize,
+        /// The duplicated input.
+        event: GameInputEvent,
+    },
+
+    /// A setting has a value outside the range the game allows.
+    SettingOutOfRange {
+        /// The name of the setting, as written in the metadata JSON.
+        field: String,
+        /// The value of the setting.
+        value: f64,
+        /// The lowest value the game allows.
+        min: f64,
+        /// The highest value the game allows.
+        max: f64,
+    },
+
+    /// The metadata has a top-level key this crate doesn't know about.
+    ///
+    /// Unknown keys of the settings are allowed, as the game adds settings often.
+    UnknownMetadataKey {
+        /// The key.
+        key: String,
+    },
+}
+
+impl fmt::Display for StrictViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use StrictViolation::*;
+
+        match self {
+            FrameTooLate { index, frame, max_frame } => write!(
+                f,
+                "Input #{index} is on frame {frame}, which is later than the limit of {max_frame}"
+            ),
+            TooManyInputs { count, max_inputs } => write!(
+                f,
+                "The replay has {count} inputs, which is more than the limit of {max_inputs}"
+            ),
+            DuplicateInput { index, event } => write!(
+                f,
+                "Input #{index} ({:?} {:?} on frame {}) is the same as the input before it",
+                event.kind, event.key, event.frame
+            ),
+            SettingOutOfRange { field, value, min, max } => write!(
+                f,
+                "Setting '{field}' is {value}, which is outside the range {min} to {max}"
+            ),
+            UnknownMetadataKey { key } => write!(f, "The metadata has the unknown key '{key}'"),
+        }
+    }
+}
+
+/// Checks a parsed replay for the violations of [`strict`][ParseOptions::strict] mode,
+/// returning the first one found.
+pub(crate) fn check(data: &GameReplayData, options: &ParseOptions) -> Result<(), StrictViolation> {
+    let inputs = &data.inputs;
+
+    if inputs.len() > options.max_inputs {
+        let (count, max_inputs) = (inputs.len(), options.max_inputs);
+        return Err(StrictViolation::TooManyInputs { count, max_inputs });
+    }
+
+    if let Some(index) = inputs.iter().position(|input| input.frame > options.max_frame) {
+        let frame = inputs[index].frame;
+        return Err(StrictViolation::FrameTooLate { index, frame, max_frame: options.max_frame });
+    }
+
+    if let Some(index) = inputs.windows(2).position(|window| window[0] == window[1]) {
+        return Err(StrictViolation::DuplicateInput { index: index + 1, event: inputs[index] });
+    }
+
+    let out_of_range = settings_issues(&data.metadata.setting).into_iter().find_map(|issue| match issue {
+        ValidationIssue::SettingOutOfRange { field, value, min, max } => Some((field, value, min, max)),
+        _ => None,
+    });
+    if let Some((field, value, min, max)) = out_of_range {
+        return Err(StrictViolation::SettingOutOfRange { field, value, min, max });
+    }
+
+    // The keys are in the order they were read in with the `preserve-order` feature, or sorted without it.
+    // The game ignores the keys of this crate, so they don't make a replay incompatible.
+    if let Some(key) = data.metadata.nonstandard.keys().find(|key| !TOOLKIT_KEYS.contains(&key.as_str())) {
+        return Err(StrictViolation::UnknownMetadataKey { key: key.clone() });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use InputEventKey::*;
+
+    fn raw(data: &GameReplayData) -> Vec<u8> {
+        data.serialize_to_raw(None).unwrap()
+    }
+
+    /// Parses the replay strictly with the options, and checks that it parses leniently.
+    fn parse_strict(data: &GameReplayData, options: ParseOptions) -> Result<(), StrictViolation> {
+        let lenient = GameReplayData::try_from_raw_with(&raw(data), &ParseOptions::new());
+        assert_eq!(lenient.as_ref().ok(), Some(data));
+
+        match GameReplayData::try_from_raw_with(&raw(data), &options.strict(true)) {
+            Ok(parsed) => {
+                assert_eq!(&parsed, data);
+                Ok(())
+            }
+            Err(ReplayParseError::StrictViolation(violation)) => Err(violation),
+            Err(other) => panic!("Expected a StrictViolation, got {other:?}"),
+        }
+    }
+
+    fn plausible_replay() -> GameReplayData {
+        GameReplayData {
+            inputs: vec![GameInputEvent::press(100, HardDrop), GameInputEvent::release(101, HardDrop)],
+            metadata: GameReplayMetadata {
+                version: "V0.17.22".to_string(),
+                setting: PlayerSettings { das: Some(10), ..Default::default() },
+                ..Default::default()
+            },
+            input_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_plausible_replay() {
+        assert_eq!(parse_strict(&plausible_replay(), ParseOptions::new()), Ok(()));
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut data = plausible_replay();
+        data.inputs.push(GameInputEvent::press(20_000_000, Hold));
+        assert_eq!(
+            parse_strict(&data, ParseOptions::new()),
+            Err(StrictViolation::FrameTooLate { index: 2, frame: 20_000_000, max_frame: 10_000_000 })
+        );
+        assert_eq!(parse_strict(&data, ParseOptions::new().max_frame(20_000_000)), Ok(()));
+
+        let data = plausible_replay();
+        assert_eq!(
+            parse_strict(&data, ParseOptions::new().max_inputs(1)),
+            Err(StrictViolation::TooManyInputs { count: 2, max_inputs: 1 })
+        );
+
+        let mut data = plausible_replay();
+        data.inputs.insert(1, GameInputEvent::press(100, HardDrop));
+        let violation = parse_strict(&data, ParseOptions::new()).unwrap_err();
+        let duplicate = GameInputEvent::press(100, HardDrop);
+        assert_eq!(violation, StrictViolation::DuplicateInput { index: 1, event: duplicate });
+        let message = "Input #1 (Press HardDrop on frame 100) is the same as the input before it";
+        assert_eq!(violation.to_string(), message);
+
+        let mut data = plausible_replay();
+        data.metadata.setting.arr = Some(16);
+        assert_eq!(
+            parse_strict(&data, ParseOptions::new()),
+            Err(StrictViolation::SettingOutOfRange {
+                field: "arr".to_string(),
+                value: 16.0,
+                min: 0.0,
+                max: 15.0
+            })
+        );
+
+        let mut data = plausible_replay();
+        data.metadata.nonstandard.insert("cheated".to_string(), serde_json::Value::Bool(true));
+        data.metadata.setting.nonstandard.insert("newSetting".to_string(), serde_json::Value::Bool(true));
+        assert_eq!(
+            parse_strict(&data, ParseOptions::new()),
+            Err(StrictViolation::UnknownMetadataKey { key: "cheated".to_string() })
+        );
+        data.metadata.nonstandard.clear();
+        assert_eq!(parse_strict(&data, ParseOptions::new()), Ok(()));
+    }
+}
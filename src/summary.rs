@@ -0,0 +1,245 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::compression::decompress;
+use crate::detect::reject_non_replay;
+use crate::types::*;
+use crate::vlq;
+use crate::{ParseOptions, ReplayEncoding, SplitReplay, FRAMES_PER_SECOND, GAME_START_FRAME};
+
+/// The number of inputs the [`Display`][fmt::Display] form of a [`GameReplayData`] lists
+/// when no precision is given.
+pub const DEFAULT_DISPLAYED_INPUTS: usize = 20;
+
+/// A summary of a replay, with what a listing of replays shows, e.g. for indexing services.
+///
+/// It's made from a parsed replay with [`From`] (or [`GameReplayData::summary`]), or straight
+/// from a compressed replay with [`from_compressed`][ReplaySummary::from_compressed], without
+/// decoding the inputs into a list.
+///
+/// It's serialized with the field names in camelCase, e.g. `modCount` and `firstInputFrame`.
+/// These names are stable: later versions of the crate only add fields, and summaries serialized
+/// by earlier versions can still be read, with the missing fields set to their defaults.
+///
+/// Its [`Display`][fmt::Display] form is one line for each of the main fields,
+/// e.g. `Player:   NOT_A_ROBOT`.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplaySummary {
+    /// The name of the player.
+    pub player: String,
+    /// The game mode.
+    pub mode: String,
+    /// The game version.
+    pub version: String,
+    /// The date the replay was saved, as written in the metadata.
+    pub date: String,
+    /// The seed of the piece sequence.
+    pub seed: u64,
+    /// Whether the game reported that a TAS tool was used, if it's known.
+    pub tas_used: Option<bool>,
+    /// The number of mods used.
+    pub mod_count: usize,
+    /// The number of inputs.
+    pub inputs: usize,
+    /// The duration of the replay in frames, including the countdown, i.e. the frame of the last input.
+    ///
+    /// The replay format has no end marker, so this is measured until the last input.
+    pub duration_frames: u64,
+    /// The duration of the replay in seconds, including the countdown.
+    pub duration_secs: f64,
+    /// The duration of the replay in frames, excluding the countdown.
+    pub gameplay_frames: u64,
+    /// The frame of the earliest input, or [`None`] if there are no inputs.
+    pub first_input_frame: Option<u64>,
+    /// The frame of the latest input, or [`None`] if there are no inputs.
+    pub last_input_frame: Option<u64>,
+    /// The [fingerprint][GameReplayData::fingerprint] of the replay as lowercase hex.
+    ///
+    /// This is only computed with the `fingerprint` feature, and not by
+    /// [`from_compressed`][ReplaySummary::from_compressed], as it needs all the inputs.
+    pub fingerprint: Option<String>,
+}
+
+impl ReplaySummary {
+    /// Summarizes a compressed replay without decoding its inputs into a list.
+    ///
+    /// Only the metadata is parsed. The input data is only scanned for the number of inputs and
+    /// the frames of the first and the last one, so malformed inputs that
+    /// [`GameReplayData::try_from_compressed_with`] would reject, e.g. ones with unknown key codes,
+    /// aren't detected. Of the options, only the input parse mode and whether to allow a missing
+    /// input section apply. The [`fingerprint`][ReplaySummary::fingerprint] is left out.
+    pub fn from_compressed(data: &[u8], options: &ParseOptions) -> Result<ReplaySummary, ReplayParseError> {
+        reject_non_replay(data, ReplayEncoding::Compressed)?;
+        let data = decompress(data, usize::MAX)?;
+        let split = SplitReplay::from_raw_with(&data, options)?;
+        let metadata = GameReplayMetadata::try_from(split.metadata_bytes)?;
+
+        let mut summary = ReplaySummary::from_metadata(&metadata);
+
+        // Each input is a time and a key code, and each value ends with a byte below 0x80
+        summary.inputs = split.input_bytes.iter().filter(|&&byte| byte & 0x80 == 0).count() / 2;
+        if summary.inputs == 0 {
+            return Ok(summary);
+        }
+
+        let parse_mode = match options.parse_mode {
+            Some(mode) => mode,
+            None => InputParseMode::try_infer_from_version(&metadata.version)
+                .ok_or(ReplayParseError::UnknownInputParseMode(metadata.version))?,
+        };
+
+        let mut values = vlq::decode_iter(split.input_bytes);
+        let (mut first, mut last, mut frame) = (u64::MAX, 0, 0_u64);
+        // The key codes are skipped, as are a trailing time without one, like the parser does
+        while let (Some(time), Some(code)) = (values.next(), values.next()) {
+            code?;
+            frame = match parse_mode {
+                InputParseMode::Relative => frame.saturating_add(time?),
+                InputParseMode::Absolute => time?,
+            };
+            first = first.min(frame);
+            last = last.max(frame);
+        }
+
+        summary.set_frames(Some(first), Some(last));
+        Ok(summary)
+    }
+
+    /// A summary with only the fields from the metadata filled in.
+    fn from_metadata(metadata: &GameReplayMetadata) -> ReplaySummary {
+        ReplaySummary {
+            player: metadata.player.clone(),
+            mode: metadata.mode.clone(),
+            version: metadata.version.clone(),
+            date: metadata.date.clone(),
+            seed: metadata.seed,
+            tas_used: metadata.tas_used,
+            mod_count: metadata.mods.as_ref().map_or(0, Vec::len),
+            ..Default::default()
+        }
+    }
+
+    fn set_frames(&mut self, first: Option<u64>, last: Option<u64>) {
+        self.first_input_frame = first;
+        self.last_input_frame = last;
+        self.duration_frames = last.unwrap_or(0);
+        self.duration_secs = self.duration_frames as f64 / FRAMES_PER_SECOND as f64;
+        self.gameplay_frames = self.duration_frames.saturating_sub(GAME_START_FRAME);
+    }
+
+    /// The duration of the replay in seconds, excluding the countdown.
+    pub fn gameplay_duration_secs(&self) -> f64 {
+        self.gameplay_frames as f64 / FRAMES_PER_SECOND as f64
+    }
+
+    /// A summary of a parsed replay, without the fingerprint.
+    fn from_replay(replay: &GameReplayData) -> ReplaySummary {
+        let mut summary = ReplaySummary::from_metadata(&replay.metadata);
+        summary.inputs = replay.inputs.len();
+        summary.set_frames(replay.inputs.iter().map(|input| input.frame).min(), replay.last_frame());
+
+        summary
+    }
+}
+
+impl From<&GameReplayData> for ReplaySummary {
+    fn from(replay: &GameReplayData) -> Self {
+        ReplaySummary {
+            #[cfg(feature = "fingerprint")]
+            fingerprint: Some(replay.fingerprint().iter().map(|byte| alloc::format!("{byte:02x}")).collect()),
+            ..ReplaySummary::from_replay(replay)
+        }
+    }
+}
+
+impl fmt::Display for ReplaySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.gameplay_duration_secs();
+
+        writeln!(f, "Player:   {}", self.player)?;
+        writeln!(f, "Mode:     {}", self.mode)?;
+        writeln!(f, "Version:  {}", self.version)?;
+        writeln!(f, "Date:     {}", self.date)?;
+        // Like the game's timer, e.g. 1:23.45
+        writeln!(
+            f,
+            "Duration: {}:{:05.2} ({} frames after the countdown)",
+            (secs / 60.0) as u64,
+            secs % 60.0,
+            self.gameplay_frames
+        )?;
+        writeln!(f, "Inputs:   {}", self.inputs)
+    }
+}
+
+impl GameReplayData {
+    /// A summary of the replay: who played it, what mode, how long, and how many inputs.
+    ///
+    /// The same as [`ReplaySummary::from`].
+    pub fn summary(&self) -> ReplaySummary {
+        ReplaySummary::from(self)
+    }
+}
+
+/// The frame, an arrow for the kind of event, and the key, e.g. `231 ▼ MoveLeft` for a press
+/// and `240 ▲ MoveLeft` for a release.
+impl fmt::Display for GameInputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.kind {
+            InputEventKind::Press => '▼',
+            InputEventKind::Release => '▲',
+        };
+
+        write!(f, "{} {arrow} {}", self.frame, self.key.name())
+    }
+}
+
+/// The [summary][GameReplayData::summary] of the replay, followed by its first inputs,
+/// one per line.
+///
+/// The precision sets how many inputs are listed, e.g. `{:.5}` for five, and defaults to
+/// [`DEFAULT_DISPLAYED_INPUTS`]. The inputs that aren't listed are counted in a last line.
+impl fmt::Display for GameReplayData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Without the fingerprint, which is only in the serialized form
+        write!(f, "{}", ReplaySummary::from_replay(self))?;
+
+        let limit = f.precision().unwrap_or(DEFAULT_DISPLAYED_INPUTS);
+        for input in self.inputs.iter().take(limit) {
+            writeln!(f, "  {input}")?;
+        }
+
+        match self.inputs.len().saturating_sub(limit) {
+            0 => Ok(()),
+            1 => writeln!(f, "  ... and 1 more input"),
+            more => writeln!(f, "  ... and {more} more inputs"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::ToString;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_display_inputs() {
+        let press = GameInputEvent::press(231, MoveLeft);
+        let release = GameInputEvent::release(240, HardDrop);
+        assert_eq!(press.to_string(), "231 ▼ MoveLeft");
+        assert_eq!(release.to_string(), "240 ▲ HardDrop");
+
+        let replay = GameReplayData { inputs: vec![press, release, press], ..Default::default() };
+        let listed = format!("{replay:.1}");
+        assert!(listed.ends_with("Inputs:   3\n  231 ▼ MoveLeft\n  ... and 2 more inputs\n"), "{listed}");
+        assert!(format!("{replay}").ends_with("  240 ▲ HardDrop\n  231 ▼ MoveLeft\n"));
+        assert!(format!("{replay:.2}").ends_with("  ... and 1 more input\n"));
+        assert!(format!("{replay:.0}").ends_with("Inputs:   3\n  ... and 3 more inputs\n"));
+    }
+}
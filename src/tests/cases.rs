@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 use crate::GameReplayData;
@@ -16,75 +17,28 @@ pub struct TestCase {
     pub data: Option<GameReplayData>
 }
 
+/// Where the fixtures are stored, for regenerating their RON.
+/// New ones also have to be added to [`crate::fixtures`].
 pub const TESTCASE_PATH: &str = "./src/tests/cases";
 
 pub fn get_test_cases() -> HashMap<String, TestCase> {
-    let files: Vec<_> = std::fs::read_dir(TESTCASE_PATH).unwrap().flatten().collect();
-
-    let mut map: HashMap<String, TestCase> = HashMap::new();
-
-    for file in files {
-        let name = file.file_name().into_string()
-            .expect("Invalid Unicode in test case file name");
-        let (name, ext) = name.split_once('.')
-            .expect("Invalid test case file name (no extension)");
-
-        let contents = std::fs::read(file.path())
-            .expect(&format!("Error reading test case {name} content"));
-
-        match ext.to_ascii_lowercase().as_str() {
-            "b64.rep" => {
-                let case = map.get_mut(name);
-
-                let contents = String::from_utf8(contents)
-                    .expect(&format!("Invalid Unicode in test case {name} contents"));
-
-                let stored = StoredReplay::Base64(contents);
-
-                if let Some(c) = case {
-                    c.serialized = Some(stored);
-                } else {
-                    map.insert(name.to_string(), TestCase {
-                        serialized: Some(stored),
-                        data: None,
-                    });
-                }
-            },
-            "bin.rep" => {
-                let case = map.get_mut(name);
-
-                let stored = StoredReplay::Binary(contents.into_boxed_slice());
-
-                if let Some(c) = case {
-                    c.serialized = Some(stored);
-                } else {
-                    map.insert(name.to_string(), TestCase {
-                        serialized: Some(stored),
-                        data: None,
-                    });
-                }
-            },
-            "ron" | "dat" | "res" => {
-                let case = map.get_mut(name);
-
-                let contents = String::from_utf8(contents)
-                    .expect(&format!("Invalid Unicode in test case {name} data"));
-
-                let data: GameReplayData = ron::from_str(&contents)
-                    .expect(&format!("Invalid RON in test case {name} data"));
+    crate::fixtures::all()
+        .into_iter()
+        .map(|fixture| {
+            let base64 = fixture.base64.map(|base64| StoredReplay::Base64(base64.to_string()));
+            let binary = fixture.binary.map(|binary| StoredReplay::Binary(binary.into()));
+
+            (fixture.name.to_string(), TestCase { serialized: base64.or(binary), data: fixture.expected })
+        })
+        .collect()
+}
 
-                if let Some(c) = case {
-                    c.data = Some(data);
-                } else {
-                    map.insert(name.to_string(), TestCase {
-                        data: Some(data),
-                        serialized: None,
-                    });
-                }
-            }
-            _ => panic!("Unknown file extension .{ext} for test {name}"),
-        }
+/// The zlib-compressed bytes of a stored replay, decoding its base64 if needed.
+pub fn compressed_bytes(replay: &StoredReplay) -> Vec<u8> {
+    match replay {
+        StoredReplay::Base64(data) => base64::engine::general_purpose::STANDARD
+            .decode(data.trim())
+            .expect("Failed to decode base64"),
+        StoredReplay::Binary(data) => data.to_vec(),
     }
-
-    map
-}
\ No newline at end of file
+}
@@ -0,0 +1,23 @@
+//! Helpers shared by the unit tests of the modules.
+
+use crate::types::*;
+
+/// A replay of the inputs, made with the latest version of the game.
+pub(crate) fn replay(inputs: Vec<GameInputEvent>) -> GameReplayData {
+    GameReplayData {
+        inputs,
+        metadata: GameReplayMetadata { version: "V0.17.22".to_string(), ..Default::default() },
+        input_mode: None,
+    }
+}
+
+/// A xorshift generator, for tests that need lots of reproducible random inputs.
+pub(crate) fn xorshift(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
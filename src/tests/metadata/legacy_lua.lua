@@ -0,0 +1,42 @@
+-- Hand-written, not taken from a replay saved by the game
+{
+	player="NOT_A_ROBOT",
+	seed=358231284,
+	version="V0.17.17",
+	date="2024/10/09 14:44:11",
+	mode="sprint_10l",
+	mod={},
+	tasUsed=true,
+	setting={
+		atkFX=0,
+		clearFX=0,
+		dropFX=0,
+		lockFX=0,
+		moveFX=0,
+		shakeFX=0,
+		splashFX=0,
+		das=3,
+		arr=0,
+		sddas=0,
+		sdarr=0,
+		dascut=0,
+		dropcut=0,
+		irs=true,
+		ihs=true,
+		ims=false,
+		RS="TRS",
+		bagLine=true,
+		block=true,
+		center=1,
+		face={0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0},
+		ghost=1,
+		grid=0.4,
+		highCam=true,
+		nextPos=true,
+		score=true,
+		skin={1,7,11,3,14,4,9,1,7,2,6,10,2,13,5,9,15,4,11,3,10,2,16,8,4,10,13,2,8},
+		smooth=true,
+		text=true,
+		warn=true,
+	},
+}
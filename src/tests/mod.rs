@@ -1,4 +1,5 @@
 mod cases; use std::fs;
+pub(crate) mod helpers;
 
 use cases::*;
 use ron::ser::PrettyConfig;
@@ -32,6 +33,8 @@ fn test_serialize_deserialize_noop() {
 
 #[test]
 fn test_deserialize_serialize_noop() {
+    use crate::{compressed_equivalent, LazyReplayData, RecompressionFidelity, SerializeOptions};
+
     let cases = get_test_cases();
 
     for (key, val) in cases {
@@ -62,14 +65,411 @@ fn test_deserialize_serialize_noop() {
             ),
         };
 
-        assert_eq!(serialized, reserialized, "Original and reserialized form doesn't match!");
+        // The game's JSON encoder writes keys in Lua table order and formats floats
+        // differently, so the reserialized bytes aren't expected to match the original.
+        // Instead, check that both forms hold the same replay.
+        let reparsed = match reserialized {
+            StoredReplay::Base64(ref data) => GameReplayData::try_from_base64(data, None),
+            StoredReplay::Binary(ref data) => GameReplayData::try_from_compressed(data, None),
+        }.expect("Failed to parse reserialized data");
+
+        assert_eq!(deserialized, reparsed, "Original and reserialized form doesn't match!");
+
+        // Unmodified replays can be written back byte for byte, whichever zlib compressed them
+        let compressed = compressed_bytes(&serialized);
+        let lazy = LazyReplayData::try_from_compressed(&compressed, None).unwrap();
+        let exact = SerializeOptions::new().recompression(RecompressionFidelity::Exact);
+        assert!(lazy.serialize_to_compressed_with(&exact).unwrap() == compressed, "Exact bytes don't match!");
+
+        // Otherwise, only the content is the same
+        let recompressed = lazy.serialize_to_compressed(None).unwrap();
+        let reserialized = deserialized.serialize_to_compressed(None).unwrap();
+        assert!(compressed_equivalent(&recompressed, &reserialized), "Recompressed content doesn't match!");
+    }
+}
+
+#[test]
+fn test_serialize_into_reused_buffer() {
+    use crate::SerializeOptions;
+
+    let cases = get_test_cases();
+    let options = SerializeOptions::new();
+
+    let mut raw_buffer = Vec::new();
+    let mut compressed_buffer = Vec::new();
+
+    // Twice, so that each replay is also written into a buffer left over from a bigger one
+    for _ in 0..2 {
+        for (key, val) in &cases {
+            let data = match &val.data {
+                Some(d) => d,
+                None => {
+                    println!("Skipping testcase '{key}' (it has no deserialized data form)");
+                    continue;
+                },
+            };
+
+            println!("Testing for testcase {key}");
+
+            let written = data.serialize_raw_into(&mut raw_buffer, &options)
+                .expect("Error while serializing replay");
+            assert_eq!(written, raw_buffer.len());
+            assert!(raw_buffer == data.serialize_to_raw_with(&options).unwrap(), "Raw bytes don't match!");
+
+            let written = data.serialize_compressed_into(&mut compressed_buffer, &options)
+                .expect("Error while serializing replay");
+            assert_eq!(written, compressed_buffer.len());
+            assert!(
+                compressed_buffer == crate::compression::compress(&raw_buffer, 6),
+                "Compressed bytes don't match!"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_raw_metadata_round_trip() {
+    use crate::RawMetadata;
+
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let serialized = match val.serialized {
+            Some(r) => r,
+            None => {
+                println!("Skipping testcase '{key}' (it has no serialized data form)");
+                continue;
+            },
+        };
+
+        println!("Testing for testcase {key}");
+
+        let compressed = compressed_bytes(&serialized);
+        let raw = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed)
+            .expect("Failed to decompress data");
+
+        let deserialized: GameReplayData<RawMetadata> = GameReplayData::try_from_raw_as(&raw, None)
+            .expect("Failed to deserialize data");
+        let reserialized = deserialized.serialize_to_raw(None)
+            .expect("Failed to reserialize data");
+
+        // Unlike with the parsed metadata, the metadata JSON is kept byte for byte
+        assert!(reserialized == raw, "Original and reserialized bytes don't match!");
     }
 }
 
 #[test]
 fn test_difference() {
-    // TODO:
-    // Check if there is a difference between parsed replay and the one gotten from the RON
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let (serialized, data) = match (val.serialized, val.data) {
+            (Some(s), Some(d)) => (s, d),
+            _ => {
+                println!("Skipping testcase '{key}' (it doesn't have both data forms)");
+                continue;
+            },
+        };
+
+        println!("Testing for testcase {key}");
+
+        let parsed = match serialized {
+            StoredReplay::Base64(ref data) => GameReplayData::try_from_base64(data, None),
+            StoredReplay::Binary(ref data) => GameReplayData::try_from_compressed(data, None),
+        }.expect("Failed to deserialize data");
+
+        let diff = parsed.diff(&data);
+
+        assert!(diff.is_empty(), "Parsed replay differs from the RON:\n{diff}");
+    }
+}
+
+#[test]
+fn test_recover_truncated() {
+    use crate::{DataLoss, ParseOptions};
+
+    let cases = get_test_cases();
+    let options = ParseOptions::new();
+
+    for (key, val) in cases {
+        let Some(serialized) = &val.serialized else {
+            println!("Skipping testcase '{key}' (it has no serialized data form)");
+            continue;
+        };
+        let compressed = compressed_bytes(serialized);
+
+        println!("Testing for testcase {key}");
+
+        let full = GameReplayData::try_from_compressed(&compressed, None)
+            .expect("Failed to deserialize data");
+
+        let result = GameReplayData::try_recover_from_compressed(&compressed, &options);
+        assert!(result.is_complete());
+        assert_eq!(result.data.as_ref(), Some(&full));
+
+        for len in [0, 1, 10, compressed.len() / 4, compressed.len() / 2, compressed.len() - 4] {
+            let result = GameReplayData::try_recover_from_compressed(&compressed[..len], &options);
+
+            assert!(matches!(result.losses.first(), Some(DataLoss::Decompression { .. })));
+
+            // Whatever was recovered has to be the beginning of the full replay
+            if let Some(data) = result.data {
+                assert_eq!(data.metadata, full.metadata);
+                assert_eq!(data.inputs, full.inputs[..data.inputs.len()]);
+            }
+        }
+
+        // Only the checksum is missing, so everything can be recovered
+        let result = GameReplayData::try_recover_from_compressed(&compressed[..compressed.len() - 4], &options);
+        assert_eq!(result.data, Some(full));
+    }
+}
+
+#[test]
+fn test_lazy_rename_keeps_inputs() {
+    use crate::{LazyReplayData, SplitReplay};
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(serialized) = &val.serialized else {
+            println!("Skipping testcase '{key}' (it has no serialized data form)");
+            continue;
+        };
+        let compressed = compressed_bytes(serialized);
+
+        println!("Testing for testcase {key}");
+
+        let mut data = LazyReplayData::try_from_compressed(&compressed, None)
+            .expect("Failed to deserialize data");
+        data.metadata.player = "Renamed".to_string();
+
+        let original = decompress_to_vec_zlib(&compressed).unwrap();
+        let renamed = decompress_to_vec_zlib(&data.serialize_to_compressed(None).unwrap()).unwrap();
+
+        assert_eq!(
+            SplitReplay::from_raw(&renamed).unwrap().input_bytes,
+            SplitReplay::from_raw(&original).unwrap().input_bytes,
+        );
+        assert_eq!(
+            GameReplayData::try_from_raw(&renamed, None).unwrap().metadata.player,
+            "Renamed"
+        );
+    }
+}
+
+#[cfg(feature = "fingerprint")]
+#[test]
+fn test_fingerprint_forms() {
+    use base64::Engine;
+
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(StoredReplay::Base64(base64)) = val.serialized else {
+            println!("Skipping testcase '{key}' (it has no base64 form)");
+            continue;
+        };
+
+        println!("Testing for testcase {key}");
+
+        let binary = base64::engine::general_purpose::STANDARD
+            .decode(base64.trim())
+            .expect("Failed to decode base64");
+
+        let from_base64 = GameReplayData::try_from_base64(base64.trim(), None).unwrap();
+        let from_binary = GameReplayData::try_from_compressed(&binary, None).unwrap();
+
+        assert_eq!(from_base64.fingerprint(), from_binary.fingerprint());
+
+        // Reserializing changes the key order of the metadata, but not the fingerprint
+        let reserialized = GameReplayData::try_from_compressed(
+            &from_binary.serialize_to_compressed(None).unwrap(),
+            None,
+        ).unwrap();
+
+        assert_eq!(reserialized.fingerprint(), from_binary.fingerprint());
+    }
+}
+
+#[test]
+fn test_text_round_trip() {
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(data) = val.data else {
+            println!("Skipping testcase '{key}' (it has no deserialized data form)");
+            continue;
+        };
+
+        println!("Testing for testcase {key}");
+
+        let text = data.to_text().expect("Failed to write text");
+        let parsed = GameReplayData::from_text(&text).expect("Failed to parse text");
+        assert_eq!(parsed, data);
+
+        // text -> binary -> text
+        let raw = parsed.serialize_to_raw(None).expect("Failed to serialize replay");
+        let reparsed = GameReplayData::try_from_raw(&raw, None).expect("Failed to deserialize replay");
+        assert_eq!(reparsed.to_text().unwrap(), text);
+    }
+}
+
+#[test]
+fn test_text_fixtures() {
+    let canonical = include_str!("text/someinputs.txt");
+    let handwritten = include_str!("text/someinputs_handwritten.txt");
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+
+    assert_eq!(data.to_text().unwrap(), canonical);
+    assert_eq!(GameReplayData::from_text(canonical).unwrap(), data);
+    assert_eq!(GameReplayData::from_text(handwritten).unwrap(), data);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_jsonl_golden() {
+    let golden = include_str!("jsonl/someinputs.jsonl");
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+
+    let mut jsonl = Vec::new();
+    data.inputs_to_jsonl(&mut jsonl).expect("Failed to write JSON Lines");
+
+    assert_eq!(String::from_utf8(jsonl).unwrap(), golden);
+    assert_eq!(GameReplayData::inputs_from_jsonl(golden.as_bytes()).unwrap(), data.inputs);
+}
+
+#[test]
+fn test_legacy_json_round_trip() {
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let json = data.to_legacy_json().unwrap();
+
+    let legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let first = data.inputs[0];
+    let (kind, key) = (u8::from(first.kind), u8::from(first.key));
+    assert_eq!(legacy["inputs"][0], serde_json::json!({ "frame": first.frame, "type": kind, "key": key }));
+    assert_eq!(legacy["metadata"]["player"], data.metadata.player.as_str());
+
+    let parsed = GameReplayData::from_legacy_json(&json).unwrap();
+    assert_eq!(parsed.inputs, data.inputs);
+    assert_eq!(parsed.metadata, data.metadata);
+}
+
+#[cfg(feature = "binary-cache")]
+#[test]
+fn test_msgpack_round_trip() {
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(data) = val.data else {
+            println!("Skipping testcase '{key}' (it has no deserialized data form)");
+            continue;
+        };
+
+        println!("Testing for testcase {key}");
+
+        let bytes = data.to_msgpack().expect("Failed to encode replay");
+        assert_eq!(GameReplayData::from_msgpack(&bytes).expect("Failed to load replay"), data);
+    }
+}
+
+/// Caches written with older schema versions must keep loading.
+/// When the schema changes, keep these fixtures and add one for the new version.
+#[cfg(feature = "binary-cache")]
+#[test]
+fn test_msgpack_old_schemas() {
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+
+    let v1 = include_bytes!("cache/someinputs.v1.msgpack");
+    assert_eq!(GameReplayData::from_msgpack(v1).unwrap(), data);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv_round_trip() {
+    use crate::CsvImportError;
+
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(data) = val.data else {
+            println!("Skipping testcase '{key}' (it has no deserialized data form)");
+            continue;
+        };
+
+        println!("Testing for testcase {key}");
+
+        let mut csv = Vec::new();
+        data.inputs_to_csv(&mut csv).expect("Failed to write CSV");
+
+        let inputs = GameReplayData::inputs_from_csv(csv.as_slice()).expect("Failed to read CSV");
+        assert_eq!(inputs, data.inputs);
+
+        if data.inputs.len() < 2 {
+            continue;
+        }
+
+        // Break the key of the second input, which is on line 3 after the header
+        let mut lines: Vec<String> = String::from_utf8(csv).unwrap().lines().map(String::from).collect();
+        let (frame_and_kind, _) = lines[2].rsplit_once(',').unwrap();
+        lines[2] = format!("{frame_and_kind},Teleport");
+
+        match GameReplayData::inputs_from_csv(lines.join("\n").as_bytes()) {
+            Err(CsvImportError::InvalidKey { line: 3, value }) => assert_eq!(value, "Teleport"),
+            other => panic!("Expected an invalid key on line 3, got {other:?}"),
+        }
+    }
+}
+
+/// Rewrites a JSON object with its keys sorted, keeping the original text of every other value.
+fn sort_json_keys(json: &str) -> String {
+    use std::collections::BTreeMap;
+    use serde_json::value::RawValue;
+
+    let Ok(object) = serde_json::from_str::<BTreeMap<String, Box<RawValue>>>(json) else {
+        return json.to_string();
+    };
+
+    let entries: Vec<String> = object
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", serde_json::to_string(&key).unwrap(), sort_json_keys(value.get())))
+        .collect();
+
+    format!("{{{}}}", entries.join(","))
+}
+
+#[test]
+fn test_canonical_metadata_json() {
+    use crate::{canonical_metadata_json, SerializeOptions, SplitReplay};
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    let cases = get_test_cases();
+
+    for (key, val) in cases {
+        let Some(serialized) = &val.serialized else {
+            println!("Skipping testcase '{key}' (it has no serialized data form)");
+            continue;
+        };
+        let compressed = compressed_bytes(serialized);
+
+        println!("Testing for testcase {key}");
+
+        let raw = decompress_to_vec_zlib(&compressed).unwrap();
+        let original = std::str::from_utf8(SplitReplay::from_raw(&raw).unwrap().metadata_bytes).unwrap();
+
+        let data = GameReplayData::try_from_raw(&raw, None).unwrap();
+        let canonical = canonical_metadata_json(&data.metadata).unwrap();
+
+        // The game's key order can't be reproduced, but everything else should match
+        assert_eq!(canonical, sort_json_keys(original));
+
+        let serialized = data.serialize_to_raw_with(&SerializeOptions::new().canonical_json(true)).unwrap();
+        assert_eq!(SplitReplay::from_raw(&serialized).unwrap().metadata_bytes, canonical.as_bytes());
+        assert_eq!(GameReplayData::try_from_raw(&serialized, None).unwrap(), data);
+    }
 }
 
 fn get_ron_config() -> PrettyConfig {
@@ -137,4 +537,574 @@ fn regenerate_cases() {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(feature = "std")]
+#[test]
+fn test_parse_replay_dir() {
+    use base64::Engine;
+    use crate::{parse_replay_dir, ParseOptions, ReplayParseError};
+
+    let dir = std::env::temp_dir().join(format!("trt-parse-replay-dir-{}", std::process::id()));
+    fs::create_dir_all(dir.join("nested.rep")).unwrap();
+
+    let someinputs = include_str!("cases/someinputs.b64.rep");
+    let someinputs = base64::engine::general_purpose::STANDARD.decode(someinputs.trim()).unwrap();
+    fs::write(dir.join("b.rep"), &someinputs).unwrap();
+    fs::write(dir.join("a.rep"), b"not a replay").unwrap();
+    fs::write(dir.join("notes.txt"), b"not a replay either").unwrap();
+
+    let results = parse_replay_dir(&dir, &ParseOptions::new()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<_> = results.iter().map(|(path, _)| path.file_name().unwrap().to_str().unwrap()).collect();
+    assert_eq!(names, ["a.rep", "b.rep"]);
+
+    assert!(matches!(results[0].1, Err(ReplayParseError::ZlibDecompressError(_))));
+    assert_eq!(results[1].1.as_ref().unwrap(), &GameReplayData::try_from_compressed(&someinputs, None).unwrap());
+}
+
+#[test]
+fn test_lenient_base64() {
+    let someinputs = include_str!("cases/someinputs.b64.rep").trim();
+    let expected = GameReplayData::try_from_base64(someinputs, None).unwrap();
+
+    let wrapped = someinputs.as_bytes().chunks(76).map(|line| std::str::from_utf8(line).unwrap())
+        .collect::<Vec<_>>().join("\r\n");
+    let url_safe = someinputs.replace('+', "-").replace('/', "_");
+    let unpadded = someinputs.trim_end_matches('=');
+    let data_url = format!("data:application/octet-stream;base64,{someinputs}");
+    let everything = format!("data:;base64,\n{}\n", url_safe.trim_end_matches('=').replace('A', " A"));
+
+    for mangled in [&wrapped, &format!("  {someinputs}\n\n"), &url_safe, unpadded, &data_url, &everything] {
+        let parsed = GameReplayData::try_from_base64(mangled, None)
+            .unwrap_or_else(|error| panic!("Failed to parse {mangled:?}: {error:?}"));
+
+        assert_eq!(parsed, expected);
+    }
+}
+
+#[test]
+fn test_wrapped_base64_output() {
+    use crate::SerializeOptions;
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let unwrapped = data.serialize_to_base64(None).unwrap();
+
+    for width in [1, 4, 63, 64, 76, unwrapped.len() - 1, unwrapped.len(), unwrapped.len() + 1] {
+        for url_safe in [false, true] {
+            let options = SerializeOptions::new().base64_line_width(width).url_safe_base64(url_safe);
+            let wrapped = data.serialize_to_base64_with(&options).unwrap();
+
+            let lines: Vec<&str> = wrapped.split('\n').collect();
+            let (last, full) = lines.split_last().unwrap();
+            assert!(full.iter().all(|line| line.len() == width), "width {width}: {lines:?}");
+            assert!(!last.is_empty() && last.len() <= width, "width {width}: {last:?}");
+
+            let expected = match url_safe {
+                true => unwrapped.replace('+', "-").replace('/', "_").trim_end_matches('=').to_string(),
+                false => unwrapped.clone(),
+            };
+            assert_eq!(lines.concat(), expected);
+
+            assert_eq!(GameReplayData::try_from_base64(&wrapped, None).unwrap(), data);
+        }
+    }
+}
+
+#[test]
+fn test_malformed_input_offset() {
+    use crate::{vlq, ReplayParseError, SplitReplay};
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let mut raw = data.serialize_to_raw(None).unwrap();
+
+    let (metadata_len, values) = {
+        let split = SplitReplay::from_raw(&raw).unwrap();
+        (split.metadata_bytes.len() + 1, vlq::decode(split.input_bytes).unwrap())
+    };
+
+    // The key code of the fourth input, which is a single byte, as all key codes are
+    let offset = vlq::encode(&values[..7]).len();
+    raw[metadata_len + offset] = 0x1f;
+
+    let error = GameReplayData::try_from_raw(&raw, None).unwrap_err();
+    match &error {
+        ReplayParseError::MalformedInputData {
+            offset: error_offset,
+            index,
+            time,
+            raw_code,
+            key_bits,
+            kind_bit,
+            values_before,
+            values_after,
+            ..
+        } => {
+            assert_eq!((*error_offset, *index), (offset, 3));
+            assert_eq!((*time, *raw_code, *key_bits, *kind_bit), (values[6], 31, 31, false));
+            assert_eq!(values_before[..], values[2..6]);
+            assert_eq!(values_after[..], values[8..12]);
+        }
+        other => panic!("Expected MalformedInputData, got {other:?}"),
+    }
+
+    let message = error.to_string();
+    let expected = format!("Input 3 at byte {offset} of the input data is malformed: ");
+    assert!(message.starts_with(&expected), "{message}");
+    assert!(message.contains("has the unknown key 31"), "{message}");
+}
+
+#[test]
+fn test_tas_likelihood_of_human_replay() {
+    use crate::analysis::tas_likelihood;
+
+    let data = get_test_cases().remove("huge").and_then(|case| case.data).unwrap();
+    let analysis = tas_likelihood(&data);
+
+    // A long marathon game by a human
+    assert!(analysis.score < 0.1, "{analysis:#?}");
+    assert!(analysis.sustained_kps.value > 5.0, "{analysis:#?}");
+
+    let json = serde_json::to_value(&analysis).unwrap();
+    assert_eq!(json["sustainedKps"]["value"], analysis.sustained_kps.value);
+}
+
+#[test]
+fn test_custom_mode_private_data() {
+    use crate::{CustomClearData, CustomPuzzleData};
+    use serde_json::{json, Value};
+
+    let clear: Value = serde_json::from_str(include_str!("private/custom_clear.json")).unwrap();
+    let puzzle: Value = serde_json::from_str(include_str!("private/custom_puzzle.json")).unwrap();
+
+    let mut data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert!(data.metadata.private_as::<Value>().is_none());
+
+    data.metadata.mode = "custom_clear".into();
+    data.metadata.private = Some(clear.clone());
+    let replay = GameReplayData::try_from_raw(&data.serialize_to_raw(None).unwrap(), None).unwrap();
+    let decoded = replay.metadata.custom_clear_data().unwrap().unwrap();
+    assert_eq!(decoded.bag.as_deref(), Some(&[1, 2, 3, 4, 5, 6, 7][..]));
+    assert_eq!(decoded.field.as_ref().unwrap()[0][1], [20, 20, 20, 20, 0, 20, 20, 20, 20, 20]);
+    assert_eq!(decoded.custom_env.as_ref().unwrap()["opponent"], "X");
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), clear);
+    assert!(replay.metadata.custom_puzzle_data().is_none());
+
+    data.metadata.mode = "custom_puzzle".into();
+    data.metadata.private = Some(puzzle.clone());
+    let decoded = data.metadata.custom_puzzle_data().unwrap().unwrap();
+    assert_eq!(decoded.field.as_ref().map(Vec::len), Some(2));
+    assert_eq!(decoded.mission.as_deref(), Some(&[][..]));
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), puzzle);
+    assert!(data.metadata.custom_clear_data().is_none());
+
+    // Keys the game may add later are kept, and missing ones aren't filled in
+    let mut extended = puzzle.clone();
+    extended["PROGRESS"] = json!({ "page": 2 });
+    extended.as_object_mut().unwrap().remove("MISSION");
+    data.metadata.private = Some(extended.clone());
+    let decoded = data.metadata.private_as::<CustomPuzzleData>().unwrap().unwrap();
+    assert_eq!(decoded.nonstandard["PROGRESS"], json!({ "page": 2 }));
+    assert_eq!(decoded.mission, None);
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), extended);
+
+    data.metadata.private = Some(json!({ "BAG": "IOSZ" }));
+    assert!(data.metadata.private_as::<CustomClearData>().unwrap().is_err());
+}
+
+#[test]
+fn test_typed_mods() {
+    use crate::GameMod;
+    use serde_json::json;
+
+    let mut data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert!(data.metadata.mods_typed().is_empty());
+    assert!(!data.metadata.has_mod(GameMod::Hold));
+
+    // Mod IDs set by hand: the fixtures have no replays that use mods
+    data.metadata.mods = Some(vec![(1, json!(1)), (9, json!(8)), (40, json!(true))]);
+    let replay = GameReplayData::try_from_raw(&data.serialize_to_raw(None).unwrap(), None).unwrap();
+
+    let mods = replay.metadata.mods_typed();
+    assert_eq!(
+        mods,
+        [(GameMod::Hold, json!(1)), (GameMod::DropDelay, json!(8)), (GameMod::Unknown(40), json!(true))]
+    );
+    assert!(replay.metadata.has_mod(GameMod::Hold) && replay.metadata.has_mod(GameMod::Unknown(40)));
+    assert!(!replay.metadata.has_mod(GameMod::Next));
+    assert_eq!(replay.metadata.mod_value(GameMod::DropDelay), Some(&json!(8)));
+    assert_eq!(replay.metadata.mod_value(GameMod::LockDelay), None);
+}
+
+#[test]
+fn test_piece_settings() {
+    use crate::PieceId;
+
+    let settings = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap().metadata.setting;
+
+    // The colors the fixture replay was recorded with
+    let skins = [(PieceId::Z, 1), (PieceId::T, 14), (PieceId::O, 4), (PieceId::I, 9), (PieceId::O1, 8)];
+    for (piece, skin) in skins {
+        assert_eq!(settings.skin_of(piece), Some(skin), "{piece:?}");
+    }
+    assert_eq!(settings.skin_of(PieceId::X), Some(4));
+    assert_eq!(settings.face_of(PieceId::I5), Some(0));
+
+    for (index, piece) in PieceId::ALL.into_iter().enumerate() {
+        assert_eq!(piece.index(), index);
+        assert_eq!(settings.skin_of(piece), Some(settings.skin.as_ref().unwrap()[index]));
+    }
+
+    // The settings are still written as plain arrays
+    let mut settings = settings;
+    settings.set_skin_of(PieceId::T5, 16);
+    let json = serde_json::to_value(&settings).unwrap();
+    assert_eq!(json["skin"][13], 16);
+    assert_eq!(json["skin"].as_array().map(Vec::len), Some(29));
+}
+
+#[test]
+fn test_other_metadata_spellings() {
+    use serde_json::json;
+
+    // Keys that only differ in case from known ones are kept as they are, next to the known ones
+    let mut metadata = serde_json::to_value(crate::GameReplayMetadata::default()).unwrap();
+    metadata["mods"] = json!([[9, 8]]);
+    metadata["setting"] = json!({ "atkFX": 1, "atkFx": 2, "rs": "SRS" });
+    let mut raw = serde_json::to_vec(&metadata).unwrap();
+    raw.push(b'\n');
+    let replay = GameReplayData::try_from_raw(&raw, None).unwrap();
+    assert_eq!(replay.metadata.mods, None);
+    assert_eq!(replay.metadata.nonstandard.get("mods"), Some(&json!([[9, 8]])));
+
+    let setting = &replay.metadata.setting;
+    assert_eq!((setting.atk_fx, setting.rs.as_deref()), (Some(1), None));
+    assert_eq!(setting.nonstandard.get("atkFx"), Some(&json!(2)));
+    assert_eq!(setting.nonstandard.get("rs"), Some(&json!("SRS")));
+
+    let reparsed = GameReplayData::try_from_raw(&replay.serialize_to_raw(None).unwrap(), None).unwrap();
+    assert_eq!(reparsed, replay);
+}
+
+#[test]
+#[cfg(feature = "preserve-order")]
+fn test_nonstandard_key_order() {
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let raw = data.serialize_to_raw(None).unwrap();
+    let (metadata, inputs) = raw.split_at(raw.iter().position(|&byte| byte == b'\n').unwrap());
+
+    // Keys added by a mod, out of alphabetical order, at the end of the metadata and the settings
+    let mut metadata = String::from_utf8(metadata.to_vec()).unwrap();
+    metadata.insert_str(metadata.len() - 1, r#","zeta":1,"alpha":2,"mu":3"#);
+    // The settings are the first object to end, as the private data is null
+    let settings_end = metadata.find("}").unwrap();
+    metadata.insert_str(settings_end, r#","skinSet":"neon","accent":7,"layout":"b""#);
+
+    let raw = [metadata.as_bytes(), inputs].concat();
+    let replay = GameReplayData::try_from_raw(&raw, None).unwrap();
+    let keys: Vec<_> = replay.metadata.nonstandard.keys().collect();
+    assert_eq!(keys, ["zeta", "alpha", "mu"]);
+    let keys: Vec<_> = replay.metadata.setting.nonstandard.keys().collect();
+    assert_eq!(keys, ["skinSet", "accent", "layout"]);
+
+    let reserialized = replay.serialize_to_raw(None).unwrap();
+    let reserialized = String::from_utf8_lossy(&reserialized);
+    let position = |key: &str| reserialized.find(&format!(r#""{key}":"#)).unwrap();
+    assert!(position("zeta") < position("alpha") && position("alpha") < position("mu"));
+    assert!(position("skinSet") < position("accent") && position("accent") < position("layout"));
+
+    // Equality doesn't depend on the order
+    let mut reordered = replay.clone();
+    let alpha = reordered.metadata.nonstandard.remove("alpha").unwrap();
+    reordered.metadata.nonstandard.insert("alpha".to_string(), alpha);
+    let last_key = |replay: &GameReplayData| replay.metadata.nonstandard.keys().next_back().cloned();
+    assert_eq!((last_key(&reordered), last_key(&replay)), (Some("alpha".into()), Some("mu".into())));
+    assert_eq!(reordered, replay);
+}
+
+#[test]
+#[cfg(feature = "schema")]
+fn test_schema_matches_fixtures() {
+    let schema: serde_json::Value = serde_json::from_str(&crate::export_schema()).unwrap();
+    let validator = jsonschema::validator_for(&schema).expect("The schema is invalid");
+
+    for (key, case) in get_test_cases() {
+        let Some(data) = case.data else { continue };
+
+        let json = serde_json::to_value(&data).unwrap();
+        let errors: Vec<String> = validator.iter_errors(&json).map(|error| error.to_string()).collect();
+        assert!(errors.is_empty(), "Testcase '{key}' doesn't match the schema: {errors:?}");
+    }
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let mut json = serde_json::to_value(&data).unwrap();
+    json["inputs"][0]["key"] = "Jump".into();
+    assert!(!validator.is_valid(&json));
+
+    let mut json = serde_json::to_value(&data).unwrap();
+    json["metadata"]["setting"]["das"] = "fast".into();
+    assert!(!validator.is_valid(&json));
+}
+
+#[test]
+fn test_replay_summary() {
+    let data = get_test_cases().remove("huge").and_then(|case| case.data).unwrap();
+    let summary = data.summary();
+    assert_eq!((summary.gameplay_frames, summary.inputs), (114831, data.inputs.len()));
+    assert_eq!(
+        summary.to_string(),
+        format!(
+            "Player:   {}\nMode:     {}\nVersion:  {}\nDate:     {}\n\
+             Duration: 31:53.85 (114831 frames after the countdown)\nInputs:   {}\n",
+            data.metadata.player,
+            data.metadata.mode,
+            data.metadata.version,
+            data.metadata.date,
+            data.inputs.len(),
+        )
+    );
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert_eq!(
+        format!("{data:.3}"),
+        "Player:   NOT_A_ROBOT\n\
+         Mode:     sprint_10l\n\
+         Version:  V0.17.17\n\
+         Date:     2024/10/09 14:44:11\n\
+         Duration: 0:00.00 (0 frames after the countdown)\n\
+         Inputs:   10\n  \
+         62 ▼ HardDrop\n  \
+         62 ▲ HardDrop\n  \
+         86 ▼ MoveRight\n  \
+         ... and 7 more inputs\n"
+    );
+}
+
+#[test]
+fn test_drop_intervals() {
+    use crate::analysis::drop_intervals;
+    use crate::{InputEventKey, InputEventKind, GAME_START_FRAME};
+
+    let data = get_test_cases().remove("huge").and_then(|case| case.data).unwrap();
+    let mut drops: Vec<u64> = data
+        .inputs
+        .iter()
+        .filter(|input| input.kind == InputEventKind::Press && input.key == InputEventKey::HardDrop)
+        .map(|input| input.frame)
+        .filter(|&frame| frame >= GAME_START_FRAME)
+        .collect();
+    drops.sort_unstable();
+
+    // The intervals add up to the time from the first drop to the last one
+    let intervals = drop_intervals(&data);
+    assert_eq!(intervals.intervals.len(), drops.len() - 1);
+    assert_eq!(intervals.intervals.iter().sum::<u64>(), drops[drops.len() - 1] - drops[0]);
+    assert_eq!((intervals.median, intervals.min), (28.0, Some(10)));
+
+    // The drops of `someinputs` are all during the countdown
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert!(drop_intervals(&data).intervals.is_empty());
+}
+
+#[test]
+fn test_summary_fast_path() {
+    use crate::{decode_base64_replay, InputParseMode, ParseOptions, ReplaySummary};
+
+    for (key, case) in get_test_cases() {
+        let Some(StoredReplay::Base64(base64)) = case.serialized else { continue };
+        let compressed = decode_base64_replay(&base64).unwrap();
+
+        let full = GameReplayData::try_from_compressed(&compressed, None).unwrap().summary();
+        let fast = ReplaySummary::from_compressed(&compressed, &ParseOptions::new()).unwrap();
+        assert_eq!(ReplaySummary { fingerprint: None, ..full.clone() }, fast, "Testcase '{key}'");
+        assert_eq!(full.fingerprint.is_some(), cfg!(feature = "fingerprint"));
+
+        // Relative timing changes the frames, but not the number of inputs
+        let relative = ParseOptions::new().parse_mode(InputParseMode::Relative);
+        let data = GameReplayData::try_from_compressed_with(&compressed, &relative).unwrap();
+        let fast = ReplaySummary::from_compressed(&compressed, &relative).unwrap();
+        assert_eq!((fast.inputs, fast.last_input_frame), (data.inputs.len(), data.last_frame()));
+    }
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let json = serde_json::to_value(data.summary()).unwrap();
+    assert_eq!(json["modCount"], 0);
+    assert_eq!(json["firstInputFrame"], 62);
+    assert_eq!(json["tasUsed"], true);
+
+    // Fields added in later versions are filled in with defaults
+    let summary: ReplaySummary = serde_json::from_str(r#"{"player":"Nova","inputs":3}"#).unwrap();
+    assert_eq!((summary.player.as_str(), summary.inputs, summary.seed), ("Nova", 3, 0));
+
+    let empty = GameReplayData { metadata: data.metadata, ..Default::default() };
+    let compressed = empty.serialize_to_compressed(None).unwrap();
+    let fast = ReplaySummary::from_compressed(&compressed, &ParseOptions::new()).unwrap();
+    assert_eq!((fast.inputs, fast.first_input_frame, fast.duration_frames), (0, None, 0));
+}
+
+#[test]
+fn test_settings_file() {
+    use crate::PlayerSettings;
+
+    // Hand-written, not saved by the game: the settings `someinputs` was played with,
+    // plus ones replays don't record, e.g. the volume and the language
+    let json = PlayerSettings::try_from_settings_file(include_bytes!("settings/settings.json")).unwrap();
+    let lua = PlayerSettings::try_from_settings_file(include_bytes!("settings/settings.lua")).unwrap();
+    assert_eq!(json, lua);
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert!(json.diff(&data.metadata.setting).is_empty(), "{:?}", json.diff(&data.metadata.setting));
+    assert!(json.nonstandard.is_empty());
+}
+
+#[test]
+fn test_lua_table_round_trip() {
+    use crate::lua::LuaParser;
+    use crate::{GameReplayMetadata, PlayerSettings};
+
+    for (key, case) in get_test_cases() {
+        let Some(data) = case.data else { continue };
+        let lua = format!("return{}", data.metadata.setting.to_lua_table());
+        let settings = PlayerSettings::try_from_settings_file(lua.as_bytes()).unwrap();
+        assert_eq!(settings, data.metadata.setting, "Testcase '{key}'");
+
+        let value = LuaParser::parse(&data.metadata.to_lua_table()).unwrap();
+        let metadata: GameReplayMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(metadata, data.metadata, "Testcase '{key}'");
+    }
+}
+
+#[test]
+fn test_legacy_lua_metadata_fixture() {
+    use crate::{ParseOptions, SplitReplay};
+
+    // TODO: Replace with metadata from a real replay if one with a Lua table turns up. This one is
+    // written by hand with the metadata of `someinputs`, and split over lines for reading.
+    let lines = include_str!("metadata/legacy_lua.lua").lines().map(str::trim);
+    let lua: String = lines.filter(|line| !line.starts_with("--")).collect();
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    let raw = data.serialize_to_raw(None).unwrap();
+
+    let raw = SplitReplay::reassemble(lua.as_bytes(), SplitReplay::from_raw(&raw).unwrap().input_bytes);
+    let options = ParseOptions::new().legacy_lua_metadata(true);
+    assert_eq!(GameReplayData::try_from_raw_with(&raw, &options).unwrap(), data);
+}
+
+#[test]
+fn test_size_report() {
+    use crate::{decode_base64_replay, SizeReport};
+
+    let case = get_test_cases().remove("someinputs").unwrap();
+    let Some(StoredReplay::Base64(base64)) = case.serialized else { panic!("someinputs is base64") };
+    let compressed = decode_base64_replay(&base64).unwrap();
+    let data = case.data.unwrap();
+
+    let report = data.size_report().unwrap();
+    let raw = data.serialize_to_raw(None).unwrap();
+    assert_eq!(report.metadata_bytes + report.input_bytes, raw.len() - 1);
+    assert_eq!(report.raw_bytes(), raw.len());
+    assert_eq!(report.events, 10);
+    assert_eq!(report.bytes_per_event, report.input_bytes as f64 / 10.0);
+    assert_eq!(report.compressed_bytes, data.serialize_to_compressed(None).unwrap().len());
+    assert_eq!(report.compression_ratio, report.compressed_bytes as f64 / raw.len() as f64);
+
+    // The original is measured as it was compressed, with the same sections
+    let original = SizeReport::from_compressed(&compressed).unwrap();
+    assert_eq!(original.compressed_bytes, compressed.len());
+    assert_eq!((original.input_bytes, original.events), (report.input_bytes, report.events));
+    assert!(original.compression_ratio > 0.0 && original.compression_ratio < 1.0, "{original:?}");
+
+    let empty = GameReplayData { metadata: data.metadata, ..Default::default() }.size_report().unwrap();
+    assert_eq!((empty.input_bytes, empty.events, empty.bytes_per_event), (0, 0, 0.0));
+}
+
+#[test]
+fn test_annotations_round_trip() {
+    use crate::{ParseOptions, ANNOTATIONS_KEY};
+
+    let mut data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    data.set_annotation(99, "soft drop for the T-spin");
+    data.set_annotation(0, "");
+    data.set_annotation(500, "done");
+    data.set_annotation(128, "two\nlines");
+    let annotations = data.annotations();
+
+    // The game ignores the key, so annotated replays are still compatible with it
+    let strict = ParseOptions::new().strict(true);
+    let base64 = data.serialize_to_base64(None).unwrap();
+    let (parsed, report) = GameReplayData::try_from_base64_with_report(&base64, &strict).unwrap();
+    assert_eq!(parsed.annotations(), annotations);
+    assert_eq!(parsed, data);
+    assert!(report.warnings.is_empty(), "{report:?}");
+
+    let text = data.to_text().unwrap();
+    assert!(text.contains("\n[inputs]\n#@0\n62 press HardDrop\n"), "{text}");
+    assert!(text.contains("86 release MoveRight\n#@99 soft drop for the T-spin\n99 press SoftDrop\n"));
+    assert!(text.ends_with("180 release RotateRight\n#@500 done\n"));
+    assert!(text.contains(&format!("{ANNOTATIONS_KEY}.128 = \"two\\nlines\"\n")));
+    assert_eq!(GameReplayData::from_text(&text).unwrap(), data);
+
+    let edited = text.replace("#@500 done", "# #@500 isn't an annotation\n#@180  indented");
+    let edited = GameReplayData::from_text(&edited).unwrap();
+    assert_eq!(edited.annotations().get(&180).map(String::as_str), Some(" indented"));
+    assert_eq!(edited.annotations().get(&500), None);
+}
+
+#[test]
+fn test_aggregate_stats() {
+    use crate::analysis::{aggregate, StatsAccumulator};
+    use crate::stats::ReplayStats;
+    use crate::InputEventKey;
+
+    let cases = get_test_cases();
+    let replays: Vec<GameReplayData> = ["earlyinput", "huge", "someinputs"]
+        .into_iter()
+        .map(|name| cases[name].data.clone().unwrap())
+        .collect();
+
+    let stats = aggregate(&replays);
+    assert_eq!(stats.replays, 3);
+    assert_eq!(stats.tas_flagged, 2);
+    assert_eq!(stats.gameplay_frames, 114831);
+    assert_eq!(stats.presses_per_key.values().sum::<u64>(), 9442);
+    assert_eq!(stats.presses_per_key[&InputEventKey::HardDrop], 1351);
+
+    // Only huge has inputs after the countdown
+    let huge = ReplayStats::from_replay(&replays[1]);
+    assert_eq!(stats.average_kps.replays, 1);
+    assert!((stats.average_kps.mean - huge.average_kps).abs() < 1e-6, "{:?}", stats.average_kps);
+    assert_eq!((stats.average_kps.min, stats.average_kps.max), (huge.average_kps, huge.average_kps));
+    assert_eq!(stats.average_kps.histogram.clone().into_iter().collect::<Vec<_>>(), [(4, 1)]);
+    assert_eq!(stats.peak_kps, 19.0);
+
+    assert_eq!(stats.das.clone().into_iter().collect::<Vec<_>>(), [(3, 2), (4, 1)]);
+    assert_eq!(stats.most_common_das(), Some(3));
+    assert_eq!(stats.most_common_arr(), Some(0));
+    assert_eq!(stats.most_common_mode(), Some("sprint_10l"));
+    assert_eq!(stats.modes["marathon_inf"], 1);
+    assert_eq!(stats.versions.len(), 3);
+
+    // Merging partial statistics gives the same result
+    let mut first = StatsAccumulator::new();
+    first.add(&replays[2]);
+    let mut rest = StatsAccumulator::new();
+    rest.add(&replays[1]);
+    rest.add(&replays[0]);
+    first.merge(rest);
+    assert_eq!(first.finish(), stats);
+
+    let json = serde_json::to_value(&stats).unwrap();
+    assert_eq!(json["das"]["3"], 2);
+    assert_eq!(json["pressesPerKey"]["HardDrop"], 1351);
+}
+
+#[test]
+fn test_fixture_modes() {
+    for (key, case) in get_test_cases() {
+        let Some(data) = case.data else { continue };
+        assert!(data.metadata.mode_info().is_some(), "Testcase '{key}' has mode '{}'", data.metadata.mode);
+    }
+
+    let data = get_test_cases().remove("someinputs").and_then(|case| case.data).unwrap();
+    assert_eq!(data.metadata.mode_display_name(), "Sprint 10L");
+}
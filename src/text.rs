@@ -0,0 +1,651 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use serde_json::{Map, Value};
+
+use crate::annotations::parse_frame;
+use crate::types::*;
+use crate::ANNOTATIONS_KEY;
+
+/// An error from parsing the text format, see [`GameReplayData::from_text`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextParseError {
+    /// The line the error is on, starting from 1.
+    pub line: usize,
+    /// The column the error is at, in characters, starting from 1.
+    pub column: usize,
+    /// What went wrong.
+    pub kind: TextParseErrorKind,
+}
+
+/// The kind of a [`TextParseError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextParseErrorKind {
+    /// Something other than what was expected was found.
+    Unexpected {
+        /// A description of what was expected.
+        expected: &'static str,
+        /// What was found instead, or an empty string if the line ended.
+        found: String,
+    },
+    /// A metadata value or a quoted key isn't valid JSON.
+    ///
+    /// Contains the message of the JSON parser.
+    InvalidJson(String),
+    /// A metadata key is set more than once.
+    DuplicateKey(String),
+    /// A section appears more than once.
+    DuplicateSection(String),
+    /// The metadata doesn't fit the replay format, e.g. the seed is a string or the player is missing.
+    ///
+    /// Contains the message of the metadata deserializer.
+    InvalidMetadata(String),
+    /// A relative frame, or the frame of an annotation, goes past the largest possible frame.
+    FrameOverflow,
+}
+
+impl fmt::Display for TextParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TextParseErrorKind::*;
+
+        write!(f, "Line {}, column {}: ", self.line, self.column)?;
+
+        match &self.kind {
+            Unexpected { expected, found } if found.is_empty() => {
+                write!(f, "expected {expected}, but the line ended")
+            }
+            Unexpected { expected, found } => write!(f, "expected {expected}, found '{found}'"),
+            InvalidJson(message) => write!(f, "invalid JSON: {message}"),
+            DuplicateKey(key) => write!(f, "the metadata key '{key}' is already set"),
+            DuplicateSection(section) => write!(f, "the section [{section}] already appeared"),
+            InvalidMetadata(message) => write!(f, "invalid metadata: {message}"),
+            FrameOverflow => write!(f, "the frame is too large"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TextParseError {}
+
+impl GameReplayData {
+    /// Writes the replay in a plain-text format meant for editing by hand and keeping in version control.
+    ///
+    /// The text consists of a `[metadata]` section with one `key = value` line per metadata field,
+    /// and an `[inputs]` section with one `frame kind key` line per input:
+    ///
+    /// ```text
+    /// [metadata]
+    /// mode = "sprint_40l"
+    /// player = "Nova"
+    /// setting.das = 8
+    /// version = "V0.17.22"
+    ///
+    /// [inputs]
+    /// 180 press HardDrop
+    /// 183 release HardDrop
+    /// ```
+    ///
+    /// Metadata values are JSON, and nested objects are written as dotted keys, sorted.
+    /// Keys that aren't made of letters, digits and underscores are quoted like JSON strings.
+    /// Values that are `null` are left out, as the game never writes them.
+    /// Keys are written by their [name][InputEventKey::name], which for keys added by forks of
+    /// the game is the name in the installed [`KeyRegistry`][crate::KeyRegistry].
+    ///
+    /// If [`input_mode`][GameReplayData::input_mode] differs from the mode inferred from the version,
+    /// it's written in the inputs section header, e.g. `[inputs relative]`.
+    ///
+    /// The [annotations][GameReplayData::annotations] are written as `#@frame text` comments
+    /// before the first input at or after their frame, and after the inputs if there's none.
+    /// Annotations spanning several lines are kept in the metadata section instead.
+    ///
+    /// Converting the text to a replay and back gives the same text, as long as the input mode
+    /// can be inferred from the version. See [`from_text`][GameReplayData::from_text] for the syntax.
+    pub fn to_text(&self) -> Result<String, ReplaySerializeError> {
+        let mut metadata = serde_json::to_value(&self.metadata)?;
+
+        let mut annotations = self.annotations();
+        annotations.retain(|_, text| !text.contains(['\n', '\r']));
+        if let Value::Object(map) = &mut metadata {
+            if let Some(Value::Object(stored)) = map.get_mut(ANNOTATIONS_KEY) {
+                stored.retain(|frame, _| !parse_frame(frame).is_some_and(|f| annotations.contains_key(&f)));
+                if stored.is_empty() {
+                    map.remove(ANNOTATIONS_KEY);
+                }
+            }
+        }
+
+        let mut out = String::from("[metadata]\n");
+        if let Value::Object(map) = &metadata {
+            write_entries(&mut out, "", map);
+        }
+
+        out.push_str("\n[inputs");
+        let inferred_mode = InputParseMode::try_infer_from_version(&self.metadata.version);
+        match self.input_mode {
+            Some(mode) if inferred_mode != Some(mode) => {
+                write!(out, " {}", format!("{mode:?}").to_ascii_lowercase()).unwrap();
+            }
+            _ => {}
+        }
+        out.push_str("]\n");
+
+        let mut annotations = annotations.into_iter().peekable();
+        for input in &self.inputs {
+            while let Some((frame, text)) = annotations.next_if(|&(frame, _)| frame <= input.frame) {
+                write_annotation(&mut out, frame, &text);
+            }
+
+            let kind = format!("{:?}", input.kind).to_ascii_lowercase();
+            writeln!(out, "{} {kind} {}", input.frame, input.key.name()).unwrap();
+        }
+        for (frame, text) in annotations {
+            write_annotation(&mut out, frame, &text);
+        }
+
+        Ok(out)
+    }
+
+    /// Parses a replay from the plain-text format written by [`to_text`][GameReplayData::to_text].
+    ///
+    /// On top of what `to_text` writes, the parser accepts:
+    /// - comments, starting with `#` and running to the end of the line;
+    /// - annotations anywhere, as `#@frame text` lines, which are added to the
+    ///   [annotations][GameReplayData::annotations] of the replay;
+    /// - empty lines, and whitespace around keys, values and input fields;
+    /// - frames relative to the previous input, e.g. `+3 press MoveLeft`,
+    ///   with the first input being relative to frame 0;
+    /// - kind and key names in any case, e.g. `PRESS harddrop`.
+    ///
+    /// Errors point at the line and column where the problem is.
+    pub fn from_text(text: &str) -> Result<GameReplayData, TextParseError> {
+        let mut parser = TextParser::default();
+
+        for (index, line) in text.lines().enumerate() {
+            parser.parse_line(index + 1, line)?;
+        }
+
+        let metadata = serde_json::from_value(Value::Object(parser.metadata));
+        let metadata = metadata.map_err(|e| TextParseError {
+            line: parser.metadata_line.max(1),
+            column: 1,
+            kind: TextParseErrorKind::InvalidMetadata(e.to_string()),
+        })?;
+
+        Ok(GameReplayData { inputs: parser.inputs, metadata, input_mode: parser.input_mode })
+    }
+}
+
+/// Writes an annotation as a comment line, without a trailing space if it's empty.
+fn write_annotation(out: &mut String, frame: u64, text: &str) {
+    match text {
+        "" => writeln!(out, "#@{frame}").unwrap(),
+        text => writeln!(out, "#@{frame} {text}").unwrap(),
+    }
+}
+
+/// Writes the entries of a JSON object as `key = value` lines, flattening nested objects.
+fn write_entries(out: &mut String, prefix: &str, map: &Map<String, Value>) {
+    // The map may keep the order the keys were read in
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+
+    for (key, value) in entries {
+        let path = if is_plain_key(key) {
+            format!("{prefix}{key}")
+        } else {
+            format!("{prefix}{}", Value::from(key.as_str()))
+        };
+
+        match value {
+            Value::Null => {}
+            Value::Object(map) if map.values().any(|v| !v.is_null()) => {
+                write_entries(out, &format!("{path}."), map)
+            }
+            Value::Object(_) => writeln!(out, "{path} = {{}}").unwrap(),
+            value => writeln!(out, "{path} = {value}").unwrap(),
+        }
+    }
+}
+
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(is_plain_key_char)
+}
+
+fn is_plain_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    None,
+    Metadata,
+    Inputs,
+}
+
+struct TextParser {
+    section: Section,
+    metadata: Map<String, Value>,
+    /// The line of the metadata section header, where errors about the metadata as a whole point to.
+    metadata_line: usize,
+    inputs: Vec<GameInputEvent>,
+    input_mode: Option<InputParseMode>,
+    seen_inputs: bool,
+}
+
+impl Default for TextParser {
+    fn default() -> Self {
+        TextParser {
+            section: Section::None,
+            metadata: Map::new(),
+            metadata_line: 0,
+            inputs: Vec::new(),
+            input_mode: None,
+            seen_inputs: false,
+        }
+    }
+}
+
+/// A line being parsed, for building errors with the right position.
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+impl Line<'_> {
+    fn error(&self, byte: usize, kind: TextParseErrorKind) -> TextParseError {
+        TextParseError {
+            line: self.number,
+            column: self.text[..byte].chars().count() + 1,
+            kind,
+        }
+    }
+
+    fn unexpected(&self, byte: usize, expected: &'static str) -> TextParseError {
+        let found = self.text[byte..]
+            .split_whitespace()
+            .next()
+            .filter(|token| !token.starts_with('#'))
+            .unwrap_or_default();
+
+        self.error(byte, TextParseErrorKind::Unexpected { expected, found: found.to_string() })
+    }
+
+    /// Returns the byte offset of the first character at or after `byte` that isn't whitespace.
+    fn skip_whitespace(&self, byte: usize) -> usize {
+        let rest = &self.text[byte..];
+        byte + rest.len() - rest.trim_start().len()
+    }
+
+    /// Checks that nothing but whitespace or a comment comes at or after `byte`.
+    fn expect_end(&self, byte: usize) -> Result<(), TextParseError> {
+        let byte = self.skip_whitespace(byte);
+
+        match self.text[byte..].chars().next() {
+            None | Some('#') => Ok(()),
+            Some(_) => Err(self.unexpected(byte, "the end of the line or a comment")),
+        }
+    }
+
+    /// Splits the line into whitespace-separated tokens with their byte offsets, up to a comment.
+    fn tokens(&self) -> Vec<(usize, &str)> {
+        let code = &self.text[..self.text.find('#').unwrap_or(self.text.len())];
+
+        code.split_whitespace()
+            .map(|token| (token.as_ptr() as usize - code.as_ptr() as usize, token))
+            .collect()
+    }
+
+    /// The byte offset where the code of the line ends, for errors about missing tokens.
+    fn code_end(&self) -> usize {
+        let code = &self.text[..self.text.find('#').unwrap_or(self.text.len())];
+        code.trim_end().len()
+    }
+}
+
+impl TextParser {
+    fn parse_line(&mut self, number: usize, text: &str) -> Result<(), TextParseError> {
+        let line = Line { number, text };
+        let start = line.skip_whitespace(0);
+
+        match text[start..].chars().next() {
+            None => Ok(()),
+            Some('#') => self.parse_comment(&line, start),
+            Some('[') => self.parse_section_header(&line, start),
+            Some(_) => match self.section {
+                Section::None => Err(line.unexpected(start, "a [metadata] or [inputs] section header")),
+                Section::Metadata => self.parse_metadata_entry(&line, start),
+                Section::Inputs => self.parse_input(&line),
+            },
+        }
+    }
+
+    /// Reads the annotation of a `#@frame text` line, ignoring other comments.
+    fn parse_comment(&mut self, line: &Line, start: usize) -> Result<(), TextParseError> {
+        let Some(rest) = line.text[start..].strip_prefix("#@") else {
+            return Ok(());
+        };
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (frame, text) = rest.split_at(digits);
+
+        let text = match text.chars().next() {
+            _ if frame.is_empty() => return Ok(()),
+            None => text,
+            Some(c) if c.is_whitespace() => &text[c.len_utf8()..],
+            Some(_) => return Ok(()),
+        };
+        let Ok(frame) = frame.parse::<u64>() else {
+            return Err(line.error(start + 2, TextParseErrorKind::FrameOverflow));
+        };
+
+        let path = [ANNOTATIONS_KEY.to_string(), frame.to_string()];
+        if !insert_path(&mut self.metadata, &path, Value::String(text.to_string())) {
+            let key = format!("{ANNOTATIONS_KEY}.{frame}");
+            return Err(line.error(start, TextParseErrorKind::DuplicateKey(key)));
+        }
+
+        Ok(())
+    }
+
+    fn parse_section_header(&mut self, line: &Line, start: usize) -> Result<(), TextParseError> {
+        let name_start = line.skip_whitespace(start + 1);
+        let Some(len) = line.text[name_start..].find(']') else {
+            return Err(line.error(
+                line.code_end(),
+                TextParseErrorKind::Unexpected { expected: "']'", found: String::new() },
+            ));
+        };
+        line.expect_end(name_start + len + 1)?;
+
+        let mut words = line.text[name_start..name_start + len].split_whitespace();
+        let name = words.next().unwrap_or_default();
+
+        let (section, seen) = match name {
+            "metadata" => (Section::Metadata, self.metadata_line != 0),
+            "inputs" => (Section::Inputs, self.seen_inputs),
+            _ => {
+                let found = name.to_string();
+                let expected = "'metadata' or 'inputs'";
+                return Err(line.error(name_start, TextParseErrorKind::Unexpected { expected, found }));
+            }
+        };
+        if seen {
+            return Err(line.error(start, TextParseErrorKind::DuplicateSection(name.to_string())));
+        }
+
+        if section == Section::Inputs {
+            if let Some(mode) = words.next() {
+                let mode_start = mode.as_ptr() as usize - line.text.as_ptr() as usize;
+
+                self.input_mode = Some(match mode.to_ascii_lowercase().as_str() {
+                    "absolute" => InputParseMode::Absolute,
+                    "relative" => InputParseMode::Relative,
+                    _ => return Err(line.unexpected(mode_start, "'absolute' or 'relative'")),
+                });
+            }
+
+            self.seen_inputs = true;
+        } else {
+            self.metadata_line = line.number;
+        }
+
+        if let Some(extra) = words.next() {
+            let extra_start = extra.as_ptr() as usize - line.text.as_ptr() as usize;
+            return Err(line.unexpected(extra_start, "']'"));
+        }
+
+        self.section = section;
+        Ok(())
+    }
+
+    fn parse_metadata_entry(&mut self, line: &Line, start: usize) -> Result<(), TextParseError> {
+        let mut path = Vec::new();
+        let mut pos = start;
+
+        loop {
+            let rest = &line.text[pos..];
+
+            if rest.starts_with('"') {
+                let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+                match stream.next() {
+                    Some(Ok(key)) => path.push(key),
+                    Some(Err(e)) => {
+                        return Err(line.error(pos, TextParseErrorKind::InvalidJson(json_message(&e))))
+                    }
+                    None => unreachable!("the key starts with a quote"),
+                }
+                pos += stream.byte_offset();
+            } else {
+                let len = rest.find(|c| !is_plain_key_char(c)).unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(line.unexpected(pos, "a key made of letters, digits and underscores"));
+                }
+
+                path.push(rest[..len].to_string());
+                pos += len;
+            }
+
+            if line.text[pos..].starts_with('.') {
+                pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        pos = line.skip_whitespace(pos);
+        if !line.text[pos..].starts_with('=') {
+            return Err(line.unexpected(pos, "'=' or '.' after the key"));
+        }
+        pos = line.skip_whitespace(pos + 1);
+
+        let mut stream = serde_json::Deserializer::from_str(&line.text[pos..]).into_iter::<Value>();
+        let value = match stream.next() {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                let column_offset = line.text[pos..]
+                    .char_indices()
+                    .nth(e.column().saturating_sub(1))
+                    .map_or(line.text.len() - pos, |(byte, _)| byte);
+
+                let kind = TextParseErrorKind::InvalidJson(json_message(&e));
+                return Err(line.error(pos + column_offset, kind));
+            }
+            None => return Err(line.unexpected(pos, "a JSON value")),
+        };
+        line.expect_end(pos + stream.byte_offset())?;
+
+        if !insert_path(&mut self.metadata, &path, value) {
+            let key = path.iter().map(|key| key.as_str()).collect::<Vec<_>>().join(".");
+            return Err(line.error(start, TextParseErrorKind::DuplicateKey(key)));
+        }
+
+        Ok(())
+    }
+
+    fn parse_input(&mut self, line: &Line) -> Result<(), TextParseError> {
+        let tokens = line.tokens();
+        let end = line.code_end();
+        let token = |index: usize| tokens.get(index).copied().unwrap_or((end, ""));
+
+        let (frame_start, frame) = token(0);
+        let prev_frame = self.inputs.last().map_or(0, |i| i.frame);
+        let frame = match frame.strip_prefix('+') {
+            Some(delta) => delta.parse::<u64>().ok().map(|delta| {
+                prev_frame
+                    .checked_add(delta)
+                    .ok_or_else(|| line.error(frame_start, TextParseErrorKind::FrameOverflow))
+            }),
+            None => frame.parse::<u64>().ok().map(Ok),
+        };
+        let frame = match frame {
+            Some(frame) => frame?,
+            None => return Err(line.unexpected(frame_start, "a frame, or '+' and a number of frames")),
+        };
+
+        let (kind_start, kind) = token(1);
+        let Some(kind) = InputEventKind::from_name(kind) else {
+            return Err(line.unexpected(kind_start, "'press' or 'release'"));
+        };
+
+        let (key_start, key) = token(2);
+        let Ok(key) = key.parse::<InputEventKey>() else {
+            return Err(line.unexpected(key_start, "a key name, e.g. 'HardDrop'"));
+        };
+
+        if let Some(&(extra_start, _)) = tokens.get(3) {
+            return Err(line.unexpected(extra_start, "the end of the line or a comment"));
+        }
+
+        self.inputs.push(GameInputEvent { frame, kind, key });
+        Ok(())
+    }
+}
+
+/// Inserts a value at a path of keys, creating the objects along the way.
+///
+/// Returns `false` if the path is already set, or goes through a value that isn't an object.
+fn insert_path(map: &mut Map<String, Value>, path: &[String], value: Value) -> bool {
+    let (last, parents) = path.split_last().expect("paths have at least one key");
+
+    let mut map = map;
+    for key in parents {
+        let entry = map.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+        match entry {
+            Value::Object(object) => map = object,
+            _ => return false,
+        }
+    }
+
+    if map.contains_key(last) {
+        return false;
+    }
+
+    map.insert(last.clone(), value);
+    true
+}
+
+/// Returns the message of a JSON error without the position, which is reported separately.
+fn json_message(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_syntax() {
+        let text = r#"
+            # A comment before any section
+            [metadata]
+            player = "Nova # not a comment" # a comment
+            seed=5
+            version = "V0.17.22"
+            date = ""
+            mode = "sprint_40l"
+            setting.das = 8
+            setting."odd key" = [1, 2]
+
+            [ inputs ]
+            180 press HardDrop
+            +3 RELEASE harddrop   # relative to the previous input
+            +0 press MoveLeft
+        "#;
+
+        let data = GameReplayData::from_text(text).unwrap();
+
+        assert_eq!(
+            data.inputs,
+            [
+                GameInputEvent::press(180, HardDrop),
+                GameInputEvent::release(183, HardDrop),
+                GameInputEvent::press(183, MoveLeft),
+            ]
+        );
+        assert_eq!(data.metadata.player, "Nova # not a comment");
+        assert_eq!(data.metadata.seed, 5);
+        assert_eq!(data.metadata.setting.das, Some(8));
+        assert_eq!(data.metadata.setting.nonstandard["odd key"], serde_json::json!([1, 2]));
+        assert_eq!(data.input_mode, None);
+
+        let text = data.to_text().unwrap();
+        assert!(text.contains("setting.\"odd key\" = [1,2]\n"));
+        assert!(text.contains("\n[inputs]\n180 press HardDrop\n183 release HardDrop\n"));
+        assert_eq!(GameReplayData::from_text(&text).unwrap(), data);
+    }
+
+    #[test]
+    fn test_input_mode() {
+        let mut data = GameReplayData {
+            metadata: GameReplayMetadata { version: "V0.17.22".to_string(), ..Default::default() },
+            ..Default::default()
+        };
+
+        data.input_mode = Some(InputParseMode::Absolute);
+        assert!(data.to_text().unwrap().contains("\n[inputs]\n"));
+
+        data.input_mode = Some(InputParseMode::Relative);
+        let text = data.to_text().unwrap();
+        assert!(text.contains("\n[inputs relative]\n"));
+        assert_eq!(GameReplayData::from_text(&text).unwrap(), data);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str| GameReplayData::from_text(text).unwrap_err().to_string();
+
+        assert_eq!(
+            error("180 press HardDrop"),
+            "Line 1, column 1: expected a [metadata] or [inputs] section header, found '180'"
+        );
+        assert_eq!(error("[replay]"), "Line 1, column 2: expected 'metadata' or 'inputs', found 'replay'");
+        assert_eq!(
+            error("[inputs]\n180 press HardDrop\n  181 tap Hold"),
+            "Line 3, column 7: expected 'press' or 'release', found 'tap'"
+        );
+        assert_eq!(
+            error("[inputs]\n180 press Teleport"),
+            "Line 2, column 11: expected a key name, e.g. 'HardDrop', found 'Teleport'"
+        );
+        assert_eq!(
+            error("[inputs]\n180 press # comment"),
+            "Line 2, column 10: expected a key name, e.g. 'HardDrop', but the line ended"
+        );
+        assert_eq!(
+            error("[inputs]\n-1 press Hold"),
+            "Line 2, column 1: expected a frame, or '+' and a number of frames, found '-1'"
+        );
+        assert_eq!(
+            error("[inputs]\n180 press Hold\n+18446744073709551615 release Hold"),
+            "Line 3, column 1: the frame is too large"
+        );
+        assert_eq!(
+            error("[inputs]\n180 press Hold Hold"),
+            "Line 2, column 16: expected the end of the line or a comment, found 'Hold'"
+        );
+        assert_eq!(
+            error("[metadata]\nplayer \"Nova\""),
+            "Line 2, column 8: expected '=' or '.' after the key, found '\"Nova\"'"
+        );
+        assert_eq!(
+            error("[metadata]\nplayer = Nova"),
+            "Line 2, column 10: invalid JSON: expected value"
+        );
+        assert_eq!(
+            error("[metadata]\nseed = 1\nseed = 2"),
+            "Line 3, column 1: the metadata key 'seed' is already set"
+        );
+        assert_eq!(
+            error("[inputs]\n[inputs]"),
+            "Line 2, column 1: the section [inputs] already appeared"
+        );
+        assert!(error("[metadata]\nseed = \"one\"").starts_with("Line 1, column 1: invalid metadata: "));
+    }
+}
@@ -0,0 +1,145 @@
+use alloc::vec::Vec;
+
+use crate::keyset::KeySet;
+use crate::types::*;
+
+/// A timeline of which keys are held throughout a replay.
+///
+/// The timeline stores a snapshot of the held keys every time they change,
+/// so looking up the held keys at any frame is a binary search rather than a
+/// re-scan of the inputs.
+///
+/// The held keys at a certain frame are the keys held after all the events on that frame
+/// have happened. That is, a key pressed and released on the same frame is not held on that frame.
+///
+/// Pressing a key that is already held, or releasing a key that isn't held, does nothing.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct KeyStateTimeline {
+    /// The held keys from each frame onwards, sorted by frame.
+    ///
+    /// Only contains frames where the held keys changed.
+    snapshots: Vec<(u64, KeySet)>,
+}
+
+impl KeyStateTimeline {
+    /// Creates a timeline from a list of input events.
+    ///
+    /// The events don't need to be sorted; they're sorted by frame internally,
+    /// with events on the same frame keeping their original order.
+    pub fn new(inputs: &[GameInputEvent]) -> KeyStateTimeline {
+        let mut inputs = inputs.to_vec();
+        inputs.sort_by_key(|i| i.frame);
+
+        let mut snapshots: Vec<(u64, KeySet)> = Vec::new();
+        let mut held = KeySet::new();
+
+        for (index, input) in inputs.iter().enumerate() {
+            match input.kind {
+                InputEventKind::Press => held.insert(input.key),
+                InputEventKind::Release => held.remove(input.key),
+            };
+
+            let is_last_on_frame = inputs
+                .get(index + 1)
+                .is_none_or(|next| next.frame != input.frame);
+
+            let prev = snapshots.last().map_or(KeySet::new(), |&(_, keys)| keys);
+
+            if is_last_on_frame && held != prev {
+                snapshots.push((input.frame, held));
+            }
+        }
+
+        KeyStateTimeline { snapshots }
+    }
+
+    /// Returns the keys held at a certain frame.
+    pub fn held_at(&self, frame: u64) -> KeySet {
+        let index = self.snapshots.partition_point(|&(f, _)| f <= frame);
+
+        match index {
+            0 => KeySet::new(),
+            i => self.snapshots[i - 1].1,
+        }
+    }
+
+    /// Returns whether a key is held at a certain frame.
+    pub fn is_held(&self, frame: u64, key: InputEventKey) -> bool {
+        self.held_at(frame).contains(key)
+    }
+
+    /// Iterates over every change in the held keys, as pairs of the frame the change
+    /// happened in and the keys held from that frame onwards.
+    pub fn changes(&self) -> impl Iterator<Item = (u64, KeySet)> + '_ {
+        self.snapshots.iter().copied()
+    }
+}
+
+impl GameReplayData {
+    /// Creates a [`KeyStateTimeline`] from the inputs of the replay.
+    pub fn key_state_timeline(&self) -> KeyStateTimeline {
+        KeyStateTimeline::new(&self.inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InputEventKey::*;
+    use InputEventKind::*;
+
+    fn brute_force_held_at(inputs: &[GameInputEvent], frame: u64) -> KeySet {
+        let mut sorted = inputs.to_vec();
+        sorted.sort_by_key(|i| i.frame);
+
+        let mut held = KeySet::new();
+        for input in sorted.iter().take_while(|i| i.frame <= frame) {
+            match input.kind {
+                Press => held.insert(input.key),
+                Release => held.remove(input.key),
+            };
+        }
+
+        held
+    }
+
+    #[test]
+    fn test_timeline_against_brute_force() {
+        let inputs = [
+            GameInputEvent::press(40, MoveRight),
+            GameInputEvent::press(5, MoveLeft),
+            GameInputEvent::release(12, MoveLeft),
+            GameInputEvent::press(12, RotateLeft),
+            GameInputEvent::press(15, RotateLeft),
+            GameInputEvent::press(20, HardDrop),
+            GameInputEvent::release(20, HardDrop),
+            GameInputEvent::release(25, Hold),
+            GameInputEvent::release(30, RotateLeft),
+            GameInputEvent::press(30, SoftDrop),
+            GameInputEvent::release(40, SoftDrop),
+        ];
+
+        let timeline = KeyStateTimeline::new(&inputs);
+
+        for frame in 0..60 {
+            let expected = brute_force_held_at(&inputs, frame);
+
+            assert_eq!(timeline.held_at(frame), expected, "Mismatch at frame {frame}");
+
+            for key in [MoveLeft, MoveRight, RotateLeft, HardDrop, SoftDrop, Hold] {
+                assert_eq!(timeline.is_held(frame, key), expected.contains(key));
+            }
+        }
+
+        let changes: Vec<_> = timeline.changes().collect();
+        assert_eq!(
+            changes,
+            vec![
+                (5, KeySet::from_iter([MoveLeft])),
+                (12, KeySet::from_iter([RotateLeft])),
+                (30, KeySet::from_iter([SoftDrop])),
+                (40, KeySet::from_iter([MoveRight])),
+            ]
+        );
+    }
+}
@@ -1,13 +1,23 @@
-use std::{collections::{HashMap, HashSet}, string::FromUtf8Error};
+use alloc::format;
+use alloc::string::{FromUtf8Error, String};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
 
 use base64::DecodeError;
-use miniz_oxide::inflate::DecompressError;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
+use crate::codec::{KEY_MASK, KIND_BIT};
+use crate::compression::{DecompressError, DecompressErrorKind};
+use crate::vlq::VlqError;
+use crate::{DetectedFormat, StrictViolation};
+
 /// Represents the type of input event this is.  
 /// That is, whether or not this is a button press event, or a button release event.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum InputEventKind {
     /// A certain button is being pressed in the event.
     Press = 0,
@@ -26,6 +36,15 @@ impl TryFrom<u8> for InputEventKind {
     }
 }
 
+impl InputEventKind {
+    /// Finds the kind with a name, ignoring case, e.g. `press` or `Release`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        [Self::Press, Self::Release]
+            .into_iter()
+            .find(|kind| name.eq_ignore_ascii_case(&format!("{kind:?}")))
+    }
+}
+
 impl From<bool> for InputEventKind {
     fn from(value: bool) -> Self {
         match value {
@@ -54,8 +73,11 @@ impl From<InputEventKind> for bool {
 }
 
 /// Represents the key/button of the input event.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[allow(missing_docs)]
+#[repr(u8)]
 pub enum InputEventKey {
     MoveLeft = 1,
     MoveRight = 2,
@@ -79,6 +101,15 @@ pub enum InputEventKey {
     RightDrop = 18,
     LeftZangi = 19,
     RightZangi = 20,
+
+    /// A key added by a fork of the game, with its code from 21 to 31.
+    ///
+    /// Only parsed with a [`KeyRegistry`][crate::KeyRegistry] that has a name for the code,
+    /// see [`ParseOptions::key_registry`][crate::ParseOptions::key_registry].
+    /// Serializing one with a code outside of 21 to 31 fails with
+    /// [`InvalidKeyCode`][ReplaySerializeError::InvalidKeyCode].
+    #[cfg_attr(feature = "arbitrary", arbitrary(skip))]
+    Other(u8),
 }
 
 impl TryFrom<u8> for InputEventKey {
@@ -137,18 +168,27 @@ impl From<InputEventKey> for u8 {
             RightDrop => 18,
             LeftZangi => 19,
             RightZangi => 20,
+            Other(code) => code,
         }
     }
 }
 
 /// A struct representing a single input event in the game.
+///
+/// Events are ordered by frame, then with releases before presses, and then by key code,
+/// with an [`Other`][InputEventKey::Other] key after the known key with the same code.
+/// This is the order [`sort_inputs_canonical`][GameReplayData::sort_inputs_canonical] sorts by,
+/// so a sorted list of events can always be serialized.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GameInputEvent {
     /// A number representing the frame this event occurred in.
     /// 
-    /// Note that the game starts at frame 180, and the frames before that
-    /// happen during the game start countdown. Nevertheless,
-    /// the game still records inputs before the countdown finishes.
+    /// Note that the game starts at frame 180 ([`GAME_START_FRAME`][crate::GAME_START_FRAME]),
+    /// and the frames before that happen during the game start countdown. Nevertheless,
+    /// the game still records inputs before the countdown finishes.  
+    /// See [`gameplay_frame`][GameInputEvent::gameplay_frame] for the frame relative to the game start.
     pub frame: u64,
     /// The kind of input event this represents.  
     /// That is - whether or not this is a key press event or a key release event.
@@ -159,18 +199,73 @@ pub struct GameInputEvent {
     pub key: InputEventKey,
 }
 
+impl GameInputEvent {
+    /// A press of the key on the frame.
+    pub const fn press(frame: u64, key: InputEventKey) -> Self {
+        GameInputEvent { frame, kind: InputEventKind::Press, key }
+    }
+
+    /// A release of the key on the frame.
+    pub const fn release(frame: u64, key: InputEventKey) -> Self {
+        GameInputEvent { frame, kind: InputEventKind::Release, key }
+    }
+
+    /// The key events are ordered by, see the [type docs][GameInputEvent].
+    ///
+    /// The last part tells an `Other` key from the known key with the same code, which it isn't
+    /// equal to, so that the order agrees with [`Eq`].
+    fn sort_key(&self) -> (u64, bool, u8, bool) {
+        let other = matches!(self.key, InputEventKey::Other(_));
+        (self.frame, self.kind == InputEventKind::Press, u8::from(self.key), other)
+    }
+}
+
+impl Ord for GameInputEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for GameInputEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// A struct representing all the data contained within the game replay.
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
-pub struct GameReplayData {
+///
+/// The metadata is a [`GameReplayMetadata`] by default, but it can be any [`ReplayMetadata`],
+/// e.g. a [`RawMetadata`] to keep the metadata JSON untouched.
+///
+/// [`ReplayMetadata`]: crate::ReplayMetadata
+/// [`RawMetadata`]: crate::RawMetadata
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GameReplayData<M = GameReplayMetadata> {
     /// A list of game input events that happened during the replay.
     pub inputs: Vec<GameInputEvent>,
     /// Metadata contained within the replay data.
-    pub metadata: GameReplayMetadata,
+    pub metadata: M,
+    /// The input timing to use when serializing the replay, if no input parse mode
+    /// is passed in explicitly.
+    ///
+    /// If this is [`None`], the input parse mode is inferred from the game version instead.  
+    /// See [`convert_timing`][GameReplayData::convert_timing] for more information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_mode: Option<InputParseMode>,
+}
+
+// Only for the default metadata type, so that `GameReplayData::default()` still infers it
+impl Default for GameReplayData {
+    fn default() -> Self {
+        GameReplayData { inputs: Vec::new(), metadata: GameReplayMetadata::default(), input_mode: None }
+    }
 }
 
 // TODO: Find more version info for these entries
 /// A struct representing the settings of the player who made the replay.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PlayerSettings {
     /// The attack FX slider in the video settings.
@@ -286,15 +381,26 @@ pub struct PlayerSettings {
     /// The "draw active piece" option in the video settings.
     pub block: Option<bool>,
     /// The rotation center opacity option in the video settings.
-    pub center: Option<f64>,
+    ///
+    /// Normal values: 0 to 1  
+    /// The opacities are kept as [`Number`][serde_json::Number]s so they're written back exactly
+    /// as they were read (e.g. `1` instead of `1.0`). See [`center_f64`][PlayerSettings::center_f64].
+    pub center: Option<serde_json::Number>,
     /// The starting orientations of all the pieces.
     /// 
     /// Normally contains 29 elements: 7 tetrominoes, 18 pentominoes, 2 trominoes, 1 domino, and 1 monomino, in that order.
+    /// See [`face_of`][PlayerSettings::face_of] and [`PieceId`][crate::PieceId].
     pub face: Option<Vec<u64>>,
     /// The ghost piece opacity option in the video settings.
-    pub ghost: Option<f64>,
+    ///
+    /// Normal values: 0 to 1  
+    /// See [`ghost_f64`][PlayerSettings::ghost_f64].
+    pub ghost: Option<serde_json::Number>,
     /// The grid opacity option in the video settings.
-    pub grid: Option<f64>,
+    ///
+    /// Normal values: 0 to 1  
+    /// See [`grid_f64`][PlayerSettings::grid_f64].
+    pub grid: Option<serde_json::Number>,
     /// The screen scrolling option in the video settings.
     pub high_cam: Option<bool>,
     /// The spawn preview option in the video settings.
@@ -304,12 +410,14 @@ pub struct PlayerSettings {
     /// The colors of all the pieces.
     /// 
     /// Normally contains 29 elements: 7 tetrominoes, 18 pentominoes, 2 trominoes, 1 domino, and 1 monomino, in that order.
+    /// See [`skin_of`][PlayerSettings::skin_of] and [`PieceId`][crate::PieceId].
     pub skin: Option<Vec<u64>>,
     /// THe smooth falling option option in the video settings.
     pub smooth: Option<bool>,
     // TODO: Investigate what this does
     // ...seems like I somehow got it at Jul 11 2024
     // https://github.com/techmino-hub/techmino-replay-parser/commit/36b4ab33acb451c3a76ef951ef58ae308d711c50
+    /// The `swap` option. Its purpose is currently unknown.
     pub swap: Option<bool>,
     /// The line clear popups option in the video settings.
     pub text: Option<bool>,
@@ -323,12 +431,33 @@ pub struct PlayerSettings {
     pub ft_lock: Option<bool>,
     
     /// Additional settings that may not be standard.
+    ///
+    /// With the `preserve-order` feature, the keys stay in the order they were read in, so they're
+    /// written back in that order; without it, they're sorted. Comparisons ignore the order either way.
     #[serde(flatten)]
-    pub nonstandard: HashMap<String, serde_json::Value>,
+    pub nonstandard: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PlayerSettings {
+    /// The rotation center opacity as a float.
+    pub fn center_f64(&self) -> Option<f64> {
+        self.center.as_ref().and_then(|n| n.as_f64())
+    }
+
+    /// The ghost piece opacity as a float.
+    pub fn ghost_f64(&self) -> Option<f64> {
+        self.ghost.as_ref().and_then(|n| n.as_f64())
+    }
+
+    /// The grid opacity as a float.
+    pub fn grid_f64(&self) -> Option<f64> {
+        self.grid.as_ref().and_then(|n| n.as_f64())
+    }
 }
 
 /// A struct representing the metadata stored within the replay.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct GameReplayMetadata {
     /// Whether or not the replay is marked as a TAS.
@@ -337,7 +466,8 @@ pub struct GameReplayMetadata {
     /// The 'private' field of the replay, used to store mode-specific data.  
     /// Its contents differ based on the mode played.  
     /// Currently, only the `custom_clear` and `custom_puzzle` modes are known to
-    /// store any data here.
+    /// store any data here; see [`custom_clear_data`][GameReplayMetadata::custom_clear_data]
+    /// and [`custom_puzzle_data`][GameReplayMetadata::custom_puzzle_data].
     pub private: Option<serde_json::Value>,
 
     /// The username of the player.
@@ -358,6 +488,7 @@ pub struct GameReplayMetadata {
     /// A list of mods applied to the run.
     ///
     /// It's in the format of [mod, value], where mod is the mod ID and value is the value given to the mod.
+    /// See [`mods_typed`][GameReplayMetadata::mods_typed] for what the IDs mean.
     /// 
     /// Note: the original metadata JSON has calls this value `mod`, but since it's misleading (not plural)
     /// and is a special keyword in Rust, this has been renamed to `mods` in the struct.  
@@ -375,8 +506,10 @@ pub struct GameReplayMetadata {
     pub setting: PlayerSettings,
 
     /// Additional replay metadata, if any, that may not be standard.
+    ///
+    /// The keys are kept in order like in [`PlayerSettings::nonstandard`].
     #[serde(flatten)]
-    pub nonstandard: HashMap<String, serde_json::Value>,
+    pub nonstandard: serde_json::Map<String, serde_json::Value>,
 }
 
 /// An error from parsing the replay data.
@@ -403,10 +536,24 @@ pub enum ReplayParseError {
     MetadataNotUtf8(FromUtf8Error),
 
     /// The metadata could not be deserialized into the [GameReplayMetadata] struct,
-    /// possibly due to missing values.
+    /// either because it isn't valid JSON, or because values are missing or have the wrong type.
     ///
     /// See [`serde_json`'s Error type][serde_json::Error] for more information.
-    MetadataDeserializeError(serde_json::Error),
+    MetadataDeserializeError {
+        /// Whether the metadata isn't valid JSON, or is valid JSON that doesn't fit the struct.
+        kind: MetadataErrorKind,
+        /// The offset of the byte the error was found at in the raw replay,
+        /// which starts with the metadata.
+        offset: usize,
+        /// The line the error was found at, starting from 1.
+        line: usize,
+        /// The column the error was found at, starting from 1.
+        column: usize,
+        /// The metadata from 40 bytes before the error to 40 bytes after it.
+        snippet: String,
+        /// The error from `serde_json`.
+        error: serde_json::Error,
+    },
 
     /// The mode in which to parse the inputs could not be inferred from the version string.
     ///
@@ -416,13 +563,52 @@ pub enum ReplayParseError {
     UnknownInputParseMode(String),
 
     /// The input data was malformed and could not be casted into the proper enum types.
+    ///
+    /// The malformed value is the key code, unless the frame doesn't fit in a [`u64`],
+    /// in which case it's the time.
     MalformedInputData {
-        /// The first input data index in which the input data is malformed.
-        position: u64,
-        /// The "frame"/time value of the input data point.
-        frame: u64,
-        /// The "kind" value of the input data point.
-        kind: u64,
+        /// The offset of the first byte of the malformed value in the input data,
+        /// i.e. the part of the raw replay after the metadata.
+        offset: usize,
+        /// The index of the malformed input, counting time and key code pairs.
+        index: usize,
+        /// The time of the input as stored, i.e. relative to the previous input in relative mode.
+        time: u64,
+        /// The frame of the input, or `None` if it doesn't fit in a [`u64`].
+        frame: Option<u64>,
+        /// The whole key code of the input, including the kind bit.
+        raw_code: u64,
+        /// The key bits of the key code, i.e. its lowest 5 bits.
+        key_bits: u8,
+        /// Whether the kind bit of the key code is set, i.e. whether it's a release.
+        kind_bit: bool,
+        /// Up to 4 decoded values before the time of the input.
+        values_before: Vec<u64>,
+        /// Up to 4 decoded values after the key code of the input.
+        values_after: Vec<u64>,
+    },
+
+    /// The input data contains a variable-length quantity that is cut off or too large.
+    ///
+    /// See [`VlqError`] for more information.
+    MalformedVlq(VlqError),
+
+    /// The replay parsed, but it's implausible, and [`strict`][crate::ParseOptions::strict]
+    /// parsing was requested.
+    ///
+    /// See [`StrictViolation`] for more information.
+    StrictViolation(StrictViolation),
+
+    /// The data is empty, or only whitespace.
+    EmptyInput,
+
+    /// The data is clearly something other than a replay, e.g. a screenshot.
+    ///
+    /// This is only detected for data that isn't in the expected encoding, e.g. base64 for
+    /// [`GameReplayData::try_from_base64`], before it's decoded.
+    NotAReplay {
+        /// What the data most likely is.
+        detected: DetectedFormat,
     },
 }
 
@@ -444,12 +630,126 @@ impl From<FromUtf8Error> for ReplayParseError {
     }
 }
 
-impl From<serde_json::Error> for ReplayParseError {
-    fn from(value: serde_json::Error) -> Self {
-        Self::MetadataDeserializeError(value)
+impl From<StrictViolation> for ReplayParseError {
+    fn from(value: StrictViolation) -> Self {
+        Self::StrictViolation(value)
+    }
+}
+
+impl From<VlqError> for ReplayParseError {
+    fn from(value: VlqError) -> Self {
+        Self::MalformedVlq(value)
+    }
+}
+
+impl fmt::Display for ReplayParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ReplayParseError::*;
+
+        match self {
+            ZlibDecompressError(error) => match error.kind {
+                DecompressErrorKind::Truncated => write!(f, "The compressed data is cut off"),
+                DecompressErrorKind::Corrupt => write!(f, "The compressed data is corrupt"),
+                DecompressErrorKind::TooLarge => write!(f, "The decompressed data is too large"),
+            },
+            Base64DecodeError(error) => write!(f, "The replay isn't valid base64: {error}"),
+            MetadataSeparatorNotFound => write!(f, "There's no line feed after the metadata"),
+            MetadataNotUtf8(error) => write!(f, "The metadata isn't valid UTF-8: {error}"),
+            MetadataDeserializeError { kind, offset, line, column, snippet, error } => {
+                match kind {
+                    MetadataErrorKind::Syntax => write!(f, "The metadata isn't valid JSON: ")?,
+                    MetadataErrorKind::Data => write!(f, "The metadata is valid JSON, but doesn't fit: ")?,
+                }
+
+                // The message without the position, which is written below
+                let message = format!("{error}");
+                let position = format!(" at line {} column {}", error.line(), error.column());
+                let message = message.strip_suffix(&position).unwrap_or(&message);
+
+                write!(f, "{message} at line {line}, column {column} (byte {offset}), near `{snippet}`")
+            }
+            UnknownInputParseMode(version) => {
+                write!(f, "Can't tell how the inputs are timed from the version '{version}'")
+            }
+            MalformedInputData {
+                offset,
+                index,
+                time,
+                frame,
+                raw_code,
+                key_bits,
+                kind_bit,
+                values_before,
+                values_after,
+            } => {
+                write!(f, "Input {index} at byte {offset} of the input data is malformed: ")?;
+
+                match frame {
+                    None => write!(f, "the frame of its time {time} is too large")?,
+                    Some(_) if raw_code & !(KIND_BIT | KEY_MASK) != 0 => {
+                        write!(f, "its key code {raw_code} has bits set above the kind bit")?
+                    }
+                    Some(_) => write!(f, "its key code {raw_code} has the unknown key {key_bits}")?,
+                }
+
+                let kind_bit = u8::from(*kind_bit);
+                match frame {
+                    Some(frame) => write!(f, " (frame {frame}, key {key_bits}, kind bit {kind_bit})")?,
+                    None => write!(f, " (key {key_bits}, kind bit {kind_bit})")?,
+                }
+
+                // The values around the input, with the input itself in brackets
+                write!(f, "; values around it:")?;
+                for value in values_before {
+                    write!(f, " {value}")?;
+                }
+                write!(f, " [{time} {raw_code}]")?;
+                for value in values_after {
+                    write!(f, " {value}")?;
+                }
+
+                Ok(())
+            }
+            MalformedVlq(VlqError::Truncated { position }) => {
+                write!(f, "The input data ends in the middle of the value at byte {position}")
+            }
+            MalformedVlq(VlqError::Overflow { position }) => {
+                write!(f, "The value at byte {position} of the input data is too large")
+            }
+            StrictViolation(violation) => write!(f, "The replay is implausible: {violation}"),
+            EmptyInput => write!(f, "The replay is empty; {EXPECTED_REPLAY}"),
+            NotAReplay { detected } => {
+                write!(f, "This is {detected}, not a replay")?;
+                let hint = match detected {
+                    DetectedFormat::Zip => " (the game's data export has the replays in it, unzip it first)",
+                    DetectedFormat::Lua => " (the game's save files aren't replays)",
+                    _ => "",
+                };
+                write!(f, "{hint}")?;
+                write!(f, "; {EXPECTED_REPLAY}")
+            }
+        }
     }
 }
 
+/// What the errors for data that isn't a replay tell the user to give instead.
+const EXPECTED_REPLAY: &str =
+    "expected the text the game copies when exporting a replay, or a .rep file from its replay folder";
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplayParseError {}
+
+/// What's wrong with metadata that can't be deserialized.
+///
+/// See [`ReplayParseError::MetadataDeserializeError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetadataErrorKind {
+    /// The metadata isn't valid JSON, e.g. it has a trailing comma or is cut off.
+    Syntax,
+    /// The metadata is valid JSON, but a value is missing or has the wrong type.
+    Data,
+}
+
 /// An error from serializing the replay data, e.g. to base64.
 #[derive(Debug)]
 pub enum ReplaySerializeError {
@@ -480,6 +780,23 @@ pub enum ReplaySerializeError {
     ///
     /// See [`serde_json`'s Error type][serde_json::Error] for more information.
     MetadataSerializeError(serde_json::Error),
+
+    /// The inputs have to be re-timed into another input mode, but they couldn't be decoded.
+    ///
+    /// Contains the error from decoding them. Only
+    /// [`LazyReplayData`][crate::LazyReplayData] can hold inputs that weren't decoded.
+    UndecodableInputs(ReplayParseError),
+
+    /// An input has an [`Other`][InputEventKey::Other] key with a code outside of 21 to 31.
+    ///
+    /// The code would be written as another key, or as a release if it doesn't fit in the
+    /// key bits, so the input can't be serialized.
+    InvalidKeyCode {
+        /// The index of the input.
+        index: usize,
+        /// The code of the key.
+        code: u8,
+    },
 }
 
 impl From<serde_json::Error> for ReplaySerializeError {
@@ -493,7 +810,9 @@ impl From<serde_json::Error> for ReplaySerializeError {
 /// Replays made before version 0.17.22 of the game (i.e., 0.17.21 and before it)
 /// use relative timing for its inputs.  
 /// However, starting from version 0.17.22 of the game, absolute timing is used.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum InputParseMode {
     /// Relative timing.
     ///
@@ -515,6 +834,33 @@ pub enum InputParseMode {
     Absolute,
 }
 
+/// Tries to parse the semver part of a game version string, e.g. `0.17.1` from `V0.17.1@8fa2`.
+///
+/// Returns [`None`] if no semver version could be found.
+pub(crate) fn parse_game_version(version: &str) -> Option<Version> {
+    let lower = version.to_ascii_lowercase();
+    let lower = lower
+        .trim_start_matches('v')
+        .trim_start_matches("alpha")
+        .trim_start();
+
+    // Snapshots use @ as version@commit delimiter
+    let lower = match lower.find('@') {
+        Some(idx) => &lower[..idx],
+        None => lower,
+    };
+
+    // Electra's mods have multiple elements to them
+    let lower = lower.split(' ').next().unwrap_or_default();
+
+    let filtered_version: String = lower
+        .chars()
+        .filter(|c| c.is_numeric() || *c == '.')
+        .collect();
+
+    Version::parse(&filtered_version).ok()
+}
+
 impl InputParseMode {
     /// The first version where absolute timing is used.
     pub const ABSOLUTE_TIMING_START: Version = Version::new(0, 17, 22);
@@ -541,31 +887,11 @@ impl InputParseMode {
             return Some(InputParseMode::Relative);
         }
 
-        // Snapshots use @ as version@commit delimiter
-        let lower = match lower.find('@') {
-            Some(idx) => &lower[..idx],
-            None => lower,
-        };
-
-        // Electra's mods have multiple elements to them
-        let lower = lower.split(' ').next().unwrap_or_default();
-
-        let filtered_version: String = lower
-            .chars()
-            .filter(|c| c.is_numeric() || *c == '.')
-            .collect();
-
-        let version = Version::parse(&filtered_version);
-
-        if let Ok(v) = version {
-            if v < Self::ABSOLUTE_TIMING_START {
-                return Some(InputParseMode::Relative);
-            } else {
-                return Some(InputParseMode::Absolute);
-            }
+        match parse_game_version(version) {
+            Some(v) if v < Self::ABSOLUTE_TIMING_START => Some(InputParseMode::Relative),
+            Some(_) => Some(InputParseMode::Absolute),
+            None => None,
         }
-
-        return None;
     }
 
     /// Tries to infer the input parse mode based on the input slice.
@@ -623,4 +949,27 @@ mod tests {
             assert_eq!(InputParseMode::try_infer_from_version(input), expected);
         }
     }
+
+    #[test]
+    fn test_opacity_round_trip() {
+        let json = r#"{"center":1,"ghost":0.3,"grid":0.6999999}"#;
+
+        let settings: PlayerSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.center_f64(), Some(1.0));
+        assert_eq!(settings.ghost_f64(), Some(0.3));
+        assert_eq!(settings.grid_f64(), Some(0.6999999));
+
+        let value = serde_json::to_value(&settings).unwrap();
+        let opacities: Vec<String> = ["center", "ghost", "grid"]
+            .iter()
+            .map(|key| format!("\"{key}\":{}", value[key]))
+            .collect();
+        assert_eq!(format!("{{{}}}", opacities.join(",")), json);
+
+        let metadata = format!(
+            r#"{{"date":"","mode":"","player":"","seed":0,"setting":{json},"version":"V0.17.22"}}"#
+        );
+        let parsed: GameReplayMetadata = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(crate::canonical_metadata_json(&parsed).unwrap(), metadata);
+    }
 }
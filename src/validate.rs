@@ -0,0 +1,311 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::serialize::get_first_unsorted;
+use crate::types::*;
+use crate::{KeyPressAnomaly, SettingsIssue};
+
+/// The highest frame number that is considered plausible for a replay.
+///
+/// This is about 46 hours of gameplay at 60 frames per second.
+pub const MAX_PLAUSIBLE_FRAME: u64 = 10_000_000;
+
+/// The most inputs that are considered plausible for a replay.
+///
+/// This is about 6 inputs per second over [`MAX_PLAUSIBLE_FRAME`] frames.
+pub const MAX_PLAUSIBLE_INPUTS: usize = 1_000_000;
+
+/// A suspicious property of a replay, found by [`GameReplayData::validate`].
+///
+/// None of these stop the replay from being parsed, but some of them stop it from being
+/// serialized, or make the game behave strangely when playing it back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "issue")]
+pub enum ValidationIssue {
+    /// The inputs aren't sorted by frame.
+    ///
+    /// Only the first unsorted input is reported.
+    /// Replays with this issue can't be serialized until [`GameReplayData::sort_inputs`] is called.
+    #[serde(rename_all = "camelCase")]
+    UnsortedInput {
+        /// The index of the first input that is earlier than the one before it.
+        index: usize,
+        /// The frame of the input before it.
+        prev_frame: u64,
+        /// The frame of the input.
+        frame: u64,
+    },
+
+    /// A key was released without being pressed beforehand.
+    ReleaseWithoutPress {
+        /// The index of the release event.
+        index: usize,
+        /// The frame of the release event.
+        frame: u64,
+        /// The key that was released.
+        key: InputEventKey,
+    },
+
+    /// A key was pressed while it was already held.
+    PressWhileHeld {
+        /// The index of the press event.
+        index: usize,
+        /// The frame of the press event.
+        frame: u64,
+        /// The key that was pressed.
+        key: InputEventKey,
+    },
+
+    /// An input happens on a frame later than [`MAX_PLAUSIBLE_FRAME`].
+    ///
+    /// Only the first such input is reported.
+    ImplausibleFrame {
+        /// The index of the input.
+        index: usize,
+        /// The frame of the input.
+        frame: u64,
+    },
+
+    /// A setting has a value outside the range the game allows.
+    SettingOutOfRange {
+        /// The name of the setting, as written in the metadata JSON.
+        field: String,
+        /// The value of the setting.
+        value: f64,
+        /// The lowest value the game allows.
+        min: f64,
+        /// The highest value the game allows.
+        max: f64,
+    },
+
+    /// A per-piece setting doesn't have one value for each piece.
+    SettingWrongLength {
+        /// The name of the setting, as written in the metadata JSON.
+        field: String,
+        /// The number of values.
+        len: usize,
+        /// The number of pieces.
+        expected: usize,
+    },
+
+    /// The version string doesn't contain a version number this crate can parse,
+    /// but the input parse mode can still be inferred from it (e.g. for some mods).
+    UnparseableVersion {
+        /// The version string.
+        version: String,
+    },
+
+    /// The version string doesn't contain a version number this crate can parse,
+    /// and the input parse mode can't be inferred from it either.
+    ///
+    /// Replays with this issue can only be parsed and serialized with an explicit [`InputParseMode`].
+    UnknownInputParseMode {
+        /// The version string.
+        version: String,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ValidationIssue::*;
+
+        match self {
+            UnsortedInput { index, prev_frame, frame } => write!(
+                f,
+                "Input #{index} is on frame {frame}, which is before the previous input's frame {prev_frame}"
+            ),
+            ReleaseWithoutPress { index, frame, key } => write!(
+                f,
+                "Input #{index} releases {key:?} on frame {frame}, but it wasn't held"
+            ),
+            PressWhileHeld { index, frame, key } => write!(
+                f,
+                "Input #{index} presses {key:?} on frame {frame}, but it was already held"
+            ),
+            ImplausibleFrame { index, frame } => write!(
+                f,
+                "Input #{index} is on frame {frame}, which is implausibly late"
+            ),
+            SettingOutOfRange { field, value, min, max } => write!(
+                f,
+                "Setting '{field}' is {value}, which is outside the range {min} to {max}"
+            ),
+            SettingWrongLength { field, len, expected } => write!(
+                f,
+                "Setting '{field}' has {len} values, but there are {expected} pieces"
+            ),
+            UnparseableVersion { version } => write!(
+                f,
+                "Version '{version}' doesn't contain a recognizable version number"
+            ),
+            UnknownInputParseMode { version } => write!(
+                f,
+                "Version '{version}' isn't recognized, so the input timing can't be inferred"
+            ),
+        }
+    }
+}
+
+impl GameReplayData {
+    /// Checks the replay for suspicious properties.
+    ///
+    /// Unlike parsing and serializing, this doesn't stop at the first problem;
+    /// all the issues found are returned. An empty list means the replay looks fine.
+    /// See [`ValidationIssue`] for the issues that are checked for.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        if let Some(ReplaySerializeError::UnsortedInput {
+            first_unsorted_index,
+            prev_time,
+            unsorted_time,
+        }) = get_first_unsorted(&self.inputs)
+        {
+            issues.push(ValidationIssue::UnsortedInput {
+                index: first_unsorted_index,
+                prev_frame: prev_time,
+                frame: unsorted_time,
+            });
+        }
+
+        for anomaly in self.pair_key_presses().anomalies {
+            issues.push(match anomaly {
+                KeyPressAnomaly::UnpairedRelease { index, frame, key } => {
+                    ValidationIssue::ReleaseWithoutPress { index, frame, key }
+                }
+                KeyPressAnomaly::RepeatedPress { index, frame, key } => {
+                    ValidationIssue::PressWhileHeld { index, frame, key }
+                }
+            });
+        }
+
+        if let Some(index) = self.inputs.iter().position(|i| i.frame > MAX_PLAUSIBLE_FRAME) {
+            issues.push(ValidationIssue::ImplausibleFrame {
+                index,
+                frame: self.inputs[index].frame,
+            });
+        }
+
+        issues.extend(settings_issues(&self.metadata.setting));
+
+        let version = &self.metadata.version;
+        if parse_game_version(version).is_none() {
+            issues.push(match InputParseMode::try_infer_from_version(version) {
+                Some(_) => ValidationIssue::UnparseableVersion { version: version.clone() },
+                None => ValidationIssue::UnknownInputParseMode { version: version.clone() },
+            });
+        }
+
+        issues
+    }
+}
+
+pub(crate) fn settings_issues(settings: &PlayerSettings) -> Vec<ValidationIssue> {
+    let issues = settings.validate().into_iter();
+
+    issues
+        .map(|issue| match issue {
+            SettingsIssue::OutOfRange { field, value, min, max } => {
+                ValidationIssue::SettingOutOfRange { field, value, min, max }
+            }
+            SettingsIssue::WrongLength { field, len, expected } => {
+                ValidationIssue::SettingWrongLength { field, len, expected }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use InputEventKey::*;
+
+    fn valid_replay() -> GameReplayData {
+        GameReplayData {
+            inputs: vec![
+                GameInputEvent::press(100, HardDrop),
+                GameInputEvent::release(101, HardDrop),
+            ],
+            metadata: GameReplayMetadata {
+                version: "V0.17.22".to_string(),
+                setting: PlayerSettings {
+                    das: Some(20),
+                    ghost: serde_json::Number::from_f64(0.3),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            input_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_replay() {
+        assert_eq!(valid_replay().validate(), vec![]);
+    }
+
+    #[test]
+    fn test_input_issues() {
+        let mut data = valid_replay();
+        data.inputs = vec![
+            GameInputEvent::press(50, MoveLeft),
+            GameInputEvent::release(40, Hold),
+            GameInputEvent::press(60, MoveLeft),
+            GameInputEvent::release(MAX_PLAUSIBLE_FRAME + 1, MoveLeft),
+        ];
+
+        assert_eq!(
+            data.validate(),
+            vec![
+                ValidationIssue::UnsortedInput { index: 1, prev_frame: 50, frame: 40 },
+                ValidationIssue::ReleaseWithoutPress { index: 1, frame: 40, key: Hold },
+                ValidationIssue::PressWhileHeld { index: 2, frame: 60, key: MoveLeft },
+                ValidationIssue::ImplausibleFrame { index: 3, frame: MAX_PLAUSIBLE_FRAME + 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata_issues() {
+        let mut data = valid_replay();
+        data.metadata.setting.das = Some(21);
+        data.metadata.setting.ghost = serde_json::Number::from_f64(1.5);
+
+        assert_eq!(
+            data.validate(),
+            vec![
+                ValidationIssue::SettingOutOfRange {
+                    field: "das".to_string(),
+                    value: 21.0,
+                    min: 0.0,
+                    max: 20.0
+                },
+                ValidationIssue::SettingOutOfRange {
+                    field: "ghost".to_string(),
+                    value: 1.5,
+                    min: 0.0,
+                    max: 1.0
+                },
+            ]
+        );
+
+        let mut data = valid_replay();
+        data.metadata.version = "WTF".to_string();
+        assert_eq!(
+            data.validate(),
+            vec![ValidationIssue::UnparseableVersion { version: "WTF".to_string() }]
+        );
+
+        data.metadata.version = "Techmino is fun!".to_string();
+        assert_eq!(
+            data.validate(),
+            vec![ValidationIssue::UnknownInputParseMode {
+                version: "Techmino is fun!".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Frame-accurate comparisons of the inputs of two replays, e.g. to verify a TAS.
+//!
+//! Unlike [`GameReplayData::diff`], only the inputs are compared, so a replay re-recorded by
+//! another player or on another version passes if it presses the same keys on the same frames.
+//! See [`inputs_equal`].
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::*;
+use crate::GAME_START_FRAME;
+
+/// Chooses which differences [`inputs_equal_with`] overlooks.
+///
+/// By default, nothing is overlooked, so the inputs have to be the same, in the same order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyOptions {
+    /// Ignores the inputs during the countdown, i.e. before [`GAME_START_FRAME`], which don't
+    /// affect the game.
+    pub ignore_countdown: bool,
+    /// Ignores the inputs after this frame, e.g. where one replay goes on after the run ended.
+    pub until_frame: Option<u64>,
+    /// Ignores the order of inputs on the same frame.
+    pub ignore_same_frame_order: bool,
+}
+
+/// The first frame on which the inputs of two replays differ, found by [`inputs_equal`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameMismatch {
+    /// The frame.
+    pub frame: u64,
+    /// The inputs of the first replay on the frame, which may be none.
+    pub a: Vec<GameInputEvent>,
+    /// The inputs of the second replay on the frame, which may be none.
+    pub b: Vec<GameInputEvent>,
+}
+
+/// Whether the inputs of two replays are equal, computed by [`inputs_equal`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationReport {
+    /// The number of inputs of the first replay that were compared, i.e. that weren't ignored.
+    pub events_a: usize,
+    /// The number of inputs of the second replay that were compared.
+    pub events_b: usize,
+    /// The first frame where the inputs differ, or `None` if they're equal.
+    pub mismatch: Option<FrameMismatch>,
+}
+
+impl VerificationReport {
+    /// Returns whether the inputs are equal.
+    pub fn passed(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+/// `PASS` or `FAIL` with the inputs of both replays on the first frame that differs, e.g.:
+///
+/// ```text
+/// FAIL: the inputs differ on frame 245
+///   a: 245 ▼ MoveLeft
+///   b: 245 ▼ MoveRight
+/// ```
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(mismatch) = &self.mismatch else {
+            return writeln!(f, "PASS: the {} inputs are equal", self.events_a);
+        };
+
+        writeln!(f, "FAIL: the inputs differ on frame {}", mismatch.frame)?;
+        for (side, events) in [("a", &mismatch.a), ("b", &mismatch.b)] {
+            write!(f, "  {side}:")?;
+            if events.is_empty() {
+                write!(f, " no inputs")?;
+            }
+            for (index, event) in events.iter().enumerate() {
+                let separator = if index == 0 { " " } else { ", " };
+                write!(f, "{separator}{event}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the inputs of two replays exactly, reporting the first frame where they differ.
+///
+/// See [`inputs_equal_with`] for overlooking some differences.
+pub fn inputs_equal(a: &GameReplayData, b: &GameReplayData) -> VerificationReport {
+    inputs_equal_with(a, b, VerifyOptions::default())
+}
+
+/// Compares the inputs of two replays, overlooking the differences chosen in the options,
+/// and reports the first frame where they differ.
+///
+/// The metadata isn't compared, and neither is the [`input_mode`][GameReplayData::input_mode],
+/// as it only affects how the inputs are serialized. The inputs don't need to be sorted, but
+/// inputs on the same frame are compared in their order unless
+/// [`ignore_same_frame_order`][VerifyOptions::ignore_same_frame_order] is set.
+pub fn inputs_equal_with(
+    a: &GameReplayData,
+    b: &GameReplayData,
+    options: VerifyOptions,
+) -> VerificationReport {
+    let (a, b) = (normalize(a, options), normalize(b, options));
+    let mut report = VerificationReport { events_a: a.len(), events_b: b.len(), mismatch: None };
+
+    let (mut a_frames, mut b_frames) = (a.chunk_by(same_frame), b.chunk_by(same_frame));
+    let (mut a_next, mut b_next) = (a_frames.next(), b_frames.next());
+    loop {
+        let frame = match (a_next, b_next) {
+            (None, None) => return report,
+            (Some(a), Some(b)) if a == b => {
+                (a_next, b_next) = (a_frames.next(), b_frames.next());
+                continue;
+            }
+            (Some(a), Some(b)) => a[0].frame.min(b[0].frame),
+            (Some(events), None) | (None, Some(events)) => events[0].frame,
+        };
+
+        let on_frame = |events: Option<&[GameInputEvent]>| {
+            events.filter(|events| events[0].frame == frame).map_or_else(Vec::new, <[_]>::to_vec)
+        };
+        report.mismatch = Some(FrameMismatch { frame, a: on_frame(a_next), b: on_frame(b_next) });
+        return report;
+    }
+}
+
+/// The inputs to compare, sorted by frame, and by kind and key within a frame if their order
+/// is ignored.
+fn normalize(replay: &GameReplayData, options: VerifyOptions) -> Vec<GameInputEvent> {
+    let start = if options.ignore_countdown { GAME_START_FRAME } else { 0 };
+    let end = options.until_frame.unwrap_or(u64::MAX);
+
+    let mut inputs: Vec<GameInputEvent> =
+        replay.inputs.iter().filter(|input| (start..=end).contains(&input.frame)).copied().collect();
+    if options.ignore_same_frame_order {
+        inputs.sort();
+    } else {
+        // Stable, so the order within a frame is kept
+        inputs.sort_by_key(|input| input.frame);
+    }
+
+    inputs
+}
+
+fn same_frame(a: &GameInputEvent, b: &GameInputEvent) -> bool {
+    a.frame == b.frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helpers::replay;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use InputEventKey::*;
+
+    #[test]
+    fn test_exact() {
+        let a = replay(vec![GameInputEvent::press(200, MoveLeft), GameInputEvent::release(205, MoveLeft)]);
+        let report = inputs_equal(&a, &a);
+        assert!(report.passed());
+        assert_eq!(report.to_string(), "PASS: the 2 inputs are equal\n");
+
+        // Other metadata doesn't matter
+        let mut b = a.clone();
+        b.metadata.player = "someone else".to_string();
+        b.input_mode = Some(InputParseMode::Relative);
+        assert!(inputs_equal(&a, &b).passed());
+
+        b.inputs[1].frame = 206;
+        let report = inputs_equal(&a, &b);
+        assert_eq!(
+            report.mismatch,
+            Some(FrameMismatch { frame: 205, a: vec![GameInputEvent::release(205, MoveLeft)], b: vec![] })
+        );
+        assert_eq!(
+            report.to_string(),
+            "FAIL: the inputs differ on frame 205\n  a: 205 ▲ MoveLeft\n  b: no inputs\n"
+        );
+
+        b.inputs.pop();
+        let mismatch = inputs_equal(&a, &b).mismatch.unwrap();
+        assert_eq!((mismatch.frame, mismatch.b), (205, vec![]));
+    }
+
+    #[test]
+    fn test_ignore_countdown() {
+        let a = replay(vec![
+            GameInputEvent::press(100, Hold),
+            GameInputEvent::release(101, Hold),
+            GameInputEvent::press(200, HardDrop),
+        ]);
+        let b = replay(vec![GameInputEvent::press(200, HardDrop)]);
+
+        let mismatch = inputs_equal(&a, &b).mismatch.unwrap();
+        let expected = FrameMismatch { frame: 100, a: vec![GameInputEvent::press(100, Hold)], b: vec![] };
+        assert_eq!(mismatch, expected);
+
+        let options = VerifyOptions { ignore_countdown: true, ..Default::default() };
+        let report = inputs_equal_with(&a, &b, options);
+        assert!(report.passed());
+        assert_eq!((report.events_a, report.events_b), (1, 1));
+    }
+
+    #[test]
+    fn test_until_frame() {
+        let a = replay(vec![GameInputEvent::press(200, HardDrop), GameInputEvent::press(300, Hold)]);
+        let b = replay(vec![
+            GameInputEvent::press(200, HardDrop),
+            GameInputEvent::press(300, MoveLeft),
+            GameInputEvent::press(301, Hold),
+        ]);
+
+        assert_eq!(inputs_equal(&a, &b).mismatch.unwrap().frame, 300);
+
+        // The frame itself is still compared
+        let until = |frame| VerifyOptions { until_frame: Some(frame), ..Default::default() };
+        assert!(inputs_equal_with(&a, &b, until(299)).passed());
+        let mismatch = inputs_equal_with(&a, &b, until(300)).mismatch.unwrap();
+        assert_eq!(mismatch.a, [GameInputEvent::press(300, Hold)]);
+        assert_eq!(mismatch.b, [GameInputEvent::press(300, MoveLeft)]);
+    }
+
+    #[test]
+    fn test_ignore_same_frame_order() {
+        let (left, right) = (GameInputEvent::press(200, MoveLeft), GameInputEvent::press(200, RotateRight));
+        let hold = GameInputEvent::press(210, Hold);
+        let a = replay(vec![left, right, hold]);
+        let b = replay(vec![right, left, hold]);
+
+        let mismatch = inputs_equal(&a, &b).mismatch.unwrap();
+        assert_eq!(mismatch.frame, 200);
+        assert_eq!(mismatch.a, a.inputs[..2]);
+        assert_eq!(mismatch.b, b.inputs[..2]);
+
+        let options = VerifyOptions { ignore_same_frame_order: true, ..Default::default() };
+        assert!(inputs_equal_with(&a, &b, options).passed());
+
+        // Unsorted inputs are compared by frame either way
+        let unsorted = replay(vec![a.inputs[2], a.inputs[0], a.inputs[1]]);
+        assert!(inputs_equal(&a, &unsorted).passed());
+    }
+}
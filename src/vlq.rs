@@ -0,0 +1,161 @@
+//! Encoding and decoding of the variable-length quantities (VLQs) used in the input section of replays.
+//!
+//! Each value is split into groups of 7 bits, most significant group first.
+//! Every byte except the last one of a value has its highest bit set.
+//!
+//! ```
+//! use techmino_replay_toolkit::vlq;
+//!
+//! let bytes = vlq::encode(&[0x7F, 0x80, 1920]);
+//! assert_eq!(bytes, [0x7F, 0x81, 0x00, 0x8F, 0x00]);
+//! assert_eq!(vlq::decode(&bytes).unwrap(), [0x7F, 0x80, 1920]);
+//! ```
+
+use alloc::vec::Vec;
+
+/// The most bytes a [`u64`] can take up when encoded.
+pub const MAX_ENCODED_LEN: usize = 10;
+
+/// An error from decoding VLQs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VlqError {
+    /// The bytes ended in the middle of a value.
+    Truncated {
+        /// The position of the first byte of the unfinished value.
+        position: usize,
+    },
+
+    /// A value doesn't fit in a [`u64`].
+    Overflow {
+        /// The position of the first byte of the value.
+        position: usize,
+    },
+}
+
+/// Encodes a list of values.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "vlq::encode", level = "trace", skip_all, fields(values = values.len()))
+)]
+pub fn encode(values: &[u64]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    encode_into(&mut buffer, values);
+    buffer
+}
+
+/// Encodes a list of values, appending them to the end of the buffer.
+pub fn encode_into(buffer: &mut Vec<u8>, values: &[u64]) {
+    // Estimation: most values need around 2 bytes
+    buffer.reserve(values.len() * 2);
+
+    for &value in values {
+        encode_value_into(buffer, value);
+    }
+}
+
+/// Encodes a single value, appending it to the end of the buffer.
+pub fn encode_value_into(buffer: &mut Vec<u8>, value: u64) {
+    let bits = u64::BITS - value.leading_zeros();
+    let groups = bits.div_ceil(7).max(1);
+
+    for group in (1..groups).rev() {
+        buffer.push((value >> (group * 7)) as u8 | 0x80);
+    }
+
+    buffer.push(value as u8 & 0x7F);
+}
+
+/// Decodes a list of values.
+///
+/// Fails if the bytes end in the middle of a value, or if a value doesn't fit in a [`u64`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "vlq::decode", level = "trace", skip_all, fields(bytes = bytes.len()))
+)]
+pub fn decode(bytes: &[u8]) -> Result<Vec<u64>, VlqError> {
+    decode_iter(bytes).collect()
+}
+
+/// Returns an iterator that decodes the values one at a time.
+///
+/// The iterator stops after the first error.
+pub fn decode_iter(bytes: &[u8]) -> DecodeIter<'_> {
+    DecodeIter { bytes, position: 0 }
+}
+
+/// An iterator over the values decoded from VLQs, created by [`decode_iter`].
+#[derive(Clone, Debug)]
+pub struct DecodeIter<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl DecodeIter<'_> {
+    /// Returns the position of the byte the next value starts at.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Iterator for DecodeIter<'_> {
+    type Item = Result<u64, VlqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.position;
+        if start >= self.bytes.len() {
+            return None;
+        }
+
+        let mut value: u64 = 0;
+        for (offset, &byte) in self.bytes[start..].iter().enumerate() {
+            if value >> (u64::BITS - 7) != 0 {
+                self.position = self.bytes.len();
+                return Some(Err(VlqError::Overflow { position: start }));
+            }
+
+            value = (value << 7) | (byte & 0x7F) as u64;
+
+            if byte < 0x80 {
+                self.position = start + offset + 1;
+                return Some(Ok(value));
+            }
+        }
+
+        self.position = self.bytes.len();
+        Some(Err(VlqError::Truncated { position: start }))
+    }
+}
+
+impl core::iter::FusedIterator for DecodeIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let values = [0, 1, 0x7F, 0x80, 0x3FFF, 0x4000, 1920, u32::MAX as u64, u64::MAX];
+        let bytes = encode(&values);
+
+        assert_eq!(decode(&bytes).unwrap(), values);
+        assert_eq!(encode(&[u64::MAX]).len(), MAX_ENCODED_LEN);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(&[0x01, 0x81]), Err(VlqError::Truncated { position: 1 }));
+        assert_eq!(decode(&[0x81, 0x80, 0x80]), Err(VlqError::Truncated { position: 0 }));
+
+        let mut too_big = vec![0x82];
+        too_big.extend([0x80; 9]);
+        too_big.push(0x00);
+        assert_eq!(decode(&too_big), Err(VlqError::Overflow { position: 0 }));
+
+        let mut iter = decode_iter(&[0x05, 0xFF, 0x7F, 0x80]);
+        assert_eq!(iter.next(), Some(Ok(5)));
+        assert_eq!(iter.next(), Some(Ok(0x3FFF)));
+        assert_eq!(iter.position(), 3);
+        assert_eq!(iter.next(), Some(Err(VlqError::Truncated { position: 3 })));
+        assert_eq!(iter.next(), None);
+    }
+}
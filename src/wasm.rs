@@ -0,0 +1,67 @@
+//! Bindings for using the crate from JavaScript through WebAssembly.
+
+use js_sys::{Error, Reflect};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::types::*;
+
+/// Parses a base64 replay string into an object shaped like the serde form of [`GameReplayData`].
+#[wasm_bindgen(js_name = parseBase64)]
+pub fn parse_base64(string: &str) -> Result<JsValue, JsValue> {
+    let data = GameReplayData::try_from_base64(string, None).map_err(parse_error)?;
+
+    to_js(&data)
+}
+
+/// Parses a compressed replay, e.g. the contents of a `.rep` file, into an object shaped like
+/// the serde form of [`GameReplayData`].
+#[wasm_bindgen(js_name = parseBinary)]
+pub fn parse_binary(data: &[u8]) -> Result<JsValue, JsValue> {
+    let data = GameReplayData::try_from_compressed(data, None).map_err(parse_error)?;
+
+    to_js(&data)
+}
+
+/// Serializes an object shaped like the serde form of [`GameReplayData`] into a base64 replay string.
+#[wasm_bindgen(js_name = serializeToBase64)]
+pub fn serialize_to_base64(replay: JsValue) -> Result<String, JsValue> {
+    from_js(replay)?.serialize_to_base64(None).map_err(serialize_error)
+}
+
+/// Serializes an object shaped like the serde form of [`GameReplayData`] into a compressed replay.
+#[wasm_bindgen(js_name = serializeToBinary)]
+pub fn serialize_to_binary(replay: JsValue) -> Result<Vec<u8>, JsValue> {
+    from_js(replay)?.serialize_to_compressed(None).map_err(serialize_error)
+}
+
+fn to_js(data: &GameReplayData) -> Result<JsValue, JsValue> {
+    // Plain objects rather than `Map`s, so the result looks like the JSON form
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+
+    data.serialize(&serializer)
+        .map_err(|e| js_error("InvalidReplayData", &e.to_string()))
+}
+
+fn from_js(replay: JsValue) -> Result<GameReplayData, JsValue> {
+    serde_wasm_bindgen::from_value(replay).map_err(|e| js_error("InvalidReplayData", &e.to_string()))
+}
+
+fn parse_error(error: ReplayParseError) -> JsValue {
+    js_error(error.code(), &format!("{error:?}"))
+}
+
+fn serialize_error(error: ReplaySerializeError) -> JsValue {
+    js_error(error.code(), &format!("{error:?}"))
+}
+
+/// Creates a JavaScript `Error` with a `code` property naming the error variant.
+fn js_error(code: &str, message: &str) -> JsValue {
+    let error = Error::new(message);
+    error.set_name("ReplayError");
+
+    // Setting a property on a fresh `Error` object can't fail
+    let _ = Reflect::set(&error, &"code".into(), &code.into());
+
+    error.into()
+}
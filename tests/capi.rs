@@ -0,0 +1,92 @@
+//! Tests for the C API, calling the functions through their C signatures.
+
+#![cfg(feature = "capi")]
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use techmino_replay_toolkit::capi::*;
+
+const SOMEINPUTS: &str = include_str!("../src/tests/cases/someinputs.b64.rep");
+
+fn last_error() -> String {
+    let message = trt_last_error_message();
+    assert!(!message.is_null());
+
+    unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+}
+
+#[test]
+fn parse_inspect_and_serialize() {
+    let string = CString::new(SOMEINPUTS.trim()).unwrap();
+
+    unsafe {
+        let mut replay = ptr::null_mut();
+        assert_eq!(trt_parse_base64(string.as_ptr(), &mut replay), TrtStatus::Ok);
+
+        assert_eq!(trt_replay_input_count(replay), 10);
+
+        let mut input = TrtInput::default();
+        assert_eq!(trt_replay_input_at(replay, 0, &mut input), TrtStatus::Ok);
+        assert_eq!((input.frame, input.kind, input.key), (62, 0, 6));
+        assert_eq!(trt_replay_input_at(replay, 9, &mut input), TrtStatus::Ok);
+        assert_eq!((input.frame, input.kind, input.key), (180, 1, 3));
+
+        assert_eq!(trt_replay_input_at(replay, 10, &mut input), TrtStatus::IndexOutOfBounds);
+        assert_eq!(last_error(), "Index 10 is out of bounds for 10 inputs");
+
+        let json = trt_replay_metadata_json(replay);
+        let metadata: serde_json::Value = serde_json::from_slice(CStr::from_ptr(json).to_bytes()).unwrap();
+        assert_eq!(metadata["player"], "NOT_A_ROBOT");
+        trt_free_string(json);
+
+        let mut base64 = ptr::null_mut();
+        assert_eq!(trt_serialize_base64(replay, &mut base64), TrtStatus::Ok);
+        trt_free_replay(replay);
+
+        // The serialized replay parses back into the same inputs
+        let mut reparsed = ptr::null_mut();
+        assert_eq!(trt_parse_base64(base64, &mut reparsed), TrtStatus::Ok);
+        assert_eq!(trt_replay_input_count(reparsed), 10);
+        trt_free_replay(reparsed);
+        trt_free_string(base64);
+    }
+}
+
+#[test]
+fn parse_compressed() {
+    use base64::Engine;
+
+    let data = base64::engine::general_purpose::STANDARD.decode(SOMEINPUTS.trim()).unwrap();
+
+    unsafe {
+        let mut replay = ptr::null_mut();
+        assert_eq!(trt_parse_compressed(data.as_ptr(), data.len(), &mut replay), TrtStatus::Ok);
+        assert_eq!(trt_replay_input_count(replay), 10);
+        trt_free_replay(replay);
+    }
+}
+
+#[test]
+fn errors() {
+    unsafe {
+        let mut replay = ptr::null_mut();
+
+        let string = CString::new("not base64!").unwrap();
+        assert_eq!(trt_parse_base64(string.as_ptr(), &mut replay), TrtStatus::Base64DecodeError);
+        assert!(last_error().starts_with("Base64DecodeError"));
+        assert!(replay.is_null());
+
+        let data = [1, 2, 3];
+        assert_eq!(trt_parse_compressed(data.as_ptr(), data.len(), &mut replay), TrtStatus::ZlibDecompressError);
+        assert_eq!(trt_parse_compressed(data.as_ptr(), 0, &mut replay), TrtStatus::EmptyInput);
+
+        assert_eq!(trt_parse_base64(ptr::null(), &mut replay), TrtStatus::NullPointer);
+        assert_eq!(trt_replay_input_count(ptr::null()), 0);
+        assert!(trt_replay_metadata_json(ptr::null()).is_null());
+
+        // Freeing null does nothing
+        trt_free_replay(ptr::null_mut());
+        trt_free_string(ptr::null_mut());
+    }
+}
@@ -0,0 +1,670 @@
+//! Tests for the `trt` command-line tool, running it against the replays in `src/tests/cases`.
+
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use techmino_replay_toolkit::{GameReplayData, InputEventKey};
+
+const SOMEINPUTS: &str = "src/tests/cases/someinputs.b64.rep";
+const SOMEINPUTS_RON: &str = "src/tests/cases/someinputs.ron";
+const SOMEINPUTS_WRAPPED: &str = "tests/fixtures/someinputs_wrapped.b64.rep";
+const EARLYINPUT: &str = "src/tests/cases/earlyinput.b64.rep";
+const HUGE: &str = "src/tests/cases/huge.b64.rep";
+
+fn trt() -> Command {
+    Command::cargo_bin("trt").unwrap()
+}
+
+/// Runs the command, expecting it to succeed, and returns what it wrote to stdout.
+fn stdout(command: &mut Command) -> String {
+    let output = command.assert().success().get_output().stdout.clone();
+
+    String::from_utf8(output).unwrap()
+}
+
+fn someinputs() -> GameReplayData {
+    GameReplayData::try_from_base64(&fs::read_to_string(SOMEINPUTS).unwrap(), None).unwrap()
+}
+
+/// A directory for the files a test writes, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("trt-cli-{name}-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn decode() {
+    let output = stdout(trt().args(["decode", SOMEINPUTS]));
+    assert_eq!(serde_json::from_str::<GameReplayData>(&output).unwrap(), someinputs());
+
+    let output = stdout(trt().args(["decode", SOMEINPUTS, "--format", "ron"]));
+    assert_eq!(ron::from_str::<GameReplayData>(&output).unwrap(), someinputs());
+
+    trt()
+        .args(["decode", "-", "-f", "text"])
+        .write_stdin(fs::read(SOMEINPUTS).unwrap())
+        .assert()
+        .success()
+        .stdout(someinputs().to_text().unwrap());
+
+    let output = stdout(trt().args(["decode", SOMEINPUTS, "--format", "jsonl"]));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], r#"{"frame":62,"kind":"Press","key":"HardDrop","gameplayFrame":null}"#);
+    assert_eq!(lines[9], r#"{"frame":180,"kind":"Release","key":"RotateRight","gameplayFrame":0}"#);
+}
+
+#[test]
+fn decode_detects_encoding() {
+    let replay = someinputs();
+    let base64 = fs::read_to_string(SOMEINPUTS).unwrap();
+    assert!(base64.starts_with("eJ"));
+
+    let encodings = [
+        ("base64", base64.clone().into_bytes()),
+        ("wrapped base64", base64.as_bytes().chunks(60).collect::<Vec<_>>().join(&b"\n"[..])),
+        ("compressed", replay.serialize_to_compressed(None).unwrap()),
+        ("raw", replay.serialize_to_raw(None).unwrap()),
+    ];
+
+    for (name, data) in encodings {
+        let output = stdout(trt().args(["decode", "-", "--format", "text"]).write_stdin(data));
+        assert_eq!(output, replay.to_text().unwrap(), "{name}");
+    }
+
+    trt()
+        .args(["info", "-"])
+        .write_stdin(b"\x89PNG\r\n".to_vec())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("choose the encoding with --from"));
+
+    // The encoding can be chosen explicitly, even if it doesn't match the data
+    let raw = replay.serialize_to_raw(None).unwrap();
+    let output = stdout(trt().args(["decode", "-", "--from", "raw", "-f", "text"]).write_stdin(raw.clone()));
+    assert_eq!(output, replay.to_text().unwrap());
+
+    trt()
+        .args(["decode", "-", "--from", "b64"])
+        .write_stdin(raw)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't a valid replay"));
+}
+
+#[test]
+fn encode() {
+    let base64 = stdout(trt().args(["encode", SOMEINPUTS_RON]));
+    assert_eq!(GameReplayData::try_from_base64(&base64, None).unwrap(), someinputs());
+
+    let json = serde_json::to_string(&someinputs()).unwrap();
+    let output = trt()
+        .args(["encode", "-", "--to", "rep"])
+        .write_stdin(json)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(GameReplayData::try_from_compressed(&output, None).unwrap(), someinputs());
+}
+
+#[test]
+fn info() {
+    trt()
+        .args(["info", SOMEINPUTS])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Player:   NOT_A_ROBOT\n"))
+        .stdout(predicate::str::contains("Mode:     sprint_10l\n"))
+        .stdout(predicate::str::contains("Version:  V0.17.17\n"))
+        .stdout(predicate::str::contains("Duration: 0:00.00 (0 frames after the countdown)\n"))
+        .stdout(predicate::str::contains("Inputs:   10\n"));
+
+    trt()
+        .args(["info", HUGE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Duration: 31:53.85 (114831 frames after the countdown)\n"));
+
+    trt()
+        .args(["info", SOMEINPUTS, "--inputs", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "Inputs:   10\n  62 ▼ HardDrop\n  62 ▲ HardDrop\n  ... and 8 more inputs\n",
+        ));
+}
+
+#[test]
+fn info_sizes() {
+    let output = stdout(trt().args(["info", SOMEINPUTS, "--sizes"]));
+    assert!(output.contains("Inputs:   10\n"), "{output}");
+    assert!(output.contains("Input data: 20 bytes (10 events, 2.00 bytes per event)\n"), "{output}");
+    assert!(output.contains("Compressed: "), "{output}");
+
+    // Replays that aren't compressed are measured as they would be saved
+    let raw = someinputs().serialize_to_raw(None).unwrap();
+    let output = stdout(trt().args(["info", "-", "--from", "raw", "--sizes"]).write_stdin(raw));
+    assert!(output.contains("Input data: 20 bytes (10 events, 2.00 bytes per event)\n"), "{output}");
+}
+
+#[test]
+fn stats() {
+    trt().args(["stats", HUGE]).assert().success().stdout(include_str!("golden/stats_huge.txt"));
+
+    trt()
+        .args(["stats", "--aggregate", "--json", HUGE, SOMEINPUTS, EARLYINPUT])
+        .assert()
+        .success()
+        .stdout(include_str!("golden/stats_aggregate.json"));
+
+    // One table per replay, in the order they're given
+    let output = stdout(trt().args(["stats", HUGE, SOMEINPUTS]));
+    let (huge, someinputs) = output.split_at(include_str!("golden/stats_huge.txt").len());
+    assert_eq!(huge, include_str!("golden/stats_huge.txt"));
+    assert!(someinputs.starts_with(&format!("\n{SOMEINPUTS}\nDuration:")), "{someinputs}");
+
+    let output = stdout(trt().args(["stats", "--json", SOMEINPUTS, EARLYINPUT]));
+    let reports: Vec<serde_json::Value> = serde_json::from_str(&output).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[1]["name"], EARLYINPUT);
+
+    trt().args(["stats"]).assert().failure().code(2);
+}
+
+#[test]
+fn stats_aggregate_dir() {
+    let dir = TempDir::new("stats_aggregate_dir");
+    for (name, path) in [("a.rep", SOMEINPUTS), ("b.rep", EARLYINPUT)] {
+        let replay = GameReplayData::try_from_base64(&fs::read_to_string(path).unwrap(), None).unwrap();
+        fs::write(dir.0.join(name), replay.serialize_to_compressed(None).unwrap()).unwrap();
+    }
+    fs::write(dir.0.join("broken.rep"), b"not a replay").unwrap();
+    fs::write(dir.0.join("notes.txt"), b"not a replay either").unwrap();
+
+    let output = trt().args(["stats", "--aggregate"]).arg(&dir.0).arg(HUGE).assert().success();
+    let output = output.get_output();
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    assert!(stdout.contains("TAS:          2 of 3 replays\n"), "{stdout}");
+    assert!(stdout.contains("Modes:        sprint_10l (2), marathon_inf (1)\n"), "{stdout}");
+    assert!(stdout.contains("DAS:          3 (2), 4 (1)\n"), "{stdout}");
+    assert!(stdout.contains("of 1 replays with gameplay\n"), "{stdout}");
+
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    assert!(stderr.starts_with("warning: Skipped '") && stderr.contains("broken.rep"), "{stderr}");
+    assert!(!stderr.contains("notes.txt"), "{stderr}");
+}
+
+#[test]
+fn verify() {
+    let dir = TempDir::new("verify");
+    let write = |name: &str, replay: GameReplayData| {
+        let path = dir.0.join(name).to_str().unwrap().to_string();
+        fs::write(&path, replay.serialize_to_compressed(None).unwrap()).unwrap();
+        path
+    };
+
+    // Only the inputs are compared
+    let mut renamed = someinputs();
+    renamed.metadata.player = "Nova".to_string();
+    let renamed = write("renamed.rep", renamed);
+    trt().args(["verify", SOMEINPUTS, &renamed]).assert().success().stdout("PASS: the 10 inputs are equal\n");
+
+    let mut edited = someinputs();
+    edited.inputs[6].key = InputEventKey::Hold;
+    let edited = write("edited.rep", edited);
+
+    let output = trt().args(["verify", SOMEINPUTS, &edited]).assert().failure().code(1).get_output().clone();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "FAIL: the inputs differ on frame 128\n  \
+           a: 128 ▼ MoveLeft, 128 ▲ MoveLeft\n  \
+           b: 128 ▼ Hold, 128 ▲ MoveLeft\n"
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: The inputs differ\n");
+
+    trt().args(["verify", "--until-frame", "127", SOMEINPUTS, &edited]).assert().success();
+    // All the inputs of someinputs are in the countdown
+    trt().args(["verify", "--ignore-countdown", SOMEINPUTS, &edited]).assert().success();
+
+    let output = trt().args(["verify", "--json", SOMEINPUTS, &edited]).assert().failure();
+    let report: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(report["mismatch"]["frame"], 128);
+    assert_eq!(report["mismatch"]["b"][0]["key"], "Hold");
+}
+
+#[test]
+fn diff() {
+    trt().args(["diff", SOMEINPUTS, SOMEINPUTS]).assert().success().stdout("No differences\n");
+
+    let dir = TempDir::new("diff");
+    let write = |name: &str, replay: GameReplayData| {
+        let path = dir.0.join(name).to_str().unwrap().to_string();
+        fs::write(&path, replay.serialize_to_compressed(None).unwrap()).unwrap();
+        path
+    };
+
+    let mut renamed = someinputs();
+    renamed.metadata.player = "Nova".to_string();
+    let renamed = write("renamed.rep", renamed);
+
+    // Base64 against compressed
+    let output = trt().args(["diff", SOMEINPUTS, &renamed]).assert().failure().code(1).get_output().clone();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Metadata: 1 difference\n  \
+           player: \"NOT_A_ROBOT\" -> \"Nova\"\n\
+         \n\
+         Inputs: identical\n"
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: The replays differ\n");
+
+    trt().args(["diff", "--inputs-only", SOMEINPUTS, &renamed]).assert().success();
+
+    let mut edited = someinputs();
+    edited.inputs[4].key = InputEventKey::Hold;
+    edited.inputs.truncate(6);
+    let edited = write("edited.rep", edited);
+
+    trt().args(["diff", "--metadata-only", SOMEINPUTS, &edited]).assert().success();
+
+    let output = trt().args(["diff", "--inputs-only", SOMEINPUTS, &edited]).assert().failure();
+    let output = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(output.starts_with("Inputs: first divergence at index 4 (frame 99)"), "{output}");
+    // Three events of context on either side
+    let rows: Vec<&str> = output.lines().skip(3).collect();
+    assert_eq!(rows.len(), 7, "{output}");
+    assert!(rows[3].starts_with("  >       4  99 Press SoftDrop "), "{output}");
+    assert!(rows[3].ends_with(" 99 Press Hold"), "{output}");
+    assert!(rows[6].ends_with(" -"), "{output}");
+
+    let conflicting = ["diff", "--inputs-only", "--metadata-only", SOMEINPUTS, SOMEINPUTS];
+    trt().args(conflicting).assert().failure().code(2);
+}
+
+#[test]
+fn edit() {
+    let dir = TempDir::new("edit");
+    let path = |name: &str| dir.0.join(name).to_str().unwrap().to_string();
+    let read = |name: &str| {
+        GameReplayData::try_from_compressed(&fs::read(path(name)).unwrap(), None).unwrap()
+    };
+
+    trt()
+        .args(["edit", SOMEINPUTS, "--trim-after", "100", "--offset", "+60", "--out", &path("edited.rep")])
+        .args(["--remap", "MoveRight=MoveLeft", "--remap", "softdrop=Hold", "--set-player", "Nova"])
+        .args(["--set-meta", "seed=42", "--set-meta", "note=hello", "--set-meta", "rating=1.5"])
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut expected = someinputs();
+    expected.truncate_at_frame(100);
+    expected.offset_frames(60).unwrap();
+    expected.remap_keys(|key| match key {
+        InputEventKey::MoveRight => InputEventKey::MoveLeft,
+        InputEventKey::SoftDrop => InputEventKey::Hold,
+        key => key,
+    });
+    expected.metadata.player = "Nova".to_string();
+    expected.metadata.seed = 42;
+    expected.metadata.nonstandard.insert("note".to_string(), "hello".into());
+    expected.metadata.nonstandard.insert("rating".to_string(), 1.5.into());
+
+    let edited = read("edited.rep");
+    assert_eq!(edited, expected);
+    assert_eq!(edited.inputs.len(), 6);
+    assert_eq!(edited.inputs[0].frame, 122);
+
+    trt()
+        .args(["edit", &path("edited.rep"), "--offset", "-60", "--out", &path("edited.rep")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to overwrite"));
+    assert_eq!(read("edited.rep"), expected);
+
+    trt().args(["edit", &path("edited.rep"), "--offset", "-60", "--in-place"]).assert().success();
+    assert_eq!(read("edited.rep").inputs[0].frame, 62);
+
+    trt()
+        .args(["edit", SOMEINPUTS, "--offset", "-100", "--out", &path("negative.rep")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("NegativeFrame"));
+    trt().args(["edit", SOMEINPUTS, "--remap", "MoveLeft=Jump", "--out", &path("x.rep")]).assert().code(2);
+    trt().args(["edit", SOMEINPUTS, "--set-player", "Nova"]).assert().code(2);
+}
+
+#[test]
+fn anonymize() {
+    let mut replay = someinputs();
+    replay.metadata.nonstandard.insert("deviceId".to_string(), "1234".into());
+    replay.metadata.setting.nonstandard.insert("modSetting".to_string(), true.into());
+
+    let base64 = trt()
+        .args(["anonymize", "-"])
+        .write_stdin(replay.serialize_to_compressed(None).unwrap())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let anonymized = GameReplayData::try_from_base64(std::str::from_utf8(&base64).unwrap(), None).unwrap();
+
+    let metadata = &anonymized.metadata;
+    assert_eq!(metadata.player, "Anonymous");
+    assert_eq!(metadata.date, "2024/10/09 00:00:00");
+    assert_eq!(metadata.seed, replay.metadata.seed);
+    assert!(metadata.nonstandard.is_empty() && metadata.setting.nonstandard.is_empty());
+    assert_eq!(anonymized.inputs, replay.inputs);
+
+    let dir = TempDir::new("anonymize");
+    let out = dir.0.join("shared.json");
+    trt()
+        .args(["anonymize", SOMEINPUTS, "--player", "Someone", "--blank-date", "--clear-seed", "--out"])
+        .arg(&out)
+        .assert()
+        .success();
+
+    let anonymized: GameReplayData = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+    assert_eq!((anonymized.metadata.player.as_str(), anonymized.metadata.date.as_str()), ("Someone", ""));
+    assert_eq!(anonymized.metadata.seed, 0);
+
+    trt()
+        .args(["anonymize", SOMEINPUTS, "--out", SOMEINPUTS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to overwrite"));
+}
+
+#[test]
+fn dump() {
+    // Corrupts the key code of the fifth input
+    let mut raw = someinputs().serialize_to_raw(None).unwrap();
+    let inputs = raw.iter().position(|&byte| byte == b'\n').unwrap() + 1;
+    raw[inputs + 9] = 0x3f;
+
+    let output = stdout(trt().args(["dump", "-", "--stage", "inputs"]).write_stdin(raw.clone()));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "Relative timing, 20 bytes");
+    assert_eq!(lines[9], "         8  0x0d -> 13 | frame=99");
+    assert_eq!(lines[10], "!        9  0x3f -> 63 | malformed: unknown key 31");
+    assert_eq!(lines[11..], ["", "Malformed value 9 at byte 9, 10 bytes left after it"]);
+
+    let output = stdout(trt().args(["dump", "-", "--stage", "metadata"]).write_stdin(raw.clone()));
+    let metadata: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(metadata["player"], "NOT_A_ROBOT");
+
+    let output = stdout(trt().args(["dump", "-", "-s", "decompressed"]).write_stdin(raw.clone()));
+    let first_line = "00000000  7b 22 74 61 73 55 73 65  64 22 3a 74 72 75 65 2c  |{\"tasUsed\":true,|\n";
+    assert!(output.starts_with(first_line), "{output}");
+    assert_eq!(output.lines().count(), raw.len().div_ceil(16));
+
+    // Cut-off compressed data is decompressed as far as it goes
+    let compressed = someinputs().serialize_to_compressed(None).unwrap();
+    trt()
+        .args(["dump", "-", "-s", "metadata", "--from", "rep"])
+        .write_stdin(&compressed[..compressed.len() - 8])
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("warning: Failed to decompress after"));
+}
+
+#[test]
+fn list() {
+    let dir = TempDir::new("list");
+    let replays = dir.0.to_str().unwrap();
+
+    for name in ["earlyinput", "huge", "someinputs"] {
+        fs::copy(format!("src/tests/cases/{name}.b64.rep"), dir.0.join(format!("{name}.rep"))).unwrap();
+    }
+    fs::write(dir.0.join("broken.rep"), "not a replay").unwrap();
+
+    trt()
+        .args(["list", "--replay-dir", replays])
+        .assert()
+        .success()
+        .stdout(
+            "#  Date                 Mode          Player       Version       File\n\
+             1  2024/10/09 14:44:11  sprint_10l    NOT_A_ROBOT  V0.17.17      someinputs.rep\n\
+             2  2024/08/13 20:40:13  sprint_10l    NOT_A_ROBOT  V0.17.18      earlyinput.rep\n\
+             3  2022/01/05 20:17:46  marathon_inf  NOT_A_ROBOT  V0.17.1@8fa2  huge.rep\n",
+        )
+        .stderr(predicate::str::starts_with("warning: Skipped 'broken.rep'"));
+
+    // The numbers can be used in place of paths
+    trt()
+        .args(["info", "@3", "--replay-dir", replays])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Mode:     marathon_inf\n"));
+    trt().args(["--replay-dir", replays, "diff", "@1", SOMEINPUTS]).assert().success();
+
+    trt()
+        .args(["info", "@4", "--replay-dir", replays])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("There's no replay @4"));
+
+    let empty = TempDir::new("list-empty");
+    trt()
+        .args(["list", "--replay-dir", empty.0.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("No replays in"));
+}
+
+#[test]
+fn convert() {
+    let dir = TempDir::new("convert");
+    let path = |name: &str| dir.0.join(name).to_str().unwrap().to_string();
+
+    trt().args(["convert", SOMEINPUTS, &path("replay.txt")]).assert().success().stdout("");
+    trt().args(["convert", &path("replay.txt"), &path("replay.json")]).assert().success();
+    trt().args(["convert", &path("replay.json"), &path("replay.rep")]).assert().success();
+    trt().args(["convert", &path("replay.rep"), &path("replay.b64.rep")]).assert().success();
+
+    let compressed = fs::read(path("replay.rep")).unwrap();
+    assert_eq!(GameReplayData::try_from_compressed(&compressed, None).unwrap(), someinputs());
+    let base64 = fs::read_to_string(path("replay.b64.rep")).unwrap();
+    assert_eq!(GameReplayData::try_from_base64(&base64, None).unwrap(), someinputs());
+
+    trt()
+        .args(["convert", SOMEINPUTS, &path("replay.png")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Can't tell the format"));
+}
+
+#[test]
+fn without_command() {
+    let output = stdout(trt().write_stdin(fs::read(SOMEINPUTS_WRAPPED).unwrap()));
+    assert_eq!(serde_json::from_str::<GameReplayData>(&output).unwrap(), someinputs());
+
+    for empty in ["", "\n \n"] {
+        trt()
+            .write_stdin(empty)
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr("error: There's no replay on stdin, see `trt --help`\n");
+    }
+
+    trt()
+        .write_stdin("not a replay")
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("error: '-' isn't a valid replay: "));
+}
+
+#[test]
+fn interactive() {
+    // Replays can be wrapped, and a wrapped replay that's broken off is reported at the empty line after it
+    let wrapped = fs::read_to_string(SOMEINPUTS_WRAPPED).unwrap();
+    let (cut_off, _) = wrapped.split_at(wrapped.find('\n').unwrap() + 1);
+    let pasted = format!("{wrapped}\n{cut_off}\n{}\n", fs::read_to_string(EARLYINPUT).unwrap().trim());
+
+    let output = trt().arg("--interactive").write_stdin(pasted).assert().success().get_output().clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    let versions: Vec<&str> = stdout.lines().filter(|line| line.starts_with("Version:")).collect();
+    assert_eq!(versions, ["Version:  V0.17.17", "Version:  V0.17.18"]);
+    assert_eq!(stderr.matches("error: That isn't a valid replay").count(), 1, "{stderr}");
+
+    trt().arg("-i").write_stdin("").assert().success().stdout("");
+    trt().args(["-i", "info", SOMEINPUTS]).assert().failure().code(2);
+}
+
+#[test]
+fn failures() {
+    trt()
+        .args(["decode", "src/tests/cases/missing.rep"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::starts_with("error: Failed to read"));
+
+    trt()
+        .args(["info", "-"])
+        .write_stdin("not a replay")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("isn't a valid replay"));
+
+    trt()
+        .args(["encode", SOMEINPUTS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("isn't a JSON or RON file"));
+
+    // Usage errors are reported by clap
+    trt().args(["decode", SOMEINPUTS, "--format", "xml"]).assert().failure().code(2);
+}
+
+#[test]
+fn batch() {
+    let dir = TempDir::new("batch");
+    let replays = dir.0.join("replays");
+    let out = dir.0.join("out");
+    fs::create_dir_all(&replays).unwrap();
+
+    let mut expected = Vec::new();
+    for name in ["earlyinput", "huge", "someinputs"] {
+        let base64 = fs::read_to_string(format!("src/tests/cases/{name}.b64.rep")).unwrap();
+        let replay = GameReplayData::try_from_base64(&base64, None).unwrap();
+        fs::write(replays.join(format!("{name}.rep")), replay.serialize_to_compressed(None).unwrap()).unwrap();
+
+        expected.push((name, replay));
+    }
+    fs::write(replays.join("broken.rep"), "not a replay").unwrap();
+    fs::write(replays.join("notes.txt"), "not a replay either").unwrap();
+
+    let run = |extra_args: &[&str]| {
+        let mut command = trt();
+        command.args(["batch", replays.to_str().unwrap(), "--out", out.to_str().unwrap()]).args(extra_args);
+
+        let output = command.assert().failure().code(1).get_output().clone();
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: Some replays failed to convert\n");
+
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let summary = run(&["--jobs", "2"]);
+    let counts = "converted  3\nskipped    0\nfailed     1\n";
+    assert!(summary.starts_with(&format!("{counts}\nfailed: broken.rep\n")), "{summary}");
+
+    let mut written: Vec<_> = fs::read_dir(&out).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    written.sort();
+    assert_eq!(written, ["earlyinput.json", "huge.json", "someinputs.json"]);
+
+    for (name, replay) in &expected {
+        let json = fs::read_to_string(out.join(format!("{name}.json"))).unwrap();
+        assert_eq!(&serde_json::from_str::<GameReplayData>(&json).unwrap(), replay);
+    }
+
+    // Only the replay whose output was removed is converted again
+    fs::remove_file(out.join("huge.json")).unwrap();
+    fs::write(out.join("someinputs.json"), "left alone").unwrap();
+
+    let summary = run(&["--skip-existing", "--jobs", "1"]);
+    assert!(summary.starts_with("converted  1\nskipped    2\nfailed     1\n"), "{summary}");
+    assert_eq!(fs::read_to_string(out.join("someinputs.json")).unwrap(), "left alone");
+    assert!(out.join("huge.json").exists());
+
+    let summary = run(&["--to", "b64"]);
+    assert!(summary.starts_with("converted  3\n"), "{summary}");
+    let base64 = fs::read_to_string(out.join("someinputs.b64.rep")).unwrap();
+    assert_eq!(GameReplayData::try_from_base64(&base64, None).unwrap(), expected[2].1);
+}
+
+#[test]
+fn batch_from_zip() {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let dir = TempDir::new("batch-zip");
+    let export = dir.0.join("export.zip");
+    let out = dir.0.join("out");
+
+    // Built here from two of the test replays, like the game's data export
+    let mut zip = zip::ZipWriter::new(fs::File::create(&export).unwrap());
+    let mut expected = Vec::new();
+    for (name, path) in [("earlyinput", EARLYINPUT), ("someinputs", SOMEINPUTS)] {
+        let replay = GameReplayData::try_from_base64(&fs::read_to_string(path).unwrap(), None).unwrap();
+        zip.start_file(format!("replay/{name}.rep"), SimpleFileOptions::default()).unwrap();
+        zip.write_all(&replay.serialize_to_compressed(None).unwrap()).unwrap();
+
+        expected.push((name, replay));
+    }
+    zip.finish().unwrap();
+
+    let mut command = trt();
+    command.args(["batch", "--from-zip", export.to_str().unwrap(), "--out", out.to_str().unwrap()]);
+    assert_eq!(stdout(&mut command), "converted  2\nskipped    0\nfailed     0\n");
+
+    for (name, replay) in &expected {
+        let json = fs::read_to_string(out.join(format!("{name}.json"))).unwrap();
+        assert_eq!(&serde_json::from_str::<GameReplayData>(&json).unwrap(), replay);
+    }
+
+    trt()
+        .args(["batch", "--from-zip", SOMEINPUTS, "--out", out.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't read the zip archive"));
+
+    // Either a directory or a zip archive
+    trt().args(["batch", "--out", out.to_str().unwrap()]).assert().failure().code(2);
+    trt()
+        .args(["batch", "dir", "--from-zip", export.to_str().unwrap(), "--out", out.to_str().unwrap()])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn schema() {
+    let schema: serde_json::Value = serde_json::from_str(&stdout(trt().arg("schema"))).unwrap();
+
+    assert_eq!(schema["title"], "GameReplayData");
+    assert!(schema["$defs"]["GameReplayMetadata"]["properties"]["mod"].is_object(), "{schema:#}");
+}
@@ -0,0 +1,29 @@
+//! Parses and serializes a replay using only `core` and `alloc`, as a `no_std` user would.
+//!
+//! Run with `cargo test --no-default-features` to check the crate without its `std` feature.
+
+#![no_std]
+
+// Only for the test harness
+extern crate std;
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use techmino_replay_toolkit::{GameReplayData, InputEventKey, InputEventKind};
+
+const SOMEINPUTS: &str = include_str!("../src/tests/cases/someinputs.b64.rep");
+
+#[test]
+fn parse_and_serialize() {
+    let data = GameReplayData::try_from_base64(SOMEINPUTS.trim(), None).unwrap();
+
+    assert_eq!(data.metadata.player, "NOT_A_ROBOT");
+    assert_eq!(data.inputs[0].frame, 62);
+    assert_eq!(data.inputs[0].kind, InputEventKind::Press);
+    assert_eq!(data.inputs[0].key, InputEventKey::HardDrop);
+
+    let base64: String = data.serialize_to_base64(None).unwrap();
+    assert_eq!(GameReplayData::try_from_base64(&base64, None).unwrap(), data);
+}
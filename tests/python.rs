@@ -0,0 +1,48 @@
+//! Runs the pytest-style tests in `python/tests` against the Python module, through an
+//! embedded interpreter, so they don't need maturin or pytest installed.
+
+#![cfg(feature = "python")]
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+const TESTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/python/tests/test_replay.py");
+
+/// Calls every `test_*` function of a module, passing a fresh `tmp_path` like pytest does.
+const RUNNER: &std::ffi::CStr = c"
+import inspect, pathlib, tempfile
+
+for name, test in list(vars(module).items()):
+    if name.startswith('test_') and callable(test):
+        with tempfile.TemporaryDirectory() as tmp_path:
+            wants_tmp_path = 'tmp_path' in inspect.signature(test).parameters
+            test(**({'tmp_path': pathlib.Path(tmp_path)} if wants_tmp_path else {}))
+";
+
+#[test]
+fn pytest_tests() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        if let Err(error) = run_tests(py) {
+            // Prints the traceback, which the panic message alone doesn't have
+            error.print(py);
+            panic!("{error}");
+        }
+    });
+}
+
+fn run_tests(py: Python<'_>) -> PyResult<()> {
+    let module = pyo3::wrap_pymodule!(techmino_replay_toolkit::python::techmino_replay_toolkit)(py);
+    py.import("sys")?.getattr("modules")?.set_item("techmino_replay_toolkit", module)?;
+
+    let code = CString::new(std::fs::read_to_string(TESTS_PATH).unwrap()).unwrap();
+    let path = CString::new(TESTS_PATH).unwrap();
+    let tests = PyModule::from_code(py, &code, &path, c"test_replay")?;
+
+    let globals = PyDict::new(py);
+    globals.set_item("module", tests)?;
+    py.run(RUNNER, Some(&globals), None)
+}
@@ -0,0 +1,91 @@
+//! Tests for the spans and events of the `tracing` feature.
+
+#![cfg(feature = "tracing")]
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use techmino_replay_toolkit::GameReplayData;
+
+const SOMEINPUTS: &str = include_str!("../src/tests/cases/someinputs.b64.rep");
+
+/// A writer that keeps what's logged, to look into it after the fact.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MakeWriter<'_> for Logs {
+    type Writer = Logs;
+
+    fn make_writer(&self) -> Logs {
+        self.clone()
+    }
+}
+
+/// Runs the function with the spans and events logged, returning the logs.
+fn logged(f: impl FnOnce()) -> String {
+    let logs = Logs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_max_level(LevelFilter::TRACE)
+        .with_span_events(FmtSpan::CLOSE)
+        .without_time()
+        .finish();
+
+    let guard = subscriber.set_default();
+    f();
+    drop(guard);
+
+    let logs = logs.0.lock().unwrap();
+    String::from_utf8(logs.clone()).unwrap()
+}
+
+#[test]
+fn test_parse_spans() {
+    let logs = logged(|| {
+        let replay = GameReplayData::try_from_base64(SOMEINPUTS, None).unwrap();
+        replay.serialize_to_base64(None).unwrap();
+    });
+
+    for span in [
+        "try_from_base64{bytes=",
+        "try_from_compressed{bytes=",
+        "try_from_raw{bytes=",
+        "decode_inputs{bytes=",
+        "serialize_to_base64{inputs=",
+        "serialize_to_compressed{inputs=",
+        "serialize_to_raw{inputs=",
+        "encode_inputs{events=",
+    ] {
+        assert!(logs.contains(span), "{span} is missing from:\n{logs}");
+    }
+    assert!(logs.contains("parse_mode=Relative"), "{logs}");
+    assert!(!logs.contains("WARN"), "{logs}");
+}
+
+#[test]
+fn test_recovery_warnings() {
+    let (first, rest) = SOMEINPUTS.trim().split_at(20);
+    let mangled = format!("{first}\n  {rest}");
+
+    let logs = logged(|| {
+        GameReplayData::try_from_base64(&mangled, None).unwrap();
+    });
+
+    assert!(logs.contains("WARN"), "{logs}");
+    assert!(logs.contains("cleaned up"), "{logs}");
+}
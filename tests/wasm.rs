@@ -0,0 +1,60 @@
+//! Tests for the JavaScript bindings.
+//!
+//! Run with `wasm-pack test --node --features wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use js_sys::Reflect;
+use techmino_replay_toolkit::wasm::*;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+const SOMEINPUTS: &str = include_str!("../src/tests/cases/someinputs.b64.rep");
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn parse_fixture() {
+    let replay = parse_base64(SOMEINPUTS.trim()).unwrap();
+
+    let metadata = get(&replay, "metadata");
+    assert_eq!(get(&metadata, "player").as_string().unwrap(), "NOT_A_ROBOT");
+    assert_eq!(get(&metadata, "mode").as_string().unwrap(), "sprint_10l");
+
+    let inputs = js_sys::Array::from(&get(&replay, "inputs"));
+    assert_eq!(inputs.length(), 10);
+
+    let first = inputs.get(0);
+    assert_eq!(get(&first, "frame").as_f64().unwrap(), 62.0);
+    assert_eq!(get(&first, "kind").as_string().unwrap(), "Press");
+    assert_eq!(get(&first, "key").as_string().unwrap(), "HardDrop");
+}
+
+#[wasm_bindgen_test]
+fn round_trip_fixture() {
+    let replay = parse_base64(SOMEINPUTS.trim()).unwrap();
+
+    let binary = serialize_to_binary(replay.clone()).unwrap();
+    let reparsed = parse_binary(&binary).unwrap();
+    assert_eq!(js_sys::JSON::stringify(&reparsed).unwrap(), js_sys::JSON::stringify(&replay).unwrap());
+
+    let base64 = serialize_to_base64(reparsed).unwrap();
+    assert!(parse_base64(&base64).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn errors_have_codes() {
+    let error = parse_base64("not base64!").unwrap_err();
+    assert_eq!(get(&error, "code").as_string().unwrap(), "Base64DecodeError");
+
+    let error = parse_binary(&[1, 2, 3]).unwrap_err();
+    assert_eq!(get(&error, "code").as_string().unwrap(), "ZlibDecompressError");
+
+    let error = parse_binary(&[]).unwrap_err();
+    assert_eq!(get(&error, "code").as_string().unwrap(), "EmptyInput");
+
+    let error = serialize_to_base64(JsValue::from_str("not a replay")).unwrap_err();
+    assert_eq!(get(&error, "code").as_string().unwrap(), "InvalidReplayData");
+}